@@ -0,0 +1,9 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="tilesheet_collection" tilewidth="448" tileheight="192" tilecount="2" columns="0">
+ <tile id="0">
+  <image width="448" height="192" source="tilesheet.png"/>
+ </tile>
+ <tile id="3">
+  <image width="32" height="16" source="tilesheet.png"/>
+ </tile>
+</tileset>
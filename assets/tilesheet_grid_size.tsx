@@ -0,0 +1,4 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="decals" tilewidth="32" tileheight="32" tilecount="84" columns="14" tilerendersize="grid" fillmode="preserve-aspect-fit">
+ <image source="tilesheet.png" width="448" height="192"/>
+</tileset>
@@ -0,0 +1,41 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="tilesheet_tiles" class="terrain" tilewidth="32" tileheight="32" spacing="0" margin="0" tilecount="84" columns="14">
+ <tileoffset x="2" y="-4"/>
+ <image source="tilesheet.png" trans="ff00ff" width="448" height="192"/>
+ <tile id="0" type="grass" probability="0.5">
+  <properties>
+   <property name="hp" type="int" value="3"/>
+   <property name="solid" type="bool" value="true"/>
+   <property name="speed" type="float" value="1.5"/>
+   <property name="tint" type="color" value="#ff336699"/>
+  </properties>
+  <objectgroup draworder="index" id="2">
+   <object id="1" name="box" type="hitbox" x="2" y="4" width="28" height="24"/>
+   <object id="2" x="8" y="8" width="16" height="16" rotation="45">
+    <ellipse/>
+   </object>
+   <object id="3" x="0" y="32" visible="0">
+    <polygon points="0,0 16,-16 32,0"/>
+   </object>
+   <object id="4" x="16" y="16">
+    <point/>
+   </object>
+  </objectgroup>
+ </tile>
+ <tile id="1">
+  <animation>
+   <frame tileid="1" duration="100"/>
+   <frame tileid="2" duration="200"/>
+  </animation>
+ </tile>
+ <wangsets>
+  <wangset name="Ground" type="corner" tile="0">
+   <properties>
+    <property name="layer" value="floor"/>
+   </properties>
+   <wangcolor name="Grass" color="#00ff00" tile="0" probability="1"/>
+   <wangtile tileid="0" wangid="0,1,0,1,0,1,0,1"/>
+   <wangtile tileid="1" wangid="0,1,0,0,0,1,0,1"/>
+  </wangset>
+ </wangsets>
+</tileset>
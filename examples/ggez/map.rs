@@ -18,8 +18,8 @@ impl MapHandler {
         // Load the map's tileset images
         let mut tileset_image_cache = HashMap::new();
         for ts in map.tilesets().iter() {
-            if let Some(image) = &ts.image {
-                let img = graphics::Image::from_path(ctx, &image.source)?;
+            if let Some(source) = ts.image.as_ref().and_then(|image| image.source.as_ref()) {
+                let img = graphics::Image::from_path(ctx, source)?;
 
                 tileset_image_cache.insert(ts.name.clone(), img);
             }
@@ -175,7 +175,12 @@ impl MapHandler {
 
                                     batch.push(
                                         DrawParam::default()
-                                            .src(get_tile_rect(ts, tile.id(), ts_size.0, ts_size.1))
+                                            .src(get_tile_rect(
+                                                ts,
+                                                tile.id().get(),
+                                                ts_size.0,
+                                                ts_size.1,
+                                            ))
                                             .dest([dx, dy])
                                             .color(ggez::graphics::Color::from_rgba(
                                                 0xFF,
@@ -260,8 +265,9 @@ impl MapHandler {
                 )?;
                 canvas.draw(&shape, draw_param);
             }
-            tiled::ObjectShape::Point(_, _) | tiled::ObjectShape::Text { .. } => {
-                // Left as an exercise for the reader
+            _ => {
+                // Points, text and shapes added in the future are left as an exercise for the
+                // reader
             }
         }
 
@@ -34,8 +34,11 @@ fn main() {
                 println!(
                     "Image layer with {}",
                     match &layer.image {
-                        Some(img) =>
-                            format!("an image with source = {}", img.source.to_string_lossy()),
+                        Some(img) => match &img.source {
+                            Some(source) =>
+                                format!("an image with source = {}", source.to_string_lossy()),
+                            None => "an embedded image".to_owned(),
+                        },
                         None => "no image".to_owned(),
                     }
                 )
@@ -69,7 +69,7 @@ fn generate_mesh(layer: &FiniteTileLayer, tilesheet: &Tilesheet) -> QuadMesh {
     for x in 0..width as i32 {
         for y in 0..height as i32 {
             if let Some(tile) = layer.get_tile(x, y) {
-                let uv = tilesheet.tile_rect(tile.id());
+                let uv = tilesheet.tile_rect(tile.id().get());
                 mesh.add_quad(Vector2f::new(x as f32, y as f32), 1., uv);
             }
         }
@@ -20,6 +20,8 @@ impl Tilesheet {
         let texture = {
             let texture_path = &tileset_image
                 .source
+                .as_ref()
+                .expect("tileset image should not be embedded")
                 .to_str()
                 .expect("obtaining valid UTF-8 path");
             Texture::from_file(texture_path).unwrap()
@@ -1,20 +1,27 @@
 //! Structures related to tile animations.
 
+use std::io::Write;
+
 use xml::attribute::OwnedAttribute;
 
+#[cfg(feature = "json")]
+use crate::parse::json::{required, JsonObject};
 use crate::{
     error::{Error, Result},
     util::{get_attrs, parse_tag, XmlEventResult},
+    write::XmlWriter,
+    TileId,
 };
 
 /// A structure describing a [frame] of a [TMX tile animation].
 ///
 /// [frame]: https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tmx-frame
 /// [TMX tile animation]: https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#animation
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Frame {
     /// The local ID of a tile within the parent tileset.
-    pub tile_id: u32,
+    pub tile_id: TileId,
     /// How long (in milliseconds) this frame should be displayed before advancing to the next frame.
     pub duration: u32,
 }
@@ -23,7 +30,7 @@ impl Frame {
     pub(crate) fn new(attrs: Vec<OwnedAttribute>) -> Result<Frame> {
         let (tile_id, duration) = get_attrs!(
             for v in attrs {
-                "tileid" => tile_id ?= v.parse::<u32>(),
+                "tileid" => tile_id ?= v.parse::<u32>().map(TileId::new),
                 "duration" => duration ?= v.parse::<u32>(),
             }
             (tile_id, duration)
@@ -32,6 +39,16 @@ impl Frame {
     }
 }
 
+#[cfg(feature = "json")]
+impl Frame {
+    pub(crate) fn parse_json(json: &JsonObject) -> Result<Frame> {
+        Ok(Frame {
+            tile_id: TileId::new(required(json.u32("tileid")?, "tileid")?),
+            duration: required(json.u32("duration")?, "duration")?,
+        })
+    }
+}
+
 pub(crate) fn parse_animation(
     parser: &mut impl Iterator<Item = XmlEventResult>,
 ) -> Result<Vec<Frame>> {
@@ -44,3 +61,30 @@ pub(crate) fn parse_animation(
     });
     Ok(animation)
 }
+
+#[cfg(feature = "json")]
+pub(crate) fn write_json_animation(frames: &[Frame]) -> serde_json::Value {
+    frames
+        .iter()
+        .map(|frame| {
+            serde_json::json!({
+                "tileid": frame.tile_id.get(),
+                "duration": frame.duration,
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn write_animation(writer: &mut XmlWriter<impl Write>, frames: &[Frame]) -> Result<()> {
+    writer.start("animation", &[])?;
+    for frame in frames {
+        writer.empty(
+            "frame",
+            &[
+                ("tileid", frame.tile_id.to_string()),
+                ("duration", frame.duration.to_string()),
+            ],
+        )?;
+    }
+    writer.end()
+}
@@ -1,7 +1,9 @@
 use std::{
+    any::Any,
     collections::HashMap,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, PoisonError, RwLock},
+    time::SystemTime,
 };
 
 use crate::{Template, Tileset};
@@ -11,14 +13,49 @@ pub type ResourcePath = Path;
 /// An owned type that is used to refer to a resource. For the non-owned variant, see [`ResourcePath`].
 pub type ResourcePathBuf = PathBuf;
 
+/// The kinds of resources that can be stored in a [`ResourceCache`].
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[non_exhaustive]
+pub enum ResourceKind {
+    /// A [`Tileset`].
+    Tileset,
+    /// A [`Template`].
+    Template,
+}
+
+/// A resource that can be stored in a [`ResourceCache`].
+///
+/// Caches usually store every kind of resource together as `Arc<dyn Any + Send + Sync>`, and turn
+/// them back into their concrete type with [`Arc::downcast`].
+pub trait CachedResource: Any + Send + Sync {
+    /// The kind of this resource, which can be used to tell resources apart when they are stored
+    /// together.
+    const KIND: ResourceKind;
+}
+
+impl CachedResource for Tileset {
+    const KIND: ResourceKind = ResourceKind::Tileset;
+}
+
+impl CachedResource for Template {
+    const KIND: ResourceKind = ResourceKind::Template;
+}
+
 /// A trait identifying a data type that holds resources (such as tilesets) and maps them to a
 /// [`ResourcePath`] to prevent loading them more than once. Normally you don't need to use this
 /// type yourself unless you want to create a custom caching solution to, for instance, integrate
 /// with your own.
 ///
+/// Implementors only need to provide [`Self::get`] and [`Self::insert`], which work the same way
+/// for every [kind of resource](CachedResource). Two resources of different kinds never share an
+/// entry, even if they have the same path.
+///
+/// Loaders store resources under the path they were read from without its `.` components, so
+/// that `./tiles.tsx` and `tiles.tsx` share an entry.
+///
 /// If you simply want to load a map or tileset, use the [`Loader`](crate::Loader) type.
 pub trait ResourceCache {
-    /// Obtains a tileset from the cache, if it exists.
+    /// Obtains a resource from the cache, if it exists.
     ///
     /// # Example
     /// ```
@@ -31,57 +68,317 @@ pub trait ResourceCache {
     /// let mut loader = Loader::new();
     /// let path = "assets/tilesheet.tsx";
     ///
-    /// assert!(loader.cache().get_tileset(path).is_none());
+    /// assert!(loader.cache().get::<Tileset>(path).is_none());
     /// let tileset = Arc::new(loader.load_tsx_tileset(path)?);
-    /// loader.cache_mut().insert_tileset(path, tileset);
-    /// assert!(loader.cache().get_tileset(path).is_some());
+    /// loader.cache_mut().insert(path, tileset);
+    /// assert!(loader.cache().get::<Tileset>(path).is_some());
     /// # Ok(())
     /// # }
     /// ```
-    fn get_tileset(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<Tileset>>;
-    /// Insert a new tileset into the cache.
+    fn get<T: CachedResource>(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<T>>;
+    /// Insert a new resource into the cache.
     ///
-    /// See [`Self::get_tileset()`] for an example.
-    fn insert_tileset(&mut self, path: impl AsRef<ResourcePath>, tileset: Arc<Tileset>);
+    /// See [`Self::get()`] for an example.
+    fn insert<T: CachedResource>(&mut self, path: impl AsRef<ResourcePath>, resource: Arc<T>);
+
+    /// Obtains a resource from the cache, or creates it with `f` and inserts it if it doesn't
+    /// exist. Errors returned by `f` are passed on and nothing is inserted.
+    ///
+    /// `f` is given the cache itself, so that the resources the new one depends on, such as the
+    /// tileset of a template, can be cached too.
+    ///
+    /// The resource returned is the one found in the cache after inserting, if any, so caches that
+    /// keep the first resource inserted under a path are respected.
+    fn insert_with<T: CachedResource, E>(
+        &mut self,
+        path: impl AsRef<ResourcePath>,
+        f: impl FnOnce(&mut Self) -> Result<Arc<T>, E>,
+    ) -> Result<Arc<T>, E> {
+        let path = path.as_ref();
+        if let Some(resource) = self.get(path) {
+            return Ok(resource);
+        }
+        let resource = f(self)?;
+        self.insert(path, resource.clone());
+        Ok(self.get(path).unwrap_or(resource))
+    }
+
+    /// Obtains a tileset from the cache, if it exists.
+    ///
+    /// This is the same as `get::<Tileset>(path)`.
+    fn get_tileset(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<Tileset>> {
+        self.get(path)
+    }
+    /// Insert a new tileset into the cache.
+    fn insert_tileset(&mut self, path: impl AsRef<ResourcePath>, tileset: Arc<Tileset>) {
+        self.insert(path, tileset)
+    }
     /// Obtains a template from the cache, if it exists.
-    fn get_template(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<Template>>;
+    ///
+    /// This is the same as `get::<Template>(path)`.
+    fn get_template(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<Template>> {
+        self.get(path)
+    }
     /// Insert a new template into the cache.
-    fn insert_template(&mut self, path: impl AsRef<ResourcePath>, tileset: Arc<Template>);
+    fn insert_template(&mut self, path: impl AsRef<ResourcePath>, template: Arc<Template>) {
+        self.insert(path, template)
+    }
+}
+
+/// A resource of any kind, as stored in the caches in this module.
+type AnyResource = Arc<dyn Any + Send + Sync>;
+/// The resources stored in the caches in this module, grouped by kind so that resources of
+/// different kinds never collide.
+type ResourceMap = HashMap<ResourceKind, HashMap<ResourcePathBuf, AnyResource>>;
+
+fn downcast<T: CachedResource>(resource: &AnyResource) -> Option<Arc<T>> {
+    resource.clone().downcast().ok()
 }
 
 /// A cache that identifies resources by their path, storing them in a [`HashMap`].
-#[derive(Debug, Default)]
+///
+/// Resources are kept until they are removed. Removing a resource doesn't affect any other code
+/// still holding an [`Arc`] to it.
+///
+/// ## Modification checks
+/// Optionally, the cache can record the modification time of each file when it is inserted and
+/// treat the resource as missing once the file on disk is newer, so that edited files are loaded
+/// again. This is off by default since it queries the filesystem on every lookup, and it only
+/// applies to resources inserted while it is enabled. See [`Self::with_modification_checks`].
+#[derive(Debug, Clone, Default)]
 pub struct DefaultResourceCache {
-    /// The tilesets cached until now.
-    pub tilesets: HashMap<ResourcePathBuf, Arc<Tileset>>,
-    /// The templates cached until now.
-    pub templates: HashMap<ResourcePathBuf, Arc<Template>>,
+    resources: ResourceMap,
+    check_modified: bool,
+    /// The modification times of the cached files, recorded when they were inserted.
+    modified: HashMap<ResourcePathBuf, SystemTime>,
 }
 
 impl DefaultResourceCache {
     /// Creates an empty [`DefaultResourceCache`].
     pub fn new() -> Self {
         Self {
-            tilesets: HashMap::new(),
-            templates: HashMap::new(),
+            resources: HashMap::new(),
+            check_modified: false,
+            modified: HashMap::new(),
+        }
+    }
+
+    /// Enables or disables checking the modification times of cached files on lookup.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::sync::Arc;
+    /// use tiled::{DefaultResourceCache, Loader, ResourceCache};
+    /// # fn main() -> tiled::Result<()> {
+    ///
+    /// let mut loader = Loader::new().with_cache(DefaultResourceCache::new().with_modification_checks(true));
+    /// let tileset = Arc::new(loader.load_tsx_tileset("assets/tilesheet.tsx")?);
+    /// loader.cache_mut().insert_tileset("assets/tilesheet.tsx", tileset);
+    ///
+    /// // The file hasn't changed since it was inserted, so the tileset is still cached.
+    /// assert!(loader.cache().get_tileset("assets/tilesheet.tsx").is_some());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_modification_checks(mut self, enabled: bool) -> Self {
+        self.check_modified = enabled;
+        self
+    }
+
+    /// Returns the number of resources of every kind in the cache.
+    pub fn len(&self) -> usize {
+        self.resources.values().map(HashMap::len).sum()
+    }
+
+    /// Returns whether the cache holds no resources at all.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the resources of kind `T` in the cache, along with their paths,
+    /// in no particular order.
+    ///
+    /// Unlike [`ResourceCache::get`], this doesn't check the modification times of files.
+    pub fn resources<T: CachedResource>(
+        &self,
+    ) -> impl Iterator<Item = (&ResourcePath, Arc<T>)> + '_ {
+        self.resources
+            .get(&T::KIND)
+            .into_iter()
+            .flatten()
+            .filter_map(|(path, resource)| Some((path.as_path(), downcast(resource)?)))
+    }
+
+    /// Removes all the resources from the cache.
+    pub fn clear(&mut self) {
+        self.resources.clear();
+        self.modified.clear();
+    }
+
+    /// Removes a resource from the cache, returning it if it was present.
+    pub fn remove<T: CachedResource>(&mut self, path: impl AsRef<ResourcePath>) -> Option<Arc<T>> {
+        self.modified.remove(path.as_ref());
+        let resource = self.resources.get_mut(&T::KIND)?.remove(path.as_ref())?;
+        downcast(&resource)
+    }
+
+    /// Removes a tileset from the cache, returning it if it was present.
+    pub fn remove_tileset(&mut self, path: impl AsRef<ResourcePath>) -> Option<Arc<Tileset>> {
+        self.remove(path)
+    }
+
+    /// Removes a template from the cache, returning it if it was present.
+    pub fn remove_template(&mut self, path: impl AsRef<ResourcePath>) -> Option<Arc<Template>> {
+        self.remove(path)
+    }
+
+    fn record_modified(&mut self, path: &ResourcePath) {
+        if !self.check_modified {
+            return;
+        }
+        match modification_time(path) {
+            Some(time) => self.modified.insert(path.to_owned(), time),
+            None => self.modified.remove(path),
+        };
+    }
+
+    /// Whether the file at `path` has changed since it was inserted. Files whose modification time
+    /// can't be obtained anymore are considered to have changed.
+    fn is_stale(&self, path: &ResourcePath) -> bool {
+        if !self.check_modified {
+            return false;
+        }
+        match self.modified.get(path) {
+            Some(recorded) => !matches!(modification_time(path), Some(time) if time <= *recorded),
+            None => false,
         }
     }
 }
 
+fn modification_time(path: &ResourcePath) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
 impl ResourceCache for DefaultResourceCache {
-    fn get_tileset(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<Tileset>> {
-        self.tilesets.get(path.as_ref()).map(Clone::clone)
+    fn get<T: CachedResource>(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<T>> {
+        if self.is_stale(path.as_ref()) {
+            return None;
+        }
+        downcast(self.resources.get(&T::KIND)?.get(path.as_ref())?)
     }
 
-    fn insert_tileset(&mut self, path: impl AsRef<ResourcePath>, tileset: Arc<Tileset>) {
-        self.tilesets.insert(path.as_ref().to_path_buf(), tileset);
+    fn insert<T: CachedResource>(&mut self, path: impl AsRef<ResourcePath>, resource: Arc<T>) {
+        self.record_modified(path.as_ref());
+        self.resources
+            .entry(T::KIND)
+            .or_default()
+            .insert(path.as_ref().to_path_buf(), resource);
     }
+}
 
-    fn get_template(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<Template>> {
-        self.templates.get(path.as_ref()).map(Clone::clone)
+/// A thread-safe cache that identifies resources by their path, which can be shared between
+/// several [`Loader`](crate::Loader)s running concurrently.
+///
+/// [`ResourceCache`] is implemented for both `&ConcurrentResourceCache` and
+/// `Arc<ConcurrentResourceCache>`, so each thread can own a loader using a reference to the same
+/// cache.
+///
+/// ## Concurrent misses
+/// There is no per-resource locking: if several threads miss the same resource at the same time,
+/// each of them loads it, and the first one to insert it wins. Later insertions of a resource that
+/// is already cached are ignored, so every subsequent lookup returns the same [`Arc`].
+///
+/// ## Example
+/// ```
+/// use tiled::{ConcurrentResourceCache, FilesystemResourceReader, Loader};
+///
+/// let cache = ConcurrentResourceCache::new();
+/// std::thread::scope(|scope| {
+///     for _ in 0..4 {
+///         scope.spawn(|| {
+///             Loader::with_cache_and_reader(&cache, FilesystemResourceReader)
+///                 .load_tmx_map("assets/tiled_base64_external.tmx")
+///                 .unwrap();
+///         });
+///     }
+/// });
+/// assert_eq!(cache.tileset_count(), 1);
+/// ```
+#[derive(Debug, Default)]
+pub struct ConcurrentResourceCache {
+    resources: RwLock<ResourceMap>,
+}
+
+impl ConcurrentResourceCache {
+    /// Creates an empty [`ConcurrentResourceCache`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of resources of kind `kind` in the cache.
+    pub fn count(&self, kind: ResourceKind) -> usize {
+        self.resources
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&kind)
+            .map_or(0, HashMap::len)
+    }
+
+    /// Returns the number of tilesets in the cache.
+    pub fn tileset_count(&self) -> usize {
+        self.count(ResourceKind::Tileset)
+    }
+
+    /// Returns the number of templates in the cache.
+    pub fn template_count(&self) -> usize {
+        self.count(ResourceKind::Template)
+    }
+
+    /// Removes all the resources from the cache.
+    pub fn clear(&self) {
+        self.resources
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    fn get_resource<T: CachedResource>(&self, path: &ResourcePath) -> Option<Arc<T>> {
+        downcast(
+            self.resources
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(&T::KIND)?
+                .get(path)?,
+        )
+    }
+
+    fn insert_resource<T: CachedResource>(&self, path: &ResourcePath, resource: Arc<T>) {
+        self.resources
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(T::KIND)
+            .or_default()
+            .entry(path.to_owned())
+            .or_insert(resource);
+    }
+}
+
+impl ResourceCache for &ConcurrentResourceCache {
+    fn get<T: CachedResource>(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<T>> {
+        self.get_resource(path.as_ref())
+    }
+
+    fn insert<T: CachedResource>(&mut self, path: impl AsRef<ResourcePath>, resource: Arc<T>) {
+        self.insert_resource(path.as_ref(), resource)
+    }
+}
+
+impl ResourceCache for Arc<ConcurrentResourceCache> {
+    fn get<T: CachedResource>(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<T>> {
+        self.get_resource(path.as_ref())
     }
 
-    fn insert_template(&mut self, path: impl AsRef<ResourcePath>, tileset: Arc<Template>) {
-        self.templates.insert(path.as_ref().to_path_buf(), tileset);
+    fn insert<T: CachedResource>(&mut self, path: impl AsRef<ResourcePath>, resource: Arc<T>) {
+        self.insert_resource(path.as_ref(), resource)
     }
 }
@@ -1,5 +1,7 @@
 use std::{fmt, path::PathBuf};
 
+use crate::TileId;
+
 /// Errors which occured when parsing the file
 #[derive(Debug)]
 #[non_exhaustive]
@@ -13,7 +15,19 @@ pub enum Error {
     /// An error occured when decoding a base64 encoded dataset.
     Base64DecodingError(base64::DecodeError),
     /// An error occured when parsing a XML file, such as a TMX or TSX file.
+    ///
+    /// This is returned when the file itself isn't valid XML, for instance because it is truncated
+    /// or isn't valid UTF-8, while files that are valid XML but not valid Tiled files return
+    /// other errors, such as [`Error::MalformedAttributes`].
     XmlDecodingError(xml::reader::Error),
+    /// An error occured when writing a XML file, such as a TMX file.
+    XmlEncodingError(xml::writer::Error),
+    /// An error occured when parsing a JSON file, such as a JSON tileset.
+    #[cfg(feature = "json")]
+    JsonDecodingError(serde_json::Error),
+    /// An error occured when writing a JSON file, such as a JSON map.
+    #[cfg(feature = "json")]
+    JsonEncodingError(serde_json::Error),
     /// The XML stream ended before the document was fully parsed.
     PrematureEnd(String),
     /// The path given is invalid because it isn't contained in any folder.
@@ -28,6 +42,12 @@ pub enum Error {
     },
     /// There was an invalid tile in the map parsed.
     InvalidTileFound,
+    /// The binary tile data of a layer doesn't hold a whole number of tiles, since its length
+    /// isn't a multiple of 4 bytes, or holds more tiles than fit in the layer or chunk.
+    InvalidTileDataLength {
+        /// The length of the decoded tile data, in bytes.
+        length: usize,
+    },
     /// Unknown encoding or compression format or invalid combination of both (for tile layers)
     InvalidEncodingFormat {
         /// The `encoding` attribute of the tile layer data, if any.
@@ -57,6 +77,250 @@ pub enum Error {
         /// Stores the wrongly parsed String.
         read_string: String,
     },
+    /// A builder, such as [`MapBuilder`](crate::MapBuilder), was used without setting a value it
+    /// requires.
+    MissingBuilderField {
+        /// The name of the value that wasn't set.
+        field: String,
+    },
+    /// The tile data of a finite tile layer couldn't be decoded.
+    LayerDecodingError {
+        /// The name of the layer whose data couldn't be decoded.
+        layer_name: String,
+        /// The error that occured when decoding it.
+        err: Box<Error>,
+    },
+    /// A file went over one of the [`ParseLimits`](crate::ParseLimits) it was loaded with.
+    LimitExceeded {
+        /// A description of the limit that was exceeded.
+        description: String,
+    },
+    /// A file uses something the crate can't load, at least without one of its optional features
+    /// enabled. Unlike most other errors, this doesn't mean the file is invalid.
+    UnsupportedFeature {
+        /// A description of what isn't supported.
+        description: String,
+    },
+    /// An element has more than one custom property with the same name, and the
+    /// [`ParseLimits`](crate::ParseLimits) used don't allow it.
+    DuplicateProperty {
+        /// The name of the element the properties belong to, such as `object` or `tile`.
+        element: String,
+        /// The ID of the element, if it has one.
+        element_id: Option<u32>,
+        /// The name shared by the properties.
+        name: String,
+    },
+}
+
+/// A non-fatal problem found in a map that was parsed successfully, returned by
+/// [`Map::validate`](crate::Map::validate).
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
+pub enum Warning {
+    /// A finite tile layer is bigger than the map it is in.
+    LayerLargerThanMap {
+        /// The name of the layer.
+        layer: String,
+        /// The width of the layer, in tiles.
+        width: u32,
+        /// The height of the layer, in tiles.
+        height: u32,
+    },
+    /// More than one layer in the map has the same name.
+    DuplicateLayerName {
+        /// The name shared by the layers.
+        name: String,
+    },
+    /// An element had more than one custom property with the same name, of which only the last
+    /// was kept. This is only looked for when loading with
+    /// [`DuplicatePropertyPolicy::Warn`](crate::DuplicatePropertyPolicy::Warn).
+    DuplicateProperty {
+        /// The name of the element the properties belong to, such as `object` or `tile`.
+        element: String,
+        /// The ID of the element, if it has one.
+        element_id: Option<u32>,
+        /// The name shared by the properties.
+        name: String,
+    },
+    /// A tile layer references a tile that doesn't exist in its tileset, which means its GID
+    /// is beyond the range of the tileset it falls in.
+    TileNotInTileset {
+        /// The name of the layer the tile is in.
+        layer: String,
+        /// The X coordinate of the tile in the layer.
+        x: i32,
+        /// The Y coordinate of the tile in the layer.
+        y: i32,
+        /// The index of the tileset in the map the tile's GID falls in.
+        tileset_index: usize,
+        /// The local ID of the tile within the tileset.
+        tile_id: TileId,
+    },
+    /// The GID ranges of two tilesets overlap, so some GIDs are ambiguous.
+    OverlappingTilesets {
+        /// The index of the tileset in the map with the lowest first GID.
+        first: usize,
+        /// The index of the tileset in the map that starts before the first one ends.
+        second: usize,
+    },
+    /// The GID range of a tileset, which starts at its first GID, is too large for GIDs to hold.
+    TilesetGidOverflow {
+        /// The index of the tileset in the map.
+        tileset_index: usize,
+    },
+    /// The image of a tileset is too small to fit its tiles, given the tile size, spacing, margin
+    /// and number of columns.
+    TilesetImageTooSmall {
+        /// The index of the tileset in the map.
+        tileset_index: usize,
+    },
+    /// The number of tiles stored in a finite tile layer doesn't match its width and height.
+    LayerDataSizeMismatch {
+        /// The name of the layer.
+        layer: String,
+        /// The number of tiles the layer should have, which is its width times its height.
+        expected: usize,
+        /// The number of tiles stored in the layer.
+        actual: usize,
+    },
+    /// A tile object uses a tile that doesn't exist in its tileset.
+    ObjectTileNotInTileset {
+        /// The name of the layer the object is in.
+        layer: String,
+        /// The ID of the object.
+        object_id: u32,
+        /// The index of the tileset in the map the object's GID falls in, or [`None`] if the
+        /// tileset comes from the object's template.
+        tileset_index: Option<usize>,
+        /// The local ID of the tile within the tileset.
+        tile_id: TileId,
+    },
+    /// An object property refers to an object that isn't in the map.
+    MissingObjectReference {
+        /// The name of the layer the property is in, or [`None`] for properties of the map.
+        layer: Option<String>,
+        /// The ID of the object the property is in, or [`None`] for properties of the map or of a
+        /// layer.
+        object_id: Option<u32>,
+        /// The name of the property. Members of class properties are named after the property
+        /// and the member, separated by a dot.
+        property: String,
+        /// The ID of the object the property refers to.
+        target: u32,
+    },
+    /// A frame of a tile's animation refers to a tile that doesn't exist in the tileset.
+    AnimationFrameNotInTileset {
+        /// The index of the tileset in the map.
+        tileset_index: usize,
+        /// The local ID of the animated tile.
+        tile_id: TileId,
+        /// The local ID of the tile the frame refers to.
+        frame_tile_id: TileId,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Warning::LayerLargerThanMap { layer, width, height } => write!(
+                fmt,
+                "Layer '{}' ({}x{}) is larger than the map",
+                layer, width, height
+            ),
+            Warning::DuplicateLayerName { name } => {
+                write!(fmt, "More than one layer is named '{}'", name)
+            }
+            Warning::DuplicateProperty {
+                element,
+                element_id,
+                name,
+            } => {
+                write!(fmt, "More than one property of <{}>", element)?;
+                if let Some(id) = element_id {
+                    write!(fmt, " {}", id)?;
+                }
+                write!(fmt, " is named '{}'", name)
+            }
+            Warning::TileNotInTileset {
+                layer,
+                x,
+                y,
+                tileset_index,
+                tile_id,
+            } => write!(
+                fmt,
+                "Tile at ({}, {}) in layer '{}' refers to tile {} of tileset #{}, which doesn't exist",
+                x, y, layer, tile_id, tileset_index
+            ),
+            Warning::OverlappingTilesets { first, second } => write!(
+                fmt,
+                "The GID ranges of tilesets #{} and #{} overlap",
+                first, second
+            ),
+            Warning::TilesetGidOverflow { tileset_index } => write!(
+                fmt,
+                "The GID range of tileset #{} goes past the largest GID",
+                tileset_index
+            ),
+            Warning::TilesetImageTooSmall { tileset_index } => write!(
+                fmt,
+                "The image of tileset #{} is too small for its tiles",
+                tileset_index
+            ),
+            Warning::LayerDataSizeMismatch {
+                layer,
+                expected,
+                actual,
+            } => write!(
+                fmt,
+                "Layer '{}' has {} tiles instead of {}",
+                layer, actual, expected
+            ),
+            Warning::ObjectTileNotInTileset {
+                layer,
+                object_id,
+                tileset_index,
+                tile_id,
+            } => match tileset_index {
+                Some(tileset_index) => write!(
+                    fmt,
+                    "Object {} in layer '{}' uses tile {} of tileset #{}, which doesn't exist",
+                    object_id, layer, tile_id, tileset_index
+                ),
+                None => write!(
+                    fmt,
+                    "Object {} in layer '{}' uses tile {} of its template's tileset, which doesn't exist",
+                    object_id, layer, tile_id
+                ),
+            },
+            Warning::MissingObjectReference {
+                layer,
+                object_id,
+                property,
+                target,
+            } => {
+                write!(fmt, "Property '{}' of ", property)?;
+                match (layer, object_id) {
+                    (Some(layer), Some(object_id)) => {
+                        write!(fmt, "object {} in layer '{}'", object_id, layer)?
+                    }
+                    (Some(layer), None) => write!(fmt, "layer '{}'", layer)?,
+                    (None, _) => write!(fmt, "the map")?,
+                }
+                write!(fmt, " refers to object {}, which isn't in the map", target)
+            }
+            Warning::AnimationFrameNotInTileset {
+                tileset_index,
+                tile_id,
+                frame_tile_id,
+            } => write!(
+                fmt,
+                "The animation of tile {} in tileset #{} shows tile {}, which doesn't exist",
+                tile_id, tileset_index, frame_tile_id
+            ),
+        }
+    }
 }
 
 /// A result with an error variant of [`crate::Error`].
@@ -69,6 +333,11 @@ impl fmt::Display for Error {
             Error::DecompressingError(e) => write!(fmt, "{}", e),
             Error::Base64DecodingError(e) => write!(fmt, "{}", e),
             Error::XmlDecodingError(e) => write!(fmt, "{}", e),
+            Error::XmlEncodingError(e) => write!(fmt, "{}", e),
+            #[cfg(feature = "json")]
+            Error::JsonDecodingError(e) => write!(fmt, "{}", e),
+            #[cfg(feature = "json")]
+            Error::JsonEncodingError(e) => write!(fmt, "{}", e),
             Error::PrematureEnd(e) => write!(fmt, "{}", e),
             Error::PathIsNotFile => {
                 write!(
@@ -85,6 +354,8 @@ impl fmt::Display for Error {
                 )
             }
             Error::InvalidTileFound => write!(fmt, "Invalid tile found in map being parsed"),
+            Error::InvalidTileDataLength { length } =>
+                write!(fmt, "Tile data is {} bytes long, which isn't a whole number of tiles fitting in the layer", length),
             Error::InvalidEncodingFormat { encoding: None, compression: None } =>
                 write!(
                     fmt,
@@ -104,6 +375,21 @@ impl fmt::Display for Error {
             Error::TemplateHasNoObject => write!(fmt, "A template was found with no object element"),
             Error::InvalidWangIdEncoding{read_string} =>
                 write!(fmt, "\"{}\" is not a valid WangId format", read_string),
+            Error::MissingBuilderField { field } =>
+                write!(fmt, "The builder is missing a value for '{}'", field),
+            Error::LayerDecodingError { layer_name, err } =>
+                write!(fmt, "Could not decode the data of layer '{}'. Error: {}", layer_name, err),
+            Error::LimitExceeded { description } =>
+                write!(fmt, "A parsing limit was exceeded: {}", description),
+            Error::UnsupportedFeature { description } =>
+                write!(fmt, "Unsupported feature: {}", description),
+            Error::DuplicateProperty { element, element_id, name } => {
+                write!(fmt, "More than one property of <{}>", element)?;
+                if let Some(id) = element_id {
+                    write!(fmt, " {}", id)?;
+                }
+                write!(fmt, " is named '{}'", name)
+            }
         }
     }
 }
@@ -114,7 +400,13 @@ impl std::error::Error for Error {
             Error::DecompressingError(e) => Some(e as &dyn std::error::Error),
             Error::Base64DecodingError(e) => Some(e as &dyn std::error::Error),
             Error::XmlDecodingError(e) => Some(e as &dyn std::error::Error),
+            Error::XmlEncodingError(e) => Some(e as &dyn std::error::Error),
+            #[cfg(feature = "json")]
+            Error::JsonDecodingError(e) => Some(e as &dyn std::error::Error),
+            #[cfg(feature = "json")]
+            Error::JsonEncodingError(e) => Some(e as &dyn std::error::Error),
             Error::ResourceLoadingError { err, .. } => Some(err.as_ref()),
+            Error::LayerDecodingError { err, .. } => Some(err.as_ref()),
             _ => None,
         }
     }
@@ -1,24 +1,32 @@
-use std::path::{Path, PathBuf};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
 
+use base64::Engine;
 use xml::attribute::OwnedAttribute;
 
+#[cfg(feature = "json")]
+use crate::parse::json::{required, JsonObject};
+#[cfg(feature = "json")]
+use crate::write::{color_to_string, json_object, JsonMap, JsonWriter};
 use crate::{
     error::{Error, Result},
-    properties::Color,
+    properties::{parse_properties, write_properties, Color, Properties},
     util::*,
+    write::XmlWriter,
 };
 
-/// A reference to an image stored somewhere within the filesystem.
-#[derive(Debug, PartialEq, Eq, Clone)]
+/// A reference to an image stored somewhere within the filesystem, or embedded in the file it is
+/// in.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Image {
     /// The **uncanonicalized** filepath of the image, starting from the path given to load the file
     /// this image is in. See the example for more details.
     ///
-    /// ## Note
-    /// The crate does not currently support embedded images (Even though Tiled
-    /// does not allow creating maps with embedded image data, the TMX format does; [source])
-    ///
-    /// [source]: https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#image
+    /// This is [`None`] if the image is embedded, in which case its contents are stored in
+    /// [`Self::embedded_data`] instead.
     ///
     /// ## Example
     /// ```
@@ -42,7 +50,7 @@ pub struct Image {
     /// // Image layer has an image with the source attribute set to "../tilesheet.png"
     /// // Given the information we gave to the `parse_file` function, the image source should be
     /// // "assets/folder/../tilesheet.png". The filepath is not canonicalized.
-    /// let image_source = &image_layer.image.as_ref().unwrap().source;
+    /// let image_source = image_layer.image.as_ref().unwrap().source.as_ref().unwrap();
     ///
     /// assert_eq!(
     ///     image_source,
@@ -57,38 +65,179 @@ pub struct Image {
     /// ```
     /// Check the assets/tiled_relative_paths.tmx file at the crate root to see the structure of the
     /// file this example is referring to.
-    // TODO: Embedded images
-    pub source: PathBuf,
+    pub source: Option<PathBuf>,
+    /// The contents of the image file, if it is embedded in the file this image is in instead of
+    /// being referenced by [`Self::source`].
+    ///
+    /// Even though Tiled does not allow creating maps with embedded image data, the TMX format
+    /// does; [source]
+    ///
+    /// [source]: https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#image
+    pub embedded_data: Option<Vec<u8>>,
     /// The width in pixels of the image.
     pub width: i32,
     /// The height in pixels of the image.
     pub height: i32,
     /// A color that should be interpreted as transparent (0 alpha), if any.
-    pub transparent_colour: Option<Color>,
+    #[cfg_attr(feature = "serde", serde(alias = "transparent_colour"))]
+    pub transparent_color: Option<Color>,
+    /// The custom properties of the image, as arbitrarily set by the user.
+    ///
+    /// JSON files can't store these, so images loaded from them never have any.
+    pub properties: Properties,
 }
 
 impl Image {
+    /// The color that should be interpreted as transparent, if any.
+    #[deprecated(since = "0.12.0", note = "renamed to the `transparent_color` field")]
+    pub fn transparent_colour(&self) -> Option<Color> {
+        self.transparent_color
+    }
+
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
         path_relative_to: impl AsRef<Path>,
     ) -> Result<Image> {
-        let (c, (s, w, h)) = get_attrs!(
+        let (c, s, (w, h)) = get_attrs!(
             for v in attrs {
                 Some("trans") => trans ?= v.parse(),
-                "source" => source = v,
+                Some("source") => source = v,
                 "width" => width ?= v.parse::<i32>(),
                 "height" => height ?= v.parse::<i32>(),
             }
-            (trans, (source, width, height))
+            (trans, source, (width, height))
         );
 
-        parse_tag!(parser, "image", {});
+        let mut embedded_data = None;
+        let mut properties = Properties::new();
+        parse_tag!(parser, "image", {
+            "data" => |attrs| {
+                embedded_data = Some(parse_embedded_data(parser, attrs)?);
+                Ok(())
+            },
+            "properties" => |_| {
+                properties = parse_properties(parser)?;
+                Ok(())
+            },
+        });
+
+        if s.is_none() && embedded_data.is_none() {
+            return Err(Error::MalformedAttributes(
+                "Image has neither a source nor embedded data".to_owned(),
+            ));
+        }
+
         Ok(Image {
-            source: path_relative_to.as_ref().join(s),
+            source: s.map(|s| path_relative_to.as_ref().join(s)),
+            embedded_data,
             width: w,
             height: h,
-            transparent_colour: c,
+            transparent_color: c,
+            properties,
         })
     }
 }
+
+#[cfg(feature = "json")]
+impl Image {
+    /// Parses the image of a JSON tileset or tile, which is stored in the `image`, `imagewidth`,
+    /// `imageheight` and `transparentcolor` members of the object itself.
+    pub(crate) fn parse_json(
+        json: &JsonObject,
+        path_relative_to: impl AsRef<Path>,
+    ) -> Result<Option<Image>> {
+        let source = match json.str("image")? {
+            Some(source) => source,
+            None => return Ok(None),
+        };
+        Ok(Some(Image {
+            source: Some(path_relative_to.as_ref().join(source)),
+            embedded_data: None,
+            width: required(json.i32("imagewidth")?, "imagewidth")?,
+            height: required(json.i32("imageheight")?, "imageheight")?,
+            transparent_color: json.color("transparentcolor")?,
+            properties: Properties::new(),
+        }))
+    }
+}
+
+impl Image {
+    pub(crate) fn write_xml(&self, writer: &mut XmlWriter<impl Write>) -> Result<()> {
+        let mut attrs = Vec::new();
+        if let Some(source) = &self.source {
+            attrs.push(("source", writer.relative_path(source)));
+        }
+        if let Some(trans) = self.transparent_color {
+            // Tiled stores transparent colors without a leading '#'
+            attrs.push((
+                "trans",
+                format!("{:02x}{:02x}{:02x}", trans.red, trans.green, trans.blue),
+            ));
+        }
+        attrs.push(("width", self.width.to_string()));
+        attrs.push(("height", self.height.to_string()));
+
+        writer.start("image", &attrs)?;
+        write_properties(writer, &self.properties)?;
+        if let Some(data) = &self.embedded_data {
+            writer.start("data", &[("encoding", "base64".to_owned())])?;
+            writer.characters(
+                &base64::engine::GeneralPurpose::new(
+                    &base64::alphabet::STANDARD,
+                    base64::engine::general_purpose::PAD,
+                )
+                .encode(data),
+            )?;
+            writer.end()?;
+        }
+        writer.end()
+    }
+}
+
+#[cfg(feature = "json")]
+impl Image {
+    /// Adds the image to the JSON tileset, tile or layer `json`, which store the members of
+    /// their image along with their own. JSON files can't contain embedded images, so images
+    /// without a source are left out.
+    pub(crate) fn write_json(&self, writer: &JsonWriter, json: &mut JsonMap) {
+        let source = match &self.source {
+            Some(source) => source,
+            None => return,
+        };
+        json.extend(json_object(vec![
+            ("image", writer.relative_path(source).into()),
+            ("imagewidth", self.width.into()),
+            ("imageheight", self.height.into()),
+        ]));
+        if let Some(trans) = self.transparent_color {
+            json.insert("transparentcolor".to_owned(), color_to_string(trans).into());
+        }
+    }
+}
+
+fn parse_embedded_data(
+    parser: &mut impl Iterator<Item = XmlEventResult>,
+    attrs: Vec<OwnedAttribute>,
+) -> Result<Vec<u8>> {
+    let encoding = get_attrs!(
+        for v in attrs {
+            Some("encoding") => encoding = v,
+        }
+        encoding
+    );
+    if encoding.as_deref() != Some("base64") {
+        return Err(Error::MalformedAttributes(format!(
+            "Unsupported embedded image encoding: {}",
+            encoding.as_deref().unwrap_or("none")
+        )));
+    }
+
+    let data = read_characters(parser)?.unwrap_or_default();
+    base64::engine::GeneralPurpose::new(
+        &base64::alphabet::STANDARD,
+        base64::engine::general_purpose::PAD,
+    )
+    .decode(data.trim().as_bytes())
+    .map_err(Error::Base64DecodingError)
+}
@@ -1,28 +1,33 @@
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{collections::HashMap, io::Write, path::Path, sync::Arc};
 
 use crate::{
     error::Result,
     layers::{LayerData, LayerTag},
-    properties::{parse_properties, Properties},
+    parse::MapParseContext,
+    properties::{parse_properties, write_properties, Properties},
     util::*,
-    Error, Layer, MapTilesetGid, ResourceCache, ResourceReader, Tileset,
+    write::XmlWriter,
+    Error, Layer, MapTilesetGid, ProjectTypes, ResourceCache, ResourceReader, Tileset,
 };
 
 /// The raw data of a [`GroupLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GroupLayerData {
     layers: Vec<LayerData>,
 }
 
 impl GroupLayerData {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         infinite: bool,
-        map_path: &Path,
+        path_relative_to: &Path,
         tilesets: &[MapTilesetGid],
         for_tileset: Option<Arc<Tileset>>,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        context: &mut MapParseContext,
     ) -> Result<(Self, Properties)> {
         let mut properties = HashMap::new();
         let mut layers = Vec::new();
@@ -33,10 +38,11 @@ impl GroupLayerData {
                     attrs,
                     LayerTag::Tiles,
                     infinite,
-                    map_path,
+                    path_relative_to,
                     tilesets,
                     for_tileset.as_ref().cloned(),reader,
-                    cache
+                    cache,
+                    context
                 )?);
                 Ok(())
             },
@@ -46,10 +52,11 @@ impl GroupLayerData {
                     attrs,
                     LayerTag::Image,
                     infinite,
-                    map_path,
+                    path_relative_to,
                     tilesets,
                     for_tileset.as_ref().cloned(),reader,
-                    cache
+                    cache,
+                    context
                 )?);
                 Ok(())
             },
@@ -59,10 +66,11 @@ impl GroupLayerData {
                     attrs,
                     LayerTag::Objects,
                     infinite,
-                    map_path,
+                    path_relative_to,
                     tilesets,
                     for_tileset.as_ref().cloned(),reader,
-                    cache
+                    cache,
+                    context
                 )?);
                 Ok(())
             },
@@ -72,10 +80,11 @@ impl GroupLayerData {
                     attrs,
                     LayerTag::Group,
                     infinite,
-                    map_path,
+                    path_relative_to,
                     tilesets,
                     for_tileset.as_ref().cloned(),reader,
-                    cache
+                    cache,
+                    context
                 )?);
                 Ok(())
             },
@@ -88,6 +97,91 @@ impl GroupLayerData {
     }
 }
 
+impl GroupLayerData {
+    /// Returns whether all of the layers in this group are empty. See [`LayerData::is_empty`].
+    pub fn is_empty(&self) -> bool {
+        self.layers.iter().all(LayerData::is_empty)
+    }
+
+    pub(crate) fn write_xml(
+        &self,
+        writer: &mut XmlWriter<impl Write>,
+        attrs: Vec<(&str, String)>,
+        properties: &Properties,
+    ) -> Result<()> {
+        writer.start("group", &attrs)?;
+        write_properties(writer, properties)?;
+        for layer in &self.layers {
+            layer.write_xml(writer)?;
+        }
+        writer.end()
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn write_json(
+        &self,
+        writer: &crate::write::JsonWriter,
+        mut json: crate::write::JsonMap,
+        properties: &Properties,
+    ) -> Result<serde_json::Value> {
+        json.insert("type".to_owned(), "group".into());
+        json.insert(
+            "layers".to_owned(),
+            self.layers
+                .iter()
+                .map(|layer| layer.write_json(writer))
+                .collect::<Result<_>>()?,
+        );
+        if let Some(properties) = crate::properties::write_json_properties(properties) {
+            json.insert("properties".to_owned(), properties);
+        }
+        Ok(json.into())
+    }
+
+    /// Returns the highest layer and object IDs used by the layers in this group.
+    pub(crate) fn layer_data(&self) -> &[LayerData] {
+        &self.layers
+    }
+
+    pub(crate) fn max_ids(&self) -> (u32, u32) {
+        self.layers.iter().map(LayerData::max_ids).fold(
+            (0, 0),
+            |(layer, object), (max_layer, max_object)| {
+                (layer.max(max_layer), object.max(max_object))
+            },
+        )
+    }
+
+    pub(crate) fn resolve_project_types(&mut self, types: &ProjectTypes) {
+        for layer in &mut self.layers {
+            layer.resolve_project_types(types);
+        }
+    }
+
+    pub(crate) fn resize_tiles(
+        &mut self,
+        width: u32,
+        height: u32,
+        fill: Option<crate::LayerTileData>,
+    ) {
+        for layer in &mut self.layers {
+            layer.resize_tiles(width, height, fill);
+        }
+    }
+
+    /// Gives the layers in this group the next results of decoding their deferred tile data.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn fill_deferred_tiles(
+        &mut self,
+        decoded: &mut impl Iterator<Item = Result<Vec<Option<crate::LayerTileData>>>>,
+        limits: &crate::ParseLimits,
+    ) -> Result<()> {
+        self.layers
+            .iter_mut()
+            .try_for_each(|layer| layer.fill_deferred_tiles(decoded, limits))
+    }
+}
+
 map_wrapper!(
     #[doc = "A group layer, used to organize the layers of the map in a hierarchy."]
     #[doc = "\nAlso see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#group)."]
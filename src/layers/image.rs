@@ -1,28 +1,45 @@
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, io::Write, path::Path};
+
+use xml::attribute::OwnedAttribute;
 
 use crate::{
     parse_properties,
-    util::{map_wrapper, parse_tag, XmlEventResult},
+    properties::write_properties,
+    util::{get_attrs, map_wrapper, parse_bool_attr, parse_tag, XmlEventResult},
+    write::{bool_to_string, XmlWriter},
     Error, Image, Properties, Result,
 };
 
 /// The raw data of an [`ImageLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ImageLayerData {
     /// The single image this layer contains, if it exists.
     pub image: Option<Image>,
+    /// Whether the image should be repeated horizontally, e.g. for scrolling backgrounds.
+    /// Defaults to `false`.
+    pub repeat_x: bool,
+    /// Whether the image should be repeated vertically, e.g. for scrolling backgrounds.
+    /// Defaults to `false`.
+    pub repeat_y: bool,
 }
 
 impl ImageLayerData {
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
-        map_path: &Path,
+        attrs: Vec<OwnedAttribute>,
+        path_relative_to: &Path,
     ) -> Result<(Self, Properties)> {
+        let (repeat_x, repeat_y) = get_attrs!(
+            for v in attrs {
+                Some("repeatx") => repeat_x ?= parse_bool_attr(v).ok_or(()),
+                Some("repeaty") => repeat_y ?= parse_bool_attr(v).ok_or(()),
+            }
+            (repeat_x, repeat_y)
+        );
         let mut image: Option<Image> = None;
         let mut properties = HashMap::new();
 
-        let path_relative_to = map_path.parent().ok_or(Error::PathIsNotFile)?;
-
         parse_tag!(parser, "imagelayer", {
             "image" => |attrs| {
                 image = Some(Image::new(parser, attrs, path_relative_to)?);
@@ -33,7 +50,58 @@ impl ImageLayerData {
                 Ok(())
             },
         });
-        Ok((ImageLayerData { image }, properties))
+        Ok((
+            ImageLayerData {
+                image,
+                repeat_x: repeat_x.unwrap_or(false),
+                repeat_y: repeat_y.unwrap_or(false),
+            },
+            properties,
+        ))
+    }
+}
+
+impl ImageLayerData {
+    #[cfg(feature = "json")]
+    pub(crate) fn write_json(
+        &self,
+        writer: &crate::write::JsonWriter,
+        mut json: crate::write::JsonMap,
+        properties: &Properties,
+    ) -> serde_json::Value {
+        json.insert("type".to_owned(), "imagelayer".into());
+        json.insert("repeatx".to_owned(), self.repeat_x.into());
+        json.insert("repeaty".to_owned(), self.repeat_y.into());
+        if let Some(properties) = crate::properties::write_json_properties(properties) {
+            json.insert("properties".to_owned(), properties);
+        }
+        match &self.image {
+            Some(image) => image.write_json(writer, &mut json),
+            None => {
+                json.insert("image".to_owned(), "".into());
+            }
+        }
+        json.into()
+    }
+
+    pub(crate) fn write_xml(
+        &self,
+        writer: &mut XmlWriter<impl Write>,
+        mut attrs: Vec<(&str, String)>,
+        properties: &Properties,
+    ) -> Result<()> {
+        if self.repeat_x {
+            attrs.push(("repeatx", bool_to_string(true)));
+        }
+        if self.repeat_y {
+            attrs.push(("repeaty", bool_to_string(true)));
+        }
+        writer.start("imagelayer", &attrs)?;
+        write_properties(writer, properties)?;
+        if let Some(image) = &self.image {
+            image.write_xml(writer)?;
+        }
+        writer.end()
     }
 }
 
@@ -42,3 +110,17 @@ map_wrapper!(
     #[doc = "\nAlso see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#imagelayer)."]
     ImageLayer => ImageLayerData
 );
+
+impl<'map> ImageLayer<'map> {
+    /// Whether a renderer should tile the image horizontally instead of drawing it once.
+    #[inline]
+    pub fn should_tile_x(&self) -> bool {
+        self.repeat_x
+    }
+
+    /// Whether a renderer should tile the image vertically instead of drawing it once.
+    #[inline]
+    pub fn should_tile_y(&self) -> bool {
+        self.repeat_y
+    }
+}
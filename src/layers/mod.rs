@@ -1,12 +1,21 @@
-use std::{path::Path, sync::Arc};
+use std::{collections::HashMap, io::Write, path::Path, sync::Arc};
 
 use xml::attribute::OwnedAttribute;
 
 use crate::{
-    error::Result, properties::Properties, util::*, Color, Map, MapTilesetGid, ResourceCache,
-    ResourceReader, Tileset,
+    error::Result,
+    map::ObjectLocation,
+    parse::MapParseContext,
+    properties::Properties,
+    util::*,
+    write::{bool_to_string, color_to_string, push_unparsed_attributes, XmlWriter},
+    Color, Gid, Map, MapTilesetGid, ProjectTypes, ResourceCache, ResourceReader, Tileset,
 };
 
+#[cfg(feature = "rayon")]
+pub(crate) use tile::DeferredTileData;
+pub(crate) use tile::TileSink;
+
 mod image;
 pub use image::*;
 mod object;
@@ -17,6 +26,7 @@ mod group;
 pub use group::*;
 
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) enum LayerDataType {
     Tiles(TileLayerData),
     Objects(ObjectLayerData),
@@ -24,6 +34,17 @@ pub(crate) enum LayerDataType {
     Group(GroupLayerData),
 }
 
+impl LayerDataType {
+    fn tag(&self) -> LayerTag {
+        match self {
+            LayerDataType::Tiles(_) => LayerTag::Tiles,
+            LayerDataType::Objects(_) => LayerTag::Objects,
+            LayerDataType::Image(_) => LayerTag::Image,
+            LayerDataType::Group(_) => LayerTag::Group,
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub(crate) enum LayerTag {
     Tiles,
@@ -32,8 +53,22 @@ pub(crate) enum LayerTag {
     Group,
 }
 
+impl LayerTag {
+    /// The attributes of the element parsed along with the contents of this kind of layer, rather
+    /// than by [`LayerData::new`].
+    fn attribute_names(self) -> &'static [&'static str] {
+        match self {
+            LayerTag::Tiles => &["width", "height"],
+            LayerTag::Objects => &["color", "draworder"],
+            LayerTag::Image => &["repeatx", "repeaty"],
+            LayerTag::Group => &[],
+        }
+    }
+}
+
 /// The raw data of a [`Layer`]. Does not include a reference to its parent [`Map`](crate::Map).
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayerData {
     /// The layer's name, set arbitrarily by the user.
     pub name: String,
@@ -56,9 +91,28 @@ pub struct LayerData {
     pub properties: Properties,
     /// The layer's type, which is arbitrarily setby the user.
     pub user_type: Option<String>,
+    /// The attributes of the layer's element this crate doesn't parse, by name, such as the ones
+    /// added by versions of Tiled newer than it supports. They are written back out along with the
+    /// layer.
+    ///
+    /// Only kept if its map was loaded with
+    /// [`Loader::with_unparsed_attributes`](crate::Loader::with_unparsed_attributes), and
+    /// [`None`] if there are none.
+    pub unparsed_attributes: Option<Box<HashMap<String, String>>>,
     layer_type: LayerDataType,
 }
 
+impl Default for LayerData {
+    /// Returns an unnamed, visible and opaque object layer with no objects.
+    fn default() -> Self {
+        Self::from_builder(
+            String::new(),
+            Properties::new(),
+            LayerDataType::Objects(ObjectLayerData::default()),
+        )
+    }
+}
+
 impl LayerData {
     /// Get the layer's id. Unique within the parent map. Valid only if greater than 0. Defaults to
     /// 0 if the layer was loaded from a file that didn't have the attribute present.
@@ -67,17 +121,45 @@ impl LayerData {
         self.id
     }
 
+    /// Returns `base` with its alpha multiplied by the layer's opacity, clamped to `0.0..=1.0`.
+    ///
+    /// Tiled applies a layer's opacity to everything drawn in it: tiles, images, and the fill,
+    /// outline and text of objects alike. The opacity of any group layers containing this one
+    /// isn't taken into account.
+    pub fn effective_color(&self, base: Color) -> Color {
+        let opacity = self.opacity.clamp(0.0, 1.0);
+        Color {
+            alpha: (base.alpha as f32 * opacity).round() as u8,
+            ..base
+        }
+    }
+
+    /// Returns whether this layer has no contents, which is the case for tile layers whose tiles
+    /// are all empty, object layers with no objects, image layers with no image and group layers
+    /// whose layers are all empty.
+    pub fn is_empty(&self) -> bool {
+        match &self.layer_type {
+            LayerDataType::Tiles(TileLayerData::Finite(data)) => data.is_empty(),
+            LayerDataType::Tiles(TileLayerData::Infinite(data)) => data.is_empty(),
+            LayerDataType::Objects(data) => data.is_empty(),
+            LayerDataType::Image(data) => data.image.is_none(),
+            LayerDataType::Group(data) => data.is_empty(),
+        }
+    }
+
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
         tag: LayerTag,
         infinite: bool,
-        map_path: &Path,
+        path_relative_to: &Path,
         tilesets: &[MapTilesetGid],
         for_tileset: Option<Arc<Tileset>>,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        context: &mut MapParseContext,
     ) -> Result<Self> {
+        let mut unparsed_attributes;
         let (
             opacity,
             tint_color,
@@ -91,10 +173,10 @@ impl LayerData {
             user_type,
             user_class,
         ) = get_attrs!(
-            for v in attrs {
+            for v in attrs, unparsed(context.options.unparsed_attributes) unparsed_attributes {
                 Some("opacity") => opacity ?= v.parse(),
                 Some("tintcolor") => tint_color ?= v.parse(),
-                Some("visible") => visible ?= v.parse().map(|x:i32| x == 1),
+                Some("visible") => visible ?= parse_bool_attr(v).ok_or(()),
                 Some("offsetx") => offset_x ?= v.parse(),
                 Some("offsety") => offset_y ?= v.parse(),
                 Some("parallaxx") => parallax_x ?= v.parse(),
@@ -106,10 +188,22 @@ impl LayerData {
             }
             (opacity, tint_color, visible, offset_x, offset_y, parallax_x, parallax_y, name, id, user_type, user_class)
         );
+        if let Some(attributes) = &mut unparsed_attributes {
+            attributes.retain(|name, _| !tag.attribute_names().contains(&name.as_str()));
+        }
+        let unparsed_attributes = unparsed_attributes.filter(|attributes| !attributes.is_empty());
 
         let (ty, properties) = match tag {
             LayerTag::Tiles => {
-                let (ty, properties) = TileLayerData::new(parser, attrs, infinite, tilesets)?;
+                let (ty, properties) = TileLayerData::new(
+                    parser,
+                    attrs,
+                    infinite,
+                    tilesets,
+                    id.unwrap_or(0),
+                    name.as_deref().unwrap_or_default(),
+                    context,
+                )?;
                 (LayerDataType::Tiles(ty), properties)
             }
             LayerTag::Objects => {
@@ -118,25 +212,27 @@ impl LayerData {
                     attrs,
                     Some(tilesets),
                     for_tileset,
-                    map_path.parent().ok_or(crate::Error::PathIsNotFile)?,
+                    path_relative_to,
                     reader,
                     cache,
+                    &context.options,
                 )?;
                 (LayerDataType::Objects(ty), properties)
             }
             LayerTag::Image => {
-                let (ty, properties) = ImageLayerData::new(parser, map_path)?;
+                let (ty, properties) = ImageLayerData::new(parser, attrs, path_relative_to)?;
                 (LayerDataType::Image(ty), properties)
             }
             LayerTag::Group => {
                 let (ty, properties) = GroupLayerData::new(
                     parser,
                     infinite,
-                    map_path,
+                    path_relative_to,
                     tilesets,
                     for_tileset,
                     reader,
                     cache,
+                    context,
                 )?;
                 (LayerDataType::Group(ty), properties)
             }
@@ -154,16 +250,485 @@ impl LayerData {
             id: id.unwrap_or(0),
             user_type: user_type.or(user_class),
             properties,
+            unparsed_attributes,
             layer_type: ty,
         })
     }
 }
 
+impl LayerData {
+    pub(crate) fn write_xml(&self, writer: &mut XmlWriter<impl Write>) -> Result<()> {
+        let mut attrs = Vec::new();
+        if self.id != 0 {
+            attrs.push(("id", self.id.to_string()));
+        }
+        attrs.push(("name", self.name.clone()));
+        if let Some(user_type) = &self.user_type {
+            attrs.push(("class", user_type.clone()));
+        }
+        // Attributes with their default values are omitted, like Tiled does.
+        if !self.visible {
+            attrs.push(("visible", bool_to_string(false)));
+        }
+        if self.opacity != 1.0 {
+            attrs.push(("opacity", self.opacity.to_string()));
+        }
+        if let Some(tint_color) = self.tint_color {
+            attrs.push(("tintcolor", color_to_string(tint_color)));
+        }
+        if self.offset_x != 0.0 {
+            attrs.push(("offsetx", self.offset_x.to_string()));
+        }
+        if self.offset_y != 0.0 {
+            attrs.push(("offsety", self.offset_y.to_string()));
+        }
+        if self.parallax_x != 1.0 {
+            attrs.push(("parallaxx", self.parallax_x.to_string()));
+        }
+        if self.parallax_y != 1.0 {
+            attrs.push(("parallaxy", self.parallax_y.to_string()));
+        }
+        push_unparsed_attributes(&mut attrs, self.unparsed_attributes.as_deref());
+        // The attributes specific to the kind of layer are added by its own writer.
+        let tag = self.layer_type.tag();
+        attrs.retain(|(name, _)| !tag.attribute_names().contains(name));
+
+        match &self.layer_type {
+            LayerDataType::Tiles(data) => data.write_xml(writer, attrs, &self.properties),
+            LayerDataType::Objects(data) => data.write_xml(writer, attrs, &self.properties),
+            LayerDataType::Image(data) => data.write_xml(writer, attrs, &self.properties),
+            LayerDataType::Group(data) => data.write_xml(writer, attrs, &self.properties),
+        }
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn write_json(
+        &self,
+        writer: &crate::write::JsonWriter,
+    ) -> Result<serde_json::Value> {
+        use crate::write::f32_to_json;
+
+        let mut json = crate::write::json_object(vec![
+            ("id", self.id.into()),
+            ("name", self.name.clone().into()),
+            ("visible", self.visible.into()),
+            ("opacity", f32_to_json(self.opacity)),
+            ("x", 0.into()),
+            ("y", 0.into()),
+        ]);
+        if let Some(user_type) = &self.user_type {
+            json.insert("class".to_owned(), user_type.clone().into());
+        }
+        // Members with their default values are omitted, like Tiled does.
+        if let Some(tint_color) = self.tint_color {
+            json.insert("tintcolor".to_owned(), color_to_string(tint_color).into());
+        }
+        for (name, value, default) in [
+            ("offsetx", self.offset_x, 0.0),
+            ("offsety", self.offset_y, 0.0),
+            ("parallaxx", self.parallax_x, 1.0),
+            ("parallaxy", self.parallax_y, 1.0),
+        ] {
+            if value != default {
+                json.insert(name.to_owned(), f32_to_json(value));
+            }
+        }
+
+        match &self.layer_type {
+            LayerDataType::Tiles(data) => data.write_json(writer, json, &self.properties),
+            LayerDataType::Objects(data) => Ok(data.write_json(writer, json, &self.properties)),
+            LayerDataType::Image(data) => Ok(data.write_json(writer, json, &self.properties)),
+            LayerDataType::Group(data) => data.write_json(writer, json, &self.properties),
+        }
+    }
+
+    /// Creates a layer with the default attributes Tiled gives new layers.
+    pub(crate) fn from_builder(
+        name: String,
+        properties: Properties,
+        layer_type: LayerDataType,
+    ) -> Self {
+        Self {
+            name,
+            id: 0,
+            visible: true,
+            offset_x: 0.0,
+            offset_y: 0.0,
+            parallax_x: 1.0,
+            parallax_y: 1.0,
+            opacity: 1.0,
+            tint_color: None,
+            properties,
+            user_type: None,
+            unparsed_attributes: None,
+            layer_type,
+        }
+    }
+
+    /// Returns the highest layer and object IDs used by this layer and its children, if any.
+    pub(crate) fn max_ids(&self) -> (u32, u32) {
+        match &self.layer_type {
+            LayerDataType::Objects(data) => (
+                self.id,
+                data.object_data().iter().map(|o| o.id()).max().unwrap_or(0),
+            ),
+            LayerDataType::Group(data) => {
+                let (layer, object) = data.max_ids();
+                (self.id.max(layer), object)
+            }
+            _ => (self.id, 0),
+        }
+    }
+
+    /// Returns the layer at `index` in this layer, if it is a group.
+    pub(crate) fn get_child(&self, index: usize) -> Option<&LayerData> {
+        match &self.layer_type {
+            LayerDataType::Group(data) => data.layer_data().get(index),
+            _ => None,
+        }
+    }
+
+    /// Adds the objects in this layer and the layers in it to `index`, keeping the ones already
+    /// in it. `path` holds the indices leading to this layer from the top of the map.
+    pub(crate) fn index_objects(
+        &self,
+        path: &mut Vec<usize>,
+        index: &mut HashMap<u32, ObjectLocation>,
+    ) {
+        match &self.layer_type {
+            LayerDataType::Objects(data) => {
+                for (object_index, object) in data.object_data().iter().enumerate() {
+                    index.entry(object.id()).or_insert_with(|| ObjectLocation {
+                        layer_path: path.clone(),
+                        object_index,
+                    });
+                }
+            }
+            LayerDataType::Group(data) => {
+                for (layer_index, layer) in data.layer_data().iter().enumerate() {
+                    path.push(layer_index);
+                    layer.index_objects(path, index);
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Gives this layer and the layers in it, if it is a finite tile layer or a group, the next
+    /// results of decoding their deferred tile data, in the same order they were deferred in.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn fill_deferred_tiles(
+        &mut self,
+        decoded: &mut impl Iterator<Item = Result<Vec<Option<LayerTileData>>>>,
+        limits: &crate::ParseLimits,
+    ) -> Result<()> {
+        let name = &self.name;
+        match &mut self.layer_type {
+            LayerDataType::Tiles(TileLayerData::Finite(data)) => {
+                if let Some(tiles) = decoded.next() {
+                    data.set_tiles(
+                        tiles.map_err(|err| crate::Error::LayerDecodingError {
+                            layer_name: name.clone(),
+                            err: Box::new(err),
+                        })?,
+                        limits,
+                    )?;
+                }
+                Ok(())
+            }
+            LayerDataType::Group(data) => data.fill_deferred_tiles(decoded, limits),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fills in the properties of this layer and its contents from the classes in `types`.
+    pub(crate) fn resolve_project_types(&mut self, types: &ProjectTypes) {
+        types.resolve_in_place(self.user_type.as_deref(), &mut self.properties);
+        match &mut self.layer_type {
+            LayerDataType::Objects(data) => data.resolve_project_types(types),
+            LayerDataType::Group(data) => data.resolve_project_types(types),
+            _ => {}
+        }
+    }
+
+    /// Resizes the finite tile layers in this layer, which is either itself or the ones in it if
+    /// this is a group layer.
+    pub(crate) fn resize_tiles(&mut self, width: u32, height: u32, fill: Option<LayerTileData>) {
+        match &mut self.layer_type {
+            LayerDataType::Tiles(TileLayerData::Finite(data)) => data.resize(width, height, fill),
+            LayerDataType::Group(data) => data.resize_tiles(width, height, fill),
+            _ => {}
+        }
+    }
+}
+
+/// A builder for creating finite tile layers from code, to be added to a map with
+/// [`MapBuilder::with_layer`](crate::MapBuilder::with_layer).
+///
+/// Tiles are given as GIDs, which may include flipping flags, and are resolved against the
+/// tilesets passed to [`LayerBuilder::build`]. These are given consecutive ranges of GIDs starting
+/// at 1, the same as in [`MapBuilder`](crate::MapBuilder), so the same tilesets should be added to
+/// the map in the same order. A GID of 0 is an empty tile.
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// use std::sync::Arc;
+///
+/// use tiled::{LayerBuilder, MapBuilder};
+///
+/// let tileset = Arc::new(tiled::parse_tileset_file("assets/tilesheet.tsx")?);
+/// let layer = LayerBuilder::new()
+///     .with_name("ground")
+///     .with_size(4, 4)
+///     .fill(1)
+///     .set_tile(2, 3, 5)
+///     .build(&[tileset.clone()]);
+///
+/// let map = MapBuilder::new()
+///     .with_size(4, 4)
+///     .with_tile_size(32, 32)
+///     .with_tileset(tileset)
+///     .with_layer(layer)
+///     .build()?;
+/// let tiles = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+/// assert_eq!(tiles.get_tile(2, 3).unwrap().id(), tiled::TileId::new(4));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct LayerBuilder {
+    name: String,
+    width: u32,
+    height: u32,
+    /// The GIDs of the tiles, arranged in rows.
+    tiles: Vec<u32>,
+}
+
+impl LayerBuilder {
+    /// Creates a builder for an unnamed layer with no tiles.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the name of the layer.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Sets the width and height of the layer, in tiles. This empties all of its tiles, so it
+    /// should be called before setting any of them.
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self.tiles = vec![0; width as usize * height as usize];
+        self
+    }
+
+    /// Sets every tile of the layer to `gid`.
+    pub fn fill(mut self, gid: u32) -> Self {
+        self.tiles.iter_mut().for_each(|tile| *tile = gid);
+        self
+    }
+
+    /// Sets the tile at the given column and row to `gid`.
+    ///
+    /// ## Panics
+    /// Like indexing, panics in debug builds if the position is outside the layer. In release
+    /// builds, the tile is ignored instead.
+    pub fn set_tile(mut self, col: u32, row: u32, gid: u32) -> Self {
+        let in_bounds = col < self.width && row < self.height;
+        debug_assert!(
+            in_bounds,
+            "tile ({}, {}) is outside of a {}x{} layer",
+            col, row, self.width, self.height
+        );
+        if in_bounds {
+            self.tiles[col as usize + row as usize * self.width as usize] = gid;
+        }
+        self
+    }
+
+    /// Creates the layer, resolving its GIDs against `tilesets`. Tiles with a GID past the last
+    /// tileset are resolved to the last one, as they are when loading a map.
+    pub fn build(self, tilesets: &[Arc<Tileset>]) -> LayerData {
+        let mut next_gid = 1;
+        let tilesets: Vec<MapTilesetGid> = tilesets
+            .iter()
+            .map(|tileset| {
+                let first_gid = Gid(next_gid);
+                next_gid += tileset.computed_tile_count().unwrap_or(0);
+                MapTilesetGid {
+                    first_gid,
+                    tileset: tileset.clone(),
+                }
+            })
+            .collect();
+        let tiles = self
+            .tiles
+            .into_iter()
+            .map(|bits| LayerTileData::from_bits(bits, &tilesets))
+            .collect();
+
+        LayerData::from_builder(
+            self.name,
+            Properties::new(),
+            LayerDataType::Tiles(TileLayerData::Finite(FiniteTileLayerData::from_tiles(
+                self.width,
+                self.height,
+                tiles,
+            ))),
+        )
+    }
+}
+
 map_wrapper!(
     #[doc = "A generic map layer, accessed via [`Map::layers()`]."]
     Layer => LayerData
 );
 
+/// A map layer borrowed mutably so that its tiles can be changed, accessed via
+/// [`Map::get_layer_mut()`].
+///
+/// Dereferences to the layer's [`LayerData`], so that its other attributes can be changed too.
+#[derive(Debug)]
+pub struct LayerMut<'map> {
+    data: &'map mut LayerData,
+    gid_lookup: &'map [(Gid, usize)],
+}
+
+impl<'map> LayerMut<'map> {
+    pub(crate) fn new(data: &'map mut LayerData, gid_lookup: &'map [(Gid, usize)]) -> Self {
+        Self { data, gid_lookup }
+    }
+
+    /// Sets the tile at `x` and `y`, or empties it if `tile` is [`None`].
+    ///
+    /// In infinite layers, the chunk containing the position is created if needed. Calling this
+    /// on a layer that isn't a tile layer, on one loaded with
+    /// [`Loader::with_raw_tile_data`](crate::Loader::with_raw_tile_data), with a position outside
+    /// of a finite layer or with a tileset index the map doesn't have panics in debug builds, and
+    /// does nothing otherwise.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use tiled::{LayerTileData, TileId};
+    ///
+    /// let mut map = tiled::Loader::new().load_tmx_map("assets/tiled_base64.tmx")?;
+    /// let tile = LayerTileData::new(0, TileId::new(12));
+    /// map.get_layer_mut(0).unwrap().set_tile(0, 0, Some(tile));
+    ///
+    /// let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    /// assert_eq!(layer.get_tile(0, 0).unwrap().id(), TileId::new(12));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_tile(&mut self, x: i32, y: i32, tile: Option<LayerTileData>) {
+        if let Some(tile) = tile {
+            if tile.tileset_index() >= self.gid_lookup.len() {
+                debug_assert!(
+                    false,
+                    "tileset index {} not in the map",
+                    tile.tileset_index()
+                );
+                return;
+            }
+        }
+        match &mut self.data.layer_type {
+            LayerDataType::Tiles(data) if data.raw_tile_data().is_some() => {
+                debug_assert!(false, "tile set in a layer loaded with raw tile data")
+            }
+            LayerDataType::Tiles(data) => {
+                let in_bounds = data.set_tile(x, y, tile);
+                debug_assert!(in_bounds, "tile ({}, {}) out of the layer's bounds", x, y);
+            }
+            _ => debug_assert!(false, "tile set in a layer that isn't a tile layer"),
+        }
+    }
+
+    /// Sets the tile at `x` and `y` from its global tile ID plus flipping flags, as stored in map
+    /// files. A GID of 0 empties the tile.
+    ///
+    /// See [`LayerMut::set_tile`] for what happens with invalid positions, which also applies to
+    /// GIDs that don't belong to any of the map's tilesets.
+    pub fn set_tile_gid(&mut self, x: i32, y: i32, gid: u32) {
+        if let Some(tile) = resolve_tile_gid(self.gid_lookup, gid) {
+            self.set_tile(x, y, tile);
+        }
+    }
+
+    /// Resizes the layer to `new_width` by `new_height` tiles, keeping the tiles still within it.
+    /// New tiles are set from `fill_gid`, a global tile ID plus flipping flags like in
+    /// [`LayerMut::set_tile_gid`], so a GID of 0 leaves them empty. Shrinking a layer never
+    /// allocates.
+    ///
+    /// Group layers have all the finite tile layers in them resized. Other layers, including
+    /// infinite tile layers, have no size and are left as they are. So are layers that don't store
+    /// their tiles, namely those loaded with
+    /// [`Loader::with_raw_tile_data`](crate::Loader::with_raw_tile_data) or
+    /// [`Loader::load_tmx_map_streaming`](crate::Loader::load_tmx_map_streaming), which keep the
+    /// size of the data they were loaded from. A GID that doesn't belong to any of the map's
+    /// tilesets panics in debug builds, and does nothing otherwise.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut map = tiled::Loader::new().load_tmx_map("assets/tiled_base64.tmx")?;
+    /// map.get_layer_mut(0).unwrap().resize(120, 100, 1);
+    ///
+    /// let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    /// assert_eq!(layer.width(), Some(120));
+    /// assert_eq!(layer.get_tile(110, 0).unwrap().id(), tiled::TileId::new(0));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resize(&mut self, new_width: u32, new_height: u32, fill_gid: u32) {
+        if let Some(fill) = resolve_tile_gid(self.gid_lookup, fill_gid) {
+            self.data.resize_tiles(new_width, new_height, fill);
+        }
+    }
+}
+
+/// Resolves a global tile ID plus flipping flags into the tile it stands for, which is [`None`]
+/// for empty tiles. GIDs that don't belong to any of the map's tilesets panic in debug builds, and
+/// return [`None`] otherwise.
+pub(crate) fn resolve_tile_gid(
+    gid_lookup: &[(Gid, usize)],
+    gid: u32,
+) -> Option<Option<LayerTileData>> {
+    match Gid::from_bits(gid) {
+        Gid::EMPTY => Some(None),
+        tile_gid => match crate::map::resolve_gid(gid_lookup, tile_gid) {
+            Some((tileset_index, id)) => Some(Some(
+                LayerTileData::new(tileset_index, id).with_flip_bits(gid),
+            )),
+            None => {
+                debug_assert!(false, "GID {} not in any of the map's tilesets", tile_gid.0);
+                None
+            }
+        },
+    }
+}
+
+impl<'map> std::ops::Deref for LayerMut<'map> {
+    type Target = LayerData;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.data
+    }
+}
+
+impl<'map> std::ops::DerefMut for LayerMut<'map> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.data
+    }
+}
+
 impl<'map> Layer<'map> {
     /// Get the layer's type.
     #[inline]
@@ -241,7 +806,7 @@ impl<'map> Layer<'map> {
 }
 
 /// Represents some kind of map layer.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LayerType<'map> {
     /// A tile layer; Also see [`TileLayer`].
     Tiles(TileLayer<'map>),
@@ -262,4 +827,28 @@ impl<'map> LayerType<'map> {
             LayerDataType::Group(data) => Self::Group(GroupLayer::new(map, data)),
         }
     }
+
+    /// Returns which kind of layer this is.
+    pub fn kind(&self) -> LayerKind {
+        match self {
+            LayerType::Tiles(_) => LayerKind::Tiles,
+            LayerType::Objects(_) => LayerKind::Objects,
+            LayerType::Image(_) => LayerKind::Image,
+            LayerType::Group(_) => LayerKind::Group,
+        }
+    }
+}
+
+/// The kinds of map layer, matching the variants of [`LayerType`] without their contents, such as
+/// for picking which layers to get with [`Map::layers_of_type`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LayerKind {
+    /// A tile layer.
+    Tiles,
+    /// An object layer (also called object group).
+    Objects,
+    /// An image layer.
+    Image,
+    /// A group layer.
+    Group,
 }
@@ -1,25 +1,71 @@
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{collections::HashMap, fmt, io::Write, path::Path, str::FromStr, sync::Arc};
 
 use xml::attribute::OwnedAttribute;
 
+#[cfg(feature = "json")]
+use crate::parse::json::JsonObject;
 use crate::{
+    parse::ParseOptions,
     parse_properties,
+    properties::write_properties,
     util::{get_attrs, map_wrapper, parse_tag, XmlEventResult},
-    Color, Error, MapTilesetGid, Object, ObjectData, Properties, ResourceCache, ResourceReader,
-    Result, Tileset,
+    write::{color_to_string, XmlWriter},
+    Color, Error, LayerData, MapTilesetGid, Object, ObjectData, ObjectShape, ProjectTypes,
+    Properties, PropertyValue, ResourceCache, ResourceReader, Result, Tileset,
 };
 
+use super::LayerDataType;
+
+/// The order the objects of an object layer are drawn in.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum DrawOrder {
+    /// Objects are drawn in ascending order of their Y coordinate, as returned by
+    /// [`ObjectLayer::iter_objects_sorted_by_y`].
+    #[default]
+    TopDown,
+    /// Objects are drawn in the order they appear in the layer.
+    Index,
+}
+
+impl FromStr for DrawOrder {
+    type Err = ();
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "topdown" => Ok(DrawOrder::TopDown),
+            "index" => Ok(DrawOrder::Index),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for DrawOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DrawOrder::TopDown => write!(f, "topdown"),
+            DrawOrder::Index => write!(f, "index"),
+        }
+    }
+}
+
 /// Raw data referring to a map object layer or tile collision data.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ObjectLayerData {
     objects: Vec<ObjectData>,
     /// The color used in the editor to display objects in this layer.
-    pub colour: Option<Color>,
+    #[cfg_attr(feature = "serde", serde(alias = "colour"))]
+    pub color: Option<Color>,
+    /// The order the objects in this layer are drawn in.
+    pub draw_order: DrawOrder,
 }
 
 impl ObjectLayerData {
     /// If it is known that there are no objects with tile images in it (i.e. collision data)
     /// then we can pass in [`None`] as the tilesets
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
@@ -29,18 +75,20 @@ impl ObjectLayerData {
         path_relative_to: &Path,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        options: &ParseOptions,
     ) -> Result<(ObjectLayerData, Properties)> {
-        let c = get_attrs!(
+        let (c, draw_order) = get_attrs!(
             for v in attrs {
                 Some("color") => color ?= v.parse(),
+                Some("draworder") => draw_order ?= v.parse::<DrawOrder>(),
             }
-            color
+            (color, draw_order)
         );
         let mut objects = Vec::new();
         let mut properties = HashMap::new();
         parse_tag!(parser, "objectgroup", {
             "object" => |attrs| {
-                objects.push(ObjectData::new(parser, attrs, tilesets, for_tileset.as_ref().cloned(), path_relative_to, reader, cache)?);
+                objects.push(ObjectData::new(parser, attrs, tilesets, for_tileset.as_ref().cloned(), path_relative_to, reader, cache, options)?);
                 Ok(())
             },
             "properties" => |_| {
@@ -48,7 +96,62 @@ impl ObjectLayerData {
                 Ok(())
             },
         });
-        Ok((ObjectLayerData { objects, colour: c }, properties))
+        Ok((
+            ObjectLayerData {
+                objects,
+                color: c,
+                draw_order: draw_order.unwrap_or_default(),
+            },
+            properties,
+        ))
+    }
+
+    /// Like [`Self::new`], but reading from a JSON object layer.
+    #[cfg(feature = "json")]
+    pub(crate) fn parse_json(
+        json: &JsonObject,
+        tilesets: Option<&[MapTilesetGid]>,
+        for_tileset: Option<Arc<Tileset>>,
+        path_relative_to: &Path,
+        reader: &mut impl ResourceReader,
+        cache: &mut impl ResourceCache,
+        options: &ParseOptions,
+    ) -> Result<(ObjectLayerData, Properties)> {
+        let objects = json
+            .objects("objects")?
+            .iter()
+            .map(|object| {
+                ObjectData::parse_json(
+                    object,
+                    tilesets,
+                    for_tileset.as_ref().cloned(),
+                    path_relative_to,
+                    reader,
+                    cache,
+                    options,
+                )
+            })
+            .collect::<Result<_>>()?;
+        let properties = crate::properties::parse_json_properties(json)?;
+        Ok((
+            ObjectLayerData {
+                objects,
+                color: json.color("color")?,
+                draw_order: match json.str("draworder")? {
+                    Some(draw_order) => draw_order.parse().map_err(|_| {
+                        Error::MalformedAttributes("Error parsing attribute 'draworder'".to_owned())
+                    })?,
+                    None => DrawOrder::default(),
+                },
+            },
+            properties,
+        ))
+    }
+
+    /// The color used in the editor to display objects in this layer.
+    #[deprecated(since = "0.12.0", note = "renamed to the `color` field")]
+    pub fn colour(&self) -> Option<Color> {
+        self.color
     }
 
     /// Returns the data belonging to the objects contained within the layer, in the order they were
@@ -57,6 +160,169 @@ impl ObjectLayerData {
     pub fn object_data(&self) -> &[ObjectData] {
         self.objects.as_ref()
     }
+
+    /// Returns whether this layer contains no objects.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// Returns the layer as a JSON object layer, adding its members to the ones in `json`.
+    #[cfg(feature = "json")]
+    pub(crate) fn write_json(
+        &self,
+        writer: &crate::write::JsonWriter,
+        mut json: crate::write::JsonMap,
+        properties: &Properties,
+    ) -> serde_json::Value {
+        json.insert("type".to_owned(), "objectgroup".into());
+        json.insert("draworder".to_owned(), self.draw_order.to_string().into());
+        json.insert(
+            "objects".to_owned(),
+            self.objects
+                .iter()
+                .map(|object| object.write_json(writer))
+                .collect(),
+        );
+        if let Some(color) = self.color {
+            json.insert("color".to_owned(), color_to_string(color).into());
+        }
+        if let Some(properties) = crate::properties::write_json_properties(properties) {
+            json.insert("properties".to_owned(), properties);
+        }
+        json.into()
+    }
+
+    pub(crate) fn write_xml(
+        &self,
+        writer: &mut XmlWriter<impl Write>,
+        mut attrs: Vec<(&str, String)>,
+        properties: &Properties,
+    ) -> Result<()> {
+        if let Some(color) = self.color {
+            attrs.push(("color", color_to_string(color)));
+        }
+        if self.draw_order != DrawOrder::TopDown {
+            attrs.push(("draworder", self.draw_order.to_string()));
+        }
+        writer.start("objectgroup", &attrs)?;
+        write_properties(writer, properties)?;
+        for object in &self.objects {
+            object.write_xml(writer)?;
+        }
+        writer.end()
+    }
+
+    /// Fills in the properties of the objects in this layer from the classes in `types`.
+    pub(crate) fn resolve_project_types(&mut self, types: &ProjectTypes) {
+        for object in &mut self.objects {
+            types.resolve_in_place(Some(&object.user_type), &mut object.properties);
+        }
+    }
+}
+
+/// A builder for creating object layers from code, to be added to a map with
+/// [`MapBuilder::with_layer`](crate::MapBuilder::with_layer).
+///
+/// Objects are given consecutive IDs in the order they are added when the layer is built,
+/// starting at 1 unless set otherwise with [`ObjectGroupBuilder::with_next_object_id`].
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// use tiled::{DrawOrder, ObjectGroupBuilder, PropertyValue};
+///
+/// let map = tiled::parse_file("assets/tiled_base64.tmx")?;
+/// let spawn = map
+///     .layers()
+///     .find_map(|layer| layer.as_object_layer())
+///     .and_then(|layer| layer.get_object(0))
+///     .unwrap();
+///
+/// let layer = ObjectGroupBuilder::new()
+///     .with_name("spawns")
+///     .with_draw_order(DrawOrder::Index)
+///     .add_object((*spawn).clone())
+///     .add_property("difficulty", PropertyValue::IntValue(3))
+///     .build();
+/// assert_eq!(layer.name, "spawns");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct ObjectGroupBuilder {
+    name: String,
+    objects: Vec<ObjectData>,
+    draw_order: DrawOrder,
+    properties: Properties,
+    next_object_id: u32,
+}
+
+impl Default for ObjectGroupBuilder {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            objects: Vec::new(),
+            draw_order: DrawOrder::default(),
+            properties: Properties::new(),
+            next_object_id: 1,
+        }
+    }
+}
+
+impl ObjectGroupBuilder {
+    /// Creates a builder for an unnamed layer with no objects.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the name of the layer.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Sets the order the layer's objects are drawn in. Defaults to [`DrawOrder::TopDown`].
+    pub fn with_draw_order(mut self, draw_order: DrawOrder) -> Self {
+        self.draw_order = draw_order;
+        self
+    }
+
+    /// Sets the ID given to the first object in the layer, which is 1 by default. Useful for
+    /// keeping IDs unique across several object layers in the same map.
+    pub fn with_next_object_id(mut self, next_object_id: u32) -> Self {
+        self.next_object_id = next_object_id;
+        self
+    }
+
+    /// Adds an object after the ones added so far. Its ID is replaced when the layer is built.
+    pub fn add_object(mut self, object: ObjectData) -> Self {
+        self.objects.push(object);
+        self
+    }
+
+    /// Sets a custom property of the layer, replacing any previous value with the same name.
+    pub fn add_property(mut self, name: &str, value: PropertyValue) -> Self {
+        self.properties.insert(name.to_owned(), value);
+        self
+    }
+
+    /// Creates the layer.
+    pub fn build(self) -> LayerData {
+        let mut objects = self.objects;
+        for (object, id) in objects.iter_mut().zip(self.next_object_id..) {
+            object.set_id(id);
+        }
+        LayerData::from_builder(
+            self.name,
+            self.properties,
+            LayerDataType::Objects(ObjectLayerData {
+                objects,
+                color: None,
+                draw_order: self.draw_order,
+            }),
+        )
+    }
 }
 
 map_wrapper!(
@@ -106,4 +372,95 @@ impl<'map> ObjectLayer<'map> {
             .iter()
             .map(move |object| Object::new(map, object))
     }
+
+    /// Returns the first object in this layer with the given name in [draw
+    /// order](ObjectLayerData::draw_order), if any. That is the one with the lowest Y coordinate
+    /// for `topdown` layers, as in [`ObjectLayer::iter_objects_sorted_by_y`], and the first one
+    /// declared for `index` layers.
+    ///
+    /// Tiled doesn't require names to be unique; use [`ObjectLayer::get_objects_by_name`] to get
+    /// every object with the name.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::Loader::new().load_tmx_map("assets/tiled_object_names.tmx")?;
+    /// let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    ///
+    /// let spawn = layer.get_object_by_name("player_spawn").unwrap();
+    /// assert_eq!((spawn.x, spawn.y), (32.0, 64.0));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_object_by_name(&self, name: &str) -> Option<Object<'map>> {
+        let mut objects = self.objects().filter(|object| object.name == name);
+        match self.draw_order {
+            DrawOrder::TopDown => {
+                objects.min_by(|a, b| draw_order_y(a).total_cmp(&draw_order_y(b)))
+            }
+            DrawOrder::Index => objects.next(),
+        }
+    }
+
+    /// Returns an iterator over the objects in this layer with the given name, in [draw
+    /// order](ObjectLayerData::draw_order) like [`ObjectLayer::get_object_by_name`].
+    pub fn get_objects_by_name<'a>(&self, name: &'a str) -> impl Iterator<Item = Object<'map>> + 'a
+    where
+        'map: 'a,
+    {
+        self.objects_in_draw_order()
+            .filter(move |object| object.name == name)
+    }
+
+    /// Returns the object in this layer with the given ID, if any.
+    pub fn get_object_by_id(&self, id: u32) -> Option<Object<'map>> {
+        self.objects().find(|object| object.id() == id)
+    }
+
+    /// Returns an iterator over the objects in this layer whose [type](ObjectData::user_type) is
+    /// `user_type`, in [draw order](ObjectLayerData::draw_order) like
+    /// [`ObjectLayer::get_object_by_name`].
+    pub fn get_objects_by_type<'a>(
+        &self,
+        user_type: &'a str,
+    ) -> impl Iterator<Item = Object<'map>> + 'a
+    where
+        'map: 'a,
+    {
+        self.objects_in_draw_order()
+            .filter(move |object| object.user_type == user_type)
+    }
+
+    /// Returns an iterator over the objects present in this layer in ascending order of their Y
+    /// coordinate, which is the order they should be drawn in if the layer uses the `topdown` draw
+    /// order.
+    ///
+    /// Objects with the same Y coordinate are kept in the order they were declared in the TMX
+    /// file. Polygons and polylines are ordered by the topmost of their points.
+    pub fn iter_objects_sorted_by_y(&self) -> impl ExactSizeIterator<Item = Object<'map>> + 'map {
+        let mut objects: Vec<_> = self.objects().collect();
+        objects.sort_by(|a, b| draw_order_y(a).total_cmp(&draw_order_y(b)));
+        objects.into_iter()
+    }
+
+    /// Returns the objects of this layer in the order given by its draw order.
+    fn objects_in_draw_order(&self) -> std::vec::IntoIter<Object<'map>> {
+        let mut objects: Vec<_> = self.objects().collect();
+        if self.draw_order == DrawOrder::TopDown {
+            objects.sort_by(|a, b| draw_order_y(a).total_cmp(&draw_order_y(b)));
+        }
+        objects.into_iter()
+    }
+}
+
+/// The Y coordinate used to sort an object in `topdown` draw order.
+fn draw_order_y(object: &ObjectData) -> f32 {
+    match &object.shape {
+        ObjectShape::Polyline { points } | ObjectShape::Polygon { points }
+            if !points.is_empty() =>
+        {
+            object.y + points.iter().map(|(_, y)| *y).fold(f32::INFINITY, f32::min)
+        }
+        _ => object.y,
+    }
 }
@@ -1,19 +1,26 @@
+use std::io::Write;
+
 use xml::attribute::OwnedAttribute;
 
 use crate::{
+    parse::MapParseContext,
     util::{get_attrs, map_wrapper, XmlEventResult},
-    LayerTile, LayerTileData, MapTilesetGid, Result,
+    write::XmlWriter,
+    Gid, LayerTile, LayerTileData, Map, MapTilesetGid, ParseLimits, RawTileData, Result,
+    StreamedTile,
 };
 
-use super::util::parse_data_line;
+use super::util::{read_raw_data, stream_data_line, DeferredTileData};
 
 /// The raw data of a [`FiniteTileLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
 #[derive(PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FiniteTileLayerData {
     width: u32,
     height: u32,
     /// The tiles are arranged in rows.
     tiles: Vec<Option<LayerTileData>>,
+    raw: Option<RawTileData>,
 }
 
 impl std::fmt::Debug for FiniteTileLayerData {
@@ -38,12 +45,111 @@ impl FiniteTileLayerData {
         self.height
     }
 
+    /// Returns whether all of the tiles in this layer are empty.
+    pub fn is_empty(&self) -> bool {
+        self.tiles.iter().all(Option::is_none)
+    }
+
+    /// Get the raw data of all the tiles in the layer as a single slice, with empty tiles set to
+    /// [`None`].
+    ///
+    /// Tiles are arranged in rows, so the tile at `(x, y)` is at index `x + y * width`. The slice
+    /// is empty if the map was loaded with
+    /// [`Loader::load_tmx_map_streaming`](crate::Loader::load_tmx_map_streaming).
+    #[inline]
+    pub fn tiles_flat(&self) -> &[Option<LayerTileData>] {
+        &self.tiles
+    }
+
+    /// Returns the tile data of the layer as it is stored in the file, if the map was loaded with
+    /// [`Loader::with_raw_tile_data`](crate::Loader::with_raw_tile_data), in which case the
+    /// layer holds no tiles.
+    #[inline]
+    pub fn raw_tile_data(&self) -> Option<&RawTileData> {
+        self.raw.as_ref()
+    }
+
+    /// Get an iterator over the rows of the layer, each being a slice of [`Self::width`] tiles.
+    #[inline]
+    pub fn rows(&self) -> std::slice::Chunks<'_, Option<LayerTileData>> {
+        self.tiles.chunks(self.width.max(1) as usize)
+    }
+
+    pub(crate) fn from_tiles(width: u32, height: u32, tiles: Vec<Option<LayerTileData>>) -> Self {
+        Self {
+            width,
+            height,
+            tiles,
+            raw: None,
+        }
+    }
+
+    /// Sets the tile at the position given, returning whether it is within the layer.
+    pub(crate) fn set_tile(&mut self, x: i32, y: i32, tile: Option<LayerTileData>) -> bool {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return false;
+        }
+        match self
+            .tiles
+            .get_mut(x as usize + y as usize * self.width as usize)
+        {
+            Some(slot) => {
+                *slot = tile;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Resizes the layer, keeping the tiles still within it and setting new ones to `fill`.
+    /// Layers that don't store their tiles, because they hold raw tile data or were loaded while
+    /// streaming their tiles, are left as they are so that their size keeps matching their data.
+    pub(crate) fn resize(&mut self, width: u32, height: u32, fill: Option<LayerTileData>) {
+        let (old_width, new_width) = (self.width as usize, width as usize);
+        if self.raw.is_some() || self.tiles.len() != old_width * self.height as usize {
+            return;
+        }
+        let kept_rows = self.height.min(height) as usize;
+        if new_width <= old_width {
+            for row in 1..kept_rows {
+                let start = row * old_width;
+                self.tiles
+                    .copy_within(start..start + new_width, row * new_width);
+            }
+            self.tiles.truncate(kept_rows * new_width);
+        } else {
+            let mut tiles = Vec::with_capacity(new_width * height as usize);
+            for row in self.tiles.chunks(old_width.max(1)).take(kept_rows) {
+                tiles.extend_from_slice(row);
+                tiles.resize(tiles.len() + new_width - old_width, fill);
+            }
+            self.tiles = tiles;
+        }
+        self.tiles.resize(new_width * height as usize, fill);
+        self.width = width;
+        self.height = height;
+    }
+
+    #[cfg(feature = "rayon")]
+    pub(crate) fn set_tiles(
+        &mut self,
+        tiles: Vec<Option<LayerTileData>>,
+        limits: &ParseLimits,
+    ) -> Result<()> {
+        self.tiles = fill_if_empty(tiles, self.width, self.height, limits)?;
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
         width: u32,
         height: u32,
         tilesets: &[MapTilesetGid],
+        layer_id: u32,
+        layer_name: &str,
+        context: &mut MapParseContext,
     ) -> Result<Self> {
         let (e, c) = get_attrs!(
             for v in attrs {
@@ -53,15 +159,109 @@ impl FiniteTileLayerData {
             (encoding, compression)
         );
 
-        let tiles = parse_data_line(e, c, parser, tilesets)?;
+        let max_size = context.options.limits.max_tile_data_size;
+        if context.raw_tile_data {
+            let data = read_raw_data(&e, &c, parser, max_size)?;
+            return Ok(Self {
+                width,
+                height,
+                tiles: Vec::new(),
+                raw: Some(RawTileData {
+                    encoding: e.unwrap_or_default(),
+                    compression: c,
+                    data,
+                    x: 0,
+                    y: 0,
+                    width,
+                    height,
+                }),
+            });
+        }
+        let tile_count = width as usize * height as usize;
+        let tiles = match &mut context.tile_sink {
+            Some(sink) => {
+                stream_data_line(e, c, parser, width, height, max_size, |x, y, gid| {
+                    if Gid::from_bits(gid) != Gid::EMPTY {
+                        sink(StreamedTile {
+                            layer_id,
+                            x: x as i32,
+                            y: y as i32,
+                            gid,
+                        });
+                    }
+                })?;
+                Vec::new()
+            }
+            #[cfg(not(feature = "rayon"))]
+            None => {
+                let tiles = DeferredTileData::read(e, c, parser, tile_count, max_size)?
+                    .decode(tilesets)
+                    .map_err(|err| crate::Error::LayerDecodingError {
+                        layer_name: layer_name.to_owned(),
+                        err: Box::new(err),
+                    })?;
+                fill_if_empty(tiles, width, height, &context.options.limits)?
+            }
+            // The data is decoded along with that of the map's other layers once the whole map
+            // has been read, so the tilesets aren't needed yet.
+            #[cfg(feature = "rayon")]
+            None => {
+                let _ = (tilesets, layer_name);
+                context
+                    .deferred_tile_data
+                    .push(DeferredTileData::read(e, c, parser, tile_count, max_size)?);
+                Vec::new()
+            }
+        };
 
         Ok(Self {
             width,
             height,
             tiles,
+            raw: None,
         })
     }
 
+    pub(crate) fn write_xml(&self, writer: &mut XmlWriter<impl Write>) -> Result<()> {
+        if let Some(raw) = &self.raw {
+            writer.start("data", &raw.attributes())?;
+            writer.characters(&raw.to_text())?;
+            return writer.end();
+        }
+        let bits: Vec<u32> = (0..self.width as usize * self.height as usize)
+            .map(|index| {
+                LayerTileData::to_bits(
+                    self.tiles.get(index).and_then(Option::as_ref),
+                    &writer.first_gids,
+                )
+            })
+            .collect();
+        let attrs = writer.encoding.attributes();
+        writer.tile_data("data", &attrs, &bits, self.width as usize)
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn write_json(
+        &self,
+        writer: &crate::write::JsonWriter,
+        json: &mut crate::write::JsonMap,
+    ) -> Result<()> {
+        if let Some(raw) = &self.raw {
+            json.insert("data".to_owned(), raw.to_json()?);
+            return Ok(());
+        }
+        let bits: Vec<u32> = (0..self.width as usize * self.height as usize)
+            .map(|index| {
+                LayerTileData::to_bits(
+                    self.tiles.get(index).and_then(Option::as_ref),
+                    &writer.first_gids,
+                )
+            })
+            .collect();
+        json.insert("data".to_owned(), writer.tile_data(&bits)?);
+        Ok(())
+    }
+
     /// Obtains the tile data present at the position given.
     ///
     /// If the position given is invalid or the position is empty, this function will return [`None`].
@@ -69,13 +269,31 @@ impl FiniteTileLayerData {
     /// If you want to get a [`Tile`](`crate::Tile`) instead, use [`FiniteTileLayer::get_tile()`].
     pub fn get_tile_data(&self, x: i32, y: i32) -> Option<&LayerTileData> {
         if x < self.width as i32 && y < self.height as i32 && x >= 0 && y >= 0 {
-            self.tiles[x as usize + y as usize * self.width as usize].as_ref()
+            self.tiles
+                .get(x as usize + y as usize * self.width as usize)
+                .and_then(Option::as_ref)
         } else {
             None
         }
     }
 }
 
+/// Layers that were never painted on may be saved with empty tile data, which is turned into a
+/// layer of empty tiles so that it can be indexed like any other. The size of the layer is checked
+/// against `limits` first, as it comes straight from the file.
+fn fill_if_empty(
+    tiles: Vec<Option<LayerTileData>>,
+    width: u32,
+    height: u32,
+    limits: &ParseLimits,
+) -> Result<Vec<Option<LayerTileData>>> {
+    if !tiles.is_empty() {
+        return Ok(tiles);
+    }
+    limits.check_tile_count("A tile layer", width, height)?;
+    Ok(vec![None; width as usize * height as usize])
+}
+
 map_wrapper!(
     #[doc = "A [`TileLayer`](super::TileLayer) with a defined bound (width and height)."]
     FiniteTileLayer => FiniteTileLayerData
@@ -91,3 +309,103 @@ impl<'map> FiniteTileLayer<'map> {
             .map(|data| LayerTile::new(self.map(), data))
     }
 }
+
+impl<'map> FiniteTileLayer<'map> {
+    /// Returns an iterator over the non-empty tiles of this layer along with their positions, in
+    /// row-major order.
+    ///
+    /// The iterator borrows from the map, so it can be stored for as long as the map is alive.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::{Loader, TileLayer};
+    /// # fn main() -> tiled::Result<()> {
+    /// # let map = Loader::new().load_tmx_map("assets/tiled_csv.tmx")?;
+    /// # let layer = match map.get_layer(0).unwrap().as_tile_layer().unwrap() {
+    /// #     TileLayer::Finite(layer) => layer,
+    /// #     TileLayer::Infinite(_) => panic!("Layer #0 is not finite"),
+    /// # };
+    /// for (x, y, tile) in layer.tiles() {
+    ///     assert_eq!(layer.get_tile(x, y).unwrap(), tile);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tiles(&self) -> FiniteTiles<'map> {
+        FiniteTiles {
+            map: self.map,
+            width: self.data.width,
+            tiles: self.data.tiles.iter().enumerate(),
+        }
+    }
+
+    /// Returns an iterator over every position of this layer in row-major order, along with the
+    /// tile present there, if any.
+    pub fn all_tiles(&self) -> FiniteAllTiles<'map> {
+        FiniteAllTiles {
+            map: self.map,
+            width: self.data.width,
+            tiles: self.data.tiles.iter().enumerate(),
+        }
+    }
+}
+
+/// An iterator over the non-empty tiles of a [`FiniteTileLayer`] and their positions.
+///
+/// Created by [`FiniteTileLayer::tiles()`].
+#[derive(Clone, Debug)]
+pub struct FiniteTiles<'map> {
+    map: &'map Map,
+    width: u32,
+    tiles: std::iter::Enumerate<std::slice::Iter<'map, Option<LayerTileData>>>,
+}
+
+impl<'map> Iterator for FiniteTiles<'map> {
+    type Item = (i32, i32, LayerTile<'map>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let map = self.map;
+        let width = self.width as usize;
+        self.tiles.find_map(|(index, tile)| {
+            tile.as_ref().map(|data| {
+                (
+                    (index % width) as i32,
+                    (index / width) as i32,
+                    LayerTile::new(map, data),
+                )
+            })
+        })
+    }
+}
+
+/// An iterator over all the positions of a [`FiniteTileLayer`] and the tiles present there.
+///
+/// Created by [`FiniteTileLayer::all_tiles()`].
+#[derive(Clone, Debug)]
+pub struct FiniteAllTiles<'map> {
+    map: &'map Map,
+    width: u32,
+    tiles: std::iter::Enumerate<std::slice::Iter<'map, Option<LayerTileData>>>,
+}
+
+impl<'map> Iterator for FiniteAllTiles<'map> {
+    type Item = (i32, i32, Option<LayerTile<'map>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let map = self.map;
+        let width = self.width as usize;
+        self.tiles.next().map(|(index, tile)| {
+            (
+                (index % width) as i32,
+                (index / width) as i32,
+                tile.as_ref().map(|data| LayerTile::new(map, data)),
+            )
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.tiles.size_hint()
+    }
+}
+
+impl<'map> ExactSizeIterator for FiniteAllTiles<'map> {}
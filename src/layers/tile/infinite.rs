@@ -1,18 +1,23 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, io::Write};
 
 use xml::attribute::OwnedAttribute;
 
 use crate::{
+    parse::MapParseContext,
     util::{floor_div, get_attrs, map_wrapper, parse_tag, XmlEventResult},
-    Error, LayerTile, LayerTileData, MapTilesetGid, Result,
+    write::XmlWriter,
+    Error, Gid, LayerTile, LayerTileData, Map, MapTilesetGid, RawTileData, Result, StreamedTile,
 };
 
-use super::util::parse_data_line;
+use super::util::{parse_data_line, read_raw_data, stream_data_line};
 
 /// The raw data of a [`InfiniteTileLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
 #[derive(PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct InfiniteTileLayerData {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_impls::chunk_map"))]
     chunks: HashMap<(i32, i32), ChunkData>,
+    raw_chunks: Vec<RawTileData>,
 }
 
 impl std::fmt::Debug for InfiniteTileLayerData {
@@ -26,6 +31,8 @@ impl InfiniteTileLayerData {
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
         tilesets: &[MapTilesetGid],
+        layer_id: u32,
+        context: &mut MapParseContext,
     ) -> Result<Self> {
         let (e, c) = get_attrs!(
             for v in attrs {
@@ -36,9 +43,18 @@ impl InfiniteTileLayerData {
         );
 
         let mut chunks = HashMap::<(i32, i32), ChunkData>::new();
+        let mut raw_chunks = Vec::new();
         parse_tag!(parser, "data", {
             "chunk" => |attrs| {
-                let chunk = InternalChunk::new(parser, attrs, e.clone(), c.clone(), tilesets)?;
+                let chunk = InternalChunk::new(parser, attrs, e.clone(), c.clone(), tilesets, layer_id, context)?;
+                if let Some(raw) = chunk.raw {
+                    raw_chunks.push(raw);
+                    return Ok(());
+                }
+                if context.tile_sink.is_some() {
+                    // The tiles have already been streamed, so there's nothing to store.
+                    return Ok(());
+                }
                 for x in chunk.x..chunk.x + chunk.width as i32 {
                     for y in chunk.y..chunk.y + chunk.height as i32 {
                         let chunk_pos = ChunkData::tile_to_chunk_pos(x, y);
@@ -47,14 +63,140 @@ impl InfiniteTileLayerData {
                         let internal_pos = (x - chunk.x, y - chunk.y);
                         let internal_index = (internal_pos.0 + internal_pos.1 * chunk.width as i32) as usize;
 
-                        chunks.entry(chunk_pos).or_insert_with(ChunkData::new).tiles[chunk_index] = chunk.tiles[internal_index];
+                        // Chunks with less data than their size claims are padded with empty tiles.
+                        chunks.entry(chunk_pos).or_insert_with(ChunkData::new).tiles[chunk_index] = chunk.tiles.get(internal_index).copied().flatten();
                     }
                 }
                 Ok(())
             }
         });
 
-        Ok(Self { chunks })
+        Ok(Self { chunks, raw_chunks })
+    }
+
+    /// The size in tiles of the smallest area containing all of the layer's chunks.
+    pub(crate) fn size(&self) -> (u32, u32) {
+        if !self.raw_chunks.is_empty() {
+            let (min_x, min_y) = self.raw_start();
+            let max_x = self.raw_chunks.iter().map(|raw| raw.x + raw.width as i32);
+            let max_y = self.raw_chunks.iter().map(|raw| raw.y + raw.height as i32);
+            return (
+                (max_x.max().unwrap_or(min_x) - min_x) as u32,
+                (max_y.max().unwrap_or(min_y) - min_y) as u32,
+            );
+        }
+        let xs = self.chunks.keys().map(|(x, _)| *x);
+        let ys = self.chunks.keys().map(|(_, y)| *y);
+        match (xs.clone().min(), xs.max(), ys.clone().min(), ys.max()) {
+            (Some(min_x), Some(max_x), Some(min_y), Some(max_y)) => (
+                (max_x - min_x + 1) as u32 * ChunkData::WIDTH,
+                (max_y - min_y + 1) as u32 * ChunkData::HEIGHT,
+            ),
+            _ => (0, 0),
+        }
+    }
+
+    /// The position of the top-left-most tile of the raw chunks.
+    fn raw_start(&self) -> (i32, i32) {
+        let xs = self.raw_chunks.iter().map(|raw| raw.x);
+        let ys = self.raw_chunks.iter().map(|raw| raw.y);
+        (xs.min().unwrap_or(0), ys.min().unwrap_or(0))
+    }
+
+    pub(crate) fn write_xml(&self, writer: &mut XmlWriter<impl Write>) -> Result<()> {
+        if let Some(first) = self.raw_chunks.first() {
+            writer.start("data", &first.attributes())?;
+            for raw in &self.raw_chunks {
+                let attrs = [
+                    ("x", raw.x.to_string()),
+                    ("y", raw.y.to_string()),
+                    ("width", raw.width.to_string()),
+                    ("height", raw.height.to_string()),
+                ];
+                writer.start("chunk", &attrs)?;
+                writer.characters(&raw.to_text())?;
+                writer.end()?;
+            }
+            return writer.end();
+        }
+        let mut chunks: Vec<_> = self.chunks.iter().collect();
+        chunks.sort_unstable_by_key(|((x, y), _)| (*y, *x));
+
+        writer.start("data", &writer.encoding.attributes())?;
+        for ((x, y), chunk) in chunks {
+            let bits: Vec<u32> = chunk
+                .tiles
+                .iter()
+                .map(|tile| LayerTileData::to_bits(tile.as_ref(), &writer.first_gids))
+                .collect();
+            let attrs = [
+                ("x", (x * ChunkData::WIDTH as i32).to_string()),
+                ("y", (y * ChunkData::HEIGHT as i32).to_string()),
+                ("width", ChunkData::WIDTH.to_string()),
+                ("height", ChunkData::HEIGHT.to_string()),
+            ];
+            writer.tile_data("chunk", &attrs, &bits, ChunkData::WIDTH as usize)?;
+        }
+        writer.end()
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn write_json(
+        &self,
+        writer: &crate::write::JsonWriter,
+        json: &mut crate::write::JsonMap,
+    ) -> Result<()> {
+        if !self.raw_chunks.is_empty() {
+            let (start_x, start_y) = self.raw_start();
+            json.insert("startx".to_owned(), start_x.into());
+            json.insert("starty".to_owned(), start_y.into());
+            let json_chunks = self
+                .raw_chunks
+                .iter()
+                .map(|raw| {
+                    Ok(serde_json::json!({
+                        "x": raw.x,
+                        "y": raw.y,
+                        "width": raw.width,
+                        "height": raw.height,
+                        "data": raw.to_json()?,
+                    }))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            json.insert("chunks".to_owned(), json_chunks.into());
+            return Ok(());
+        }
+        let mut chunks: Vec<_> = self.chunks.iter().collect();
+        chunks.sort_unstable_by_key(|((x, y), _)| (*y, *x));
+
+        let start_x = chunks.iter().map(|((x, _), _)| *x).min().unwrap_or(0);
+        let start_y = chunks.iter().map(|((_, y), _)| *y).min().unwrap_or(0);
+        json.insert(
+            "startx".to_owned(),
+            (start_x * ChunkData::WIDTH as i32).into(),
+        );
+        json.insert(
+            "starty".to_owned(),
+            (start_y * ChunkData::HEIGHT as i32).into(),
+        );
+
+        let mut json_chunks = Vec::new();
+        for ((x, y), chunk) in chunks {
+            let bits: Vec<u32> = chunk
+                .tiles
+                .iter()
+                .map(|tile| LayerTileData::to_bits(tile.as_ref(), &writer.first_gids))
+                .collect();
+            json_chunks.push(serde_json::json!({
+                "x": x * ChunkData::WIDTH as i32,
+                "y": y * ChunkData::HEIGHT as i32,
+                "width": ChunkData::WIDTH,
+                "height": ChunkData::HEIGHT,
+                "data": writer.tile_data(&bits)?,
+            }));
+        }
+        json.insert("chunks".to_owned(), json_chunks.into());
+        Ok(())
     }
 
     /// Obtains the tile data present at the position given.
@@ -78,6 +220,30 @@ impl InfiniteTileLayerData {
             .flatten()
     }
 
+    /// Sets the tile at the position given, creating the chunk that contains it if it doesn't
+    /// exist yet and the tile isn't empty.
+    pub(crate) fn set_tile(&mut self, x: i32, y: i32, tile: Option<LayerTileData>) {
+        let chunk_pos = ChunkData::tile_to_chunk_pos(x, y);
+        let chunk_index = (x - chunk_pos.0 * ChunkData::WIDTH as i32) as usize
+            + (y - chunk_pos.1 * ChunkData::HEIGHT as i32) as usize * ChunkData::WIDTH as usize;
+        match (self.chunks.get_mut(&chunk_pos), tile) {
+            (Some(chunk), tile) => chunk.tiles[chunk_index] = tile,
+            (None, Some(tile)) => {
+                let mut chunk = ChunkData::new();
+                chunk.tiles[chunk_index] = Some(tile);
+                self.chunks.insert(chunk_pos, chunk);
+            }
+            (None, None) => {}
+        }
+    }
+
+    /// Returns whether all of the tiles in this layer are empty.
+    pub fn is_empty(&self) -> bool {
+        self.chunks
+            .values()
+            .all(|chunk| chunk.tiles.iter().all(Option::is_none))
+    }
+
     /// Returns an iterator over only the data part of the chunks of this tile layer.
     ///
     /// In 99.99% of cases you'll want to use [`InfiniteTileLayer::chunks()`] instead; Using this method is only
@@ -90,6 +256,17 @@ impl InfiniteTileLayerData {
         self.chunks.iter().map(|(pos, chunk)| (*pos, chunk))
     }
 
+    /// Returns the tile data of the layer's chunks as they are stored in the file, in the order
+    /// they appear in it, if the map was loaded with
+    /// [`Loader::with_raw_tile_data`](crate::Loader::with_raw_tile_data).
+    ///
+    /// Unlike [`Self::chunk_data`], these are the chunks of the file, with whatever positions and
+    /// sizes they have there. The layer itself holds no tiles in that case.
+    #[inline]
+    pub fn raw_chunks(&self) -> &[RawTileData] {
+        &self.raw_chunks
+    }
+
     /// Obtains a chunk's data by its position. To obtain the position of the chunk that contains a
     /// tile, use [`ChunkData::tile_to_chunk_pos()`].
     ///
@@ -107,7 +284,9 @@ impl InfiniteTileLayerData {
 /// Has only the tile data contained within and not a reference to the map it is part of.
 /// In 99.99% of cases you'll actually want to use [`Chunk`].
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ChunkData {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_impls::chunk_tiles"))]
     tiles: Box<[Option<LayerTileData>; Self::TILE_COUNT]>,
 }
 
@@ -141,6 +320,11 @@ impl ChunkData {
         }
     }
 
+    /// Returns how many of the chunk's tiles aren't empty.
+    pub(crate) fn non_empty_tile_count(&self) -> u64 {
+        self.tiles.iter().filter(|tile| tile.is_some()).count() as u64
+    }
+
     /// Returns the position of the chunk that contains the given tile position.
     pub fn tile_to_chunk_pos(x: i32, y: i32) -> (i32, i32) {
         (
@@ -150,12 +334,33 @@ impl ChunkData {
     }
 }
 
-map_wrapper!(
-    #[doc = "Part of an [`InfiniteTileLayer`]."]
-    Chunk => ChunkData
-);
+/// Part of an [`InfiniteTileLayer`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Chunk<'map> {
+    map: &'map Map,
+    data: &'map ChunkData,
+    pos: (i32, i32),
+}
 
 impl<'map> Chunk<'map> {
+    #[inline]
+    fn new(map: &'map Map, data: &'map ChunkData, pos: (i32, i32)) -> Self {
+        Self { map, data, pos }
+    }
+
+    /// Get the map this object is from.
+    #[inline]
+    pub fn map(&self) -> &'map Map {
+        self.map
+    }
+
+    /// Returns the position of this chunk, in chunks. Its top-left-most tile is at this position
+    /// times [`ChunkData::WIDTH`] and [`ChunkData::HEIGHT`].
+    #[inline]
+    pub fn position(&self) -> (i32, i32) {
+        self.pos
+    }
+
     /// Obtains the tile present at the position given relative to the chunk's top-left-most tile.
     ///
     /// If the position given is invalid or the position is empty, this function will return [`None`].
@@ -164,6 +369,58 @@ impl<'map> Chunk<'map> {
             .get_tile_data(x, y)
             .map(|data| LayerTile::new(self.map(), data))
     }
+
+    /// Returns an iterator over the non-empty tiles of this chunk along with their positions in
+    /// the layer, row by row.
+    ///
+    /// Unlike [`Chunk::get_tile`], which takes positions relative to the chunk's top-left-most
+    /// tile, these are the positions [`InfiniteTileLayer::get_tile`] takes.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::{Loader, LayerType, TileLayer};
+    /// # let map = Loader::new()
+    /// #     .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+    /// #     .unwrap();
+    /// # if let LayerType::Tiles(TileLayer::Infinite(infinite_layer)) =
+    /// #     &map.get_layer(0).unwrap().layer_type()
+    /// # {
+    /// for (_, chunk) in infinite_layer.chunks() {
+    ///     for (x, y, tile) in chunk.tiles() {
+    ///         assert_eq!(infinite_layer.get_tile(x, y), Some(tile));
+    ///     }
+    /// }
+    /// # } else {
+    /// #     panic!("It is wrongly recognised as a finite map");
+    /// # }
+    /// ```
+    pub fn tiles(&self) -> impl Iterator<Item = (i32, i32, LayerTile<'map>)> + 'map {
+        let map: &'map Map = self.map;
+        let origin_x = self.pos.0 * ChunkData::WIDTH as i32;
+        let origin_y = self.pos.1 * ChunkData::HEIGHT as i32;
+        self.data
+            .tiles
+            .iter()
+            .enumerate()
+            .filter_map(move |(index, tile)| {
+                tile.as_ref().map(|data| {
+                    (
+                        origin_x + (index % ChunkData::WIDTH as usize) as i32,
+                        origin_y + (index / ChunkData::WIDTH as usize) as i32,
+                        LayerTile::new(map, data),
+                    )
+                })
+            })
+    }
+}
+
+impl<'map> std::ops::Deref for Chunk<'map> {
+    type Target = ChunkData;
+
+    #[inline]
+    fn deref(&self) -> &'map Self::Target {
+        self.data
+    }
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -177,6 +434,7 @@ struct InternalChunk {
     width: u32,
     height: u32,
     tiles: Vec<Option<LayerTileData>>,
+    raw: Option<RawTileData>,
 }
 
 impl InternalChunk {
@@ -186,6 +444,8 @@ impl InternalChunk {
         encoding: Option<String>,
         compression: Option<String>,
         tilesets: &[MapTilesetGid],
+        layer_id: u32,
+        context: &mut MapParseContext,
     ) -> Result<Self> {
         let (x, y, width, height) = get_attrs!(
             for v in attrs {
@@ -196,8 +456,66 @@ impl InternalChunk {
             }
             (x, y, width, height)
         );
+        context
+            .options
+            .limits
+            .check_map_size("A chunk", width, height)?;
+        context
+            .options
+            .limits
+            .check_tile_count("A chunk", width, height)?;
 
-        let tiles = parse_data_line(encoding, compression, parser, tilesets)?;
+        let max_size = context.options.limits.max_tile_data_size;
+        if context.raw_tile_data {
+            let data = read_raw_data(&encoding, &compression, parser, max_size)?;
+            return Ok(InternalChunk {
+                x,
+                y,
+                width,
+                height,
+                tiles: Vec::new(),
+                raw: Some(RawTileData {
+                    encoding: encoding.unwrap_or_default(),
+                    compression,
+                    data,
+                    x,
+                    y,
+                    width,
+                    height,
+                }),
+            });
+        }
+        let tiles = match &mut context.tile_sink {
+            Some(sink) => {
+                stream_data_line(
+                    encoding,
+                    compression,
+                    parser,
+                    width,
+                    height,
+                    max_size,
+                    |tile_x, tile_y, gid| {
+                        if Gid::from_bits(gid) != Gid::EMPTY {
+                            sink(StreamedTile {
+                                layer_id,
+                                x: x + tile_x as i32,
+                                y: y + tile_y as i32,
+                                gid,
+                            });
+                        }
+                    },
+                )?;
+                Vec::new()
+            }
+            None => parse_data_line(
+                encoding,
+                compression,
+                parser,
+                tilesets,
+                width as usize * height as usize,
+                max_size,
+            )?,
+        };
 
         Ok(InternalChunk {
             x,
@@ -205,6 +523,7 @@ impl InternalChunk {
             width,
             height,
             tiles,
+            raw: None,
         })
     }
 }
@@ -268,7 +587,7 @@ impl<'map> InfiniteTileLayer<'map> {
         self.data
             .chunks
             .iter()
-            .map(move |(pos, chunk)| (*pos, Chunk::new(map, chunk)))
+            .map(move |(pos, chunk)| (*pos, Chunk::new(map, chunk, *pos)))
     }
 
     /// Obtains a chunk by its position. To obtain the position of the chunk that contains a tile,
@@ -278,6 +597,73 @@ impl<'map> InfiniteTileLayer<'map> {
         let map: &'map crate::Map = self.map;
         self.data
             .get_chunk_data(x, y)
-            .map(move |data| Chunk::new(map, data))
+            .map(move |data| Chunk::new(map, data, (x, y)))
+    }
+
+    /// Obtains the chunk that contains the tile at the position given, if there is one.
+    ///
+    /// This is the same as [`InfiniteTileLayer::get_chunk()`] given the result of
+    /// [`ChunkData::tile_to_chunk_pos()`].
+    #[inline]
+    pub fn get_chunk_at(&self, x: i32, y: i32) -> Option<Chunk<'map>> {
+        let (chunk_x, chunk_y) = ChunkData::tile_to_chunk_pos(x, y);
+        self.get_chunk(chunk_x, chunk_y)
+    }
+
+    /// Returns an iterator over the non-empty tiles of this layer along with their positions.
+    ///
+    /// Tiles are visited chunk by chunk, in rows from the top chunk to the bottom one and from left
+    /// to right within each row, and the tiles of each chunk are visited in rows as well. The
+    /// iterator borrows from the map, so it can be stored for as long as the map is alive.
+    pub fn tiles(&self) -> InfiniteTiles<'map> {
+        let data: &'map InfiniteTileLayerData = self.data;
+        let mut chunks: Vec<_> = data.chunks.iter().collect();
+        chunks.sort_unstable_by_key(|((x, y), _)| (*y, *x));
+        InfiniteTiles {
+            map: self.map,
+            chunks: chunks.into_iter(),
+            current: None,
+        }
+    }
+}
+
+/// An iterator over the non-empty tiles of an [`InfiniteTileLayer`] and their positions.
+///
+/// Created by [`InfiniteTileLayer::tiles()`].
+#[derive(Clone, Debug)]
+pub struct InfiniteTiles<'map> {
+    map: &'map Map,
+    chunks: std::vec::IntoIter<(&'map (i32, i32), &'map ChunkData)>,
+    current: Option<((i32, i32), ChunkTiles<'map>)>,
+}
+
+type ChunkTiles<'map> = std::iter::Enumerate<std::slice::Iter<'map, Option<LayerTileData>>>;
+
+impl<'map> Iterator for InfiniteTiles<'map> {
+    type Item = (i32, i32, LayerTile<'map>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((chunk_pos, tiles)) = &mut self.current {
+                let map = self.map;
+                let chunk_pos = *chunk_pos;
+                let next = tiles.find_map(|(index, tile)| {
+                    tile.as_ref().map(|data| {
+                        (
+                            chunk_pos.0 * ChunkData::WIDTH as i32
+                                + (index % ChunkData::WIDTH as usize) as i32,
+                            chunk_pos.1 * ChunkData::HEIGHT as i32
+                                + (index / ChunkData::WIDTH as usize) as i32,
+                            LayerTile::new(map, data),
+                        )
+                    })
+                });
+                if next.is_some() {
+                    return next;
+                }
+            }
+            let (chunk_pos, chunk) = self.chunks.next()?;
+            self.current = Some((*chunk_pos, chunk.tiles.iter().enumerate()));
+        }
     }
 }
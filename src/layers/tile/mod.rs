@@ -1,11 +1,14 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, io::Write};
 
 use xml::attribute::OwnedAttribute;
 
 use crate::{
+    parse::MapParseContext,
     parse_properties,
+    properties::write_properties,
     util::{get_attrs, map_wrapper, parse_tag, XmlEventResult},
-    Error, Gid, Map, MapTilesetGid, Properties, Result, Tile, TileId, Tileset,
+    write::XmlWriter,
+    Error, FlipFlags, Gid, Map, MapTilesetGid, Properties, Result, Tile, TileId, Tileset,
 };
 
 mod finite;
@@ -14,9 +17,101 @@ mod util;
 
 pub use finite::*;
 pub use infinite::*;
+pub(crate) use util::TileSink;
+#[cfg(feature = "rayon")]
+pub(crate) use util::{decode_deferred, DeferredTileData};
+
+/// A non-empty tile decoded from a tile layer while loading a map with
+/// [`Loader::load_tmx_map_streaming`](crate::Loader::load_tmx_map_streaming).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StreamedTile {
+    /// The ID of the layer this tile is in. See [`LayerData::id()`](crate::LayerData::id).
+    pub layer_id: u32,
+    /// The X coordinate of the tile in the layer, in tiles.
+    pub x: i32,
+    /// The Y coordinate of the tile in the layer, in tiles.
+    pub y: i32,
+    /// The global ID of the tile as stored in the file, which includes its flipping flags.
+    pub gid: u32,
+}
+
+/// The tile data of a finite tile layer or of a chunk of an infinite one as it is stored in the
+/// file, kept instead of the tiles when loading a map with
+/// [`Loader::with_raw_tile_data`](crate::Loader::with_raw_tile_data).
+///
+/// With `base64` encoding, [`data`](Self::data) holds the bytes decoded from base64 but not
+/// decompressed, which once decompressed with [`compression`](Self::compression) (if any) are the
+/// little-endian 32-bit GIDs of the tiles, including their flipping flags. With `csv` encoding,
+/// it holds the comma-separated GIDs as text. Either way, tiles are arranged in rows and GIDs of
+/// 0 are empty tiles.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RawTileData {
+    /// The encoding of the data, which is either `base64` or `csv`.
+    pub encoding: String,
+    /// The compression of the data, such as `zlib`, `gzip` or `zstd`, if any. Only data with
+    /// `base64` encoding is compressed.
+    pub compression: Option<String>,
+    /// The data itself, as described in the [type's docs](Self).
+    pub data: Vec<u8>,
+    /// The X coordinate of the top-left-most tile of the data in the layer, which is 0 for finite
+    /// layers.
+    pub x: i32,
+    /// The Y coordinate of the top-left-most tile of the data in the layer, which is 0 for finite
+    /// layers.
+    pub y: i32,
+    /// The width of the data, in tiles.
+    pub width: u32,
+    /// The height of the data, in tiles.
+    pub height: u32,
+}
+
+impl RawTileData {
+    /// The `encoding` and `compression` attributes of the element the data was read from.
+    pub(crate) fn attributes(&self) -> Vec<(&'static str, String)> {
+        let mut attrs = vec![("encoding", self.encoding.clone())];
+        if let Some(compression) = &self.compression {
+            attrs.push(("compression", compression.clone()));
+        }
+        attrs
+    }
+
+    /// Returns the data as it was written in the file, encoding it back into base64 if needed.
+    pub(crate) fn to_text(&self) -> String {
+        use base64::Engine;
+
+        match self.encoding.as_str() {
+            "base64" => base64::engine::GeneralPurpose::new(
+                &base64::alphabet::STANDARD,
+                base64::engine::general_purpose::PAD,
+            )
+            .encode(&self.data),
+            _ => String::from_utf8_lossy(&self.data).into_owned(),
+        }
+    }
+
+    /// Returns the data as the `data` member of a JSON layer or chunk: a base64 string, or an
+    /// array of GIDs for CSV data.
+    #[cfg(feature = "json")]
+    pub(crate) fn to_json(&self) -> Result<serde_json::Value> {
+        if self.encoding == "base64" {
+            return Ok(self.to_text().into());
+        }
+        let text = String::from_utf8_lossy(&self.data);
+        if text.trim().is_empty() {
+            return Ok(serde_json::Value::Array(Vec::new()));
+        }
+        text.split(',')
+            .map(|v| v.trim().parse::<u32>().map_err(|_| Error::InvalidTileFound))
+            .collect::<Result<Vec<_>>>()
+            .map(Into::into)
+    }
+}
 
 /// Stores the internal tile gid about a layer tile, along with how it is flipped.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayerTileData {
     /// The index of the tileset this tile's in, relative to the tile's map. Guaranteed to be a
     /// valid index of the map tileset container, but **isn't guaranteed to actually contain
@@ -24,12 +119,8 @@ pub struct LayerTileData {
     tileset_index: usize,
     /// The local ID of the tile in the tileset it's in.
     id: TileId,
-    /// Whether this tile is flipped on its Y axis (horizontally).
-    pub flip_h: bool,
-    /// Whether this tile is flipped on its X axis (vertically).
-    pub flip_v: bool,
-    /// Whether this tile is flipped diagonally.
-    pub flip_d: bool,
+    /// How this tile is flipped or rotated.
+    pub flip: FlipFlags,
 }
 
 impl LayerTileData {
@@ -50,36 +141,46 @@ impl LayerTileData {
         self.id
     }
 
-    const FLIPPED_HORIZONTALLY_FLAG: u32 = 0x80000000;
-    const FLIPPED_VERTICALLY_FLAG: u32 = 0x40000000;
-    const FLIPPED_DIAGONALLY_FLAG: u32 = 0x20000000;
-    const ALL_FLIP_FLAGS: u32 = Self::FLIPPED_HORIZONTALLY_FLAG
-        | Self::FLIPPED_VERTICALLY_FLAG
-        | Self::FLIPPED_DIAGONALLY_FLAG;
+    /// Creates the data of an unflipped tile, given the index of its tileset in the map and its
+    /// local ID in it.
+    ///
+    /// The tileset index must be valid for the map the tile is placed in.
+    pub fn new(tileset_index: usize, id: TileId) -> Self {
+        Self {
+            tileset_index,
+            id,
+            flip: FlipFlags::EMPTY,
+        }
+    }
 
     /// Creates a new [`LayerTileData`] from a [`Gid`] plus its flipping bits.
     pub(crate) fn from_bits(bits: u32, tilesets: &[MapTilesetGid]) -> Option<Self> {
-        let flags = bits & Self::ALL_FLIP_FLAGS;
-        let gid = Gid(bits & !Self::ALL_FLIP_FLAGS);
-        let flip_d = flags & Self::FLIPPED_DIAGONALLY_FLAG == Self::FLIPPED_DIAGONALLY_FLAG; // Swap x and y axis (anti-diagonally) [flips over y = -x line]
-        let flip_h = flags & Self::FLIPPED_HORIZONTALLY_FLAG == Self::FLIPPED_HORIZONTALLY_FLAG; // Flip tile over y axis
-        let flip_v = flags & Self::FLIPPED_VERTICALLY_FLAG == Self::FLIPPED_VERTICALLY_FLAG; // Flip tile over x axis
-
+        let gid = Gid::from_bits(bits);
         if gid == Gid::EMPTY {
             None
         } else {
             let (tileset_index, tileset) = crate::util::get_tileset_for_gid(tilesets, gid)?;
-            let id = gid.0 - tileset.first_gid.0;
+            let id = TileId::new(gid.0 - tileset.first_gid.0);
 
-            Some(Self {
-                tileset_index,
-                id,
-                flip_h,
-                flip_v,
-                flip_d,
-            })
+            Some(Self::new(tileset_index, id).with_flip_bits(bits))
         }
     }
+
+    /// Sets the flipping flags of the tile from the ones present in `bits`.
+    pub(crate) fn with_flip_bits(mut self, bits: u32) -> Self {
+        self.flip = FlipFlags::from_gid_raw(bits);
+        self
+    }
+
+    /// Returns the GID of a tile plus its flipping bits, given the first GIDs of the map's
+    /// tilesets. Empty tiles have a GID of 0.
+    pub(crate) fn to_bits(tile: Option<&Self>, first_gids: &[Gid]) -> u32 {
+        let tile = match tile {
+            Some(tile) => tile,
+            None => return Gid::EMPTY.0,
+        };
+        (first_gids[tile.tileset_index].0 + tile.id.get()) | tile.flip.gid_bits()
+    }
 }
 
 /// The raw data of a [`TileLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
@@ -88,17 +189,42 @@ impl LayerTileData {
 /// dereference [`TileLayer`] into this structure, and even if we could, it wouldn't make much
 /// sense, since we can already deref from the finite/infinite tile layers themselves.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) enum TileLayerData {
     Finite(FiniteTileLayerData),
     Infinite(InfiniteTileLayerData),
 }
 
 impl TileLayerData {
+    /// Returns the first of the raw tile data the layer holds instead of tiles if it was loaded
+    /// with [`Loader::with_raw_tile_data`](crate::Loader::with_raw_tile_data), which has the
+    /// encoding all of it is written with.
+    pub(crate) fn raw_tile_data(&self) -> Option<&RawTileData> {
+        match self {
+            TileLayerData::Finite(data) => data.raw_tile_data(),
+            TileLayerData::Infinite(data) => data.raw_chunks().first(),
+        }
+    }
+
+    /// Sets the tile at the position given, returning whether it is within the layer.
+    pub(crate) fn set_tile(&mut self, x: i32, y: i32, tile: Option<LayerTileData>) -> bool {
+        match self {
+            TileLayerData::Finite(data) => data.set_tile(x, y, tile),
+            TileLayerData::Infinite(data) => {
+                data.set_tile(x, y, tile);
+                true
+            }
+        }
+    }
+
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
         infinite: bool,
         tilesets: &[MapTilesetGid],
+        layer_id: u32,
+        layer_name: &str,
+        context: &mut MapParseContext,
     ) -> Result<(Self, Properties)> {
         let (width, height) = get_attrs!(
             for v in attrs {
@@ -107,14 +233,24 @@ impl TileLayerData {
             }
             (width, height)
         );
+        context
+            .options
+            .limits
+            .check_map_size("A tile layer", width, height)?;
+        if !infinite {
+            context
+                .options
+                .limits
+                .check_tile_count("A tile layer", width, height)?;
+        }
         let mut result = Self::Finite(Default::default());
         let mut properties = HashMap::new();
         parse_tag!(parser, "layer", {
             "data" => |attrs| {
                 if infinite {
-                    result = Self::Infinite(InfiniteTileLayerData::new(parser, attrs, tilesets)?);
+                    result = Self::Infinite(InfiniteTileLayerData::new(parser, attrs, tilesets, layer_id, context)?);
                 } else {
-                    result = Self::Finite(FiniteTileLayerData::new(parser, attrs, width, height, tilesets)?);
+                    result = Self::Finite(FiniteTileLayerData::new(parser, attrs, width, height, tilesets, layer_id, layer_name, context)?);
                 }
                 Ok(())
             },
@@ -126,6 +262,59 @@ impl TileLayerData {
 
         Ok((result, properties))
     }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn write_json(
+        &self,
+        writer: &crate::write::JsonWriter,
+        mut json: crate::write::JsonMap,
+        properties: &Properties,
+    ) -> Result<serde_json::Value> {
+        let (width, height) = match self {
+            Self::Finite(data) => (data.width(), data.height()),
+            Self::Infinite(data) => data.size(),
+        };
+        json.insert("type".to_owned(), "tilelayer".into());
+        json.insert("width".to_owned(), width.into());
+        json.insert("height".to_owned(), height.into());
+        let encoding = match self.raw_tile_data() {
+            Some(raw) => raw.attributes(),
+            None => writer.encoding.attributes(),
+        };
+        for (name, value) in encoding {
+            json.insert(name.to_owned(), value.into());
+        }
+        if let Some(properties) = crate::properties::write_json_properties(properties) {
+            json.insert("properties".to_owned(), properties);
+        }
+        match self {
+            Self::Finite(data) => data.write_json(writer, &mut json)?,
+            Self::Infinite(data) => data.write_json(writer, &mut json)?,
+        }
+        Ok(json.into())
+    }
+
+    pub(crate) fn write_xml(
+        &self,
+        writer: &mut XmlWriter<impl Write>,
+        mut attrs: Vec<(&str, String)>,
+        properties: &Properties,
+    ) -> Result<()> {
+        let (width, height) = match self {
+            Self::Finite(data) => (data.width(), data.height()),
+            Self::Infinite(data) => data.size(),
+        };
+        attrs.push(("width", width.to_string()));
+        attrs.push(("height", height.to_string()));
+
+        writer.start("layer", &attrs)?;
+        write_properties(writer, properties)?;
+        match self {
+            Self::Finite(data) => data.write_xml(writer)?,
+            Self::Infinite(data) => data.write_xml(writer)?,
+        }
+        writer.end()
+    }
 }
 
 map_wrapper!(
@@ -148,7 +337,7 @@ impl<'map> LayerTile<'map> {
 }
 
 /// A map layer containing tiles in some way. May be finite or infinite.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TileLayer<'map> {
     /// An finite tile layer; Also see [`FiniteTileLayer`].
     Finite(FiniteTileLayer<'map>),
@@ -174,6 +363,25 @@ impl<'map> TileLayer<'map> {
         }
     }
 
+    /// Returns an iterator over the non-empty tiles of this layer along with their positions.
+    ///
+    /// See [`FiniteTileLayer::tiles()`] and [`InfiniteTileLayer::tiles()`] for the order in which
+    /// tiles are visited.
+    pub fn tiles(&self) -> TileLayerTiles<'map> {
+        match self {
+            TileLayer::Finite(finite) => TileLayerTiles::Finite(finite.tiles()),
+            TileLayer::Infinite(infinite) => TileLayerTiles::Infinite(infinite.tiles()),
+        }
+    }
+
+    /// Returns whether all of the tiles in this layer are empty.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            TileLayer::Finite(finite) => finite.is_empty(),
+            TileLayer::Infinite(infinite) => infinite.is_empty(),
+        }
+    }
+
     /// The width of this layer, if finite, or `None` if infinite.
     ///
     /// ## Example
@@ -242,3 +450,25 @@ impl<'map> TileLayer<'map> {
         }
     }
 }
+
+/// An iterator over the non-empty tiles of a [`TileLayer`] and their positions.
+///
+/// Created by [`TileLayer::tiles()`].
+#[derive(Clone, Debug)]
+pub enum TileLayerTiles<'map> {
+    /// Iterator over the tiles of a finite tile layer.
+    Finite(FiniteTiles<'map>),
+    /// Iterator over the tiles of an infinite tile layer.
+    Infinite(InfiniteTiles<'map>),
+}
+
+impl<'map> Iterator for TileLayerTiles<'map> {
+    type Item = (i32, i32, LayerTile<'map>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            TileLayerTiles::Finite(tiles) => tiles.next(),
+            TileLayerTiles::Infinite(tiles) => tiles.next(),
+        }
+    }
+}
@@ -1,30 +1,162 @@
 use std::{convert::TryInto, io::Read};
 
 use base64::Engine;
-use xml::reader::XmlEvent;
 
-use crate::{util::XmlEventResult, Error, LayerTileData, MapTilesetGid, Result};
+use crate::{
+    util::{read_characters, XmlEventResult},
+    Error, LayerTileData, MapTilesetGid, Result, StreamedTile,
+};
+
+/// Receives the tiles of tile layers as they are decoded, instead of storing them in the layer.
+pub(crate) type TileSink<'a> = Option<&'a mut dyn FnMut(StreamedTile)>;
 
 pub(crate) fn parse_data_line(
     encoding: Option<String>,
     compression: Option<String>,
     parser: &mut impl Iterator<Item = XmlEventResult>,
     tilesets: &[MapTilesetGid],
+    tile_count: usize,
+    max_size: usize,
 ) -> Result<Vec<Option<LayerTileData>>> {
+    let data = read_characters(parser)?;
+    decode_tile_data(
+        data.as_deref(),
+        encoding,
+        compression,
+        tilesets,
+        tile_count,
+        max_size,
+    )
+}
+
+/// Reads the tile data of the current `<data>` or `<chunk>` element without decoding it into
+/// tiles, returning the bytes of CSV data as they are and those of base64 data decoded but still
+/// compressed.
+pub(crate) fn read_raw_data(
+    encoding: &Option<String>,
+    compression: &Option<String>,
+    parser: &mut impl Iterator<Item = XmlEventResult>,
+    max_size: usize,
+) -> Result<Vec<u8>> {
+    let data = read_characters(parser)?;
+    let data = match (encoding.as_deref(), compression.as_deref()) {
+        (Some("csv"), None) => data.map(String::into_bytes).unwrap_or_default(),
+        // The compression is left to the caller, so even the ones this crate can't decompress
+        // are fine here.
+        (Some("base64"), _) => decode_base64(data.as_deref())?,
+        _ => {
+            return Err(Error::InvalidEncodingFormat {
+                encoding: encoding.clone(),
+                compression: compression.clone(),
+            })
+        }
+    };
+    check_tile_data_size(data, max_size)
+}
+
+/// The raw tile data of a layer, read while parsing a map and decoded separately: once the map
+/// has been parsed with the `rayon` feature, or right after being read otherwise.
+pub(crate) struct DeferredTileData {
+    encoding: Option<String>,
+    compression: Option<String>,
+    data: Option<String>,
+    tile_count: usize,
+    max_size: usize,
+}
+
+impl DeferredTileData {
+    /// Reads the tile data of the current `<data>` element without decoding it.
+    pub(crate) fn read(
+        encoding: Option<String>,
+        compression: Option<String>,
+        parser: &mut impl Iterator<Item = XmlEventResult>,
+        tile_count: usize,
+        max_size: usize,
+    ) -> Result<Self> {
+        Ok(Self {
+            encoding,
+            compression,
+            data: read_characters(parser)?,
+            tile_count,
+            max_size,
+        })
+    }
+
+    /// Decodes the data into tiles.
+    pub(crate) fn decode(self, tilesets: &[MapTilesetGid]) -> Result<Vec<Option<LayerTileData>>> {
+        decode_tile_data(
+            self.data.as_deref(),
+            self.encoding,
+            self.compression,
+            tilesets,
+            self.tile_count,
+            self.max_size,
+        )
+    }
+}
+
+/// Decodes the given tile data across rayon's thread pool, returning the results in the same
+/// order.
+#[cfg(feature = "rayon")]
+pub(crate) fn decode_deferred(
+    deferred: Vec<DeferredTileData>,
+    tilesets: &[MapTilesetGid],
+) -> Vec<Result<Vec<Option<LayerTileData>>>> {
+    use rayon::prelude::*;
+
+    deferred
+        .into_par_iter()
+        .map(|deferred| deferred.decode(tilesets))
+        .collect()
+}
+
+/// Decodes the tile data of a layer or chunk. `tile_count` is the number of tiles it is expected
+/// to hold, which is only used to allocate the decompressed data up front.
+fn decode_tile_data(
+    data: Option<&str>,
+    encoding: Option<String>,
+    compression: Option<String>,
+    tilesets: &[MapTilesetGid],
+    tile_count: usize,
+    max_size: usize,
+) -> Result<Vec<Option<LayerTileData>>> {
+    let size_hint = tile_count.saturating_mul(4).min(max_size);
     match (encoding.as_deref(), compression.as_deref()) {
-        (Some("csv"), None) => decode_csv(parser, tilesets),
-
-        (Some("base64"), None) => parse_base64(parser).map(|v| convert_to_tiles(&v, tilesets)),
-        (Some("base64"), Some("zlib")) => parse_base64(parser)
-            .and_then(|data| process_decoder(Ok(flate2::bufread::ZlibDecoder::new(&data[..]))))
-            .map(|v| convert_to_tiles(&v, tilesets)),
-        (Some("base64"), Some("gzip")) => parse_base64(parser)
-            .and_then(|data| process_decoder(Ok(flate2::bufread::GzDecoder::new(&data[..]))))
-            .map(|v| convert_to_tiles(&v, tilesets)),
+        (Some("csv"), None) => decode_csv(data, tilesets, max_size),
+
+        (Some("base64"), None) => decode_base64(data)
+            .and_then(|data| check_tile_data_size(data, max_size))
+            .and_then(|v| convert_to_tiles(&v, tilesets)),
+        (Some("base64"), Some("zlib")) => decode_base64(data)
+            .and_then(|data| {
+                process_decoder(
+                    Ok(flate2::bufread::ZlibDecoder::new(&data[..])),
+                    size_hint,
+                    max_size,
+                )
+            })
+            .and_then(|v| convert_to_tiles(&v, tilesets)),
+        (Some("base64"), Some("gzip")) => decode_base64(data)
+            .and_then(|data| {
+                process_decoder(
+                    Ok(flate2::bufread::GzDecoder::new(&data[..])),
+                    size_hint,
+                    max_size,
+                )
+            })
+            .and_then(|v| convert_to_tiles(&v, tilesets)),
         #[cfg(feature = "zstd")]
-        (Some("base64"), Some("zstd")) => parse_base64(parser)
-            .and_then(|data| process_decoder(zstd::stream::read::Decoder::with_buffer(&data[..])))
-            .map(|v| convert_to_tiles(&v, tilesets)),
+        (Some("base64"), Some("zstd")) => decode_base64(data)
+            .and_then(|data| {
+                process_decoder(
+                    zstd::stream::read::Decoder::with_buffer(&data[..]),
+                    size_hint,
+                    max_size,
+                )
+            })
+            .and_then(|v| convert_to_tiles(&v, tilesets)),
+        #[cfg(not(feature = "zstd"))]
+        (Some("base64"), Some("zstd")) => Err(zstd_unsupported()),
 
         _ => Err(Error::InvalidEncodingFormat {
             encoding,
@@ -33,64 +165,197 @@ pub(crate) fn parse_data_line(
     }
 }
 
-fn parse_base64(parser: &mut impl Iterator<Item = XmlEventResult>) -> Result<Vec<u8>> {
-    for next in parser {
-        match next.map_err(Error::XmlDecodingError)? {
-            XmlEvent::Characters(s) => {
-                return base64::engine::GeneralPurpose::new(
-                    &base64::alphabet::STANDARD,
-                    base64::engine::general_purpose::PAD,
-                )
-                .decode(s.trim().as_bytes())
-                .map_err(Error::Base64DecodingError)
+/// Decodes the tile data in the same way as [`parse_data_line`], but instead of collecting the
+/// tiles, calls `emit` with the position and raw bits of each of them as soon as they are decoded.
+///
+/// The position is relative to the layer or chunk, which is `width` by `height` tiles; data
+/// holding more tiles than that fails with [`Error::InvalidTileDataLength`].
+pub(crate) fn stream_data_line(
+    encoding: Option<String>,
+    compression: Option<String>,
+    parser: &mut impl Iterator<Item = XmlEventResult>,
+    width: u32,
+    height: u32,
+    max_size: usize,
+    mut emit: impl FnMut(u32, u32, u32),
+) -> Result<()> {
+    let data = match read_characters(parser)? {
+        Some(data) => data,
+        None => return Ok(()),
+    };
+    let (width, tile_count) = (width as usize, width as usize * height as usize);
+    let mut index = 0;
+    let mut emit = |bits| {
+        // Also keeps `width` from being zero below.
+        if index >= tile_count {
+            return Err(Error::InvalidTileDataLength {
+                length: (index + 1) * 4,
+            });
+        }
+        emit((index % width) as u32, (index / width) as u32, bits);
+        index += 1;
+        Ok(())
+    };
+    if let (Some("csv"), None) = (encoding.as_deref(), compression.as_deref()) {
+        for (index, value) in data.split(',').enumerate() {
+            if index >= max_size / 4 {
+                return Err(tile_data_limit_exceeded(max_size));
             }
-            XmlEvent::EndElement { name, .. } if name.local_name == "data" => {
-                return Ok(Vec::new());
+            emit(value.trim().parse().map_err(|_| Error::InvalidTileFound)?)?;
+        }
+        return Ok(());
+    }
+
+    let engine = base64::engine::GeneralPurpose::new(
+        &base64::alphabet::STANDARD,
+        base64::engine::general_purpose::PAD,
+    );
+    let decoded = base64::read::DecoderReader::new(data.trim().as_bytes(), &engine);
+    match (encoding.as_deref(), compression.as_deref()) {
+        (Some("base64"), None) => stream_bits(decoded, max_size, emit),
+        (Some("base64"), Some("zlib")) => {
+            stream_bits(flate2::read::ZlibDecoder::new(decoded), max_size, emit)
+        }
+        (Some("base64"), Some("gzip")) => {
+            stream_bits(flate2::read::GzDecoder::new(decoded), max_size, emit)
+        }
+        #[cfg(feature = "zstd")]
+        (Some("base64"), Some("zstd")) => stream_bits(
+            zstd::stream::read::Decoder::new(decoded).map_err(Error::DecompressingError)?,
+            max_size,
+            emit,
+        ),
+        #[cfg(not(feature = "zstd"))]
+        (Some("base64"), Some("zstd")) => Err(zstd_unsupported()),
+
+        _ => Err(Error::InvalidEncodingFormat {
+            encoding,
+            compression,
+        }),
+    }
+}
+
+/// Reads little-endian tile bits one at a time from `data`, so that the whole decoded stream
+/// never has to be kept in memory.
+fn stream_bits(
+    data: impl Read,
+    max_size: usize,
+    mut emit: impl FnMut(u32) -> Result<()>,
+) -> Result<()> {
+    let mut data = std::io::BufReader::new(data);
+    let mut bits = [0u8; 4];
+    // The number of bytes of `bits` read so far.
+    let mut filled = 0;
+    let mut size = 0;
+    loop {
+        match data.read(&mut bits[filled..]) {
+            Ok(0) if filled == 0 => return Ok(()),
+            Ok(0) => {
+                return Err(Error::InvalidTileDataLength {
+                    length: size + filled,
+                })
+            }
+            Ok(read) => {
+                filled += read;
+                if filled == bits.len() {
+                    size += bits.len();
+                    if size > max_size {
+                        return Err(tile_data_limit_exceeded(max_size));
+                    }
+                    emit(u32::from_le_bytes(bits))?;
+                    filled = 0;
+                }
             }
-            _ => {}
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(Error::DecompressingError(err)),
         }
     }
-    Err(Error::PrematureEnd("Ran out of XML data".to_owned()))
 }
 
-fn process_decoder(decoder: std::io::Result<impl Read>) -> Result<Vec<u8>> {
+fn decode_base64(data: Option<&str>) -> Result<Vec<u8>> {
+    match data {
+        Some(data) => base64::engine::GeneralPurpose::new(
+            &base64::alphabet::STANDARD,
+            base64::engine::general_purpose::PAD,
+        )
+        .decode(data.trim().as_bytes())
+        .map_err(Error::Base64DecodingError),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn process_decoder(
+    decoder: std::io::Result<impl Read>,
+    size_hint: usize,
+    max_size: usize,
+) -> Result<Vec<u8>> {
+    // Reading one byte past the limit is enough to tell whether the data goes over it, without
+    // decompressing all of it.
+    let limit = (max_size as u64).saturating_add(1);
     decoder
-        .and_then(|mut decoder| {
-            let mut data = Vec::new();
-            decoder.read_to_end(&mut data)?;
+        .and_then(|decoder| {
+            let mut data = Vec::with_capacity(size_hint);
+            decoder.take(limit).read_to_end(&mut data)?;
             Ok(data)
         })
         .map_err(Error::DecompressingError)
+        .and_then(|data| check_tile_data_size(data, max_size))
+}
+
+fn check_tile_data_size(data: Vec<u8>, max_size: usize) -> Result<Vec<u8>> {
+    if data.len() > max_size {
+        return Err(tile_data_limit_exceeded(max_size));
+    }
+    Ok(data)
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_unsupported() -> Error {
+    Error::UnsupportedFeature {
+        description: "zstd-compressed tile data requires the `zstd` feature".to_owned(),
+    }
+}
+
+fn tile_data_limit_exceeded(max_size: usize) -> Error {
+    Error::LimitExceeded {
+        description: format!(
+            "tile data decodes to more than the limit of {} bytes",
+            max_size
+        ),
+    }
 }
 
 fn decode_csv(
-    parser: &mut impl Iterator<Item = XmlEventResult>,
+    data: Option<&str>,
     tilesets: &[MapTilesetGid],
+    max_size: usize,
 ) -> Result<Vec<Option<LayerTileData>>> {
-    for next in parser {
-        match next.map_err(Error::XmlDecodingError)? {
-            XmlEvent::Characters(s) => {
-                let tiles = s
-                    .split(',')
-                    .map(|v| v.trim().parse().unwrap())
-                    .map(|bits| LayerTileData::from_bits(bits, tilesets))
-                    .collect();
-                return Ok(tiles);
-            }
-            XmlEvent::EndElement { name, .. } if name.local_name == "data" => {
-                return Ok(Vec::new());
-            }
-            _ => {}
-        }
+    let data = match data {
+        Some(data) => data,
+        None => return Ok(Vec::new()),
+    };
+    if data.split(',').count() > max_size / 4 {
+        return Err(tile_data_limit_exceeded(max_size));
     }
-    Err(Error::PrematureEnd("Ran out of XML data".to_owned()))
+    data.split(',')
+        .map(|v| {
+            v.trim()
+                .parse()
+                .map(|bits| LayerTileData::from_bits(bits, tilesets))
+                .map_err(|_| Error::InvalidTileFound)
+        })
+        .collect()
 }
 
-fn convert_to_tiles(data: &[u8], tilesets: &[MapTilesetGid]) -> Vec<Option<LayerTileData>> {
-    data.chunks_exact(4)
+fn convert_to_tiles(data: &[u8], tilesets: &[MapTilesetGid]) -> Result<Vec<Option<LayerTileData>>> {
+    let chunks = data.chunks_exact(4);
+    if !chunks.remainder().is_empty() {
+        return Err(Error::InvalidTileDataLength { length: data.len() });
+    }
+    Ok(chunks
         .map(|chunk| {
             let bits = u32::from_le_bytes(chunk.try_into().unwrap());
             LayerTileData::from_bits(bits, tilesets)
         })
-        .collect()
+        .collect())
 }
@@ -14,11 +14,17 @@ mod loader;
 mod map;
 mod objects;
 mod parse;
+mod project;
 mod properties;
+#[cfg(feature = "serde")]
+mod serde_impls;
 mod template;
 mod tile;
 mod tileset;
 mod util;
+#[cfg(feature = "json")]
+mod world;
+mod write;
 
 pub use animation::*;
 pub use cache::*;
@@ -28,7 +34,11 @@ pub use layers::*;
 pub use loader::*;
 pub use map::*;
 pub use objects::*;
+pub use project::*;
 pub use properties::*;
 pub use template::*;
 pub use tile::*;
 pub use tileset::*;
+#[cfg(feature = "json")]
+pub use world::*;
+pub use write::{TileDataEncoding, WriteOptions};
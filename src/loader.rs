@@ -1,6 +1,22 @@
-use std::{fs::File, io::Read, path::Path};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Cursor, Read},
+    path::{Component, Path, PathBuf},
+};
 
-use crate::{DefaultResourceCache, Map, ResourceCache, Result, Tileset};
+#[cfg(feature = "json")]
+use crate::World;
+use crate::{
+    parse::{MapParseContext, ParseOptions},
+    DefaultResourceCache, Error, Map, ProjectTypes, PropertyClassRegistry, ResourceCache, Result,
+    StreamedTile, Tileset,
+};
+
+#[cfg(feature = "async")]
+mod async_reader;
+#[cfg(feature = "async")]
+pub use async_reader::*;
 
 /// A trait defining types that can load data from a [`ResourcePath`](crate::ResourcePath).
 ///
@@ -57,6 +73,224 @@ impl ResourceReader for FilesystemResourceReader {
     }
 }
 
+/// A [`ResourceReader`] that reads from byte slices registered beforehand, such as ones embedded in
+/// the executable with [`include_bytes!`], for platforms without a filesystem.
+///
+/// Resources are looked up by the path the loader asks for, which for external tilesets and
+/// templates is the path stored in the file referencing them joined with that file's directory.
+/// Paths are compared after removing `.` components and resolving `..` ones where possible, so
+/// `./tiles.tsx` and `maps/../tiles.tsx` both match a resource registered as `tiles.tsx`.
+///
+/// Like any other resources, tilesets and templates read through it are only parsed once, and
+/// then kept in the loader's [`ResourceCache`]. References that only differ in their `.`
+/// components share a cache entry, but ones going through `..` are cached separately like with
+/// any other reader.
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// use tiled::{Loader, MemoryResourceReader};
+///
+/// let reader = MemoryResourceReader::new()
+///     .with_resource("level.tmx", include_bytes!("../assets/tiled_base64_external.tmx"))
+///     .with_resource("tilesheet.tsx", include_bytes!("../assets/tilesheet.tsx"));
+/// let mut loader = Loader::new().with_reader(reader);
+///
+/// let map = loader.load_tmx_map("level.tmx")?;
+/// assert_eq!(map.tilesets()[0].name, "tilesheet");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryResourceReader {
+    resources: HashMap<PathBuf, &'static [u8]>,
+}
+
+impl MemoryResourceReader {
+    /// Creates a reader without any resources.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `data` as the contents of `path`, replacing the resource previously registered
+    /// there, if any.
+    pub fn with_resource(mut self, path: impl AsRef<Path>, data: &'static [u8]) -> Self {
+        self.insert(path, data);
+        self
+    }
+
+    /// Registers `data` as the contents of `path`, returning the resource previously registered
+    /// there, if any.
+    pub fn insert(&mut self, path: impl AsRef<Path>, data: &'static [u8]) -> Option<&'static [u8]> {
+        self.resources.insert(normalize_path(path.as_ref()), data)
+    }
+}
+
+impl ResourceReader for MemoryResourceReader {
+    type Resource = &'static [u8];
+    type Error = std::io::Error;
+
+    fn read_from(&mut self, path: &Path) -> std::result::Result<Self::Resource, Self::Error> {
+        self.resources
+            .get(&normalize_path(path))
+            .copied()
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "no resource was registered at this path",
+                )
+            })
+    }
+}
+
+/// Removes the `.` components of `path` and resolves its `..` ones, without accessing the
+/// filesystem.
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// What to do when an element in an XML file has more than one custom property with the same
+/// name, which Tiled itself never writes. Set with [`Loader::with_duplicate_properties`].
+///
+/// JSON files aren't checked.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[non_exhaustive]
+pub enum DuplicatePropertyPolicy {
+    /// The last of the properties is kept, as Tiled does.
+    #[default]
+    KeepLast,
+    /// The last of the properties is kept, and a
+    /// [`DuplicateProperty`](crate::Warning::DuplicateProperty) warning is returned by
+    /// [`Map::validate`] for it.
+    Warn,
+    /// The file fails to load with a [`DuplicateProperty`](crate::Error::DuplicateProperty)
+    /// error.
+    Error,
+}
+
+/// Limits on the resources a [`Loader`] may spend parsing a single file, to safely load maps and
+/// tilesets from untrusted sources.
+///
+/// Files going over any of them fail to load with a
+/// [`LimitExceeded`](crate::Error::LimitExceeded) error. The defaults are generous enough for any
+/// map made by hand, but still keep a malicious file from exhausting memory or the stack.
+///
+/// ## Example
+/// ```
+/// use tiled::{Loader, ParseLimits};
+///
+/// let loader = Loader::new().with_limits(
+///     ParseLimits::new()
+///         .with_max_map_size(1024)
+///         .with_max_tile_data_size(16 * 1024 * 1024),
+/// );
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ParseLimits {
+    pub(crate) max_tile_data_size: usize,
+    pub(crate) max_map_size: u32,
+    pub(crate) max_nesting_depth: usize,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        Self {
+            max_tile_data_size: 1 << 30,
+            max_map_size: 1 << 16,
+            max_nesting_depth: 64,
+        }
+    }
+}
+
+impl ParseLimits {
+    /// Creates the default limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of bytes a single tile layer or chunk may decode to, after
+    /// decompression. Defaults to 1 GiB.
+    ///
+    /// This also bounds the declared size of finite tile layers and chunks, at 4 bytes per tile,
+    /// since their tiles are stored even when their data is empty.
+    pub fn with_max_tile_data_size(mut self, max_tile_data_size: usize) -> Self {
+        self.max_tile_data_size = max_tile_data_size;
+        self
+    }
+
+    /// Sets the maximum width and height, in tiles, of maps, tile layers and chunks. Defaults to
+    /// 65536.
+    pub fn with_max_map_size(mut self, max_map_size: u32) -> Self {
+        self.max_map_size = max_map_size;
+        self
+    }
+
+    /// Sets the maximum depth elements may be nested at in XML files. Defaults to 64.
+    pub fn with_max_nesting_depth(mut self, max_nesting_depth: usize) -> Self {
+        self.max_nesting_depth = max_nesting_depth;
+        self
+    }
+
+    /// Returns the maximum number of bytes a single tile layer or chunk may decode to.
+    pub fn max_tile_data_size(&self) -> usize {
+        self.max_tile_data_size
+    }
+
+    /// Returns the maximum width and height, in tiles, of maps, tile layers and chunks.
+    pub fn max_map_size(&self) -> u32 {
+        self.max_map_size
+    }
+
+    /// Returns the maximum depth elements may be nested at in XML files.
+    pub fn max_nesting_depth(&self) -> usize {
+        self.max_nesting_depth
+    }
+
+    /// Checks the size of a map, layer or chunk, named `what` in the error, against
+    /// [`ParseLimits::max_map_size`].
+    pub(crate) fn check_map_size(&self, what: &str, width: u32, height: u32) -> Result<()> {
+        if width > self.max_map_size || height > self.max_map_size {
+            return Err(Error::LimitExceeded {
+                description: format!(
+                    "{} is {}x{} tiles, larger than the limit of {}",
+                    what, width, height, self.max_map_size
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks the number of tiles of a finite layer or chunk, named `what` in the error, against
+    /// [`ParseLimits::max_tile_data_size`], before anything sized by it is allocated.
+    pub(crate) fn check_tile_count(&self, what: &str, width: u32, height: u32) -> Result<()> {
+        let size = width as u64 * height as u64 * 4;
+        if size > self.max_tile_data_size as u64 {
+            return Err(Error::LimitExceeded {
+                description: format!(
+                    "{} is {}x{} tiles, more than the limit of {} bytes of tile data",
+                    what, width, height, self.max_tile_data_size
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
 /// A type used for loading [`Map`]s and [`Tileset`]s.
 ///
 /// Internally, it holds a [`ResourceCache`] that, as its name implies, caches intermediate loading
@@ -69,13 +303,31 @@ impl ResourceReader for FilesystemResourceReader {
 /// This type is used for loading operations because they require a [`ResourceCache`] for
 /// intermediate artifacts, so using a type for creation can ensure that the cache is reused if
 /// loading more than one object is required.
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// use tiled::{DefaultResourceCache, Loader};
+///
+/// let mut loader = Loader::new()
+///     .with_cache(DefaultResourceCache::new())
+///     .with_base_path("assets");
+///
+/// let map = loader.load_tmx_map("tiled_base64_external.tmx")?;
+/// assert_eq!(map.tilesets()[0].name, "tilesheet");
+/// # Ok(())
+/// # }
+/// ```
 #[derive(Debug, Clone, Default)]
-pub struct Loader<
-    Cache: ResourceCache = DefaultResourceCache,
-    Reader: ResourceReader = FilesystemResourceReader,
-> {
+pub struct Loader<Cache: ResourceCache = DefaultResourceCache, Reader = FilesystemResourceReader> {
     cache: Cache,
     reader: Reader,
+    base_path: PathBuf,
+    project_types: Option<ProjectTypes>,
+    limits: ParseLimits,
+    duplicate_properties: DuplicatePropertyPolicy,
+    raw_tile_data: bool,
+    unparsed_attributes: bool,
 }
 
 impl Loader {
@@ -85,11 +337,17 @@ impl Loader {
         Self {
             cache: DefaultResourceCache::new(),
             reader: FilesystemResourceReader::new(),
+            base_path: PathBuf::new(),
+            project_types: None,
+            limits: ParseLimits::default(),
+            duplicate_properties: DuplicatePropertyPolicy::default(),
+            raw_tile_data: false,
+            unparsed_attributes: false,
         }
     }
 }
 
-impl<Cache: ResourceCache, Reader: ResourceReader> Loader<Cache, Reader> {
+impl<Cache: ResourceCache, Reader> Loader<Cache, Reader> {
     /// Creates a new loader using a specific resource cache and reader.
     ///
     /// ## Example
@@ -97,36 +355,20 @@ impl<Cache: ResourceCache, Reader: ResourceReader> Loader<Cache, Reader> {
     /// # fn main() -> tiled::Result<()> {
     /// use std::{sync::Arc, path::Path};
     ///
-    /// use tiled::{Loader, ResourceCache, FilesystemResourceReader};
+    /// use tiled::{CachedResource, Loader, ResourceCache, FilesystemResourceReader};
     ///
     /// /// An example resource cache that doesn't actually cache any resources at all.
     /// struct NoopResourceCache;
     ///
     /// impl ResourceCache for NoopResourceCache {
-    ///     fn get_tileset(
-    ///         &self,
-    ///         _path: impl AsRef<tiled::ResourcePath>,
-    ///     ) -> Option<std::sync::Arc<tiled::Tileset>> {
-    ///         None
-    ///     }
-    ///
-    ///     fn get_template(
-    ///         &self,
-    ///         _path: impl AsRef<tiled::ResourcePath>,
-    ///     ) -> Option<std::sync::Arc<tiled::Template>> {
+    ///     fn get<T: CachedResource>(&self, _path: impl AsRef<tiled::ResourcePath>) -> Option<Arc<T>> {
     ///         None
     ///     }
     ///
-    ///     fn insert_tileset(
+    ///     fn insert<T: CachedResource>(
     ///         &mut self,
     ///         _path: impl AsRef<tiled::ResourcePath>,
-    ///         _tileset: Arc<tiled::Tileset>
-    ///     ) {}
-    ///
-    ///     fn insert_template(
-    ///         &mut self,
-    ///         _path: impl AsRef<tiled::ResourcePath>,
-    ///         _template: Arc<tiled::Template>
+    ///         _resource: Arc<T>,
     ///     ) {}
     /// }
     ///
@@ -135,38 +377,203 @@ impl<Cache: ResourceCache, Reader: ResourceReader> Loader<Cache, Reader> {
     /// let map = loader.load_tmx_map("assets/tiled_base64_external.tmx")?;
     ///
     /// assert_eq!(
-    ///     map.tilesets()[0].image.as_ref().unwrap().source,
-    ///     Path::new("assets/tilesheet.png")
+    ///     map.tilesets()[0].image.as_ref().unwrap().source.as_deref(),
+    ///     Some(Path::new("assets/tilesheet.png"))
     /// );
     ///
     /// # Ok(())
     /// # }
     /// ```
     pub fn with_cache_and_reader(cache: Cache, reader: Reader) -> Self {
-        Self { cache, reader }
+        Self {
+            cache,
+            reader,
+            base_path: PathBuf::new(),
+            project_types: None,
+            limits: ParseLimits::default(),
+            duplicate_properties: DuplicatePropertyPolicy::default(),
+            raw_tile_data: false,
+            unparsed_attributes: false,
+        }
     }
 
-    /// Parses a file hopefully containing a Tiled map and tries to parse it. All external files
-    /// will be loaded relative to the path given.
+    /// Replaces the loader's [`ResourceCache`], keeping its reader and base path.
+    pub fn with_cache<NewCache: ResourceCache>(self, cache: NewCache) -> Loader<NewCache, Reader> {
+        Loader {
+            cache,
+            reader: self.reader,
+            base_path: self.base_path,
+            project_types: self.project_types,
+            limits: self.limits,
+            duplicate_properties: self.duplicate_properties,
+            raw_tile_data: self.raw_tile_data,
+            unparsed_attributes: self.unparsed_attributes,
+        }
+    }
+
+    /// Replaces the loader's [`ResourceReader`], keeping its cache and base path.
+    pub fn with_reader<NewReader>(self, reader: NewReader) -> Loader<Cache, NewReader> {
+        Loader {
+            cache: self.cache,
+            reader,
+            base_path: self.base_path,
+            project_types: self.project_types,
+            limits: self.limits,
+            duplicate_properties: self.duplicate_properties,
+            raw_tile_data: self.raw_tile_data,
+            unparsed_attributes: self.unparsed_attributes,
+        }
+    }
+
+    /// Sets the directory that relative paths given to the loader are resolved against. By
+    /// default, they are used as they are.
+    pub fn with_base_path(mut self, base_path: impl Into<PathBuf>) -> Self {
+        self.base_path = base_path.into();
+        self
+    }
+
+    /// Returns the directory that relative paths given to the loader are resolved against.
+    pub fn base_path(&self) -> &Path {
+        &self.base_path
+    }
+
+    /// Sets the custom types of the Tiled project the files loaded belong to.
     ///
-    /// All intermediate objects such as map tilesets will be stored in the [internal loader cache].
+    /// With these, the properties of maps, tilesets, layers, tiles and objects whose class is
+    /// defined in the project get the members they don't set filled in from the class' defaults,
+    /// as do class properties. Without them, properties are loaded exactly as they are stored.
+    pub fn with_project_types(mut self, project_types: ProjectTypes) -> Self {
+        self.project_types = Some(project_types);
+        self
+    }
+
+    /// Returns the custom types set with [`Loader::with_project_types`], if any.
+    pub fn project_types(&self) -> Option<&ProjectTypes> {
+        self.project_types.as_ref()
+    }
+
+    /// Sets the limits files loaded must stay within, which should be lowered when loading files
+    /// from untrusted sources.
+    pub fn with_limits(mut self, limits: ParseLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Returns the limits files loaded must stay within.
+    pub fn limits(&self) -> &ParseLimits {
+        &self.limits
+    }
+
+    /// Sets what to do with custom properties that share their name with another property of
+    /// the same element. Defaults to [`DuplicatePropertyPolicy::KeepLast`].
     ///
-    /// [internal loader cache]: Loader::cache()
-    pub fn load_tmx_map(&mut self, path: impl AsRef<Path>) -> Result<Map> {
-        crate::parse::xml::parse_map(path.as_ref(), &mut self.reader, &mut self.cache)
+    /// Tilesets and templates in the loader's cache are only reused if they were parsed with the
+    /// same policy, and are parsed again otherwise.
+    pub fn with_duplicate_properties(mut self, policy: DuplicatePropertyPolicy) -> Self {
+        self.duplicate_properties = policy;
+        self
     }
 
-    /// Parses a file hopefully containing a Tiled tileset and tries to parse it. All external files
-    /// will be loaded relative to the path given.
+    /// Returns what is done with custom properties that share their name with another property
+    /// of the same element.
+    pub fn duplicate_properties(&self) -> DuplicatePropertyPolicy {
+        self.duplicate_properties
+    }
+
+    /// Sets whether the tile data of the maps loaded is kept as it is stored in the file, without
+    /// decoding it into tiles. Defaults to `false`.
     ///
-    /// Unless you specifically want to load a tileset, you won't need to call this function. If
-    /// you are trying to load a map, simply use [`Loader::load_tmx_map`].
+    /// This is meant for callers with their own way of decoding tiles, such as straight into GPU
+    /// buffers. The tile layers of the maps loaded then hold no tiles, and their data is found in
+    /// [`FiniteTileLayerData::raw_tile_data`](crate::FiniteTileLayerData::raw_tile_data) and
+    /// [`InfiniteTileLayerData::raw_chunks`](crate::InfiniteTileLayerData::raw_chunks) instead.
+    /// Tiles aren't passed to the callback of [`Loader::load_tmx_map_streaming`] either.
     ///
-    /// ## Note
-    /// This function will **not** cache the tileset inside the internal [`ResourceCache`], since
-    /// in this context it is not an intermediate object.
-    pub fn load_tsx_tileset(&mut self, path: impl AsRef<Path>) -> Result<Tileset> {
-        crate::parse::xml::parse_tileset(path.as_ref(), &mut self.reader, &mut self.cache)
+    /// Writing such a map writes the raw data back as it was read, with its own encoding rather
+    /// than the one in the [`WriteOptions`](crate::WriteOptions). Its tiles can't be edited:
+    /// [`LayerMut::set_tile`](crate::LayerMut::set_tile) panics in debug builds and does nothing
+    /// otherwise, and [`LayerMut::resize`](crate::LayerMut::resize) leaves such layers as they are.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::Loader::new()
+    ///     .with_raw_tile_data(true)
+    ///     .load_tmx_map("assets/tiled_base64_zlib.tmx")?;
+    ///
+    /// let layer = match map.get_layer(0).unwrap().as_tile_layer() {
+    ///     Some(tiled::TileLayer::Finite(layer)) => layer,
+    ///     _ => panic!("Layer #0 is not a finite tile layer"),
+    /// };
+    /// let raw = layer.raw_tile_data().unwrap();
+    /// assert_eq!(raw.compression.as_deref(), Some("zlib"));
+    /// assert_eq!((raw.width, raw.height), (layer.width(), layer.height()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_raw_tile_data(mut self, raw_tile_data: bool) -> Self {
+        self.raw_tile_data = raw_tile_data;
+        self
+    }
+
+    /// Returns whether the tile data of the maps loaded is kept as it is stored in the file.
+    /// See [`Loader::with_raw_tile_data`].
+    pub fn raw_tile_data(&self) -> bool {
+        self.raw_tile_data
+    }
+
+    /// Sets whether the XML attributes of maps, tilesets, layers and objects this crate doesn't
+    /// parse, such as those added by newer versions of Tiled, are kept in their
+    /// `unparsed_attributes` so that they are written back out with them. Defaults to `false`.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let tmx = r#"<map version="1.10" orientation="orthogonal" width="1" height="1"
+    ///     tilewidth="16" tileheight="16" compressionlevel="-1"/>"#;
+    /// let map = tiled::Loader::new()
+    ///     .with_unparsed_attributes(true)
+    ///     .load_tmx_map_from(tmx.as_bytes(), "map.tmx")?;
+    ///
+    /// let unparsed = map.unparsed_attributes.unwrap();
+    /// assert_eq!(unparsed["compressionlevel"], "-1");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_unparsed_attributes(mut self, unparsed_attributes: bool) -> Self {
+        self.unparsed_attributes = unparsed_attributes;
+        self
+    }
+
+    /// Returns whether the attributes this crate doesn't parse are kept. See
+    /// [`Loader::with_unparsed_attributes`].
+    pub fn unparsed_attributes(&self) -> bool {
+        self.unparsed_attributes
+    }
+
+    /// The settings the loader parses files with.
+    fn parse_options(&self) -> ParseOptions {
+        ParseOptions {
+            limits: self.limits,
+            unparsed_attributes: self.unparsed_attributes,
+            duplicate_properties: self.duplicate_properties,
+        }
+    }
+
+    /// Fills in the properties of `map` from the loader's project types, if it has any.
+    fn resolve_map(&self, mut map: Map) -> Map {
+        if let Some(project_types) = &self.project_types {
+            map.resolve_project_types(project_types);
+        }
+        map
+    }
+
+    /// Fills in the properties of `tileset` from the loader's project types, if it has any.
+    fn resolve_tileset(&self, mut tileset: Tileset) -> Tileset {
+        if let Some(project_types) = &self.project_types {
+            tileset.resolve_project_types(project_types);
+        }
+        tileset
     }
 
     /// Returns a reference to the loader's internal [`ResourceCache`].
@@ -194,3 +601,392 @@ impl<Cache: ResourceCache, Reader: ResourceReader> Loader<Cache, Reader> {
         (self.cache, self.reader)
     }
 }
+
+impl<Cache: ResourceCache, Reader: ResourceReader> Loader<Cache, Reader> {
+    /// Parses the custom types defined in a Tiled project file (usually with the
+    /// `.tiled-project` extension), to be given to [`Loader::with_project_types`].
+    ///
+    /// Only available with the `json` feature.
+    #[cfg(feature = "json")]
+    pub fn load_project_types(&mut self, path: impl AsRef<Path>) -> Result<ProjectTypes> {
+        let path = self.base_path.join(path);
+        let source = self
+            .reader
+            .read_from(&path)
+            .map_err(|err| Error::ResourceLoadingError {
+                path: path.clone(),
+                err: Box::new(err),
+            })?;
+        crate::parse::json::parse_project_types_from(source)
+    }
+
+    /// Parses the object types defined in a Tiled object types file (usually named
+    /// `objecttypes.xml`), which Tiled 1.8 and earlier use to store the default properties of
+    /// each object type.
+    ///
+    /// The registry returned can be used to [resolve](PropertyClassRegistry::resolve) the
+    /// properties of objects, or set as the [classes](ProjectTypes::classes) of the
+    /// [`ProjectTypes`] given to [`Loader::with_project_types`].
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use tiled::{Loader, PropertyValue};
+    ///
+    /// let classes = Loader::new().load_object_types("assets/objecttypes.xml")?;
+    /// assert_eq!(
+    ///     classes.get("Enemy").unwrap()["health"],
+    ///     PropertyValue::IntValue(100)
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_object_types(&mut self, path: impl AsRef<Path>) -> Result<PropertyClassRegistry> {
+        let path = self.base_path.join(path);
+        let source = self
+            .reader
+            .read_from(&path)
+            .map_err(|err| Error::ResourceLoadingError {
+                path: path.clone(),
+                err: Box::new(err),
+            })?;
+        crate::parse::xml::parse_object_types_from(source, &self.parse_options())
+    }
+
+    /// Parses a file hopefully containing a Tiled map and tries to parse it. All external files
+    /// will be loaded relative to the path given.
+    ///
+    /// All intermediate objects such as map tilesets will be stored in the [internal loader cache].
+    ///
+    /// [internal loader cache]: Loader::cache()
+    pub fn load_tmx_map(&mut self, path: impl AsRef<Path>) -> Result<Map> {
+        let path = self.base_path.join(path);
+        let mut context =
+            MapParseContext::new(None, self.parse_options()).with_raw_tile_data(self.raw_tile_data);
+        let map =
+            crate::parse::xml::parse_map(&path, &mut self.reader, &mut self.cache, &mut context)?;
+        Ok(self.resolve_map(map))
+    }
+
+    /// Parses a Tiled map from `source`, treating it as if it was read from `path`. All external
+    /// files will be loaded relative to the path given, which doesn't need to exist itself.
+    ///
+    /// All intermediate objects such as map tilesets will be stored in the [internal loader cache].
+    ///
+    /// [internal loader cache]: Loader::cache()
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let source = include_bytes!("../assets/tiled_base64_external.tmx");
+    /// let map = tiled::Loader::new().load_tmx_map_from(&source[..], "assets/embedded.tmx")?;
+    ///
+    /// assert_eq!(map.tilesets()[0].source, std::path::Path::new("assets/tilesheet.tsx"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_tmx_map_from(&mut self, source: impl Read, path: impl AsRef<Path>) -> Result<Map> {
+        let path = self.base_path.join(path);
+        let mut context =
+            MapParseContext::new(None, self.parse_options()).with_raw_tile_data(self.raw_tile_data);
+        let map = crate::parse::xml::parse_map_from(
+            source,
+            Some(&path),
+            path.parent().ok_or(Error::PathIsNotFile)?,
+            &mut self.reader,
+            &mut self.cache,
+            &mut context,
+        )?;
+        Ok(self.resolve_map(map))
+    }
+
+    /// Parses a file hopefully containing a Tiled map, passing every non-empty tile of its tile
+    /// layers to `on_tile` as it is decoded instead of storing them.
+    ///
+    /// This avoids holding the whole tile grid in memory, which is useful for very large maps.
+    /// The returned map is otherwise identical to one returned by [`Loader::load_tmx_map`], but
+    /// its tile layers contain no tiles. Tiles are reported in the order they appear in the file,
+    /// and their [`StreamedTile::gid`] includes the flipping flags.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut tile_count = 0;
+    /// let map = tiled::Loader::new()
+    ///     .load_tmx_map_streaming("assets/tiled_base64_zlib.tmx", |_tile| tile_count += 1)?;
+    ///
+    /// assert!(tile_count > 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_tmx_map_streaming(
+        &mut self,
+        path: impl AsRef<Path>,
+        mut on_tile: impl FnMut(StreamedTile),
+    ) -> Result<Map> {
+        let path = self.base_path.join(path);
+        let mut context = MapParseContext::new(Some(&mut on_tile), self.parse_options())
+            .with_raw_tile_data(self.raw_tile_data);
+        let map =
+            crate::parse::xml::parse_map(&path, &mut self.reader, &mut self.cache, &mut context)?;
+        Ok(self.resolve_map(map))
+    }
+
+    /// Parses a file hopefully containing a Tiled tileset and tries to parse it. All external files
+    /// will be loaded relative to the path given.
+    ///
+    /// Unless you specifically want to load a tileset, you won't need to call this function. If
+    /// you are trying to load a map, simply use [`Loader::load_tmx_map`].
+    ///
+    /// With the `json` feature enabled, tilesets in Tiled's JSON format are supported too. These
+    /// are recognized by their `.tsj` or `.json` extension, or by their contents for unknown
+    /// extensions. This also applies to the external tilesets referenced by maps and templates.
+    ///
+    /// ## Note
+    /// This function will **not** cache the tileset inside the internal [`ResourceCache`], since
+    /// in this context it is not an intermediate object.
+    pub fn load_tsx_tileset(&mut self, path: impl AsRef<Path>) -> Result<Tileset> {
+        let path = self.base_path.join(path);
+        let options = self.parse_options();
+        let tileset =
+            crate::parse::parse_tileset(&path, &mut self.reader, &mut self.cache, &options)?;
+        Ok(self.resolve_tileset(tileset))
+    }
+
+    /// Parses a Tiled tileset from `source`, treating it as if it was read from `path`. All
+    /// external files will be loaded relative to the path given, which doesn't need to exist
+    /// itself.
+    ///
+    /// Like [`Loader::load_tsx_tileset`], this will **not** cache the tileset.
+    pub fn load_tsx_tileset_from(
+        &mut self,
+        source: impl Read,
+        path: impl AsRef<Path>,
+    ) -> Result<Tileset> {
+        let path = self.base_path.join(path);
+        let options = self.parse_options();
+        let tileset = crate::parse::parse_tileset_from(
+            source,
+            &path,
+            &mut self.reader,
+            &mut self.cache,
+            &options,
+        )?;
+        Ok(self.resolve_tileset(tileset))
+    }
+
+    /// Parses a file containing a Tiled world. The paths of the maps in it are resolved relative
+    /// to the path given, but the maps themselves aren't loaded; use [`Loader::load_world_maps`]
+    /// for that.
+    ///
+    /// Only available with the `json` feature.
+    #[cfg(feature = "json")]
+    pub fn load_world(&mut self, path: impl AsRef<Path>) -> Result<World> {
+        let path = self.base_path.join(path);
+        let source = self
+            .reader
+            .read_from(&path)
+            .map_err(|err| Error::ResourceLoadingError {
+                path: path.clone(),
+                err: Box::new(err),
+            })?;
+        crate::parse::json::parse_world_from(source, &path)
+    }
+
+    /// Parses a Tiled world from `source`, treating it as if it was read from `path`. The paths
+    /// of the maps in it are resolved relative to the path given, which doesn't need to exist
+    /// itself.
+    ///
+    /// Only available with the `json` feature.
+    #[cfg(feature = "json")]
+    pub fn load_world_from(&mut self, source: impl Read, path: impl AsRef<Path>) -> Result<World> {
+        crate::parse::json::parse_world_from(source, &self.base_path.join(path))
+    }
+
+    /// Loads every map explicitly listed in `world`, in the same order as [`World::maps`]. Maps
+    /// matched by the world's [patterns](World::patterns) aren't loaded.
+    ///
+    /// All intermediate objects such as map tilesets will be stored in the [internal loader cache],
+    /// so tilesets shared by the maps are only loaded once.
+    ///
+    /// Only available with the `json` feature.
+    ///
+    /// [internal loader cache]: Loader::cache()
+    #[cfg(feature = "json")]
+    pub fn load_world_maps(&mut self, world: &World) -> Result<Vec<Map>> {
+        // The paths are already resolved against the world's, which includes the base path.
+        world
+            .maps
+            .iter()
+            .map(|map| {
+                let mut context = MapParseContext::new(None, self.parse_options())
+                    .with_raw_tile_data(self.raw_tile_data);
+                let map = crate::parse::xml::parse_map(
+                    &map.file_name,
+                    &mut self.reader,
+                    &mut self.cache,
+                    &mut context,
+                )?;
+                Ok(self.resolve_map(map))
+            })
+            .collect()
+    }
+}
+
+/// Parses a file hopefully containing a Tiled map, using a new [`Loader`].
+///
+/// This is a shorthand for [`Loader::load_tmx_map`]; use a loader directly if you want to reuse
+/// its cache across several loads. If the file can't be opened, a
+/// [`ResourceLoadingError`](crate::Error::ResourceLoadingError) containing its path is returned.
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// let map = tiled::parse_file("assets/tiled_base64_external.tmx")?;
+///
+/// assert_eq!(
+///     map.source.as_deref(),
+///     Some(std::path::Path::new("assets/tiled_base64_external.tmx"))
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub fn parse_file(path: impl AsRef<Path>) -> Result<Map> {
+    Loader::new().load_tmx_map(path)
+}
+
+/// Parses a file hopefully containing a Tiled tileset in the TSX format, using a new [`Loader`].
+///
+/// This is a shorthand for [`Loader::load_tsx_tileset`], the same as [`Tileset::parse_file`].
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// let tileset = tiled::parse_tileset_file("assets/tilesheet.tsx")?;
+///
+/// assert_eq!(tileset.name, "tilesheet");
+/// # Ok(())
+/// # }
+/// ```
+pub fn parse_tileset_file(path: impl AsRef<Path>) -> Result<Tileset> {
+    Loader::new().load_tsx_tileset(path)
+}
+
+/// Parses a Tiled tileset in the TSX format from `source`, treating it as if it was read from
+/// `path`. External files referenced by the tileset, such as its image or object templates, are
+/// resolved relative to the path given, which doesn't need to exist itself.
+///
+/// This is a shorthand for [`Loader::load_tsx_tileset_from`].
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// let source = include_bytes!("../assets/tilesheet.tsx");
+/// let tileset = tiled::parse_tileset_reader(&source[..], "assets/tilesheet.tsx")?;
+///
+/// assert_eq!(tileset.source, std::path::Path::new("assets/tilesheet.tsx"));
+/// # Ok(())
+/// # }
+/// ```
+pub fn parse_tileset_reader(source: impl Read, path: impl AsRef<Path>) -> Result<Tileset> {
+    Loader::new().load_tsx_tileset_from(source, path)
+}
+
+/// Parses a file containing a Tiled world, using a new [`Loader`].
+///
+/// This is a shorthand for [`Loader::load_world`]. Only available with the `json` feature.
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// let world = tiled::parse_world("assets/world.world")?;
+///
+/// assert_eq!(world.maps[0].file_name, std::path::Path::new("assets/tiled_base64_zlib.tmx"));
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "json")]
+pub fn parse_world(path: impl AsRef<Path>) -> Result<World> {
+    Loader::new().load_world(path)
+}
+
+/// Parses a Tiled world from `source`, treating it as if it was read from `path`, using a new
+/// [`Loader`].
+///
+/// This is a shorthand for [`Loader::load_world_from`]. Only available with the `json` feature.
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// let source = include_bytes!("../assets/world.world");
+/// let world = tiled::parse_world_reader(&source[..], "assets/world.world")?;
+///
+/// assert_eq!(world.maps[0].file_name, std::path::Path::new("assets/tiled_base64_zlib.tmx"));
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "json")]
+pub fn parse_world_reader(source: impl Read, path: impl AsRef<Path>) -> Result<World> {
+    Loader::new().load_world_from(source, path)
+}
+
+/// Parses the object types defined in a Tiled object types file from `source`.
+///
+/// See [`Loader::load_object_types`] for details.
+pub fn parse_object_types(source: impl Read) -> Result<PropertyClassRegistry> {
+    crate::parse::xml::parse_object_types_from(source, &ParseOptions::default())
+}
+
+/// A [`ResourceReader`] for maps parsed from memory, which have no location that external
+/// resources could be resolved against.
+#[derive(Debug, Clone, Copy)]
+struct NoResourceReader;
+
+impl ResourceReader for NoResourceReader {
+    type Resource = std::io::Empty;
+    type Error = std::io::Error;
+
+    fn read_from(&mut self, _path: &Path) -> std::result::Result<Self::Resource, Self::Error> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "the map was parsed from memory, so external resources it references can't be loaded",
+        ))
+    }
+}
+
+/// Parses a string containing a Tiled map in the TMX format.
+///
+/// Since the map doesn't come from a file, there is no base path to resolve the external files it
+/// references (such as tilesets or templates) against. If the map contains any, a
+/// [`ResourceLoadingError`](crate::Error::ResourceLoadingError) is returned, whose `path` is the
+/// reference that couldn't be resolved. To load such maps, use a [`Loader`] with your own
+/// [`ResourceReader`] instead.
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// let map = tiled::parse_str(include_str!("../assets/tiled_csv.tmx"))?;
+///
+/// assert_eq!(map.width, 100);
+/// assert_eq!(map.tilesets()[0].name, "tilesheet");
+/// # Ok(())
+/// # }
+/// ```
+pub fn parse_str(s: &str) -> Result<Map> {
+    parse_bytes(s.as_bytes())
+}
+
+/// Parses a byte slice containing a Tiled map in the TMX format.
+///
+/// As with [`parse_str`], the map must not reference any external files.
+pub fn parse_bytes(b: &[u8]) -> Result<Map> {
+    let mut loader = Loader::with_cache_and_reader(DefaultResourceCache::new(), NoResourceReader);
+    crate::parse::xml::parse_map_from(
+        Cursor::new(b),
+        None,
+        &loader.base_path,
+        &mut loader.reader,
+        &mut loader.cache,
+        &mut MapParseContext::new(None, ParseOptions::default()),
+    )
+}
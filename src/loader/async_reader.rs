@@ -0,0 +1,216 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use xml::{reader::XmlEvent, EventReader};
+
+use crate::{
+    parse::{cache_key, MapParseContext, ParseOptions, ParsedResource},
+    Error, Loader, Map, ResourceCache, ResourceKind, ResourceReader, Result,
+};
+
+/// A trait defining types that can asynchronously load data from a
+/// [`ResourcePath`](crate::ResourcePath), for use with [`Loader::load_tmx_map_async`].
+///
+/// This is the asynchronous counterpart of [`ResourceReader`], and isn't tied to any runtime:
+/// [`TokioResourceReader`] reads files with tokio, and readers for other runtimes or sources only
+/// need to return the contents of the path they are given. The futures returned must be [`Send`],
+/// so that loading can happen inside a spawned task.
+///
+/// Only available with the `async` feature.
+///
+/// ## Example
+/// ```
+/// use std::path::Path;
+///
+/// /// Basic example reader impl that just keeps a few resources in memory
+/// struct MemoryReader;
+///
+/// impl tiled::AsyncResourceReader for MemoryReader {
+///     type Error = std::io::Error;
+///
+///     async fn read_from(&mut self, path: &Path) -> std::result::Result<Vec<u8>, Self::Error> {
+///         if path == Path::new("my_map.tmx") {
+///             Ok(include_bytes!("../../assets/tiled_xml.tmx").to_vec())
+///         } else {
+///             Err(std::io::Error::new(std::io::ErrorKind::NotFound, "file not found"))
+///         }
+///     }
+/// }
+/// ```
+pub trait AsyncResourceReader {
+    /// The type that is returned if [`read_from()`](Self::read_from()) fails.
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Try to fetch the whole contents of a path into the resources filesystem.
+    fn read_from(
+        &mut self,
+        path: &Path,
+    ) -> impl Future<Output = std::result::Result<Vec<u8>, Self::Error>> + Send;
+}
+
+/// An [`AsyncResourceReader`] that reads files with [`tokio::fs::read`], so it must be used
+/// within a tokio runtime.
+///
+/// Only available with the `async` feature.
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// use tiled::{Loader, TokioResourceReader};
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// let mut loader = Loader::new().with_reader(TokioResourceReader);
+/// let map = runtime.block_on(loader.load_tmx_map_async("assets/tiled_base64_external.tmx"))?;
+///
+/// assert_eq!(map.tilesets()[0].name, "tilesheet");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TokioResourceReader;
+
+impl AsyncResourceReader for TokioResourceReader {
+    type Error = std::io::Error;
+
+    fn read_from(
+        &mut self,
+        path: &Path,
+    ) -> impl Future<Output = std::result::Result<Vec<u8>, Self::Error>> + Send {
+        tokio::fs::read(path)
+    }
+}
+
+impl<Cache: ResourceCache, Reader: AsyncResourceReader> Loader<Cache, Reader> {
+    /// Loads a file hopefully containing a Tiled map like [`Loader::load_tmx_map`] does, but
+    /// fetching it and all the external files it references through an [`AsyncResourceReader`].
+    ///
+    /// Only reading files is asynchronous: once all of them have been fetched, the map is parsed
+    /// from memory. Tilesets and templates already in the [internal loader cache] aren't fetched
+    /// again, and the ones loaded are stored in it.
+    ///
+    /// Only available with the `async` feature.
+    ///
+    /// [internal loader cache]: Loader::cache()
+    pub async fn load_tmx_map_async(&mut self, path: impl AsRef<Path>) -> Result<Map> {
+        let path = self.base_path.join(path);
+        let mut fetched = FetchedResourceReader::default();
+        let mut pending = vec![path.clone()];
+        loop {
+            while let Some(resource_path) = pending.pop() {
+                if fetched.resources.contains_key(&resource_path) {
+                    continue;
+                }
+                let data: Arc<[u8]> = self
+                    .reader
+                    .read_from(&resource_path)
+                    .await
+                    .map_err(|err| Error::ResourceLoadingError {
+                        path: resource_path.clone(),
+                        err: Box::new(err),
+                    })?
+                    .into();
+                let cache = &self.cache;
+                let options = self.parse_options();
+                pending.extend(
+                    external_references(&data, &resource_path)
+                        .into_iter()
+                        .filter(|(kind, path)| !is_cached(cache, *kind, path, &options))
+                        .map(|(_, path)| path),
+                );
+                fetched.resources.insert(resource_path, data);
+            }
+
+            let mut context = MapParseContext::new(None, self.parse_options())
+                .with_raw_tile_data(self.raw_tile_data);
+            let result =
+                crate::parse::xml::parse_map(&path, &mut fetched, &mut self.cache, &mut context);
+            match result {
+                // Some references can't be found ahead of time, such as those in JSON tilesets,
+                // so whatever the parser missed is fetched before trying again.
+                Err(Error::ResourceLoadingError { .. }) if !fetched.missing.is_empty() => {
+                    pending.append(&mut fetched.missing);
+                }
+                result => return result.map(|map| self.resolve_map(map)),
+            }
+        }
+    }
+}
+
+/// A [`ResourceReader`] serving the files fetched so far by [`Loader::load_tmx_map_async`], which
+/// records the ones it is asked for but doesn't have.
+#[derive(Debug, Default)]
+struct FetchedResourceReader {
+    resources: HashMap<PathBuf, Arc<[u8]>>,
+    missing: Vec<PathBuf>,
+}
+
+impl ResourceReader for FetchedResourceReader {
+    type Resource = Cursor<Arc<[u8]>>;
+    type Error = std::io::Error;
+
+    fn read_from(&mut self, path: &Path) -> std::result::Result<Self::Resource, Self::Error> {
+        match self.resources.get(path) {
+            Some(data) => Ok(Cursor::new(data.clone())),
+            None => {
+                self.missing.push(path.to_owned());
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "the file hasn't been fetched yet",
+                ))
+            }
+        }
+    }
+}
+
+/// Returns the external tilesets and templates referenced by the XML file `data`, which was read
+/// from `path`. Files that aren't XML are only scanned up to where they stop being valid.
+fn external_references(data: &[u8], path: &Path) -> Vec<(ResourceKind, PathBuf)> {
+    let dir = match path.parent() {
+        Some(dir) => dir,
+        None => return Vec::new(),
+    };
+    EventReader::new(data)
+        .into_iter()
+        .map_while(|event| event.ok())
+        .filter_map(|event| match event {
+            XmlEvent::StartElement {
+                name, attributes, ..
+            } => {
+                let (kind, attribute) = match name.local_name.as_str() {
+                    "tileset" => (ResourceKind::Tileset, "source"),
+                    "object" => (ResourceKind::Template, "template"),
+                    _ => return None,
+                };
+                attributes
+                    .into_iter()
+                    .find(|attr| attr.name.local_name == attribute)
+                    .map(|attr| (kind, dir.join(attr.value)))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns whether the resource at `path` is in the cache, having been parsed with the options it
+/// would be parsed with now, so that it is reused rather than parsed again.
+fn is_cached(
+    cache: &impl ResourceCache,
+    kind: ResourceKind,
+    path: &Path,
+    options: &ParseOptions,
+) -> bool {
+    let path = &cache_key(path);
+    match kind {
+        ResourceKind::Tileset => cache
+            .get_tileset(path)
+            .is_some_and(|tileset| tileset.parse_report().options == *options),
+        ResourceKind::Template => cache
+            .get_template(path)
+            .is_some_and(|template| template.parse_report().options == options.for_template()),
+    }
+}
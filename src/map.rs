@@ -1,16 +1,28 @@
 //! Structures related to Tiled maps.
 
-use std::{collections::HashMap, fmt, path::Path, str::FromStr, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    io::Write,
+    ops::{BitAnd, BitOr, BitOrAssign},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{Arc, OnceLock},
+};
 
 use xml::attribute::OwnedAttribute;
 
 use crate::{
     error::{Error, Result},
     layers::{LayerData, LayerTag},
-    properties::{parse_properties, Color, Properties},
+    parse::{cached_or_parse, MapParseContext, ParseReport},
+    properties::{parse_properties, write_properties, Color, Properties},
     tileset::Tileset,
-    util::{get_attrs, parse_tag, XmlEventResult},
-    EmbeddedParseResultType, Layer, ResourceCache, ResourceReader,
+    util::{get_attrs, parse_bool_attr, parse_tag, read_element, XmlEventResult},
+    write::{bool_to_string, color_to_string, path_to_string, push_unparsed_attributes, XmlWriter},
+    ChunkData, EmbeddedParseResultType, Layer, LayerKind, LayerMut, LayerType, Object,
+    ProjectTypes, PropertyValue, ResourceCache, ResourceReader, TileId, TileLayer, TileLayerTiles,
+    TilesetLocation, Warning, WriteOptions,
 };
 
 pub(crate) struct MapTilesetGid {
@@ -18,12 +30,56 @@ pub(crate) struct MapTilesetGid {
     pub tileset: Arc<Tileset>,
 }
 
+/// Where the data of a map's tileset is stored.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TilesetSource {
+    /// The tileset is stored inside the map itself, or was added to it from code.
+    Embedded,
+    /// The tileset was loaded from a separate file, referenced by the map with this path. The path
+    /// is relative to the current directory rather than to the map, like
+    /// [`Tileset::source`](crate::Tileset::source).
+    External(PathBuf),
+}
+
+/// A tileset of a [`Map`], along with the information the map keeps about it.
+///
+/// Obtained from [`Map::map_tilesets`].
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct MapTileset<'map> {
+    /// The GID of the first tile of the tileset in the map.
+    pub first_gid: Gid,
+    /// Whether the tileset is embedded in the map or referenced by it.
+    pub source: &'map TilesetSource,
+    /// The tileset itself.
+    pub tileset: &'map Arc<Tileset>,
+}
+
 /// All Tiled map files will be parsed into this. Holds all the layers and tilesets.
+///
+/// With the `serde` feature enabled, tilesets are serialized by value along with the map. When
+/// deserializing, each map gets its own copy of its tilesets, so maps that shared a tileset
+/// through the same [`Arc`] will no longer do so. Deserializing fails if the map refers to
+/// tilesets it doesn't have.
 #[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    // The derived functions are wrapped by the trait impls below, which check the result.
+    serde(remote = "Self")
+)]
 pub struct Map {
     version: String,
+    /// The path first used in a [`ResourceReader`] to load this map.
+    ///
+    /// [`None`] for maps parsed from memory with [`parse_str`](crate::parse_str) or
+    /// [`parse_bytes`](crate::parse_bytes), and for maps created with a [`MapBuilder`] unless
+    /// given a path.
+    pub source: Option<PathBuf>,
     /// The way tiles are laid out in the map.
     pub orientation: Orientation,
+    /// The order Tiled draws the tiles of the map's tile layers in.
+    pub render_order: RenderOrder,
     /// Width of the map, in tiles.
     ///
     /// ## Note
@@ -52,8 +108,16 @@ pub struct Map {
     pub stagger_axis: StaggerAxis,
     /// The stagger index of Hexagonal/Staggered map.
     pub stagger_index: StaggerIndex,
+    /// The length in pixels of the sides of a Hexagonal map's tiles that are parallel to its
+    /// [stagger axis](Self::stagger_axis). 0 in maps of other orientations.
+    pub hex_side_length: u32,
     /// The tilesets present on this map.
     tilesets: Vec<Arc<Tileset>>,
+    /// Where each of the tilesets is stored, indexed like [`Self::tilesets`].
+    tileset_sources: Vec<TilesetSource>,
+    /// The first GID of each of the tilesets along with its index in [`Self::tilesets`], sorted by
+    /// GID so that tilesets can be looked up with a binary search.
+    gid_lookup: Vec<(Gid, usize)>,
     /// The layers present in this map.
     layers: Vec<LayerData>,
     /// The custom properties of this map.
@@ -63,86 +127,1820 @@ pub struct Map {
     infinite: bool,
     /// The type of the map, which is arbitrary and set by the user.
     pub user_type: Option<String>,
+    /// The settings Tiled stores for editing this map, if the map has any.
+    pub editor_settings: Option<EditorSettings>,
+    /// The attributes of the `<map>` element this crate doesn't parse, by name, such as the ones
+    /// added by versions of Tiled newer than it supports. They are written back out by
+    /// [`Map::write_to`].
+    ///
+    /// Only kept if the map was loaded with
+    /// [`Loader::with_unparsed_attributes`](crate::Loader::with_unparsed_attributes), and
+    /// [`None`] if there are none.
+    pub unparsed_attributes: Option<Box<HashMap<String, String>>>,
+    /// The problems found while parsing the map, returned by [`Map::validate`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) parse_report: ParseReport,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    object_index: ObjectIndex,
+}
+
+/// The settings Tiled uses while editing a map, which have no effect on the map itself.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EditorSettings {
+    /// The width and height in tiles of the chunks new tiles are placed in, in infinite maps.
+    pub chunk_size: Option<(u32, u32)>,
+    /// Where and how the map is exported when using "Export" in Tiled.
+    pub export: Option<ExportSettings>,
+}
+
+/// The file and format a map is exported to from Tiled.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExportSettings {
+    /// The path the map is exported to, as written in the file. Empty if not set.
+    pub target: String,
+    /// The name of the format the map is exported in, such as `json`. Empty if not set.
+    pub format: String,
+}
+
+impl EditorSettings {
+    fn parse_xml(parser: &mut impl Iterator<Item = XmlEventResult>) -> Result<EditorSettings> {
+        let mut settings = EditorSettings::default();
+        parse_tag!(parser, "editorsettings", {
+            "chunksize" => |attrs: Vec<OwnedAttribute>| {
+                let (width, height) = get_attrs!(
+                    for v in attrs {
+                        Some("width") => width ?= v.parse::<u32>(),
+                        Some("height") => height ?= v.parse::<u32>(),
+                    }
+                    (width, height)
+                );
+                // Tiled leaves out the sizes that are the default of 16 tiles.
+                settings.chunk_size = Some((width.unwrap_or(16), height.unwrap_or(16)));
+                Ok(())
+            },
+            "export" => |attrs: Vec<OwnedAttribute>| {
+                let (target, format) = get_attrs!(
+                    for v in attrs {
+                        Some("target") => target = v,
+                        Some("format") => format = v,
+                    }
+                    (target, format)
+                );
+                settings.export = Some(ExportSettings {
+                    target: target.unwrap_or_default(),
+                    format: format.unwrap_or_default(),
+                });
+                Ok(())
+            },
+        });
+        Ok(settings)
+    }
+
+    fn write_xml(&self, writer: &mut XmlWriter<impl Write>) -> Result<()> {
+        writer.start("editorsettings", &[])?;
+        if let Some((width, height)) = self.chunk_size {
+            writer.empty(
+                "chunksize",
+                &[("width", width.to_string()), ("height", height.to_string())],
+            )?;
+        }
+        if let Some(export) = &self.export {
+            let mut attrs = Vec::new();
+            if !export.target.is_empty() {
+                attrs.push(("target", export.target.clone()));
+            }
+            if !export.format.is_empty() {
+                attrs.push(("format", export.format.clone()));
+            }
+            writer.empty("export", &attrs)?;
+        }
+        writer.end()
+    }
+
+    #[cfg(feature = "json")]
+    fn write_json(&self) -> serde_json::Value {
+        let mut json = serde_json::Map::new();
+        if let Some((width, height)) = self.chunk_size {
+            json.insert(
+                "chunksize".to_owned(),
+                serde_json::json!({ "width": width, "height": height }),
+            );
+        }
+        if let Some(export) = &self.export {
+            json.insert(
+                "export".to_owned(),
+                serde_json::json!({ "target": export.target, "format": export.format }),
+            );
+        }
+        json.into()
+    }
+}
+
+impl Default for Map {
+    /// Returns an empty orthogonal map of 0x0 tiles, with no tilesets and no layers, like the ones
+    /// built by a [`MapBuilder`] given no other values.
+    fn default() -> Self {
+        Self {
+            version: "1.10".to_owned(),
+            source: None,
+            orientation: Orientation::Orthogonal,
+            render_order: RenderOrder::default(),
+            width: 0,
+            height: 0,
+            tile_width: 0,
+            tile_height: 0,
+            stagger_axis: StaggerAxis::default(),
+            stagger_index: StaggerIndex::default(),
+            hex_side_length: 0,
+            tilesets: Vec::new(),
+            tileset_sources: Vec::new(),
+            gid_lookup: Vec::new(),
+            layers: Vec::new(),
+            properties: Properties::new(),
+            background_color: None,
+            infinite: false,
+            user_type: None,
+            editor_settings: None,
+            unparsed_attributes: None,
+            parse_report: ParseReport::default(),
+            object_index: ObjectIndex::default(),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Map {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        Map::serialize(self, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Map {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let map = Map::deserialize(deserializer)?;
+        map.check_tileset_indices()
+            .map_err(serde::de::Error::custom)?;
+        Ok(map)
+    }
+}
+
+impl Map {
+    /// Checks that every tileset index stored in the map is that of one of its tilesets, which
+    /// the parser guarantees but deserialized maps may not hold to.
+    #[cfg(feature = "serde")]
+    fn check_tileset_indices(&self) -> std::result::Result<(), String> {
+        let count = self.tilesets.len();
+        let invalid = |index: usize| {
+            format!(
+                "invalid tileset index {}, the map has {} tilesets",
+                index, count
+            )
+        };
+        if self.tileset_sources.len() != count {
+            return Err(format!(
+                "the map has {} tilesets but {} tileset sources",
+                count,
+                self.tileset_sources.len()
+            ));
+        }
+        if let Some(&(_, index)) = self.gid_lookup.iter().find(|(_, index)| *index >= count) {
+            return Err(invalid(index));
+        }
+        let mut layers = Vec::new();
+        collect_layers(self.layers(), &mut layers);
+        for layer in layers {
+            if let Some(tile_layer) = layer.as_tile_layer() {
+                if let Some((_, _, tile)) = tile_layer
+                    .tiles()
+                    .find(|(_, _, tile)| tile.tileset_index() >= count)
+                {
+                    return Err(invalid(tile.tileset_index()));
+                }
+            }
+            for object in layer
+                .as_object_layer()
+                .into_iter()
+                .flat_map(|layer| layer.objects())
+            {
+                if let Some(TilesetLocation::Map(index)) = object
+                    .get_tile()
+                    .map(|tile| tile.tileset_location().clone())
+                {
+                    if index >= count {
+                        return Err(invalid(index));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The locations of a map's objects by their ID, built the first time they are needed. It is
+/// cleared whenever the map's layers are borrowed mutably, since they may be edited or replaced
+/// then, and rebuilt on the next lookup.
+#[derive(Clone, Default)]
+struct ObjectIndex(OnceLock<HashMap<u32, ObjectLocation>>);
+
+impl ObjectIndex {
+    /// Forgets the locations of the objects, so that they are found again when next needed.
+    fn clear(&mut self) {
+        self.0.take();
+    }
+}
+
+impl PartialEq for ObjectIndex {
+    fn eq(&self, _: &Self) -> bool {
+        // Whether the index has been built doesn't change the map.
+        true
+    }
+}
+
+impl fmt::Debug for ObjectIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObjectIndex").finish_non_exhaustive()
+    }
+}
+
+/// Where an object is in a map.
+#[derive(Clone)]
+pub(crate) struct ObjectLocation {
+    /// The indices of the layers leading to the object's layer, starting from the top level.
+    pub layer_path: Vec<usize>,
+    /// The index of the object in its layer.
+    pub object_index: usize,
+}
+
+impl Map {
+    /// The TMX format version this map was saved to. Equivalent to the map file's `version`
+    /// attribute.
+    pub fn version(&self) -> &str {
+        self.version.as_ref()
+    }
+
+    /// Whether this map is infinite. An infinite map has no fixed size and can grow in all
+    /// directions. Its layer data is stored in chunks. This value determines whether the map's
+    /// tile layers are [`FiniteTileLayer`](crate::FiniteTileLayer)s or [`crate::InfiniteTileLayer`](crate::InfiniteTileLayer)s.
+    pub fn infinite(&self) -> bool {
+        self.infinite
+    }
+
+    /// Returns the background color of this map, or transparent black if it has none, which is
+    /// how Tiled draws maps without a background color.
+    pub fn background_color_or_transparent(&self) -> Color {
+        self.background_color.unwrap_or(Color {
+            alpha: 0,
+            red: 0,
+            green: 0,
+            blue: 0,
+        })
+    }
 }
 
-impl Map {
-    /// The TMX format version this map was saved to. Equivalent to the map file's `version`
-    /// attribute.
-    pub fn version(&self) -> &str {
-        self.version.as_ref()
+impl Map {
+    /// Get a reference to the map's tilesets.
+    #[inline]
+    pub fn tilesets(&self) -> &[Arc<Tileset>] {
+        self.tilesets.as_ref()
+    }
+
+    /// Returns an iterator over the map's tilesets along with their first GIDs and whether they
+    /// are embedded in the map, in the same order as [`Self::tilesets`].
+    ///
+    /// ## Example
+    /// ```
+    /// # use std::path::Path;
+    /// use tiled::{Gid, Loader, TilesetSource};
+    /// # fn main() -> tiled::Result<()> {
+    ///
+    /// let map = Loader::new().load_tmx_map("assets/tiled_base64_external.tmx")?;
+    /// let tileset = map.map_tilesets().next().unwrap();
+    /// assert_eq!(tileset.first_gid, Gid(1));
+    /// assert_eq!(
+    ///     tileset.source,
+    ///     &TilesetSource::External(Path::new("assets/tilesheet.tsx").to_owned())
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn map_tilesets(&self) -> impl ExactSizeIterator<Item = MapTileset<'_>> {
+        self.first_gids()
+            .into_iter()
+            .zip(&self.tileset_sources)
+            .zip(&self.tilesets)
+            .map(|((first_gid, source), tileset)| MapTileset {
+                first_gid,
+                source,
+                tileset,
+            })
+    }
+
+    /// Returns where the tileset at `index` in [`Self::tilesets`] is stored, if there is one.
+    pub fn tileset_source(&self, index: usize) -> Option<&TilesetSource> {
+        self.tileset_sources.get(index)
+    }
+
+    /// Get an iterator over all the layers in the map in ascending order of their layer index.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// #
+    /// # fn main() {
+    /// # struct Renderer;
+    /// # impl Renderer {
+    /// #     fn render(&self, _: tiled::TileLayer) {}
+    /// # }
+    /// # let my_renderer = Renderer;
+    /// # let map = Loader::new()
+    /// #     .load_tmx_map("assets/tiled_group_layers.tmx")
+    /// #     .unwrap();
+    /// #
+    /// let tile_layers = map.layers().filter_map(|layer| match layer.layer_type() {
+    ///     tiled::LayerType::Tiles(layer) => Some(layer),
+    ///     _ => None,
+    /// });
+    ///
+    /// for layer in tile_layers {
+    ///     my_renderer.render(layer);
+    /// }
+    /// # }
+    /// ```
+    #[inline]
+    pub fn layers(&self) -> impl ExactSizeIterator<Item = Layer> {
+        self.layers.iter().map(move |layer| Layer::new(self, layer))
+    }
+
+    /// Returns an iterator over the map's layers of the given kind, including the ones in group
+    /// layers, in the order they appear in the map.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use tiled::{LayerKind, LayerType};
+    ///
+    /// let map = tiled::Loader::new().load_tmx_map("assets/tiled_group_layers.tmx")?;
+    ///
+    /// let tile_layers = map.layers_of_type(LayerKind::Tiles).filter_map(|layer| match layer {
+    ///     LayerType::Tiles(layer) => Some(layer),
+    ///     _ => None,
+    /// });
+    /// assert_eq!(tile_layers.count(), 3);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn layers_of_type(&self, kind: LayerKind) -> impl Iterator<Item = LayerType<'_>> {
+        let mut layers = Vec::new();
+        collect_layers(self.layers(), &mut layers);
+        layers
+            .into_iter()
+            .map(|layer| layer.layer_type())
+            .filter(move |layer| layer.kind() == kind)
+    }
+
+    /// Returns an iterator over the map's visible layers, including the ones in group layers, in
+    /// the order they appear in the map.
+    ///
+    /// Layers whose [`visible`](crate::LayerData::visible) is `false` are skipped, along with all
+    /// of their layers if they are group layers, since Tiled hides those too.
+    pub fn visible_layers(&self) -> impl Iterator<Item = Layer<'_>> {
+        let mut layers = Vec::new();
+        collect_visible_layers(self.layers(), &mut layers);
+        layers.into_iter()
+    }
+
+    /// Returns whether none of the map's layers have any contents, as determined by
+    /// [`LayerData::is_empty`](crate::LayerData::is_empty).
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::Loader::new().load_tmx_map("assets/tiled_base64.tmx")?;
+    /// assert!(!map.is_empty());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.layers.iter().all(LayerData::is_empty)
+    }
+
+    /// Returns the layer that has the specified index, if it exists.
+    pub fn get_layer(&self, index: usize) -> Option<Layer> {
+        self.layers.get(index).map(|data| Layer::new(self, data))
+    }
+
+    /// Returns the object with the given ID, along with the layer it is in, or [`None`] if there
+    /// is no such object. The layer is always an [object layer](Layer::as_object_layer), but is
+    /// returned as a [`Layer`] so that its name and properties can be read.
+    ///
+    /// Object IDs are unique in a map, so all of its object layers are searched, including the
+    /// ones in group layers; the collision shapes of tiles are not. If several objects share an
+    /// ID anyway, the first one found is returned. The objects of the map are indexed the first
+    /// time this is called, so later lookups are cheap.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::Loader::new().load_tmx_map("assets/tiled_object_names.tmx")?;
+    ///
+    /// let (layer, object) = map.get_object_by_id(5).unwrap();
+    /// assert_eq!(layer.name, "Decorations");
+    /// assert_eq!(object.name, "tree");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_object_by_id(&self, id: u32) -> Option<(Layer<'_>, Object<'_>)> {
+        let index = self.object_index.0.get_or_init(|| {
+            let mut index = HashMap::new();
+            for (layer_index, layer) in self.layers.iter().enumerate() {
+                layer.index_objects(&mut vec![layer_index], &mut index);
+            }
+            index
+        });
+        let location = index.get(&id)?;
+        let (first, rest) = location.layer_path.split_first()?;
+        let data = rest
+            .iter()
+            .try_fold(self.layers.get(*first)?, |layer, index| {
+                layer.get_child(*index)
+            })?;
+        let layer = Layer::new(self, data);
+        let object = layer.as_object_layer()?.get_object(location.object_index)?;
+        Some((layer, object))
+    }
+
+    /// Returns an iterator over the objects in the map whose [type](crate::ObjectData::user_type)
+    /// is `user_type`, in the order they appear in the map.
+    ///
+    /// All of the map's object layers are searched, including the ones in group layers; the
+    /// collision shapes of tiles are not.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::Loader::new().load_tmx_map("assets/tiled_object_names.tmx")?;
+    ///
+    /// let spawns: Vec<_> = map.find_objects_by_type("spawn").map(|o| o.id()).collect();
+    /// assert_eq!(spawns, [1, 6]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_objects_by_type<'a>(
+        &'a self,
+        user_type: &'a str,
+    ) -> impl Iterator<Item = Object<'a>> + 'a {
+        self.all_objects()
+            .filter(move |object| object.user_type == user_type)
+    }
+
+    /// Returns an iterator over the objects in the map with the given name, in the order they
+    /// appear in the map. Like [`Map::find_objects_by_type`], all of its object layers are
+    /// searched.
+    pub fn find_objects_by_name<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = Object<'a>> + 'a {
+        self.all_objects().filter(move |object| object.name == name)
+    }
+
+    /// Returns an iterator over the objects of all of the map's object layers, including the ones
+    /// in group layers.
+    fn all_objects(&self) -> impl Iterator<Item = Object<'_>> {
+        let mut layers = Vec::new();
+        collect_layers(self.layers(), &mut layers);
+        layers
+            .into_iter()
+            .filter_map(|layer| layer.as_object_layer())
+            .flat_map(|layer| layer.objects())
+    }
+
+    /// Returns a mutable view of the top-level layer at `index`, through which its tiles can be
+    /// changed, or [`None`] if there is no such layer.
+    pub fn get_layer_mut(&mut self, index: usize) -> Option<LayerMut<'_>> {
+        self.object_index.clear();
+        let gid_lookup = &self.gid_lookup;
+        self.layers
+            .get_mut(index)
+            .map(|data| LayerMut::new(data, gid_lookup))
+    }
+
+    /// Resizes the map to `new_width` by `new_height` tiles, along with all of its finite tile
+    /// layers, including those in group layers. New tiles are set from `fill_gid`; see
+    /// [`LayerMut::resize`] for details.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut map = tiled::Loader::new().load_tmx_map("assets/tiled_base64.tmx")?;
+    /// map.resize(50, 20, 0);
+    ///
+    /// let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    /// assert_eq!((map.width, layer.width()), (50, Some(50)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resize(&mut self, new_width: u32, new_height: u32, fill_gid: u32) {
+        if let Some(fill) = crate::layers::resolve_tile_gid(&self.gid_lookup, fill_gid) {
+            self.object_index.clear();
+            for layer in &mut self.layers {
+                layer.resize_tiles(new_width, new_height, fill);
+            }
+            self.width = new_width;
+            self.height = new_height;
+        }
+    }
+
+    /// Returns an iterator over the tiles of the first top-level tile layer with the given name,
+    /// along with their positions, or [`None`] if there is no such layer.
+    ///
+    /// See [`TileLayer::tiles`](crate::TileLayer::tiles) for the order the tiles are visited in.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::Loader::new().load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")?;
+    ///
+    /// for (x, y, tile) in map.iter_tiles("Background").unwrap() {
+    ///     // Render `tile` at (x, y)...
+    /// #   let _ = (x, y, tile);
+    /// }
+    /// assert!(map.iter_tiles("Nonexistent layer").is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iter_tiles(&self, layer_name: &str) -> Option<TileLayerTiles<'_>> {
+        self.layers()
+            .filter(|layer| layer.name == layer_name)
+            .find_map(|layer| layer.as_tile_layer())
+            .map(|layer| layer.tiles())
+    }
+
+    /// Returns how many tiles the map's tile layers hold, including the ones in group layers and
+    /// empty tiles.
+    ///
+    /// Finite layers count as many tiles as their width times their height, and infinite ones as
+    /// many as fit in their chunks.
+    ///
+    /// This goes through every tile layer of the map, so it is meant for diagnostics such as
+    /// estimating memory usage, not for calling every frame.
+    pub fn total_tile_count(&self) -> u64 {
+        self.all_tile_layers()
+            .iter()
+            .map(|layer| match layer {
+                TileLayer::Finite(layer) => layer.width() as u64 * layer.height() as u64,
+                TileLayer::Infinite(layer) => {
+                    layer.chunk_data().len() as u64 * ChunkData::TILE_COUNT as u64
+                }
+            })
+            .sum()
+    }
+
+    /// Returns how many of the tiles in the map's tile layers aren't empty, including the ones in
+    /// group layers.
+    ///
+    /// Like [`Map::total_tile_count`], this goes through every tile of the map, so it shouldn't be
+    /// called every frame.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::Loader::new().load_tmx_map("assets/tiled_base64.tmx")?;
+    ///
+    /// // 161 of the 100x100 tiles of the map's only tile layer are set.
+    /// assert_eq!(map.non_empty_tile_count(), 161);
+    /// assert_eq!(map.total_tile_count(), 100 * 100);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn non_empty_tile_count(&self) -> u64 {
+        self.all_tile_layers()
+            .iter()
+            .map(|layer| match layer {
+                TileLayer::Finite(layer) => layer
+                    .tiles_flat()
+                    .iter()
+                    .filter(|tile| tile.is_some())
+                    .count() as u64,
+                TileLayer::Infinite(layer) => layer
+                    .chunk_data()
+                    .map(|(_, chunk)| chunk.non_empty_tile_count())
+                    .sum(),
+            })
+            .sum()
+    }
+
+    /// Returns all of the map's tile layers, including the ones in group layers.
+    fn all_tile_layers(&self) -> Vec<TileLayer<'_>> {
+        let mut layers = Vec::new();
+        collect_layers(self.layers(), &mut layers);
+        layers
+            .into_iter()
+            .filter_map(|layer| layer.as_tile_layer())
+            .collect()
+    }
+
+    /// Returns the custom properties of the tile with the given global tile ID (GID).
+    ///
+    /// Any flipping flags present in the GID are ignored. Returns [`None`] if the GID is 0 (which
+    /// represents an empty tile) or if it doesn't point to any tile in the map tilesets.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::{Gid, Loader, PropertyValue};
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_base64.tmx")?;
+    ///
+    /// // The tile with local ID 1 in the first tileset (whose first GID is 1) has a GID of 2.
+    /// let properties = map.get_tile_properties(Gid(2)).unwrap();
+    /// assert_eq!(
+    ///     properties.get("a tile property"),
+    ///     Some(&PropertyValue::StringValue("123".to_string()))
+    /// );
+    /// assert!(map.get_tile_properties(Gid::EMPTY).is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_tile_properties(&self, gid: Gid) -> Option<&Properties> {
+        let (tileset_index, id) = self.resolve_gid(gid)?;
+        self.tilesets[tileset_index]
+            .get_tile(id)
+            .map(|tile| &tile.data.properties)
+    }
+
+    /// Returns a single custom property of the tile with the given global tile ID (GID).
+    ///
+    /// Equivalent to calling [`Map::get_tile_properties`] and then looking up `key`.
+    pub fn get_tile_property(&self, gid: Gid, key: &str) -> Option<&PropertyValue> {
+        self.get_tile_properties(gid)?.get(key)
+    }
+
+    /// Returns an iterator over the custom properties of everything in the map, along with what
+    /// each of them belongs to, which makes it possible to search the whole map for a property.
+    ///
+    /// The properties of the map itself come first, then those of each tileset followed by its
+    /// tiles, and last those of each layer followed by its objects. Group layers are followed by
+    /// the layers in them. The properties of each owner are sorted by name, and class properties
+    /// are returned as a whole.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::Loader::new().load_tmx_map("assets/tiled_base64.tmx")?;
+    ///
+    /// let owners: Vec<String> = map
+    ///     .get_all_properties()
+    ///     .filter(|(_, name, _)| *name == "a tile property")
+    ///     .map(|(owner, _, _)| owner.to_string())
+    ///     .collect();
+    /// assert_eq!(owners, ["tile:0:1"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_all_properties(
+        &self,
+    ) -> impl Iterator<Item = (PropertyOwner<'_>, &str, &PropertyValue)> {
+        let mut owners = vec![(PropertyOwner::Map, &self.properties)];
+        for (tileset_index, tileset) in self.tilesets.iter().enumerate() {
+            owners.push((PropertyOwner::Tileset(tileset_index), &tileset.properties));
+            let mut tiles: Vec<_> = tileset
+                .tiles()
+                .map(|(tile_id, tile)| {
+                    (
+                        PropertyOwner::Tile {
+                            tileset_index,
+                            tile_id,
+                        },
+                        &tile.data.properties,
+                    )
+                })
+                .collect();
+            tiles.sort_by_key(|(owner, _)| *owner);
+            owners.extend(tiles);
+        }
+        let mut layers = Vec::new();
+        collect_layers(self.layers(), &mut layers);
+        for layer in layers {
+            owners.push((
+                PropertyOwner::Layer(&layer.data.name),
+                &layer.data.properties,
+            ));
+            if let Some(object_layer) = layer.as_object_layer() {
+                owners.extend(
+                    object_layer
+                        .data
+                        .object_data()
+                        .iter()
+                        .map(|object| (PropertyOwner::Object(object.id()), &object.properties)),
+                );
+            }
+        }
+
+        owners.into_iter().flat_map(|(owner, properties)| {
+            let mut properties: Vec<_> = properties
+                .iter()
+                .map(|(name, value)| (owner, name.as_str(), value))
+                .collect();
+            properties.sort_by_key(|(_, name, _)| *name);
+            properties
+        })
+    }
+
+    /// Splits a global tile ID (GID) into the tileset it belongs to and the tile's local ID within
+    /// that tileset.
+    ///
+    /// Any flipping flags present in the GID are ignored. Returns [`None`] if the GID is 0 (which
+    /// represents an empty tile) or if it is lower than the first GID of every tileset in the map.
+    /// The local ID is not checked against the tiles of the tileset.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::Loader::new().load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")?;
+    ///
+    /// // The second tileset of this map has a first GID of 85.
+    /// let (tileset, id) = map.gid_to_local_id(tiled::Gid(87)).unwrap();
+    /// assert!(std::ptr::eq(tileset, &*map.tilesets()[1]));
+    /// assert_eq!(id, tiled::TileId::new(2));
+    /// assert!(map.gid_to_local_id(tiled::Gid::EMPTY).is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn gid_to_local_id(&self, gid: Gid) -> Option<(&Tileset, TileId)> {
+        let (tileset_index, id) = self.resolve_gid(gid)?;
+        Some((&self.tilesets[tileset_index], id))
+    }
+
+    /// Returns the first tileset in the map with the given name, if any.
+    ///
+    /// This does a linear search over the map's tilesets, so consider building a lookup table if
+    /// you need to call it often.
+    pub fn get_tileset_by_name(&self, name: &str) -> Option<&Tileset> {
+        self.tilesets
+            .iter()
+            .find(|tileset| tileset.name == name)
+            .map(Arc::as_ref)
+    }
+
+    /// Returns the tileset in the map whose first global tile ID (GID) is exactly `first_gid`, if
+    /// any.
+    pub fn get_tileset_by_first_gid(&self, first_gid: Gid) -> Option<&Tileset> {
+        let position = self
+            .gid_lookup
+            .binary_search_by_key(&first_gid, |(gid, _)| *gid)
+            .ok()?;
+        Some(&self.tilesets[self.gid_lookup[position].1])
+    }
+
+    /// Returns the tileset the tile with the given global tile ID (GID) belongs to.
+    ///
+    /// Any flipping flags present in the GID are ignored. Returns [`None`] if the GID is 0 (which
+    /// represents an empty tile) or if it is lower than the first GID of every tileset in the map.
+    /// Tilesets are found with a binary search, so this is cheap enough to call for every tile.
+    pub fn get_tileset_by_gid(&self, gid: Gid) -> Option<&Tileset> {
+        self.gid_to_local_id(gid).map(|(tileset, _)| tileset)
+    }
+
+    /// Returns the position of the top corner of the tile at `col` and `row` of an isometric map,
+    /// in pixels relative to the top corner of the tile at (0, 0).
+    ///
+    /// Only meaningful for [isometric](Orientation::Isometric) maps; calling it on a map with
+    /// any other orientation panics in debug builds.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use tiled::{MapBuilder, Orientation};
+    ///
+    /// let map = MapBuilder::new()
+    ///     .with_orientation(Orientation::Isometric)
+    ///     .with_size(10, 10)
+    ///     .with_tile_size(64, 32)
+    ///     .build()?;
+    ///
+    /// assert_eq!(map.iso_tile_to_screen(2, 1), (32, 48));
+    /// assert_eq!(map.iso_screen_to_tile(32, 48), (2, 1));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn iso_tile_to_screen(&self, col: i32, row: i32) -> (i32, i32) {
+        self.debug_assert_orientation(Orientation::Isometric);
+        (
+            (col - row) * self.tile_width as i32 / 2,
+            (col + row) * self.tile_height as i32 / 2,
+        )
+    }
+
+    /// Returns the column and row of the tile containing the pixel at `sx` and `sy` in an
+    /// isometric map, the inverse of [`Map::iso_tile_to_screen`].
+    ///
+    /// Positions outside the map give columns and rows outside of it too, which may be negative.
+    /// Only meaningful for [isometric](Orientation::Isometric) maps; calling it on a map with
+    /// any other orientation panics in debug builds.
+    pub fn iso_screen_to_tile(&self, sx: i32, sy: i32) -> (i32, i32) {
+        self.debug_assert_orientation(Orientation::Isometric);
+        self.iso_pixel_to_tile(sx as f64, sy as f64)
+    }
+
+    fn iso_pixel_to_tile(&self, x: f64, y: f64) -> (i32, i32) {
+        let x = x / self.tile_width as f64;
+        let y = y / self.tile_height as f64;
+        ((y + x).floor() as i32, (y - x).floor() as i32)
+    }
+
+    /// Returns the position of the top left corner of the bounding box of the tile at `col` and
+    /// `row` of a staggered map, in pixels relative to the one of the tile at (0, 0).
+    ///
+    /// Which rows or columns are shifted by half a tile depends on the map's
+    /// [stagger axis](Map::stagger_axis) and [stagger index](Map::stagger_index). Like in Tiled,
+    /// odd tile sizes are rounded down to even ones. Only meaningful for
+    /// [staggered](Orientation::Staggered) maps; calling it on a map with any other orientation
+    /// panics in debug builds.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use tiled::{MapBuilder, Orientation};
+    ///
+    /// let map = MapBuilder::new()
+    ///     .with_orientation(Orientation::Staggered)
+    ///     .with_size(10, 10)
+    ///     .with_tile_size(64, 32)
+    ///     .build()?;
+    ///
+    /// // By default, odd rows are shifted right by half a tile.
+    /// assert_eq!(map.staggered_tile_to_screen(2, 1), (160, 16));
+    /// assert_eq!(map.staggered_screen_to_tile(192, 32), (2, 1));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn staggered_tile_to_screen(&self, col: i32, row: i32) -> (i32, i32) {
+        self.debug_assert_orientation(Orientation::Staggered);
+        let (half_width, half_height) = self.half_tile_size();
+        match (self.stagger_axis, self.stagger_index) {
+            (StaggerAxis::Y, StaggerIndex::Odd) => (
+                col * 2 * half_width + (row & 1) * half_width,
+                row * half_height,
+            ),
+            (StaggerAxis::Y, StaggerIndex::Even) => (
+                col * 2 * half_width + (1 - (row & 1)) * half_width,
+                row * half_height,
+            ),
+            (StaggerAxis::X, StaggerIndex::Odd) => (
+                col * half_width,
+                row * 2 * half_height + (col & 1) * half_height,
+            ),
+            (StaggerAxis::X, StaggerIndex::Even) => (
+                col * half_width,
+                row * 2 * half_height + (1 - (col & 1)) * half_height,
+            ),
+        }
+    }
+
+    /// Returns the column and row of the tile whose diamond contains the pixel at `sx` and `sy`
+    /// in a staggered map, the inverse of [`Map::staggered_tile_to_screen`].
+    ///
+    /// Positions outside the map give columns and rows outside of it too, which may be negative.
+    /// Only meaningful for [staggered](Orientation::Staggered) maps; calling it on a map with
+    /// any other orientation panics in debug builds.
+    pub fn staggered_screen_to_tile(&self, sx: i32, sy: i32) -> (i32, i32) {
+        self.debug_assert_orientation(Orientation::Staggered);
+        let (half_width, half_height) = self.half_tile_size();
+        // The point is within the bounding boxes of two tiles next to each other along the
+        // stagger axis, and in the diamond of whichever has the closest center.
+        let candidates = match self.stagger_axis {
+            StaggerAxis::Y => {
+                let row = sy.div_euclid(half_height);
+                [row, row - 1].map(|row| {
+                    let offset = self.staggered_tile_to_screen(0, row).0;
+                    ((sx - offset).div_euclid(2 * half_width), row)
+                })
+            }
+            StaggerAxis::X => {
+                let col = sx.div_euclid(half_width);
+                [col, col - 1].map(|col| {
+                    let offset = self.staggered_tile_to_screen(col, 0).1;
+                    (col, (sy - offset).div_euclid(2 * half_height))
+                })
+            }
+        };
+        let distance = |&(col, row): &(i32, i32)| {
+            let (x, y) = self.staggered_tile_to_screen(col, row);
+            (sx - x - half_width).abs() * half_height + (sy - y - half_height).abs() * half_width
+        };
+        if distance(&candidates[1]) < distance(&candidates[0]) {
+            candidates[1]
+        } else {
+            candidates[0]
+        }
+    }
+
+    /// Returns the position of the top left corner of the bounding box of the tile at `col` and
+    /// `row`, in pixels relative to the top left corner of the map, following the rules of the
+    /// map's [orientation](Map::orientation).
+    ///
+    /// These are the pixel coordinates Tiled uses. For isometric maps, the top left corner is the
+    /// one of the map's bounding box, so the top corner of the tile at (0, 0) is
+    /// `height * tile_width / 2` pixels right of it. Tiles whose images are bigger than the map's
+    /// tiles are drawn with their bottom left corner at the bottom left corner of this box.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use tiled::{MapBuilder, Orientation};
+    ///
+    /// let map = MapBuilder::new()
+    ///     .with_orientation(Orientation::Isometric)
+    ///     .with_size(4, 4)
+    ///     .with_tile_size(64, 32)
+    ///     .build()?;
+    ///
+    /// assert_eq!(map.tile_to_pixel(0, 0), (96.0, 0.0));
+    /// assert_eq!(map.pixel_to_tile(128.0, 16.0), (0, 0));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tile_to_pixel(&self, col: i32, row: i32) -> (f32, f32) {
+        let (tile_width, tile_height) = (self.tile_width as f32, self.tile_height as f32);
+        match self.orientation {
+            Orientation::Orthogonal => (col as f32 * tile_width, row as f32 * tile_height),
+            Orientation::Isometric => (
+                (self.height as f32 - 1. + (col - row) as f32) * tile_width / 2.,
+                (col + row) as f32 * tile_height / 2.,
+            ),
+            Orientation::Staggered => {
+                let (x, y) = self.staggered_tile_to_screen(col, row);
+                (x as f32, y as f32)
+            }
+            Orientation::Hexagonal => {
+                let (x, y) = self.hex_tile_to_screen(col, row);
+                (x as f32, y as f32)
+            }
+        }
+    }
+
+    /// Returns the column and row of the tile containing the pixel at `x` and `y`, the inverse
+    /// of [`Map::tile_to_pixel`].
+    ///
+    /// Positions outside the map give columns and rows outside of it too, which may be negative.
+    pub fn pixel_to_tile(&self, x: f32, y: f32) -> (i32, i32) {
+        match self.orientation {
+            Orientation::Orthogonal => (
+                (x / self.tile_width as f32).floor() as i32,
+                (y / self.tile_height as f32).floor() as i32,
+            ),
+            Orientation::Isometric => {
+                let origin_x = self.height as f64 * self.tile_width as f64 / 2.;
+                self.iso_pixel_to_tile(x as f64 - origin_x, y as f64)
+            }
+            Orientation::Staggered => {
+                self.staggered_screen_to_tile(x.floor() as i32, y.floor() as i32)
+            }
+            Orientation::Hexagonal => self.hex_screen_to_tile(x.floor() as i32, y.floor() as i32),
+        }
+    }
+
+    /// Returns the position of the top left corner of the bounding box of the tile at `col` and
+    /// `row` of a hexagonal map, in pixels relative to the one of the tile at (0, 0).
+    ///
+    /// Hexagons are pointy top when the [stagger axis](Map::stagger_axis) is Y and flat top when
+    /// it is X, and the [stagger index](Map::stagger_index) tells which rows or columns are
+    /// shifted by half a tile. Like in Tiled, odd tile sizes are rounded down to even ones. Only
+    /// meaningful for [hexagonal](Orientation::Hexagonal) maps; calling it on a map with any
+    /// other orientation panics in debug builds.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::Loader::new().load_tmx_map("assets/tiled_hexagonal.tmx")?;
+    ///
+    /// // 28x32 pointy top hexagons with vertical sides of 16 pixels, and odd rows shifted.
+    /// assert_eq!(map.hex_tile_to_screen(2, 3), (70, 72));
+    /// assert_eq!(map.hex_screen_to_tile(84, 88), (2, 3));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn hex_tile_to_screen(&self, col: i32, row: i32) -> (i32, i32) {
+        self.debug_assert_orientation(Orientation::Hexagonal);
+        let hex = HexLayout::new(self);
+        if hex.stagger_x {
+            let y = row * (hex.tile_height + hex.side_length_y)
+                + hex.is_staggered(col) as i32 * hex.row_height;
+            (col * hex.column_width, y)
+        } else {
+            let x = col * (hex.tile_width + hex.side_length_x)
+                + hex.is_staggered(row) as i32 * hex.column_width;
+            (x, row * hex.row_height)
+        }
+    }
+
+    /// Returns the column and row of the tile whose hexagon contains the pixel at `sx` and `sy`
+    /// in a hexagonal map, the inverse of [`Map::hex_tile_to_screen`].
+    ///
+    /// Positions outside the map give columns and rows outside of it too, which may be negative.
+    /// Only meaningful for [hexagonal](Orientation::Hexagonal) maps; calling it on a map with
+    /// any other orientation panics in debug builds.
+    pub fn hex_screen_to_tile(&self, sx: i32, sy: i32) -> (i32, i32) {
+        self.debug_assert_orientation(Orientation::Hexagonal);
+        // Like Tiled, find the grid-aligned block of two columns or rows the point is in, and
+        // then the closest of the centers of the four tiles that overlap it.
+        let hex = HexLayout::new(self);
+        let (mut x, mut y) = (sx, sy);
+        if hex.stagger_x {
+            x -= if hex.stagger_even {
+                hex.tile_width
+            } else {
+                hex.side_offset_x
+            };
+        } else {
+            y -= if hex.stagger_even {
+                hex.tile_height
+            } else {
+                hex.side_offset_y
+            };
+        }
+
+        let (block_width, block_height) =
+            ((hex.column_width * 2).max(1), (hex.row_height * 2).max(1));
+        let (mut col, mut row) = (x.div_euclid(block_width), y.div_euclid(block_height));
+        let rel = (x - col * block_width, y - row * block_height);
+        let (centers, offsets) = if hex.stagger_x {
+            col = col * 2 + hex.stagger_even as i32;
+            let left = hex.side_length_x / 2;
+            let center_x = left + hex.column_width;
+            let center_y = hex.tile_height / 2;
+            (
+                [
+                    (left, center_y),
+                    (center_x, center_y - hex.row_height),
+                    (center_x, center_y + hex.row_height),
+                    (center_x + hex.column_width, center_y),
+                ],
+                [(0, 0), (1, -1), (1, 0), (2, 0)],
+            )
+        } else {
+            row = row * 2 + hex.stagger_even as i32;
+            let top = hex.side_length_y / 2;
+            let center_x = hex.tile_width / 2;
+            let center_y = top + hex.row_height;
+            (
+                [
+                    (center_x, top),
+                    (center_x - hex.column_width, center_y),
+                    (center_x + hex.column_width, center_y),
+                    (center_x, center_y + hex.row_height),
+                ],
+                [(0, 0), (-1, 1), (0, 1), (0, 2)],
+            )
+        };
+
+        let nearest = (0..4)
+            .min_by_key(|&i| {
+                let (dx, dy) = ((centers[i].0 - rel.0) as i64, (centers[i].1 - rel.1) as i64);
+                dx * dx + dy * dy
+            })
+            .unwrap();
+        (col + offsets[nearest].0, row + offsets[nearest].1)
+    }
+
+    /// Returns the columns and rows of the six tiles next to the one at `col` and `row` of a
+    /// hexagonal map, in clockwise order.
+    ///
+    /// For pointy top hexagons ([`StaggerAxis::Y`]), the first neighbor is the one to the right,
+    /// and for flat top ones ([`StaggerAxis::X`]), the one above. Neighbors outside the map are
+    /// included too. Only meaningful for [hexagonal](Orientation::Hexagonal) maps; calling it on
+    /// a map with any other orientation panics in debug builds.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::Loader::new().load_tmx_map("assets/tiled_hexagonal.tmx")?;
+    ///
+    /// // Row 1 is shifted right, so the tiles above and below it are in columns 1 and 2.
+    /// assert_eq!(
+    ///     map.hex_neighbors(1, 1),
+    ///     [(2, 1), (2, 2), (1, 2), (0, 1), (1, 0), (2, 0)]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn hex_neighbors(&self, col: i32, row: i32) -> [(i32, i32); 6] {
+        self.debug_assert_orientation(Orientation::Hexagonal);
+        self.staggered_neighbors(col, row)
+    }
+
+    /// Returns the six tiles next to the given one in a staggered or hexagonal map, in clockwise
+    /// order. The first and fourth ones are across the axis that isn't staggered.
+    fn staggered_neighbors(&self, col: i32, row: i32) -> [(i32, i32); 6] {
+        let hex = HexLayout::new(self);
+        if hex.stagger_x {
+            // Shifted columns are lower than their neighbors.
+            let (up, down) = if hex.is_staggered(col) {
+                (row, row + 1)
+            } else {
+                (row - 1, row)
+            };
+            [
+                (col, row - 1),
+                (col + 1, up),
+                (col + 1, down),
+                (col, row + 1),
+                (col - 1, down),
+                (col - 1, up),
+            ]
+        } else {
+            // Shifted rows are further right than their neighbors.
+            let (left, right) = if hex.is_staggered(row) {
+                (col, col + 1)
+            } else {
+                (col - 1, col)
+            };
+            [
+                (col + 1, row),
+                (right, row + 1),
+                (left, row + 1),
+                (col - 1, row),
+                (left, row - 1),
+                (right, row - 1),
+            ]
+        }
+    }
+
+    /// Returns the tiles that share an edge with the one at `col` and `row`, following the
+    /// rules of the map's [orientation](Map::orientation), in clockwise order.
+    ///
+    /// These are 4 tiles for orthogonal, isometric and staggered maps, and 6 for hexagonal ones.
+    /// Tiles outside the map are left out, except for infinite maps, which have no bounds.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::MapBuilder::new()
+    ///     .with_size(10, 10)
+    ///     .with_tile_size(32, 32)
+    ///     .build()?;
+    ///
+    /// assert_eq!(map.tile_neighbors(0, 5), vec![(1, 5), (0, 6), (0, 4)]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tile_neighbors(&self, col: i32, row: i32) -> Vec<(i32, i32)> {
+        let neighbors = match self.orientation {
+            Orientation::Orthogonal | Orientation::Isometric => vec![
+                (col + 1, row),
+                (col, row + 1),
+                (col - 1, row),
+                (col, row - 1),
+            ],
+            Orientation::Staggered => {
+                let neighbors = self.staggered_neighbors(col, row);
+                vec![neighbors[1], neighbors[2], neighbors[4], neighbors[5]]
+            }
+            Orientation::Hexagonal => self.staggered_neighbors(col, row).to_vec(),
+        };
+        self.retain_in_bounds(neighbors)
+    }
+
+    /// Returns the tiles that share an edge or a corner with the one at `col` and `row`,
+    /// following the rules of the map's [orientation](Map::orientation), in clockwise order.
+    ///
+    /// These are 8 tiles for orthogonal, isometric and staggered maps. Hexagonal tiles don't
+    /// touch by their corners only, so this returns the same 6 tiles as [`Map::tile_neighbors`]
+    /// for them. Tiles outside the map are left out, except for infinite maps.
+    pub fn tile_neighbors_including_diagonals(&self, col: i32, row: i32) -> Vec<(i32, i32)> {
+        let neighbors = match self.orientation {
+            Orientation::Orthogonal | Orientation::Isometric => vec![
+                (col + 1, row),
+                (col + 1, row + 1),
+                (col, row + 1),
+                (col - 1, row + 1),
+                (col - 1, row),
+                (col - 1, row - 1),
+                (col, row - 1),
+                (col + 1, row - 1),
+            ],
+            Orientation::Staggered => {
+                let [side, first, second, other_side, third, fourth] =
+                    self.staggered_neighbors(col, row);
+                // The tiles touching this one by a corner are next to it across the axis that
+                // isn't staggered, or two rows or columns away along the one that is.
+                let (after_first, after_third) = match self.stagger_axis {
+                    StaggerAxis::Y => ((col, row + 2), (col, row - 2)),
+                    StaggerAxis::X => ((col + 2, row), (col - 2, row)),
+                };
+                vec![
+                    first,
+                    after_first,
+                    second,
+                    other_side,
+                    third,
+                    after_third,
+                    fourth,
+                    side,
+                ]
+            }
+            Orientation::Hexagonal => self.staggered_neighbors(col, row).to_vec(),
+        };
+        self.retain_in_bounds(neighbors)
+    }
+
+    fn retain_in_bounds(&self, mut tiles: Vec<(i32, i32)>) -> Vec<(i32, i32)> {
+        if !self.infinite {
+            tiles.retain(|&(col, row)| {
+                (0..self.width as i32).contains(&col) && (0..self.height as i32).contains(&row)
+            });
+        }
+        tiles
+    }
+
+    /// Returns half the width and height of the map's tiles, which are at least 1.
+    fn half_tile_size(&self) -> (i32, i32) {
+        (
+            (self.tile_width as i32 / 2).max(1),
+            (self.tile_height as i32 / 2).max(1),
+        )
+    }
+
+    fn debug_assert_orientation(&self, orientation: Orientation) {
+        debug_assert_eq!(
+            self.orientation, orientation,
+            "coordinates of another orientation used on a map"
+        );
+    }
+
+    /// Checks the map for problems that don't prevent it from being parsed, but which are likely
+    /// mistakes, such as tiles that refer to nonexistent tiles, object properties that refer to
+    /// nonexistent objects or tilesets whose GIDs overlap.
+    ///
+    /// Returns an empty vector if no problems were found. See [`Warning`] for all of the checks.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::Loader::new().load_tmx_map("assets/tiled_base64.tmx")?;
+    /// for warning in map.validate() {
+    ///     eprintln!("{}", warning);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn validate(&self) -> Vec<Warning> {
+        let mut warnings = self.parse_report.warnings.clone();
+        for tileset in &self.tilesets {
+            warnings.extend(tileset.parse_report.warnings.iter().cloned());
+        }
+
+        let mut layers = Vec::new();
+        collect_layers(self.layers(), &mut layers);
+        let mut names = HashSet::new();
+        let mut duplicate_names = HashSet::new();
+        for layer in &layers {
+            if !names.insert(layer.name.as_str()) && duplicate_names.insert(layer.name.as_str()) {
+                warnings.push(Warning::DuplicateLayerName {
+                    name: layer.name.clone(),
+                });
+            }
+        }
+
+        for layer in &layers {
+            let tile_layer = match layer.as_tile_layer() {
+                Some(tile_layer) => tile_layer,
+                None => continue,
+            };
+            if let TileLayer::Finite(finite) = tile_layer {
+                if finite.width() > self.width || finite.height() > self.height {
+                    warnings.push(Warning::LayerLargerThanMap {
+                        layer: layer.name.clone(),
+                        width: finite.width(),
+                        height: finite.height(),
+                    });
+                }
+                let expected = finite.width() as usize * finite.height() as usize;
+                let actual = finite.tiles_flat().len();
+                // Layers loaded while streaming their tiles don't store any.
+                if actual != expected && actual != 0 {
+                    warnings.push(Warning::LayerDataSizeMismatch {
+                        layer: layer.name.clone(),
+                        expected,
+                        actual,
+                    });
+                }
+            }
+            for (x, y, tile) in tile_layer.tiles() {
+                if tile.get_tile().is_none() {
+                    warnings.push(Warning::TileNotInTileset {
+                        layer: layer.name.clone(),
+                        x,
+                        y,
+                        tileset_index: tile.tileset_index(),
+                        tile_id: tile.id(),
+                    });
+                }
+            }
+        }
+
+        let mut missing = Vec::new();
+        self.find_missing_object_references(&self.properties, "", &mut missing);
+        for (property, target) in missing.drain(..) {
+            warnings.push(Warning::MissingObjectReference {
+                layer: None,
+                object_id: None,
+                property,
+                target,
+            });
+        }
+        for layer in &layers {
+            self.find_missing_object_references(&layer.properties, "", &mut missing);
+            for (property, target) in missing.drain(..) {
+                warnings.push(Warning::MissingObjectReference {
+                    layer: Some(layer.name.clone()),
+                    object_id: None,
+                    property,
+                    target,
+                });
+            }
+            let objects = match layer.as_object_layer() {
+                Some(object_layer) => object_layer.objects(),
+                None => continue,
+            };
+            for object in objects {
+                // Objects sharing a template share its warnings too, which are only returned once.
+                for warning in &object.template_report.warnings {
+                    if !warnings.contains(warning) {
+                        warnings.push(warning.clone());
+                    }
+                }
+                if let Some(tile) = object.get_tile() {
+                    if tile.get_tile().is_none() {
+                        warnings.push(Warning::ObjectTileNotInTileset {
+                            layer: layer.name.clone(),
+                            object_id: object.id(),
+                            tileset_index: match tile.tileset_location() {
+                                TilesetLocation::Map(index) => Some(*index),
+                                TilesetLocation::Template(_) => None,
+                            },
+                            tile_id: tile.id(),
+                        });
+                    }
+                }
+                self.find_missing_object_references(&object.properties, "", &mut missing);
+                for (property, target) in missing.drain(..) {
+                    warnings.push(Warning::MissingObjectReference {
+                        layer: Some(layer.name.clone()),
+                        object_id: Some(object.id()),
+                        property,
+                        target,
+                    });
+                }
+            }
+        }
+
+        for (tileset_index, tileset) in self.tilesets.iter().enumerate() {
+            let mut tiles: Vec<_> = tileset.tiles().collect();
+            tiles.sort_by_key(|(id, _)| *id);
+            for (tile_id, tile) in tiles {
+                for frame in tile.animation.iter().flatten() {
+                    if tileset.get_tile(frame.tile_id).is_none() {
+                        warnings.push(Warning::AnimationFrameNotInTileset {
+                            tileset_index,
+                            tile_id,
+                            frame_tile_id: frame.tile_id,
+                        });
+                    }
+                }
+            }
+        }
+
+        for (position, &(first_gid, first)) in self.gid_lookup.iter().enumerate() {
+            let tile_count = self.tilesets[first].computed_tile_count().unwrap_or(0);
+            match first_gid.0.checked_add(tile_count) {
+                None => warnings.push(Warning::TilesetGidOverflow {
+                    tileset_index: first,
+                }),
+                Some(end) => {
+                    if let Some(&(next_first_gid, second)) = self.gid_lookup.get(position + 1) {
+                        if end > next_first_gid.0 {
+                            warnings.push(Warning::OverlappingTilesets { first, second });
+                        }
+                    }
+                }
+            }
+        }
+
+        for (tileset_index, tileset) in self.tilesets.iter().enumerate() {
+            let image = match &tileset.image {
+                Some(image) => image,
+                None => continue,
+            };
+            let columns = tileset.columns.max(1);
+            let rows = tileset.computed_tile_count().unwrap_or(0).div_ceil(columns);
+            let size = |count: u32, tile_size: u32| {
+                tileset
+                    .margin
+                    .saturating_mul(2)
+                    .saturating_add(count.saturating_mul(tile_size))
+                    .saturating_add(count.saturating_sub(1).saturating_mul(tileset.spacing))
+            };
+            if size(columns, tileset.tile_width) > image.width as u32
+                || size(rows, tileset.tile_height) > image.height as u32
+            {
+                warnings.push(Warning::TilesetImageTooSmall { tileset_index });
+            }
+        }
+
+        warnings
+    }
+
+    /// Fills in the properties of the map and everything in it from the classes in `types`.
+    ///
+    /// Tilesets are only replaced with resolved copies if something in them changed, so that
+    /// tilesets without class properties stay shared with the cache they were loaded from.
+    pub(crate) fn resolve_project_types(&mut self, types: &ProjectTypes) {
+        types.resolve_in_place(self.user_type.as_deref(), &mut self.properties);
+        for tileset in &mut self.tilesets {
+            let mut resolved = Tileset::clone(tileset);
+            resolved.resolve_project_types(types);
+            if resolved != **tileset {
+                *tileset = Arc::new(resolved);
+            }
+        }
+        for layer in &mut self.layers {
+            layer.resolve_project_types(types);
+        }
+    }
+
+    /// Collects the object properties in `properties`, including the members of class properties,
+    /// that refer to objects that aren't in the map, sorted by name.
+    fn find_missing_object_references(
+        &self,
+        properties: &Properties,
+        prefix: &str,
+        missing: &mut Vec<(String, u32)>,
+    ) {
+        let mut names: Vec<_> = properties.keys().collect();
+        names.sort();
+        for name in names {
+            match &properties[name] {
+                PropertyValue::ObjectValue(target)
+                    if *target != 0 && self.get_object_by_id(*target).is_none() =>
+                {
+                    missing.push((format!("{}{}", prefix, name), *target));
+                }
+                PropertyValue::ClassValue { properties, .. } => self
+                    .find_missing_object_references(
+                        properties,
+                        &format!("{}{}.", prefix, name),
+                        missing,
+                    ),
+                _ => {}
+            }
+        }
+    }
+
+    /// Splits a GID into the index of the tileset it belongs to and its local ID within it.
+    fn resolve_gid(&self, gid: Gid) -> Option<(usize, TileId)> {
+        resolve_gid(&self.gid_lookup, gid)
     }
+}
 
-    /// Whether this map is infinite. An infinite map has no fixed size and can grow in all
-    /// directions. Its layer data is stored in chunks. This value determines whether the map's
-    /// tile layers are [`FiniteTileLayer`](crate::FiniteTileLayer)s or [`crate::InfiniteTileLayer`](crate::InfiniteTileLayer)s.
-    pub fn infinite(&self) -> bool {
-        self.infinite
+/// Returns the index of the tileset a GID belongs to and the tile's local ID in it, given the
+/// first GIDs of a map's tilesets along with their indices, sorted by GID.
+pub(crate) fn resolve_gid(gid_lookup: &[(Gid, usize)], gid: Gid) -> Option<(usize, TileId)> {
+    // GIDs built directly from raw tile values may still hold flipping flags.
+    let gid = Gid::from_bits(gid.0);
+    if gid == Gid::EMPTY {
+        return None;
     }
+    let position = gid_lookup
+        .partition_point(|(first_gid, _)| *first_gid <= gid)
+        .checked_sub(1)?;
+    let (first_gid, index) = gid_lookup[position];
+    Some((index, TileId::new(gid.0 - first_gid.0)))
 }
 
 impl Map {
-    /// Get a reference to the map's tilesets.
-    #[inline]
-    pub fn tilesets(&self) -> &[Arc<Tileset>] {
-        self.tilesets.as_ref()
+    /// Writes the map to `writer` in the TMX format, with the tile layer data encoded as
+    /// zlib-compressed base64. Tilesets loaded from external files are still referenced, and the
+    /// others are embedded.
+    ///
+    /// This is a shorthand for [`Map::write_to_with_options`] using the default [`WriteOptions`].
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::Loader::new().load_tmx_map("assets/tiled_base64.tmx")?;
+    ///
+    /// let mut tmx = Vec::new();
+    /// map.write_to(&mut tmx)?;
+    ///
+    /// let written = tiled::Loader::new().load_tmx_map_from(&tmx[..], "assets/tiled_base64.tmx")?;
+    /// assert_eq!(written.layers().len(), map.layers().len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<()> {
+        self.write_to_with_options(writer, &WriteOptions::default())
     }
 
-    /// Get an iterator over all the layers in the map in ascending order of their layer index.
+    /// Writes the map to `writer` in the TMX format, as specified by `options`.
+    ///
+    /// The output can be opened by Tiled, but isn't necessarily identical to the file the map
+    /// was loaded from:
+    /// - Tilesets loaded from external files are referenced by their path relative to the
+    ///   directory of [`Map::source`], and the others are embedded in the map, unless
+    ///   [`WriteOptions::with_external_tileset`] or [`WriteOptions::with_embedded_tilesets`] are
+    ///   used. External files aren't written themselves.
+    /// - Paths, such as the ones of images, are written relative to the directory of
+    ///   [`Map::source`], so the output should be saved next to the original map for them to
+    ///   stay valid. Maps without a source have them written as they are.
+    /// - Attributes with their default values are omitted, like Tiled does.
+    /// - Objects created from templates are written with all of their values, and no longer
+    ///   reference the template. Tile objects from template tilesets lose their tile.
+    /// - The contents of text objects are not stored by this crate, so they are written empty.
+    pub fn write_to_with_options<W: Write>(&self, writer: W, options: &WriteOptions) -> Result<()> {
+        let base_path = self.base_path();
+        let mut writer = XmlWriter::new(writer, base_path, options.encoding);
+        writer.first_gids = self.first_gids();
+        let (next_layer_id, next_object_id) = self.next_ids();
+
+        let mut attrs = vec![
+            ("version", self.version.clone()),
+            ("orientation", self.orientation.to_string()),
+        ];
+        if self.render_order != RenderOrder::RightDown {
+            attrs.push(("renderorder", self.render_order.to_string()));
+        }
+        attrs.extend([
+            ("width", self.width.to_string()),
+            ("height", self.height.to_string()),
+            ("tilewidth", self.tile_width.to_string()),
+            ("tileheight", self.tile_height.to_string()),
+            ("infinite", bool_to_string(self.infinite)),
+        ]);
+        if self.orientation == Orientation::Hexagonal {
+            attrs.push(("hexsidelength", self.hex_side_length.to_string()));
+        }
+        if let Some((stagger_axis, stagger_index)) = self.stagger() {
+            attrs.push(("staggeraxis", stagger_axis.to_string()));
+            attrs.push(("staggerindex", stagger_index.to_string()));
+        }
+        if let Some(background_color) = self.background_color {
+            attrs.push(("backgroundcolor", color_to_string(background_color)));
+        }
+        if let Some(user_type) = &self.user_type {
+            attrs.push(("class", user_type.clone()));
+        }
+        attrs.push(("nextlayerid", next_layer_id.to_string()));
+        attrs.push(("nextobjectid", next_object_id.to_string()));
+        push_unparsed_attributes(&mut attrs, self.unparsed_attributes.as_deref());
+
+        writer.start("map", &attrs)?;
+        if let Some(editor_settings) = &self.editor_settings {
+            editor_settings.write_xml(&mut writer)?;
+        }
+        write_properties(&mut writer, &self.properties)?;
+        for (index, tileset) in self.tilesets.iter().enumerate() {
+            let first_gid = writer.first_gids[index];
+            match self.written_tileset_source(index, options, |path| writer.relative_path(path)) {
+                Some(source) => writer.empty(
+                    "tileset",
+                    &[("firstgid", first_gid.0.to_string()), ("source", source)],
+                )?,
+                None => tileset.write_xml(&mut writer, Some(first_gid))?,
+            }
+        }
+        for layer in &self.layers {
+            layer.write_xml(&mut writer)?;
+        }
+        writer.end()
+    }
+
+    /// Writes the map to `writer` in Tiled's JSON format, with the tile layer data written as
+    /// plain arrays of GIDs. Tilesets are referenced or embedded like with [`Map::write_to`].
+    ///
+    /// This is a shorthand for [`Map::write_json_to_with_options`] using the default
+    /// [`WriteOptions`], except for the encoding.
     ///
     /// ## Example
     /// ```
-    /// # use tiled::Loader;
-    /// #
-    /// # fn main() {
-    /// # struct Renderer;
-    /// # impl Renderer {
-    /// #     fn render(&self, _: tiled::TileLayer) {}
-    /// # }
-    /// # let my_renderer = Renderer;
-    /// # let map = Loader::new()
-    /// #     .load_tmx_map("assets/tiled_group_layers.tmx")
-    /// #     .unwrap();
-    /// #
-    /// let tile_layers = map.layers().filter_map(|layer| match layer.layer_type() {
-    ///     tiled::LayerType::Tiles(layer) => Some(layer),
-    ///     _ => None,
-    /// });
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::Loader::new().load_tmx_map("assets/tiled_base64.tmx")?;
     ///
-    /// for layer in tile_layers {
-    ///     my_renderer.render(layer);
-    /// }
+    /// let mut json = Vec::new();
+    /// map.write_json_to(&mut json)?;
+    /// assert!(String::from_utf8_lossy(&json).contains(r#""type": "map""#));
+    /// # Ok(())
     /// # }
     /// ```
-    #[inline]
-    pub fn layers(&self) -> impl ExactSizeIterator<Item = Layer> {
-        self.layers.iter().map(move |layer| Layer::new(self, layer))
+    #[cfg(feature = "json")]
+    pub fn write_json_to<W: Write>(&self, writer: W) -> Result<()> {
+        self.write_json_to_with_options(
+            writer,
+            &WriteOptions::new().with_encoding(crate::TileDataEncoding::Csv),
+        )
     }
 
-    /// Returns the layer that has the specified index, if it exists.
-    pub fn get_layer(&self, index: usize) -> Option<Layer> {
-        self.layers.get(index).map(|data| Layer::new(self, data))
+    /// Returns the map in Tiled's JSON format, like [`Map::write_json_to`].
+    #[cfg(feature = "json")]
+    pub fn to_json_string(&self) -> Result<String> {
+        let options = WriteOptions::new().with_encoding(crate::TileDataEncoding::Csv);
+        serde_json::to_string_pretty(&self.write_json(&options)?).map_err(Error::JsonEncodingError)
+    }
+
+    /// Writes the map to `writer` in Tiled's JSON format, as specified by `options`.
+    ///
+    /// [`TileDataEncoding::Csv`](crate::TileDataEncoding::Csv) writes the tile layer data as
+    /// plain arrays of GIDs. External tilesets are referenced like in TMX files, and the `source`
+    /// given to [`WriteOptions::with_external_tileset`] may point to either a TSX or a JSON
+    /// tileset.
+    ///
+    /// The same caveats as with [`Map::write_to_with_options`] apply. Additionally, images
+    /// embedded in the map can't be stored in JSON files, so they are left out.
+    #[cfg(feature = "json")]
+    pub fn write_json_to_with_options<W: Write>(
+        &self,
+        writer: W,
+        options: &WriteOptions,
+    ) -> Result<()> {
+        serde_json::to_writer_pretty(writer, &self.write_json(options)?)
+            .map_err(Error::JsonEncodingError)
+    }
+
+    #[cfg(feature = "json")]
+    fn write_json(&self, options: &WriteOptions) -> Result<serde_json::Value> {
+        use crate::write::{json_object, JsonWriter};
+
+        let base_path = self.base_path();
+        let mut json_writer = JsonWriter::new(base_path, options.encoding);
+        json_writer.first_gids = self.first_gids();
+        let (next_layer_id, next_object_id) = self.next_ids();
+
+        let tilesets = self
+            .tilesets
+            .iter()
+            .enumerate()
+            .map(|(index, tileset)| {
+                let first_gid = json_writer.first_gids[index];
+                let relative_path = |path: &Path| json_writer.relative_path(path);
+                match self.written_tileset_source(index, options, relative_path) {
+                    Some(source) => serde_json::json!({
+                        "firstgid": first_gid.0,
+                        "source": source,
+                    }),
+                    None => tileset.write_json(&json_writer, Some(first_gid)),
+                }
+            })
+            .collect();
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| layer.write_json(&json_writer))
+            .collect::<Result<_>>()?;
+
+        let mut json = json_object(vec![
+            ("type", "map".into()),
+            ("version", self.version.clone().into()),
+            ("orientation", self.orientation.to_string().into()),
+            ("renderorder", self.render_order.to_string().into()),
+            ("width", self.width.into()),
+            ("height", self.height.into()),
+            ("tilewidth", self.tile_width.into()),
+            ("tileheight", self.tile_height.into()),
+            ("infinite", self.infinite.into()),
+            ("nextlayerid", next_layer_id.into()),
+            ("nextobjectid", next_object_id.into()),
+            ("tilesets", tilesets),
+            ("layers", layers),
+        ]);
+        if self.orientation == Orientation::Hexagonal {
+            json.insert("hexsidelength".to_owned(), self.hex_side_length.into());
+        }
+        if let Some((stagger_axis, stagger_index)) = self.stagger() {
+            json.insert("staggeraxis".to_owned(), stagger_axis.to_string().into());
+            json.insert("staggerindex".to_owned(), stagger_index.to_string().into());
+        }
+        if let Some(background_color) = self.background_color {
+            json.insert(
+                "backgroundcolor".to_owned(),
+                color_to_string(background_color).into(),
+            );
+        }
+        if let Some(user_type) = &self.user_type {
+            json.insert("class".to_owned(), user_type.clone().into());
+        }
+        if let Some(editor_settings) = &self.editor_settings {
+            json.insert("editorsettings".to_owned(), editor_settings.write_json());
+        }
+        if let Some(properties) = crate::properties::write_json_properties(&self.properties) {
+            json.insert("properties".to_owned(), properties);
+        }
+        Ok(json.into())
+    }
+
+    /// Returns the directory paths are written relative to, which is that of [`Self::source`], or
+    /// the current one for maps without a source.
+    fn base_path(&self) -> &Path {
+        self.source
+            .as_deref()
+            .and_then(Path::parent)
+            .unwrap_or_else(|| Path::new(""))
+    }
+
+    /// Returns the first GID of each tileset, indexed like [`Self::tilesets`].
+    fn first_gids(&self) -> Vec<Gid> {
+        let mut first_gids = vec![Gid::EMPTY; self.tilesets.len()];
+        for (first_gid, index) in &self.gid_lookup {
+            first_gids[*index] = *first_gid;
+        }
+        first_gids
+    }
+
+    /// Returns the IDs the next layer and object added to the map would get.
+    fn next_ids(&self) -> (u32, u32) {
+        let (max_layer_id, max_object_id) = self.layers.iter().map(LayerData::max_ids).fold(
+            (0, 0),
+            |(layer, object), (max_layer, max_object)| {
+                (layer.max(max_layer), object.max(max_object))
+            },
+        );
+        (max_layer_id + 1, max_object_id + 1)
+    }
+
+    /// Returns the stagger axis and index, if the map's orientation uses them.
+    /// Returns the `source` the tileset at `index` is referenced by when writing the map, or
+    /// [`None`] if it is embedded. The paths of the files tilesets were loaded from are turned
+    /// into strings with `relative_path`.
+    fn written_tileset_source(
+        &self,
+        index: usize,
+        options: &WriteOptions,
+        relative_path: impl FnOnce(&Path) -> String,
+    ) -> Option<String> {
+        match (
+            options.external_tilesets.get(&index),
+            self.tileset_source(index),
+        ) {
+            (Some(source), _) => Some(path_to_string(source)),
+            (None, Some(TilesetSource::External(path))) if !options.embed_tilesets => {
+                Some(relative_path(path))
+            }
+            _ => None,
+        }
+    }
+
+    fn stagger(&self) -> Option<(StaggerAxis, StaggerIndex)> {
+        match self.orientation {
+            Orientation::Staggered | Orientation::Hexagonal => {
+                Some((self.stagger_axis, self.stagger_index))
+            }
+            _ => None,
+        }
     }
 }
 
 impl Map {
+    /// Parses a map read from `map_path`, if it was read from a file. The paths it references are
+    /// relative to `path_relative_to`.
     pub(crate) fn parse_xml(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
-        map_path: &Path,
+        map_path: Option<&Path>,
+        path_relative_to: &Path,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        context: &mut MapParseContext,
     ) -> Result<Map> {
+        let unparsed_attributes;
         let (
-            (c, infinite, user_type, user_class, stagger_axis, stagger_index),
+            (
+                c,
+                infinite,
+                user_type,
+                user_class,
+                stagger_axis,
+                stagger_index,
+                hex_side_length,
+                render_order,
+            ),
             (v, o, w, h, tw, th),
         ) = get_attrs!(
-            for v in attrs {
+            // These describe the file rather than the map, and are worked out again when writing it.
+            for v in attrs, unparsed(context.options.unparsed_attributes) unparsed_attributes except ["tiledversion", "nextlayerid", "nextobjectid"] {
                 Some("backgroundcolor") => colour ?= v.parse(),
-                Some("infinite") => infinite = v == "1",
+                Some("infinite") => infinite ?= parse_bool_attr(v).ok_or(()),
                 Some("type") => user_type ?= v.parse(),
                 Some("class") => user_class ?= v.parse(),
                 Some("staggeraxis") => stagger_axis ?= v.parse::<StaggerAxis>(),
                 Some("staggerindex") => stagger_index ?= v.parse::<StaggerIndex>(),
+                Some("hexsidelength") => hex_side_length ?= v.parse::<u32>(),
+                Some("renderorder") => render_order ?= v.parse::<RenderOrder>(),
                 "version" => version = v,
                 "orientation" => orientation ?= v.parse::<Orientation>(),
                 "width" => width ?= v.parse::<u32>(),
@@ -150,96 +1948,63 @@ impl Map {
                 "tilewidth" => tile_width ?= v.parse::<u32>(),
                 "tileheight" => tile_height ?= v.parse::<u32>(),
             }
-            ((colour, infinite, user_type, user_class, stagger_axis, stagger_index), (version, orientation, width, height, tile_width, tile_height))
+            ((colour, infinite, user_type, user_class, stagger_axis, stagger_index, hex_side_length, render_order), (version, orientation, width, height, tile_width, tile_height))
         );
 
+        context.options.limits.check_map_size("The map", w, h)?;
         let infinite = infinite.unwrap_or(false);
         let user_type = user_type.or(user_class);
         let stagger_axis = stagger_axis.unwrap_or_default();
         let stagger_index = stagger_index.unwrap_or_default();
+        let hex_side_length = hex_side_length.unwrap_or(0);
+        let render_order = render_order.unwrap_or_default();
 
-        // We can only parse sequentally, but tilesets are guaranteed to appear before layers.
-        // So we can pass in tileset data to layer construction without worrying about unfinished
-        // data usage.
-        let mut layers = Vec::new();
+        // Tilesets usually appear before layers, but not every tool writes them that way. Layers
+        // need all of the map's tilesets to resolve their tiles, so they are only read here and
+        // parsed once the rest of the map has been.
+        let mut pending_layers = Vec::new();
         let mut properties = HashMap::new();
         let mut tilesets = Vec::new();
+        let mut tileset_sources = Vec::new();
+        let mut editor_settings = None;
 
         parse_tag!(parser, "map", {
+            "editorsettings" => |_| {
+                editor_settings = Some(EditorSettings::parse_xml(parser)?);
+                Ok(())
+            },
             "tileset" => |attrs: Vec<OwnedAttribute>| {
-                let res = Tileset::parse_xml_in_map(parser, &attrs, map_path,  reader, cache)?;
+                let res = Tileset::parse_xml_in_map(parser, &attrs, map_path.unwrap_or_else(|| Path::new("")), path_relative_to, reader, cache, &context.options)?;
                 match res.result_type {
                     EmbeddedParseResultType::ExternalReference { tileset_path } => {
-                        let tileset = if let Some(ts) = cache.get_tileset(&tileset_path) {
-                            ts
-                        } else {
-                            let tileset = Arc::new(crate::parse::xml::parse_tileset(&tileset_path,  reader, cache)?);
-                            cache.insert_tileset(tileset_path.clone(), tileset.clone());
-                            tileset
-                        };
+                        let tileset = cached_or_parse(cache, &tileset_path, &context.options, |cache| {
+                            crate::parse::parse_tileset(&tileset_path, reader, cache, &context.options).map(Arc::new)
+                        })?;
 
                         tilesets.push(MapTilesetGid{first_gid: res.first_gid, tileset});
+                        tileset_sources.push(TilesetSource::External(tileset_path));
                     }
                     EmbeddedParseResultType::Embedded { tileset } => {
                         tilesets.push(MapTilesetGid{first_gid: res.first_gid, tileset: Arc::new(tileset)});
+                        tileset_sources.push(TilesetSource::Embedded);
                     },
                 };
                 Ok(())
             },
             "layer" => |attrs| {
-                layers.push(LayerData::new(
-                    parser,
-                    attrs,
-                    LayerTag::Tiles,
-                    infinite,
-                    map_path,
-                    &tilesets,
-                    None,
-                    reader,
-                    cache
-                )?);
+                pending_layers.push((LayerTag::Tiles, attrs, read_element(parser)?));
                 Ok(())
             },
             "imagelayer" => |attrs| {
-                layers.push(LayerData::new(
-                    parser,
-                    attrs,
-                    LayerTag::Image,
-                    infinite,
-                    map_path,
-                    &tilesets,
-                    None,
-                    reader,
-                    cache
-                )?);
+                pending_layers.push((LayerTag::Image, attrs, read_element(parser)?));
                 Ok(())
             },
             "objectgroup" => |attrs| {
-                layers.push(LayerData::new(
-                    parser,
-                    attrs,
-                    LayerTag::Objects,
-                    infinite,
-                    map_path,
-                    &tilesets,
-                    None,
-                    reader,
-                    cache
-                )?);
+                pending_layers.push((LayerTag::Objects, attrs, read_element(parser)?));
                 Ok(())
             },
             "group" => |attrs| {
-                layers.push(LayerData::new(
-                    parser,
-                    attrs,
-                    LayerTag::Group,
-                    infinite,
-                    map_path,
-                    &tilesets,
-                    None,
-                    reader,
-                    cache
-                )?);
+                pending_layers.push((LayerTag::Group, attrs, read_element(parser)?));
                 Ok(())
             },
             "properties" => |_| {
@@ -248,39 +2013,312 @@ impl Map {
             },
         });
 
-        // We do not need first GIDs any more
+        let mut layers = Vec::with_capacity(pending_layers.len());
+        for (tag, attrs, events) in pending_layers {
+            layers.push(LayerData::new(
+                &mut events.into_iter(),
+                attrs,
+                tag,
+                infinite,
+                path_relative_to,
+                &tilesets,
+                None,
+                reader,
+                cache,
+                context,
+            )?);
+        }
+
+        #[cfg(feature = "rayon")]
+        {
+            let deferred = std::mem::take(&mut context.deferred_tile_data);
+            let mut decoded = crate::layers::decode_deferred(deferred, &tilesets).into_iter();
+            for layer in &mut layers {
+                layer.fill_deferred_tiles(&mut decoded, &context.options.limits)?;
+            }
+        }
+
+        let mut gid_lookup: Vec<_> = tilesets
+            .iter()
+            .enumerate()
+            .map(|(index, ts)| (ts.first_gid, index))
+            .collect();
+        gid_lookup.sort_unstable();
         let tilesets = tilesets.into_iter().map(|ts| ts.tileset).collect();
 
         Ok(Map {
             version: v,
+            source: map_path.map(Path::to_owned),
             orientation: o,
+            render_order,
             width: w,
             height: h,
             tile_width: tw,
             tile_height: th,
             stagger_axis,
             stagger_index,
+            hex_side_length,
             tilesets,
+            tileset_sources,
+            gid_lookup,
             layers,
             properties,
             background_color: c,
             infinite,
             user_type,
+            editor_settings,
+            unparsed_attributes,
+            parse_report: ParseReport::default(),
+            object_index: ObjectIndex::default(),
         })
     }
 }
 
+/// A builder for creating [`Map`]s from code rather than from a file.
+///
+/// The size of the map and of its tiles must be set before calling [`MapBuilder::build`]. The
+/// rest of its values default to those of an empty orthogonal map.
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// use tiled::{MapBuilder, Orientation};
+///
+/// let tileset = tiled::parse_tileset_file("assets/tilesheet.tsx")?;
+/// let map = MapBuilder::new()
+///     .with_orientation(Orientation::Isometric)
+///     .with_size(20, 10)
+///     .with_tile_size(32, 16)
+///     .with_tileset(tileset)
+///     .build()?;
+///
+/// assert_eq!(map.width, 20);
+/// assert_eq!(map.tilesets()[0].name, "tilesheet");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct MapBuilder {
+    source: Option<PathBuf>,
+    orientation: Orientation,
+    size: Option<(u32, u32)>,
+    tile_size: Option<(u32, u32)>,
+    infinite: bool,
+    tilesets: Vec<Arc<Tileset>>,
+    layers: Vec<LayerData>,
+    properties: Properties,
+    background_color: Option<Color>,
+    user_type: Option<String>,
+}
+
+impl MapBuilder {
+    /// Creates a builder with no size set, no tilesets and no layers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the path the map is treated as being loaded from, which is used to resolve the paths
+    /// written with [`Map::write_to`]. Defaults to none, in which case they are written as they
+    /// are.
+    pub fn with_source(mut self, source: impl Into<PathBuf>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Sets the way tiles are laid out in the map. Defaults to [`Orientation::Orthogonal`].
+    pub fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Sets the width and height of the map, in tiles.
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    /// Sets the width and height of the map's tiles, in pixels.
+    pub fn with_tile_size(mut self, tile_width: u32, tile_height: u32) -> Self {
+        self.tile_size = Some((tile_width, tile_height));
+        self
+    }
+
+    /// Sets whether the map is infinite. Defaults to `false`.
+    pub fn with_infinite(mut self, infinite: bool) -> Self {
+        self.infinite = infinite;
+        self
+    }
+
+    /// Adds a tileset to the map. Tilesets are given consecutive ranges of GIDs in the order
+    /// they are added, starting at 1.
+    pub fn with_tileset(mut self, tileset: impl Into<Arc<Tileset>>) -> Self {
+        self.tilesets.push(tileset.into());
+        self
+    }
+
+    /// Adds a layer on top of the layers added so far. The tiles in it refer to tilesets by their
+    /// index, in the order they were added.
+    pub fn with_layer(mut self, layer: LayerData) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    /// Sets the custom properties of the map.
+    pub fn with_properties(mut self, properties: Properties) -> Self {
+        self.properties = properties;
+        self
+    }
+
+    /// Sets the background color of the map.
+    pub fn with_background_color(mut self, background_color: Color) -> Self {
+        self.background_color = Some(background_color);
+        self
+    }
+
+    /// Sets the type of the map, which is arbitrary and set by the user.
+    pub fn with_user_type(mut self, user_type: impl Into<String>) -> Self {
+        self.user_type = Some(user_type.into());
+        self
+    }
+
+    /// Creates the map.
+    ///
+    /// Returns [`Error::MissingBuilderField`] if its size or tile size weren't set, and
+    /// [`Error::InvalidTileFound`] if a layer contains a tile from a tileset that wasn't added.
+    pub fn build(self) -> Result<Map> {
+        let (width, height) = self.size.ok_or_else(|| Error::MissingBuilderField {
+            field: "size".to_owned(),
+        })?;
+        let (tile_width, tile_height) =
+            self.tile_size.ok_or_else(|| Error::MissingBuilderField {
+                field: "tile_size".to_owned(),
+            })?;
+
+        let mut next_gid = 1;
+        let gid_lookup = self
+            .tilesets
+            .iter()
+            .enumerate()
+            .map(|(index, tileset)| {
+                let first_gid = Gid(next_gid);
+                next_gid += tileset.computed_tile_count().unwrap_or(0);
+                (first_gid, index)
+            })
+            .collect();
+
+        let map = Map {
+            source: self.source,
+            orientation: self.orientation,
+            width,
+            height,
+            tile_width,
+            tile_height,
+            tileset_sources: vec![TilesetSource::Embedded; self.tilesets.len()],
+            tilesets: self.tilesets,
+            gid_lookup,
+            layers: self.layers,
+            properties: self.properties,
+            background_color: self.background_color,
+            infinite: self.infinite,
+            user_type: self.user_type,
+            ..Map::default()
+        };
+
+        let mut layers = Vec::new();
+        collect_layers(map.layers(), &mut layers);
+        let tileset_count = map.tilesets.len();
+        let has_unknown_tileset =
+            layers
+                .iter()
+                .filter_map(|layer| layer.as_tile_layer())
+                .any(|tiles| {
+                    tiles
+                        .tiles()
+                        .any(|(_, _, tile)| tile.tileset_index() >= tileset_count)
+                });
+        if has_unknown_tileset {
+            return Err(Error::InvalidTileFound);
+        }
+
+        Ok(map)
+    }
+}
+
+/// What a property returned by [`Map::get_all_properties`] belongs to.
+///
+/// Displays as a short path identifying the owner, such as `layer:Ground`, `object:42` or
+/// `tile:1:3`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum PropertyOwner<'map> {
+    /// The map itself.
+    Map,
+    /// The tileset with the given index in the map.
+    Tileset(usize),
+    /// A tile of one of the map's tilesets.
+    Tile {
+        /// The index of the tileset in the map.
+        tileset_index: usize,
+        /// The local ID of the tile within the tileset.
+        tile_id: TileId,
+    },
+    /// The layer with the given name.
+    Layer(&'map str),
+    /// The object with the given ID.
+    Object(u32),
+}
+
+impl fmt::Display for PropertyOwner<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropertyOwner::Map => write!(f, "map"),
+            PropertyOwner::Tileset(index) => write!(f, "tileset:{}", index),
+            PropertyOwner::Tile {
+                tileset_index,
+                tile_id,
+            } => write!(f, "tile:{}:{}", tileset_index, tile_id),
+            PropertyOwner::Layer(name) => write!(f, "layer:{}", name),
+            PropertyOwner::Object(id) => write!(f, "object:{}", id),
+        }
+    }
+}
+
+/// Collects the given layers along with all of the layers nested in them, in order.
+fn collect_layers<'map>(layers: impl Iterator<Item = Layer<'map>>, into: &mut Vec<Layer<'map>>) {
+    for layer in layers {
+        into.push(layer);
+        if let Some(group) = layer.as_group_layer() {
+            collect_layers(group.layers(), into);
+        }
+    }
+}
+
+/// Like [`collect_layers`], but skipping hidden layers and the layers within hidden groups.
+fn collect_visible_layers<'map>(
+    layers: impl Iterator<Item = Layer<'map>>,
+    into: &mut Vec<Layer<'map>>,
+) {
+    for layer in layers.filter(|layer| layer.visible) {
+        into.push(layer);
+        if let Some(group) = layer.as_group_layer() {
+            collect_visible_layers(group.layers(), into);
+        }
+    }
+}
+
 // Specifies whether the odd or even rows/columns are shifted half a tile
 // right/down. Only applies to Staggered and Hexagonal map orientations.
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum StaggerIndex {
     Even,
     #[default]
     Odd,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 /// An error arising from trying to parse an [`StaggerIndex`] that is not valid.
 pub struct StaggerIndexError {
     /// The invalid string found.
@@ -310,17 +2348,28 @@ impl FromStr for StaggerIndex {
     }
 }
 
+impl fmt::Display for StaggerIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StaggerIndex::Even => write!(f, "even"),
+            StaggerIndex::Odd => write!(f, "odd"),
+        }
+    }
+}
+
 // Specifies which axis is staggered. Only applies to Staggered and Hexagonal
 // map orientations.
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum StaggerAxis {
     X,
     #[default]
     Y,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 /// An error arising from trying to parse an [`StaggerAxis`] that is not valid.
 pub struct StaggerAxisError {
     /// The invalid string found.
@@ -350,17 +2399,77 @@ impl FromStr for StaggerAxis {
     }
 }
 
+impl fmt::Display for StaggerAxis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StaggerAxis::X => write!(f, "x"),
+            StaggerAxis::Y => write!(f, "y"),
+        }
+    }
+}
+
+/// The measurements of the tiles of a hexagonal map, as Tiled's hexagonal renderer computes them.
+struct HexLayout {
+    tile_width: i32,
+    tile_height: i32,
+    side_length_x: i32,
+    side_length_y: i32,
+    side_offset_x: i32,
+    side_offset_y: i32,
+    column_width: i32,
+    row_height: i32,
+    stagger_x: bool,
+    stagger_even: bool,
+}
+
+impl HexLayout {
+    fn new(map: &Map) -> Self {
+        // Odd tile sizes are rounded down, so that tiles are always split in equal halves.
+        let tile_width = (map.tile_width & !1) as i32;
+        let tile_height = (map.tile_height & !1) as i32;
+        let stagger_x = map.stagger_axis == StaggerAxis::X;
+        let side_length = map.hex_side_length as i32;
+        let (side_length_x, side_length_y) = if stagger_x {
+            (side_length, 0)
+        } else {
+            (0, side_length)
+        };
+        let side_offset_x = (tile_width - side_length_x) / 2;
+        let side_offset_y = (tile_height - side_length_y) / 2;
+        Self {
+            tile_width,
+            tile_height,
+            side_length_x,
+            side_length_y,
+            side_offset_x,
+            side_offset_y,
+            column_width: side_offset_x + side_length_x,
+            row_height: side_offset_y + side_length_y,
+            stagger_x,
+            stagger_even: map.stagger_index == StaggerIndex::Even,
+        }
+    }
+
+    /// Returns whether the column or row `index` along the stagger axis is shifted.
+    fn is_staggered(&self, index: i32) -> bool {
+        (index & 1 != 0) != self.stagger_even
+    }
+}
+
 /// Represents the way tiles are laid out in a map.
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum Orientation {
+    #[default]
     Orthogonal,
     Isometric,
     Staggered,
     Hexagonal,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 /// An error arising from trying to parse an [`Orientation`] that is not valid.
 pub struct OrientationParseError {
     /// The invalid string found.
@@ -403,6 +2512,65 @@ impl fmt::Display for Orientation {
     }
 }
 
+/// The order in which the tiles of a map's tile layers are drawn, starting from the given corner.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+#[allow(missing_docs)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum RenderOrder {
+    #[default]
+    RightDown,
+    RightUp,
+    LeftDown,
+    LeftUp,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// An error arising from trying to parse a [`RenderOrder`] that is not valid.
+pub struct RenderOrderParseError {
+    /// The invalid string found.
+    pub str_found: String,
+}
+
+impl std::fmt::Display for RenderOrderParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "failed to parse render order, valid options are `right-down`, `right-up`, \
+        `left-down` and `left-up` but got `{}` instead",
+            self.str_found
+        ))
+    }
+}
+
+impl std::error::Error for RenderOrderParseError {}
+
+impl FromStr for RenderOrder {
+    type Err = RenderOrderParseError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "right-down" => Ok(RenderOrder::RightDown),
+            "right-up" => Ok(RenderOrder::RightUp),
+            "left-down" => Ok(RenderOrder::LeftDown),
+            "left-up" => Ok(RenderOrder::LeftUp),
+            _ => Err(RenderOrderParseError {
+                str_found: s.to_owned(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for RenderOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderOrder::RightDown => write!(f, "right-down"),
+            RenderOrder::RightUp => write!(f, "right-up"),
+            RenderOrder::LeftDown => write!(f, "left-down"),
+            RenderOrder::LeftUp => write!(f, "left-up"),
+        }
+    }
+}
+
 /// A Tiled global tile ID.
 ///
 /// These are used to identify tiles in a map. Since the map may have more than one tileset, an
@@ -413,11 +2581,117 @@ impl fmt::Display for Orientation {
 /// a starting GID of 1.
 ///
 /// See also: <https://doc.mapeditor.org/en/latest/reference/global-tile-ids/>
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub(crate) struct Gid(pub u32);
+///
+/// The raw values of tiles stored in maps also hold whether the tile is flipped in their highest
+/// bits. Use [`Gid::from_bits`] to turn them into a GID, rather than wrapping them directly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Gid(pub u32);
 
 impl Gid {
     /// The GID representing an empty tile in the map.
-    #[allow(dead_code)]
     pub const EMPTY: Gid = Gid(0);
+
+    /// Creates a GID from the raw value of a tile, such as [`StreamedTile::gid`], leaving its
+    /// flipping flags out.
+    ///
+    /// [`StreamedTile::gid`]: crate::StreamedTile::gid
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Gid;
+    ///
+    /// // The highest bit of this tile marks it as flipped horizontally.
+    /// assert_eq!(Gid::from_bits(0x8000_0005), Gid(5));
+    /// ```
+    pub fn from_bits(bits: u32) -> Gid {
+        Gid(bits & !FlipFlags::GID_MASK)
+    }
+}
+
+/// How a tile placed in a map is flipped or rotated, stored in the highest bits of its raw GID.
+///
+/// Flags can be combined and tested like a bitflag set:
+/// ```
+/// use tiled::FlipFlags;
+///
+/// let flip = FlipFlags::from_gid_raw(0xA000_0005);
+/// assert_eq!(flip, FlipFlags::HORIZONTAL | FlipFlags::DIAGONAL);
+/// assert!(flip.contains(FlipFlags::HORIZONTAL));
+/// assert!(!flip.contains(FlipFlags::VERTICAL));
+/// assert_eq!((flip & FlipFlags::DIAGONAL).bits(), FlipFlags::DIAGONAL.bits());
+/// ```
+///
+/// See also: <https://doc.mapeditor.org/en/latest/reference/global-tile-ids/#tile-flipping>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FlipFlags(u8);
+
+impl FlipFlags {
+    /// No flipping at all.
+    pub const EMPTY: FlipFlags = FlipFlags(0);
+    /// The tile is flipped on its Y axis (horizontally).
+    pub const HORIZONTAL: FlipFlags = FlipFlags(0b1000);
+    /// The tile is flipped on its X axis (vertically).
+    pub const VERTICAL: FlipFlags = FlipFlags(0b0100);
+    /// The tile's X and Y axes are swapped, flipping it over its anti-diagonal.
+    pub const DIAGONAL: FlipFlags = FlipFlags(0b0010);
+    /// The tile is rotated by 120 degrees, which is only possible for the tiles of hexagonal maps.
+    /// Tiled never sets it on maps of other orientations, and calls it `RotatedHexagonal120`.
+    pub const ROTATED_120: FlipFlags = FlipFlags(0b0001);
+
+    /// How far the flags are shifted in a raw GID.
+    const GID_SHIFT: u32 = 28;
+    /// The bits of a raw GID holding flags rather than the GID itself.
+    pub(crate) const GID_MASK: u32 = 0xF000_0000;
+
+    /// Decodes the flags stored in the highest bits of a raw tile value, such as
+    /// [`StreamedTile::gid`], ignoring the GID itself.
+    ///
+    /// [`StreamedTile::gid`]: crate::StreamedTile::gid
+    pub fn from_gid_raw(gid: u32) -> FlipFlags {
+        FlipFlags((gid >> Self::GID_SHIFT) as u8)
+    }
+
+    /// Returns the flags as they are stored in the highest bits of a raw GID.
+    pub(crate) fn gid_bits(self) -> u32 {
+        (self.0 as u32) << Self::GID_SHIFT
+    }
+
+    /// Returns the raw value of the flags, the highest four bits of a raw GID shifted down.
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Returns whether every flag set in `other` is also set in `self`.
+    pub fn contains(self, other: FlipFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Returns whether no flag is set.
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for FlipFlags {
+    type Output = FlipFlags;
+
+    fn bitor(self, rhs: FlipFlags) -> FlipFlags {
+        FlipFlags(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for FlipFlags {
+    fn bitor_assign(&mut self, rhs: FlipFlags) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl BitAnd for FlipFlags {
+    type Output = FlipFlags;
+
+    fn bitand(self, rhs: FlipFlags) -> FlipFlags {
+        FlipFlags(self.0 & rhs.0)
+    }
 }
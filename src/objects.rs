@@ -1,19 +1,25 @@
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{collections::HashMap, io::Write, path::Path, sync::Arc};
 
 use xml::attribute::OwnedAttribute;
 
+#[cfg(feature = "json")]
+use crate::parse::json::{required, JsonObject};
 use crate::{
     error::{Error, Result},
-    properties::{parse_properties, Properties},
+    parse::{cached_or_parse, ParseOptions, ParseReport},
+    properties::{parse_properties, write_properties, Properties},
     template::Template,
-    util::{get_attrs, map_wrapper, parse_tag, XmlEventResult},
-    Color, Gid, MapTilesetGid, ResourceCache, ResourceReader, Tile, TileId, Tileset,
+    util::{get_attrs, map_wrapper, parse_bool_attr, parse_tag, XmlEventResult},
+    write::{bool_to_string, color_to_string, push_unparsed_attributes, XmlWriter},
+    Color, FlipFlags, Gid, MapTilesetGid, ObjectAlignment, Orientation, ResourceCache,
+    ResourceReader, Tile, TileId, Tileset,
 };
 
 /// The location of the tileset this tile is in
 ///
 /// Tilesets can be contained within either a map or a template.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TilesetLocation {
     /// Index into the Map's tileset list, guaranteed to be a valid index of the map tileset container.
     Map(usize),
@@ -23,17 +29,14 @@ pub enum TilesetLocation {
 
 /// Stores the internal tile gid about a layer tile, along with how it is flipped.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ObjectTileData {
     /// A valid TilesetLocation that points to a tileset that **may or may not contain** this tile.
     tileset_location: TilesetLocation,
     /// The local ID of the tile in the tileset it's in.
     id: TileId,
-    /// Whether this tile is flipped on its Y axis (horizontally).
-    pub flip_h: bool,
-    /// Whether this tile is flipped on its X axis (vertically).
-    pub flip_v: bool,
-    /// Whether this tile is flipped diagonally.
-    pub flip_d: bool,
+    /// How this tile is flipped or rotated.
+    pub flip: FlipFlags,
 }
 
 impl ObjectTileData {
@@ -50,33 +53,22 @@ impl ObjectTileData {
         &self.tileset_location
     }
 
-    const FLIPPED_HORIZONTALLY_FLAG: u32 = 0x80000000;
-    const FLIPPED_VERTICALLY_FLAG: u32 = 0x40000000;
-    const FLIPPED_DIAGONALLY_FLAG: u32 = 0x20000000;
-    const ALL_FLIP_FLAGS: u32 = Self::FLIPPED_HORIZONTALLY_FLAG
-        | Self::FLIPPED_VERTICALLY_FLAG
-        | Self::FLIPPED_DIAGONALLY_FLAG;
-
     /// Creates a new [`ObjectTileData`] from a [`Gid`] plus its flipping bits.
     pub(crate) fn from_bits(
         bits: u32,
         tilesets: &[MapTilesetGid],
         for_tileset: Option<Arc<Tileset>>,
     ) -> Option<Self> {
-        let flags = bits & Self::ALL_FLIP_FLAGS;
-        let gid = Gid(bits & !Self::ALL_FLIP_FLAGS);
-        let flip_d = flags & Self::FLIPPED_DIAGONALLY_FLAG == Self::FLIPPED_DIAGONALLY_FLAG; // Swap x and y axis (anti-diagonally) [flips over y = -x line]
-        let flip_h = flags & Self::FLIPPED_HORIZONTALLY_FLAG == Self::FLIPPED_HORIZONTALLY_FLAG; // Flip tile over y axis
-        let flip_v = flags & Self::FLIPPED_VERTICALLY_FLAG == Self::FLIPPED_VERTICALLY_FLAG; // Flip tile over x axis
+        let gid = Gid::from_bits(bits);
 
         if gid == Gid::EMPTY {
             None
         } else {
             let (tileset_location, id) = match for_tileset {
-                Some(tileset) => (TilesetLocation::Template(tileset), gid.0 - 1),
+                Some(tileset) => (TilesetLocation::Template(tileset), TileId::new(gid.0 - 1)),
                 None => {
                     let (tileset_index, tileset) = crate::util::get_tileset_for_gid(tilesets, gid)?;
-                    let id = gid.0 - tileset.first_gid.0;
+                    let id = TileId::new(gid.0 - tileset.first_gid.0);
                     (TilesetLocation::Map(tileset_index), id)
                 }
             };
@@ -84,12 +76,20 @@ impl ObjectTileData {
             Some(Self {
                 tileset_location,
                 id,
-                flip_h,
-                flip_v,
-                flip_d,
+                flip: FlipFlags::from_gid_raw(bits),
             })
         }
     }
+
+    /// Returns the GID of this tile plus its flipping bits, given the first GIDs of the map's
+    /// tilesets. Tiles from template tilesets have no GID in the map, so [`None`] is returned.
+    pub(crate) fn to_bits(&self, first_gids: &[Gid]) -> Option<u32> {
+        let first_gid = match self.tileset_location {
+            TilesetLocation::Map(index) => first_gids.get(index)?,
+            TilesetLocation::Template(_) => return None,
+        };
+        Some((first_gid.0 + self.id.get()) | self.flip.gid_bits())
+    }
 }
 
 map_wrapper!(
@@ -119,6 +119,8 @@ impl<'map> ObjectTile<'map> {
 /// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tmx-object).
 #[derive(Debug, PartialEq, Clone)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum ObjectShape {
     Rect {
         width: f32,
@@ -150,9 +152,84 @@ pub enum ObjectShape {
     },
 }
 
+impl ObjectShape {
+    /// Returns the points of polygons and polylines, relative to the position of their object.
+    fn points(&self) -> Option<&[(f32, f32)]> {
+        match self {
+            ObjectShape::Polyline { points } | ObjectShape::Polygon { points } => Some(points),
+            _ => None,
+        }
+    }
+
+    /// Returns the box around the points of a polygon or polyline as `(x, y, width, height)`,
+    /// relative to the position of their object and ignoring its rotation.
+    ///
+    /// Returns [`None`] for other shapes, and for polygons and polylines without any points.
+    pub fn bounds(&self) -> Option<(f32, f32, f32, f32)> {
+        let points = self.points().filter(|points| !points.is_empty())?;
+        let (left, top, right, bottom) = points.iter().fold(
+            (
+                f32::INFINITY,
+                f32::INFINITY,
+                f32::NEG_INFINITY,
+                f32::NEG_INFINITY,
+            ),
+            |(left, top, right, bottom), &(x, y)| {
+                (left.min(x), top.min(y), right.max(x), bottom.max(y))
+            },
+        );
+        Some((left, top, right - left, bottom - top))
+    }
+
+    /// Returns whether the point at (`x`, `y`), relative to the position of the object and
+    /// ignoring its rotation, is inside a polygon, using the even-odd rule.
+    ///
+    /// Always returns `false` for other shapes, including polylines, and for polygons with fewer
+    /// than three points. Use [`ObjectData::contains_point`] for points in the coordinates of the
+    /// object's layer.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::ObjectShape;
+    ///
+    /// let triangle = ObjectShape::Polygon {
+    ///     points: vec![(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)],
+    /// };
+    /// assert!(triangle.contains_point(2.0, 2.0));
+    /// assert!(!triangle.contains_point(8.0, 8.0));
+    /// ```
+    pub fn contains_point(&self, x: f32, y: f32) -> bool {
+        let points = match self {
+            ObjectShape::Polygon { points } if points.len() >= 3 => points,
+            _ => return false,
+        };
+        let mut inside = false;
+        let mut previous = points[points.len() - 1];
+        for &point in points {
+            // Count the edges a ray going right from the point crosses.
+            let ((x1, y1), (x2, y2)) = (previous, point);
+            if (y1 > y) != (y2 > y) && x < x1 + (y - y1) * (x2 - x1) / (y2 - y1) {
+                inside = !inside;
+            }
+            previous = point;
+        }
+        inside
+    }
+
+    /// Returns the points of a polygon or polyline in the coordinates of the layer of `object`,
+    /// which this is expected to be the shape of, with the object's position and rotation applied.
+    ///
+    /// Returns [`None`] for other shapes.
+    pub fn absolute_points(&self, object: &ObjectData) -> Option<Vec<(f32, f32)>> {
+        self.points()
+            .map(|points| points.iter().map(|&point| object.to_world(point)).collect())
+    }
+}
+
 /// The horizontal alignment of an [`ObjectShape::Text`].
-#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum HorizontalAlignment {
     #[default]
     Left,
@@ -162,8 +239,9 @@ pub enum HorizontalAlignment {
 }
 
 /// The vertical alignment of an [`ObjectShape::Text`].
-#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum VerticalAlignment {
     #[default]
     Top,
@@ -171,10 +249,34 @@ pub enum VerticalAlignment {
     Bottom,
 }
 
+impl HorizontalAlignment {
+    /// The name Tiled uses for this alignment.
+    fn name(self) -> &'static str {
+        match self {
+            HorizontalAlignment::Left => "left",
+            HorizontalAlignment::Center => "center",
+            HorizontalAlignment::Right => "right",
+            HorizontalAlignment::Justify => "justify",
+        }
+    }
+}
+
+impl VerticalAlignment {
+    /// The name Tiled uses for this alignment.
+    fn name(self) -> &'static str {
+        match self {
+            VerticalAlignment::Top => "top",
+            VerticalAlignment::Center => "center",
+            VerticalAlignment::Bottom => "bottom",
+        }
+    }
+}
+
 /// Raw data belonging to an object. Used internally and for tile collisions.
 ///
 /// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tmx-object).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ObjectData {
     id: u32,
     tile: Option<ObjectTileData>,
@@ -194,6 +296,18 @@ pub struct ObjectData {
     pub shape: ObjectShape,
     /// The object's custom properties as set by the user.
     pub properties: Properties,
+    /// The attributes of the `<object>` element this crate doesn't parse, by name, such as the
+    /// ones added by versions of Tiled newer than it supports. Those of the object's template are
+    /// included, unless overridden. They are written back out along with the object.
+    ///
+    /// Only kept if the file it is in was loaded with
+    /// [`Loader::with_unparsed_attributes`](crate::Loader::with_unparsed_attributes), and
+    /// [`None`] if there are none.
+    pub unparsed_attributes: Option<Box<HashMap<String, String>>>,
+    /// How the object's template was parsed, if it has one, along with the problems found in it,
+    /// which [`Map::validate`](crate::Map::validate) returns.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) template_report: ParseReport,
 }
 
 impl ObjectData {
@@ -210,11 +324,123 @@ impl ObjectData {
     pub fn tile_data(&self) -> Option<ObjectTileData> {
         self.tile.clone()
     }
+
+    /// Returns the axis-aligned bounding box of the object as `(x, y, width, height)`, in pixels.
+    ///
+    /// The object's [rotation](Self::rotation) is taken into account, so the box of a rotated
+    /// object encloses the whole rotated shape. Points, as well as text objects (whose size isn't
+    /// stored), have a zero-sized box at their position. Tile objects are anchored at their
+    /// bottom-left corner, the default in orthogonal maps; use [`Object::aabb`] to take the
+    /// [alignment](crate::Tileset::object_alignment) of their tileset into account.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::Loader::new().load_tmx_map("assets/tiled_base64.tmx")?;
+    /// let layer = map
+    ///     .layers()
+    ///     .find_map(|layer| layer.as_object_layer())
+    ///     .unwrap();
+    ///
+    /// // A 285x135 rectangle at (14, 9)
+    /// assert_eq!(layer.get_object(0).unwrap().bounding_box(), (14.0, 9.0, 285.0, 135.0));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn bounding_box(&self) -> (f32, f32, f32, f32) {
+        self.aabb_with_anchor(ObjectAlignment::BottomLeft.anchor(Orientation::Orthogonal))
+    }
+
+    /// Returns the rectangle the unrotated object covers as `(left, top, right, bottom)`,
+    /// relative to its position. Tile objects are placed so that `anchor`, as fractions of their
+    /// size, is at their position.
+    fn local_bounds(&self, anchor: (f32, f32)) -> (f32, f32, f32, f32) {
+        match &self.shape {
+            ObjectShape::Rect { width, height } if self.tile.is_some() => {
+                let (left, top) = (-anchor.0 * width, -anchor.1 * height);
+                (left, top, left + width, top + height)
+            }
+            ObjectShape::Rect { width, height } | ObjectShape::Ellipse { width, height } => {
+                (0.0, 0.0, *width, *height)
+            }
+            shape => match shape.bounds() {
+                Some((x, y, width, height)) => (x, y, x + width, y + height),
+                None => (0.0, 0.0, 0.0, 0.0),
+            },
+        }
+    }
+
+    /// Rotates a point relative to the object's position around it, returning it in the
+    /// coordinates of the object's layer.
+    fn to_world(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        (self.x + x * cos - y * sin, self.y + x * sin + y * cos)
+    }
+
+    /// The inverse of [`Self::to_world`].
+    fn to_local(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let (x, y) = (x - self.x, y - self.y);
+        (x * cos + y * sin, y * cos - x * sin)
+    }
+
+    /// Returns whether the point at (`x`, `y`), in the coordinates of the object's layer, is
+    /// inside the object if it is a polygon. The object's position and rotation are taken into
+    /// account; see [`ObjectShape::contains_point`] for the rest.
+    pub fn contains_point(&self, x: f32, y: f32) -> bool {
+        let (x, y) = self.to_local((x, y));
+        self.shape.contains_point(x, y)
+    }
+
+    fn world_corners_with_anchor(&self, anchor: (f32, f32)) -> [(f32, f32); 4] {
+        let (left, top, right, bottom) = self.local_bounds(anchor);
+        [(left, top), (right, top), (right, bottom), (left, bottom)].map(|p| self.to_world(p))
+    }
+
+    fn aabb_with_anchor(&self, anchor: (f32, f32)) -> (f32, f32, f32, f32) {
+        let points = match &self.shape {
+            ObjectShape::Ellipse { width, height } => {
+                // The box of a rotated ellipse is given by its semi-axes rotated around its center.
+                let (sin, cos) = self.rotation.to_radians().sin_cos();
+                let (a, b) = (width / 2.0, height / 2.0);
+                let half_width = ((a * cos).powi(2) + (b * sin).powi(2)).sqrt();
+                let half_height = ((a * sin).powi(2) + (b * cos).powi(2)).sqrt();
+                let (x, y) = self.to_world((a, b));
+                return (
+                    x - half_width,
+                    y - half_height,
+                    2.0 * half_width,
+                    2.0 * half_height,
+                );
+            }
+            ObjectShape::Polyline { points } | ObjectShape::Polygon { points }
+                if !points.is_empty() =>
+            {
+                points.iter().map(|&p| self.to_world(p)).collect()
+            }
+            _ => self.world_corners_with_anchor(anchor).to_vec(),
+        };
+
+        let (mut min_x, mut min_y) = (f32::INFINITY, f32::INFINITY);
+        let (mut max_x, mut max_y) = (f32::NEG_INFINITY, f32::NEG_INFINITY);
+        for (x, y) in points {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+        (min_x, min_y, max_x - min_x, max_y - min_y)
+    }
 }
 
 impl ObjectData {
+    pub(crate) fn set_id(&mut self, id: u32) {
+        self.id = id;
+    }
+
     /// If it is known that the object has no tile images in it (i.e. collision data)
     /// then we can pass in [`None`] as the tilesets
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
@@ -224,9 +450,11 @@ impl ObjectData {
         base_path: &Path,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        options: &ParseOptions,
     ) -> Result<ObjectData> {
+        let mut unparsed_attributes;
         let (id, tile, mut n, mut t, c, w, h, mut v, mut r, template, x, y) = get_attrs!(
-            for v in attrs {
+            for v in attrs, unparsed(options.unparsed_attributes) unparsed_attributes {
                 Some("id") => id ?= v.parse(),
                 Some("gid") => tile ?= v.parse::<u32>(),
                 Some("name") => name ?= v.parse(),
@@ -234,7 +462,7 @@ impl ObjectData {
                 Some("class") => user_class ?= v.parse(),
                 Some("width") => width ?= v.parse(),
                 Some("height") => height ?= v.parse(),
-                Some("visible") => visible ?= v.parse().map(|x:i32| x == 1),
+                Some("visible") => visible ?= parse_bool_attr(v).ok_or(()),
                 Some("rotation") => rotation ?= v.parse(),
                 Some("template") => template ?= v.parse(),
                 Some("x") => x ?= v.parse::<f32>(),
@@ -250,17 +478,8 @@ impl ObjectData {
         // If the template attribute is there, we need to go fetch the template file
         let template = template
             .map(|template_path: String| {
-                let template_path = base_path.join(Path::new(&template_path));
-
-                // Check the cache to see if this template exists
-                let template = if let Some(templ) = cache.get_template(&template_path) {
-                    templ
-                } else {
-                    let template = Template::parse_template(&template_path, reader, cache)?;
-                    // Insert it into the cache
-                    cache.insert_template(&template_path, template.clone());
-                    template
-                };
+                let template =
+                    Self::load_template(&template_path, base_path, reader, cache, options)?;
 
                 // The template sets the default values for the object
                 let obj = &template.object;
@@ -317,7 +536,9 @@ impl ObjectData {
 
         // Possibly copy properties from the template into the object
         // Any that already exist in the object's map don't get copied over
+        let mut template_report = ParseReport::default();
         if let Some(templ) = template {
+            template_report = templ.parse_report.clone();
             shape.get_or_insert(templ.object.shape.clone());
 
             for (k, v) in &templ.object.properties {
@@ -325,6 +546,14 @@ impl ObjectData {
                     properties.insert(k.clone(), v.clone());
                 }
             }
+            if let Some(template_attributes) = &templ.object.unparsed_attributes {
+                let unparsed_attributes = unparsed_attributes.get_or_insert_with(Default::default);
+                for (k, v) in template_attributes.iter() {
+                    unparsed_attributes
+                        .entry(k.clone())
+                        .or_insert_with(|| v.clone());
+                }
+            }
         }
 
         let shape = shape.unwrap_or(ObjectShape::Rect { width, height });
@@ -340,11 +569,353 @@ impl ObjectData {
             visible,
             shape,
             properties,
+            unparsed_attributes,
+            template_report,
         })
     }
 }
 
+#[cfg(feature = "json")]
 impl ObjectData {
+    /// Like [`Self::new`], but reading from a JSON object.
+    pub(crate) fn parse_json(
+        json: &JsonObject,
+        tilesets: Option<&[MapTilesetGid]>,
+        for_tileset: Option<Arc<Tileset>>,
+        // Base path is a directory to which all other files are relative to
+        base_path: &Path,
+        reader: &mut impl ResourceReader,
+        cache: &mut impl ResourceCache,
+        options: &ParseOptions,
+    ) -> Result<ObjectData> {
+        let x = json.f32("x")?.unwrap_or(0.);
+        let y = json.f32("y")?.unwrap_or(0.);
+        let width = json.f32("width")?.unwrap_or(0.);
+        let height = json.f32("height")?.unwrap_or(0.);
+        let mut tile = json.u32("gid")?.and_then(|bits| {
+            ObjectTileData::from_bits(bits, tilesets?, for_tileset.as_ref().cloned())
+        });
+        let template = json
+            .str("template")?
+            .map(|template_path| {
+                Self::load_template(template_path, base_path, reader, cache, options)
+            })
+            .transpose()?;
+        let template_report = template
+            .as_ref()
+            .map(|template| template.parse_report.clone())
+            .unwrap_or_default();
+        // The template sets the default values for the object
+        let template = template.as_ref().map(|template| &template.object);
+        if let Some(templ_tile) = template.and_then(|obj| obj.tile.as_ref()) {
+            tile.get_or_insert_with(|| templ_tile.clone());
+        }
+
+        let shape = if json.bool("ellipse")? == Some(true) {
+            Some(ObjectShape::Ellipse { width, height })
+        } else if json.bool("point")? == Some(true) {
+            Some(ObjectShape::Point(x, y))
+        } else if let Some(points) = Self::parse_json_points(json, "polygon")? {
+            Some(ObjectShape::Polygon { points })
+        } else if let Some(points) = Self::parse_json_points(json, "polyline")? {
+            Some(ObjectShape::Polyline { points })
+        } else {
+            json.object("text")?
+                .map(|text| Self::parse_json_text(&text))
+                .transpose()?
+        };
+
+        // Properties from the template are only used if the object doesn't override them
+        let mut properties = crate::properties::parse_json_properties(json)?;
+        if let Some(obj) = template {
+            for (k, v) in &obj.properties {
+                if !properties.contains_key(k) {
+                    properties.insert(k.clone(), v.clone());
+                }
+            }
+        }
+
+        Ok(ObjectData {
+            id: json.u32("id")?.unwrap_or(0),
+            tile,
+            name: json
+                .string("name")?
+                .or_else(|| template.map(|obj| obj.name.clone()))
+                .unwrap_or_default(),
+            user_type: json
+                .string("type")?
+                .or(json.string("class")?)
+                .or_else(|| template.map(|obj| obj.user_type.clone()))
+                .unwrap_or_default(),
+            x,
+            y,
+            rotation: json
+                .f32("rotation")?
+                .or_else(|| template.map(|obj| obj.rotation))
+                .unwrap_or(0.),
+            visible: json
+                .bool("visible")?
+                .or_else(|| template.map(|obj| obj.visible))
+                .unwrap_or(true),
+            shape: shape
+                .or_else(|| template.map(|obj| obj.shape.clone()))
+                .unwrap_or(ObjectShape::Rect { width, height }),
+            properties,
+            unparsed_attributes: template.and_then(|obj| obj.unparsed_attributes.clone()),
+            template_report,
+        })
+    }
+
+    /// Parses an array of `{"x": .., "y": ..}` points, as used by polygons and polylines.
+    fn parse_json_points(json: &JsonObject, key: &str) -> Result<Option<Vec<(f32, f32)>>> {
+        if json.get(key).is_none() {
+            return Ok(None);
+        }
+        json.objects(key)?
+            .iter()
+            .map(|point| {
+                Ok((
+                    required(point.f32("x")?, "x")?,
+                    required(point.f32("y")?, "y")?,
+                ))
+            })
+            .collect::<Result<_>>()
+            .map(Some)
+    }
+
+    fn parse_json_text(json: &JsonObject) -> Result<ObjectShape> {
+        let halign = match json.str("halign")? {
+            None | Some("left") => HorizontalAlignment::Left,
+            Some("center") => HorizontalAlignment::Center,
+            Some("right") => HorizontalAlignment::Right,
+            Some("justify") => HorizontalAlignment::Justify,
+            Some(_) => return Err(Error::MalformedAttributes("`halign` property did not contain a valid value of 'left', 'center', 'right' or 'justify'".to_string())),
+        };
+        let valign = match json.str("valign")? {
+            None | Some("top") => VerticalAlignment::Top,
+            Some("center") => VerticalAlignment::Center,
+            Some("bottom") => VerticalAlignment::Bottom,
+            Some(_) => return Err(Error::MalformedAttributes(
+                "`valign` property did not contain a valid value of 'top', 'center' or 'bottom'"
+                    .to_string(),
+            )),
+        };
+
+        Ok(ObjectShape::Text {
+            font_family: json
+                .string("fontfamily")?
+                .unwrap_or_else(|| "sans-serif".to_string()),
+            pixel_size: json.u32("pixelsize")?.unwrap_or(16) as usize,
+            wrap: json.bool("wrap")?.unwrap_or(false),
+            color: json.color("color")?.unwrap_or(Color {
+                red: 0,
+                green: 0,
+                blue: 0,
+                alpha: 255,
+            }),
+            bold: json.bool("bold")?.unwrap_or(false),
+            italic: json.bool("italic")?.unwrap_or(false),
+            underline: json.bool("underline")?.unwrap_or(false),
+            strikeout: json.bool("strikeout")?.unwrap_or(false),
+            // Same default as for TMX files, so that both formats give the same result.
+            kerning: json.bool("kerning")?.unwrap_or(false),
+            halign,
+            valign,
+        })
+    }
+}
+
+impl ObjectData {
+    pub(crate) fn write_xml(&self, writer: &mut XmlWriter<impl Write>) -> Result<()> {
+        let mut attrs = Vec::new();
+        if self.id != 0 {
+            attrs.push(("id", self.id.to_string()));
+        }
+        if !self.name.is_empty() {
+            attrs.push(("name", self.name.clone()));
+        }
+        if !self.user_type.is_empty() {
+            attrs.push(("type", self.user_type.clone()));
+        }
+        if let Some(bits) = self
+            .tile
+            .as_ref()
+            .and_then(|tile| tile.to_bits(&writer.first_gids))
+        {
+            attrs.push(("gid", bits.to_string()));
+        }
+        attrs.push(("x", self.x.to_string()));
+        attrs.push(("y", self.y.to_string()));
+        if let ObjectShape::Rect { width, height } | ObjectShape::Ellipse { width, height } =
+            self.shape
+        {
+            if width != 0.0 {
+                attrs.push(("width", width.to_string()));
+            }
+            if height != 0.0 {
+                attrs.push(("height", height.to_string()));
+            }
+        }
+        if self.rotation != 0.0 {
+            attrs.push(("rotation", self.rotation.to_string()));
+        }
+        if !self.visible {
+            attrs.push(("visible", bool_to_string(false)));
+        }
+        push_unparsed_attributes(&mut attrs, self.unparsed_attributes.as_deref());
+
+        writer.start("object", &attrs)?;
+        write_properties(writer, &self.properties)?;
+        match &self.shape {
+            ObjectShape::Rect { .. } => {}
+            ObjectShape::Ellipse { .. } => writer.empty("ellipse", &[])?,
+            ObjectShape::Point(..) => writer.empty("point", &[])?,
+            ObjectShape::Polyline { points } => {
+                writer.empty("polyline", &[("points", Self::points_to_string(points))])?
+            }
+            ObjectShape::Polygon { points } => {
+                writer.empty("polygon", &[("points", Self::points_to_string(points))])?
+            }
+            ObjectShape::Text {
+                font_family,
+                pixel_size,
+                wrap,
+                color,
+                bold,
+                italic,
+                underline,
+                strikeout,
+                kerning,
+                halign,
+                valign,
+            } => {
+                let mut attrs = vec![
+                    ("fontfamily", font_family.clone()),
+                    ("pixelsize", pixel_size.to_string()),
+                    ("color", color_to_string(*color)),
+                ];
+                for (name, value) in [
+                    ("wrap", wrap),
+                    ("bold", bold),
+                    ("italic", italic),
+                    ("underline", underline),
+                    ("strikeout", strikeout),
+                ] {
+                    if *value {
+                        attrs.push((name, bool_to_string(true)));
+                    }
+                }
+                // Tiled enables kerning by default, so it is always written.
+                attrs.push(("kerning", bool_to_string(*kerning)));
+                attrs.push(("halign", halign.name().to_owned()));
+                attrs.push(("valign", valign.name().to_owned()));
+                writer.empty("text", &attrs)?;
+            }
+        }
+        writer.end()
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn write_json(&self, writer: &crate::write::JsonWriter) -> serde_json::Value {
+        use crate::write::f32_to_json;
+
+        let (width, height) = match self.shape {
+            ObjectShape::Rect { width, height } | ObjectShape::Ellipse { width, height } => {
+                (width, height)
+            }
+            _ => (0.0, 0.0),
+        };
+        let mut json = crate::write::json_object(vec![
+            ("id", self.id.into()),
+            ("name", self.name.clone().into()),
+            ("type", self.user_type.clone().into()),
+            ("x", f32_to_json(self.x)),
+            ("y", f32_to_json(self.y)),
+            ("width", f32_to_json(width)),
+            ("height", f32_to_json(height)),
+            ("rotation", f32_to_json(self.rotation)),
+            ("visible", self.visible.into()),
+        ]);
+        if let Some(bits) = self
+            .tile
+            .as_ref()
+            .and_then(|tile| tile.to_bits(&writer.first_gids))
+        {
+            json.insert("gid".to_owned(), bits.into());
+        }
+        if let Some(properties) = crate::properties::write_json_properties(&self.properties) {
+            json.insert("properties".to_owned(), properties);
+        }
+        let points_to_json = |points: &[(f32, f32)]| -> serde_json::Value {
+            points
+                .iter()
+                .map(|(x, y)| serde_json::json!({ "x": f32_to_json(*x), "y": f32_to_json(*y) }))
+                .collect()
+        };
+        let (name, value) = match &self.shape {
+            ObjectShape::Rect { .. } => return json.into(),
+            ObjectShape::Ellipse { .. } => ("ellipse", true.into()),
+            ObjectShape::Point(..) => ("point", true.into()),
+            ObjectShape::Polyline { points } => ("polyline", points_to_json(points)),
+            ObjectShape::Polygon { points } => ("polygon", points_to_json(points)),
+            ObjectShape::Text {
+                font_family,
+                pixel_size,
+                wrap,
+                color,
+                bold,
+                italic,
+                underline,
+                strikeout,
+                kerning,
+                halign,
+                valign,
+            } => (
+                "text",
+                serde_json::json!({
+                    "text": "",
+                    "fontfamily": font_family,
+                    "pixelsize": pixel_size,
+                    "wrap": wrap,
+                    "color": color_to_string(*color),
+                    "bold": bold,
+                    "italic": italic,
+                    "underline": underline,
+                    "strikeout": strikeout,
+                    "kerning": kerning,
+                    "halign": halign.name(),
+                    "valign": valign.name(),
+                }),
+            ),
+        };
+        json.insert(name.to_owned(), value);
+        json.into()
+    }
+
+    /// Loads the template at `template_path`, relative to `base_path`, reusing it if it is
+    /// already in the cache.
+    fn load_template(
+        template_path: &str,
+        base_path: &Path,
+        reader: &mut impl ResourceReader,
+        cache: &mut impl ResourceCache,
+        options: &ParseOptions,
+    ) -> Result<Arc<Template>> {
+        let template_path = base_path.join(Path::new(template_path));
+        let options = options.for_template();
+        cached_or_parse(cache, &template_path, &options, |cache| {
+            Template::parse_template(&template_path, reader, cache, &options)
+        })
+    }
+
+    fn points_to_string(points: &[(f32, f32)]) -> String {
+        points
+            .iter()
+            .map(|(x, y)| format!("{},{}", x, y))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
     fn new_polyline(attrs: Vec<OwnedAttribute>) -> Result<ObjectShape> {
         let points = get_attrs!(
             for v in attrs {
@@ -382,13 +953,13 @@ impl ObjectData {
             for v in attrs {
                 Some("fontfamily") => font_family = v,
                 Some("pixelsize") => pixel_size ?= v.parse(),
-                Some("wrap") => wrap ?= v.parse(),
+                Some("wrap") => wrap ?= parse_bool_attr(v).ok_or(()),
                 Some("color") => color ?= v.parse(),
-                Some("bold") => bold ?= v.parse(),
-                Some("italic") => italic ?= v.parse(),
-                Some("underline") => underline ?= v.parse(),
-                Some("strikeout") => strikeout ?= v.parse(),
-                Some("kerning") => kerning ?= v.parse(),
+                Some("bold") => bold ?= parse_bool_attr(v).ok_or(()),
+                Some("italic") => italic ?= parse_bool_attr(v).ok_or(()),
+                Some("underline") => underline ?= parse_bool_attr(v).ok_or(()),
+                Some("strikeout") => strikeout ?= parse_bool_attr(v).ok_or(()),
+                Some("kerning") => kerning ?= parse_bool_attr(v).ok_or(()),
                 Some("halign") => halign = match v.as_str() {
                     "left" => HorizontalAlignment::Left,
                     "center" => HorizontalAlignment::Center,
@@ -428,12 +999,12 @@ impl ObjectData {
             blue: 0,
             alpha: 255,
         });
-        let wrap = wrap == Some(1);
-        let bold = bold == Some(1);
-        let italic = italic == Some(1);
-        let underline = underline == Some(1);
-        let strikeout = strikeout == Some(1);
-        let kerning = kerning == Some(1);
+        let wrap = wrap.unwrap_or(false);
+        let bold = bold.unwrap_or(false);
+        let italic = italic.unwrap_or(false);
+        let underline = underline.unwrap_or(false);
+        let strikeout = strikeout.unwrap_or(false);
+        let kerning = kerning.unwrap_or(false);
         let halign = halign.unwrap_or_default();
         let valign = valign.unwrap_or_default();
 
@@ -490,4 +1061,57 @@ impl<'map> Object<'map> {
             .as_ref()
             .map(|tile| ObjectTile::new(self.map, tile))
     }
+
+    /// Returns the corners of the object in the coordinates of its layer, which are pixels in
+    /// orthogonal maps, following Tiled's rules for where objects are rotated around.
+    ///
+    /// The corners are those of the unrotated object's top-left, top-right, bottom-right and
+    /// bottom-left, in that order, rotated by its [rotation](ObjectData::rotation) around its
+    /// position. That is the top-left corner of rectangles and ellipses, the origin of the points
+    /// of polygons and polylines (whose corners are those of the box around their points), and the
+    /// [alignment](crate::Tileset::object_alignment) of the tileset of tile objects. All corners
+    /// of points and text objects, whose size isn't stored, are at their position.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::parse_str(
+    ///     r#"<map version="1.10" orientation="orthogonal" width="4" height="4"
+    ///      tilewidth="32" tileheight="32">
+    ///  <objectgroup id="1" name="Objects">
+    ///   <object id="1" x="10" y="20" width="30" height="40" rotation="90"/>
+    ///  </objectgroup>
+    /// </map>"#,
+    /// )?;
+    /// let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    ///
+    /// let corners = layer.get_object(0).unwrap().world_corners();
+    /// let corners = corners.map(|(x, y)| (x.round(), y.round()));
+    /// assert_eq!(corners, [(10.0, 20.0), (10.0, 50.0), (-30.0, 50.0), (-30.0, 20.0)]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn world_corners(&self) -> [(f32, f32); 4] {
+        self.data.world_corners_with_anchor(self.anchor())
+    }
+
+    /// Returns the axis-aligned bounding box of the object as `(x, y, width, height)`, in the
+    /// coordinates of its layer.
+    ///
+    /// This is like [`ObjectData::bounding_box`], except that tile objects are placed according
+    /// to the [alignment](crate::Tileset::object_alignment) of their tileset, like
+    /// [`Object::world_corners`] does, rather than always at their bottom-left corner.
+    pub fn aabb(&self) -> (f32, f32, f32, f32) {
+        self.data.aabb_with_anchor(self.anchor())
+    }
+
+    /// Returns where tile objects are placed relative to their position, as fractions of their
+    /// size.
+    fn anchor(&self) -> (f32, f32) {
+        let alignment = self
+            .get_tile()
+            .map(|tile| tile.get_tileset().object_alignment)
+            .unwrap_or_default();
+        alignment.anchor(self.map.orientation)
+    }
 }
@@ -0,0 +1,121 @@
+//! Parsing of Tiled's JSON formats, enabled by the `json` feature.
+
+use std::{
+    convert::TryInto,
+    io::{BufRead, BufReader, Read},
+};
+
+use serde_json::{Map, Value};
+
+use crate::{Color, Error, Result};
+
+mod project;
+mod tileset;
+mod world;
+pub use project::*;
+pub use tileset::*;
+pub use world::*;
+
+/// A JSON object, with accessors for its members that fail the same way missing or malformed XML
+/// attributes do.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct JsonObject<'a> {
+    members: &'a Map<String, Value>,
+}
+
+impl<'a> JsonObject<'a> {
+    /// Interprets `value` as an object, where `what` describes it in the error returned otherwise.
+    pub(crate) fn new(value: &'a Value, what: &str) -> Result<Self> {
+        value
+            .as_object()
+            .map(|members| Self { members })
+            .ok_or_else(|| Error::MalformedAttributes(format!("Expected {} to be an object", what)))
+    }
+
+    /// Returns the raw value of a member, if present.
+    pub(crate) fn get(&self, key: &str) -> Option<&'a Value> {
+        self.members.get(key).filter(|value| !value.is_null())
+    }
+
+    fn parse<T>(&self, key: &str, f: impl FnOnce(&'a Value) -> Option<T>) -> Result<Option<T>> {
+        self.get(key)
+            .map(|value| {
+                f(value).ok_or_else(|| {
+                    Error::MalformedAttributes(format!("Error parsing attribute '{}'", key))
+                })
+            })
+            .transpose()
+    }
+
+    pub(crate) fn str(&self, key: &str) -> Result<Option<&'a str>> {
+        self.parse(key, Value::as_str)
+    }
+
+    pub(crate) fn string(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.str(key)?.map(str::to_owned))
+    }
+
+    pub(crate) fn bool(&self, key: &str) -> Result<Option<bool>> {
+        self.parse(key, Value::as_bool)
+    }
+
+    pub(crate) fn u32(&self, key: &str) -> Result<Option<u32>> {
+        self.parse(key, |v| v.as_u64().and_then(|v| v.try_into().ok()))
+    }
+
+    pub(crate) fn i32(&self, key: &str) -> Result<Option<i32>> {
+        self.parse(key, |v| v.as_i64().and_then(|v| v.try_into().ok()))
+    }
+
+    pub(crate) fn i64(&self, key: &str) -> Result<Option<i64>> {
+        self.parse(key, Value::as_i64)
+    }
+
+    pub(crate) fn f32(&self, key: &str) -> Result<Option<f32>> {
+        self.parse(key, |v| v.as_f64().map(|v| v as f32))
+    }
+
+    pub(crate) fn color(&self, key: &str) -> Result<Option<Color>> {
+        self.parse(key, |v| v.as_str()?.parse().ok())
+    }
+
+    pub(crate) fn object(&self, key: &str) -> Result<Option<JsonObject<'a>>> {
+        self.get(key).map(|v| JsonObject::new(v, key)).transpose()
+    }
+
+    /// Returns the elements of an array of objects, or an empty list if the member isn't present.
+    pub(crate) fn objects(&self, key: &str) -> Result<Vec<JsonObject<'a>>> {
+        match self.get(key) {
+            Some(value) => value
+                .as_array()
+                .ok_or_else(|| {
+                    Error::MalformedAttributes(format!("Error parsing attribute '{}'", key))
+                })?
+                .iter()
+                .map(|v| JsonObject::new(v, key))
+                .collect(),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+/// The byte order mark some tools put at the start of UTF-8 files.
+pub(crate) const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Reads a JSON document from `source`, skipping the UTF-8 byte order mark it may start with,
+/// which isn't valid JSON.
+pub(crate) fn read_value(source: impl Read) -> Result<Value> {
+    let mut source = BufReader::new(source);
+    let start = source
+        .fill_buf()
+        .map_err(|err| Error::JsonDecodingError(serde_json::Error::io(err)))?;
+    if start.starts_with(UTF8_BOM) {
+        source.consume(UTF8_BOM.len());
+    }
+    serde_json::from_reader(source).map_err(Error::JsonDecodingError)
+}
+
+/// Turns a missing member into the same error a missing required XML attribute would produce.
+pub(crate) fn required<T>(value: Option<T>, key: &str) -> Result<T> {
+    value.ok_or_else(|| Error::MalformedAttributes(format!("Missing attribute: {}", key)))
+}
@@ -0,0 +1,11 @@
+use std::io::Read;
+
+use crate::{ProjectTypes, Result};
+
+use super::{read_value, JsonObject};
+
+/// Parses the custom types defined in a Tiled project file from `source`.
+pub fn parse_project_types_from(source: impl Read) -> Result<ProjectTypes> {
+    let value = read_value(source)?;
+    ProjectTypes::parse_json(&JsonObject::new(&value, "project")?)
+}
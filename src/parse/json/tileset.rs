@@ -0,0 +1,26 @@
+use std::{io::Read, path::Path};
+
+use crate::{parse::ParseOptions, ResourceCache, ResourceReader, Result, Tileset};
+
+use super::{read_value, JsonObject};
+
+/// Parses a JSON tileset from `source`, treating it as if it was read from `path` so that any
+/// external files referenced by the tileset can be resolved.
+pub fn parse_tileset_from(
+    source: impl Read,
+    path: &Path,
+    reader: &mut impl ResourceReader,
+    cache: &mut impl ResourceCache,
+    options: &ParseOptions,
+) -> Result<Tileset> {
+    let value = read_value(source)?;
+    let mut tileset = Tileset::parse_json(
+        &JsonObject::new(&value, "tileset")?,
+        path,
+        reader,
+        cache,
+        options,
+    )?;
+    tileset.parse_report.options = *options;
+    Ok(tileset)
+}
@@ -0,0 +1,12 @@
+use std::{io::Read, path::Path};
+
+use crate::{Result, World};
+
+use super::{read_value, JsonObject};
+
+/// Parses a world from `source`, treating it as if it was read from `path` so that the maps in
+/// it can be resolved.
+pub fn parse_world_from(source: impl Read, path: &Path) -> Result<World> {
+    let value = read_value(source)?;
+    World::parse_json(&JsonObject::new(&value, "world")?, path)
+}
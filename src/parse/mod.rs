@@ -1 +1,216 @@
+use std::{
+    io::Read,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+#[cfg(feature = "rayon")]
+use crate::layers::DeferredTileData;
+use crate::{
+    layers::TileSink, CachedResource, DuplicatePropertyPolicy, Error, ParseLimits, ResourceCache,
+    ResourceReader, Result, Template, Tileset, Warning,
+};
+
+#[cfg(feature = "json")]
+pub mod json;
 pub mod xml;
+
+/// The settings of a [`Loader`](crate::Loader) that apply to every file it parses.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub(crate) struct ParseOptions {
+    /// The limits files must stay within.
+    pub limits: ParseLimits,
+    /// Whether the attributes this crate doesn't parse are kept.
+    pub unparsed_attributes: bool,
+    /// What to do with custom properties sharing their name with another one of the same element.
+    pub duplicate_properties: DuplicatePropertyPolicy,
+}
+
+impl ParseOptions {
+    /// The options templates are parsed with. Templates are shared between the maps using them,
+    /// so they are parsed within the default limits rather than those of whichever map loads
+    /// them first.
+    pub fn for_template(&self) -> Self {
+        Self {
+            limits: ParseLimits::default(),
+            ..*self
+        }
+    }
+}
+
+/// What parsing a map, tileset or template left behind besides the resource itself.
+///
+/// It is ignored when comparing resources, since two resources with the same contents are equal
+/// no matter how they were parsed.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ParseReport {
+    /// The options the resource was parsed with.
+    pub options: ParseOptions,
+    /// The problems found while parsing the resource, returned by
+    /// [`Map::validate`](crate::Map::validate).
+    pub warnings: Vec<Warning>,
+}
+
+impl PartialEq for ParseReport {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+/// A resource that can be cached, which remembers how it was parsed.
+pub(crate) trait ParsedResource: CachedResource {
+    fn parse_report(&self) -> &ParseReport;
+}
+
+impl ParsedResource for Tileset {
+    fn parse_report(&self) -> &ParseReport {
+        &self.parse_report
+    }
+}
+
+impl ParsedResource for Template {
+    fn parse_report(&self) -> &ParseReport {
+        &self.parse_report
+    }
+}
+
+/// Obtains the resource at `path` from the cache, or parses it with `parse` and inserts it, like
+/// [`ResourceCache::insert_with`]. Resources are cached under their [`cache_key`].
+///
+/// Resources that were cached after being parsed with other options are parsed again and replace
+/// the cached one, since the warnings they have and the errors they fail with depend on them.
+pub(crate) fn cached_or_parse<T: ParsedResource, C: ResourceCache>(
+    cache: &mut C,
+    path: &Path,
+    options: &ParseOptions,
+    parse: impl FnOnce(&mut C) -> Result<Arc<T>>,
+) -> Result<Arc<T>> {
+    let key = cache_key(path);
+    let parsed_with = |resource: &Arc<T>| resource.parse_report().options == *options;
+    if let Some(resource) = cache.get(&key).filter(parsed_with) {
+        return Ok(resource);
+    }
+    let resource = parse(cache)?;
+    cache.insert(&key, resource.clone());
+    Ok(cache.get(&key).filter(parsed_with).unwrap_or(resource))
+}
+
+/// Returns the path a resource read from `path` is cached under, which leaves out its `.`
+/// components so that `./tiles.tsx` and `tiles.tsx` share a cache entry. `..` components are
+/// kept, since they can't be resolved without knowing whether the directories before them are
+/// symbolic links.
+pub(crate) fn cache_key(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+/// Settings that apply to the parsing of a whole map.
+pub(crate) struct MapParseContext<'a> {
+    /// If set, tiles are passed to it as they are decoded instead of being stored in the map's
+    /// tile layers.
+    pub tile_sink: TileSink<'a>,
+    /// The settings of the loader parsing the map.
+    pub options: ParseOptions,
+    /// Whether the tile data of the map's tile layers is kept as it is in the file instead of
+    /// being decoded.
+    pub raw_tile_data: bool,
+    /// The data of the map's finite tile layers, in the order they appear in it, to be decoded in
+    /// parallel once the whole map has been read.
+    #[cfg(feature = "rayon")]
+    pub deferred_tile_data: Vec<DeferredTileData>,
+}
+
+impl<'a> MapParseContext<'a> {
+    pub fn new(tile_sink: TileSink<'a>, options: ParseOptions) -> Self {
+        Self {
+            tile_sink,
+            options,
+            raw_tile_data: false,
+            #[cfg(feature = "rayon")]
+            deferred_tile_data: Vec::new(),
+        }
+    }
+
+    pub fn with_raw_tile_data(mut self, raw_tile_data: bool) -> Self {
+        self.raw_tile_data = raw_tile_data;
+        self
+    }
+}
+
+/// Reads the tileset at `path` through `reader` and parses it, in whichever format it is in.
+pub fn parse_tileset(
+    path: &Path,
+    reader: &mut impl ResourceReader,
+    cache: &mut impl ResourceCache,
+    options: &ParseOptions,
+) -> Result<Tileset> {
+    let source = reader
+        .read_from(path)
+        .map_err(|err| Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err: Box::new(err),
+        })?;
+    parse_tileset_from(source, path, reader, cache, options)
+}
+
+/// Parses a tileset from `source`, treating it as if it was read from `path`.
+///
+/// With the `json` feature, JSON tilesets are told apart from TSX ones by the extension of
+/// `path`, or by their contents if the extension isn't a known one.
+pub fn parse_tileset_from(
+    source: impl Read,
+    path: &Path,
+    reader: &mut impl ResourceReader,
+    cache: &mut impl ResourceCache,
+    options: &ParseOptions,
+) -> Result<Tileset> {
+    #[cfg(feature = "json")]
+    let source = {
+        let mut source = std::io::BufReader::new(source);
+        if is_json(path, &mut source)? {
+            return json::parse_tileset_from(source, path, reader, cache, options);
+        }
+        source
+    };
+    #[cfg(not(feature = "json"))]
+    if json_extension(path) == Some(true) {
+        return Err(Error::UnsupportedFeature {
+            description: format!(
+                "'{}' is a JSON tileset, which requires the `json` feature",
+                path.display()
+            ),
+        });
+    }
+    xml::parse_tileset_from(source, path, reader, cache, options)
+}
+
+/// Returns whether the extension of `path` is one of those of Tiled's JSON formats, or [`None`]
+/// if it isn't a known extension.
+fn json_extension(path: &Path) -> Option<bool> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    if ["json", "tsj", "tmj", "tj"].contains(&ext.as_str()) {
+        Some(true)
+    } else if ["xml", "tsx", "tmx", "tx"].contains(&ext.as_str()) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Returns whether the file at `path` is in one of Tiled's JSON formats. Only the start of
+/// `source` is looked at, without consuming it.
+#[cfg(feature = "json")]
+fn is_json(path: &Path, source: &mut impl std::io::BufRead) -> Result<bool> {
+    if let Some(is_json) = json_extension(path) {
+        return Ok(is_json);
+    }
+    let start = source
+        .fill_buf()
+        .map_err(|err| Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err: Box::new(err),
+        })?;
+    let start = start.strip_prefix(json::UTF8_BOM).unwrap_or(start);
+    Ok(start.iter().find(|byte| !byte.is_ascii_whitespace()) == Some(&b'{'))
+}
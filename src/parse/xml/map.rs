@@ -1,44 +1,78 @@
-use std::path::Path;
+use std::{io::Read, path::Path};
 
 use xml::{reader::XmlEvent, EventReader};
 
-use crate::{Error, Map, ResourceCache, ResourceReader, Result};
+use crate::{
+    parse::{MapParseContext, ParseReport},
+    util::{xml_source, LimitedEvents},
+    Error, Map, ResourceCache, ResourceReader, Result,
+};
 
 pub fn parse_map(
     path: &Path,
     reader: &mut impl ResourceReader,
     cache: &mut impl ResourceCache,
+    context: &mut MapParseContext,
 ) -> Result<Map> {
-    let mut parser =
-        EventReader::new(
-            reader
-                .read_from(path)
-                .map_err(|err| Error::ResourceLoadingError {
-                    path: path.to_owned(),
-                    err: Box::new(err),
-                })?,
-        );
-    loop {
-        match parser.next().map_err(Error::XmlDecodingError)? {
+    let source = reader
+        .read_from(path)
+        .map_err(|err| Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err: Box::new(err),
+        })?;
+    let path_relative_to = path.parent().ok_or(Error::PathIsNotFile)?;
+    parse_map_from(source, Some(path), path_relative_to, reader, cache, context)
+}
+
+/// Parses a map from `source`, treating it as if it was read from `path`, if any. Any external
+/// files referenced by the map are resolved relative to `path_relative_to`.
+///
+/// If the context has a tile sink, tiles are passed to it as they are decoded instead of being
+/// stored in the map's tile layers.
+pub fn parse_map_from(
+    source: impl Read,
+    path: Option<&Path>,
+    path_relative_to: &Path,
+    reader: &mut impl ResourceReader,
+    cache: &mut impl ResourceCache,
+    context: &mut MapParseContext,
+) -> Result<Map> {
+    let mut parser = LimitedEvents::new(
+        EventReader::new(xml_source(source)?).into_iter(),
+        &context.options,
+    );
+    while let Some(event) = parser.next() {
+        let event = match event {
+            Ok(event) => event,
+            Err(err) => return parser.check(Err(Error::XmlDecodingError(err))),
+        };
+        match event {
             XmlEvent::StartElement {
                 name, attributes, ..
-            } => {
-                if name.local_name == "map" {
-                    return Map::parse_xml(
-                        &mut parser.into_iter(),
-                        attributes,
-                        path,
-                        reader,
-                        cache,
-                    );
-                }
-            }
-            XmlEvent::EndDocument => {
-                return Err(Error::PrematureEnd(
-                    "Document ended before map was parsed".to_string(),
-                ))
+            } if name.local_name == "map" => {
+                let result = Map::parse_xml(
+                    &mut parser,
+                    attributes,
+                    path,
+                    path_relative_to,
+                    reader,
+                    cache,
+                    context,
+                )
+                .map(|mut map| {
+                    map.parse_report = ParseReport {
+                        options: context.options,
+                        warnings: parser.take_warnings(),
+                    };
+                    map
+                });
+                return parser.check(result);
             }
+            XmlEvent::EndDocument => break,
             _ => {}
         }
     }
+    Err(Error::PrematureEnd(
+        "Document ended before map was parsed".to_string(),
+    ))
 }
@@ -1,4 +1,6 @@
 mod map;
 pub use map::*;
+mod object_types;
+pub use object_types::*;
 mod tileset;
 pub use tileset::*;
@@ -0,0 +1,35 @@
+use std::io::Read;
+
+use xml::{reader::XmlEvent, EventReader};
+
+use crate::{
+    parse::ParseOptions,
+    util::{xml_source, LimitedEvents},
+    Error, PropertyClassRegistry, Result,
+};
+
+/// Parses the object types defined in a Tiled object types file (usually named
+/// `objecttypes.xml`) from `source`.
+pub fn parse_object_types_from(
+    source: impl Read,
+    options: &ParseOptions,
+) -> Result<PropertyClassRegistry> {
+    let mut parser = LimitedEvents::new(EventReader::new(xml_source(source)?).into_iter(), options);
+    while let Some(event) = parser.next() {
+        let event = match event {
+            Ok(event) => event,
+            Err(err) => return parser.check(Err(Error::XmlDecodingError(err))),
+        };
+        match event {
+            XmlEvent::StartElement { name, .. } if name.local_name == "objecttypes" => {
+                let result = PropertyClassRegistry::parse_object_types_xml(&mut parser);
+                return parser.check(result);
+            }
+            XmlEvent::EndDocument => break,
+            _ => {}
+        }
+    }
+    Err(Error::PrematureEnd(
+        "Object types document ended before any object types were parsed".to_string(),
+    ))
+}
@@ -1,42 +1,55 @@
-use std::path::Path;
+use std::{io::Read, path::Path};
 
 use xml::{reader::XmlEvent, EventReader};
 
-use crate::{Error, ResourceCache, ResourceReader, Result, Tileset};
+use crate::{
+    parse::{ParseOptions, ParseReport},
+    util::{xml_source, LimitedEvents},
+    Error, ResourceCache, ResourceReader, Result, Tileset,
+};
 
-pub fn parse_tileset(
+/// Parses a tileset from `source`, treating it as if it was read from `path` so that any external
+/// files referenced by the tileset can be resolved.
+pub fn parse_tileset_from(
+    source: impl Read,
     path: &Path,
     reader: &mut impl ResourceReader,
     cache: &mut impl ResourceCache,
+    options: &ParseOptions,
 ) -> Result<Tileset> {
     let mut tileset_parser =
-        EventReader::new(
-            reader
-                .read_from(path)
-                .map_err(|err| Error::ResourceLoadingError {
-                    path: path.to_owned(),
-                    err: Box::new(err),
-                })?,
-        );
-    loop {
-        match tileset_parser.next().map_err(Error::XmlDecodingError)? {
+        LimitedEvents::new(EventReader::new(xml_source(source)?).into_iter(), options);
+    while let Some(event) = tileset_parser.next() {
+        let event = match event {
+            Ok(event) => event,
+            Err(err) => return tileset_parser.check(Err(Error::XmlDecodingError(err))),
+        };
+        match event {
             XmlEvent::StartElement {
                 name, attributes, ..
             } if name.local_name == "tileset" => {
-                return Tileset::parse_external_tileset(
-                    &mut tileset_parser.into_iter(),
+                let result = Tileset::parse_external_tileset(
+                    &mut tileset_parser,
                     &attributes,
                     path,
                     reader,
                     cache,
-                );
-            }
-            XmlEvent::EndDocument => {
-                return Err(Error::PrematureEnd(
-                    "Tileset Document ended before map was parsed".to_string(),
-                ))
+                    options,
+                )
+                .map(|mut tileset| {
+                    tileset.parse_report = ParseReport {
+                        options: *options,
+                        warnings: tileset_parser.take_warnings(),
+                    };
+                    tileset
+                });
+                return tileset_parser.check(result);
             }
+            XmlEvent::EndDocument => break,
             _ => {}
         }
     }
+    Err(Error::PrematureEnd(
+        "Tileset Document ended before map was parsed".to_string(),
+    ))
 }
@@ -0,0 +1,273 @@
+use std::{collections::HashMap, convert::TryFrom};
+
+#[cfg(feature = "json")]
+use crate::{
+    parse::json::{required, JsonObject},
+    properties::parse_json_property_list,
+    Error, Result,
+};
+use crate::{Properties, PropertyClassRegistry, PropertyValue};
+
+/// The custom types defined in a Tiled project, which are needed to fully resolve class and enum
+/// properties.
+///
+/// Tiled only stores the members of class properties that differ from the class' defaults, and
+/// stores enum properties as plain strings or integers. The definitions of both are kept in the
+/// project file instead, and can be loaded with the `json` feature using
+/// `Loader::load_project_types`. Giving them to a loader
+/// with [`Loader::with_project_types`](crate::Loader::with_project_types) then fills in the
+/// missing members of everything it loads.
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// # #[cfg(feature = "json")]
+/// # {
+/// use tiled::{Loader, PropertyValue};
+///
+/// let mut loader = Loader::new();
+/// let types = loader.load_project_types("assets/project.tiled-project")?;
+/// let mut loader = loader.with_project_types(types);
+///
+/// let map = loader.load_tmx_map("assets/tiled_project_types.tmx")?;
+/// match &map.properties["enemy"] {
+///     PropertyValue::ClassValue { properties, .. } => {
+///         // Set in the map itself.
+///         assert_eq!(properties["health"], PropertyValue::IntValue(50));
+///         // Taken from the class defaults in the project.
+///         assert_eq!(properties["speed"], PropertyValue::FloatValue(1.5));
+///     }
+///     _ => panic!("expected a class value"),
+/// }
+/// # }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProjectTypes {
+    /// The classes defined in the project, along with the default values of their members.
+    pub classes: PropertyClassRegistry,
+    /// The enums defined in the project, by name.
+    pub enums: HashMap<String, EnumType>,
+}
+
+/// An enum defined in a Tiled project.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnumType {
+    /// The names of the enum's values, in order.
+    pub values: Vec<String>,
+    /// The way properties of this enum are stored in maps and tilesets.
+    pub storage_type: EnumStorageType,
+    /// Whether properties of this enum can hold more than one value at once.
+    pub values_as_flags: bool,
+}
+
+/// The way properties of an [`EnumType`] are stored.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EnumStorageType {
+    /// As a [`PropertyValue::StringValue`] holding the name of the value, or a comma-separated
+    /// list of names for flags.
+    String,
+    /// As a [`PropertyValue::IntValue`] holding the index of the value, or a bitmask of indices
+    /// for flags.
+    Int,
+}
+
+impl EnumType {
+    /// Returns the names of the values held by a property of this enum, or [`None`] if it isn't
+    /// stored as expected or holds a value the enum doesn't have.
+    ///
+    /// Unless the enum's [values are flags](Self::values_as_flags), there is exactly one name.
+    /// [`PropertyValue::EnumValue`]s are looked at through the value they wrap, whichever enum
+    /// they name.
+    pub fn value_names(&self, value: &PropertyValue) -> Option<Vec<&str>> {
+        match value {
+            PropertyValue::EnumValue { value, .. } => self.value_names(value),
+            PropertyValue::StringValue(names) if self.values_as_flags => names
+                .split(',')
+                .filter(|name| !name.is_empty())
+                .map(|name| self.find_value(name))
+                .collect(),
+            PropertyValue::StringValue(name) => Some(vec![self.find_value(name)?]),
+            PropertyValue::IntValue(flags) if self.values_as_flags => {
+                // Only the first 31 values fit in the bits of a property.
+                let flag_count = self.values.len().min(31);
+                if *flags < 0 || *flags >> flag_count != 0 {
+                    return None;
+                }
+                Some(
+                    (0..flag_count)
+                        .filter(|index| flags & (1 << index) != 0)
+                        .map(|index| self.values[index].as_str())
+                        .collect(),
+                )
+            }
+            PropertyValue::IntValue(index) => usize::try_from(*index)
+                .ok()
+                .and_then(|index| self.values.get(index))
+                .map(|name| vec![name.as_str()]),
+            _ => None,
+        }
+    }
+
+    fn find_value(&self, name: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|value| *value == name)
+            .map(String::as_str)
+    }
+}
+
+impl ProjectTypes {
+    /// Creates an empty set of types.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the names of the values held by a [`PropertyValue::EnumValue`], looking up its
+    /// enum by name. Returns [`None`] for other values, values of enums that aren't defined and
+    /// values the enum doesn't have, as [`EnumType::value_names`] does.
+    pub fn enum_value_names(&self, value: &PropertyValue) -> Option<Vec<&str>> {
+        match value {
+            PropertyValue::EnumValue { property_type, .. } => {
+                self.enums.get(property_type)?.value_names(value)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the full set of properties of an instance of `class`, like
+    /// [`PropertyClassRegistry::resolve`], but also filling in the members of any class values
+    /// in them.
+    pub fn resolve(&self, class: &str, instance: &Properties) -> Properties {
+        let mut properties = instance.clone();
+        self.resolve_in_place(Some(class), &mut properties);
+        properties
+    }
+
+    /// Fills in the properties of an instance of `class`, if any, along with the members of any
+    /// class values in them.
+    pub(crate) fn resolve_in_place(&self, class: Option<&str>, properties: &mut Properties) {
+        if let Some(defaults) = class.and_then(|class| self.classes.get(class)) {
+            for (name, default) in defaults {
+                match properties.get_mut(name) {
+                    Some(value) => coerce_to_default_type(value, default),
+                    None => {
+                        properties.insert(name.clone(), default.clone());
+                    }
+                }
+            }
+        }
+        for value in properties.values_mut() {
+            if let PropertyValue::ClassValue {
+                property_type,
+                properties,
+            } = value
+            {
+                self.resolve_in_place(Some(property_type), properties);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl ProjectTypes {
+    pub(crate) fn parse_json(json: &JsonObject) -> Result<ProjectTypes> {
+        let mut types = ProjectTypes::new();
+        for property_type in json.objects("propertyTypes")? {
+            let name = required(property_type.string("name")?, "name")?;
+            match property_type.str("type")? {
+                Some("class") => {
+                    types
+                        .classes
+                        .insert(name, parse_json_property_list(&property_type, "members")?);
+                }
+                Some("enum") => {
+                    let values = match property_type.get("values") {
+                        Some(values) => values
+                            .as_array()
+                            .and_then(|values| {
+                                values
+                                    .iter()
+                                    .map(|value| value.as_str().map(str::to_owned))
+                                    .collect()
+                            })
+                            .ok_or_else(|| {
+                                Error::MalformedAttributes(
+                                    "Error parsing attribute 'values'".to_owned(),
+                                )
+                            })?,
+                        None => Vec::new(),
+                    };
+                    let storage_type = match property_type.str("storageType")? {
+                        Some("int") => EnumStorageType::Int,
+                        Some("string") | None => EnumStorageType::String,
+                        Some(_) => {
+                            return Err(Error::MalformedAttributes(
+                                "Error parsing attribute 'storageType'".to_owned(),
+                            ))
+                        }
+                    };
+                    types.enums.insert(
+                        name,
+                        EnumType {
+                            values,
+                            storage_type,
+                            values_as_flags: property_type.bool("valuesAsFlags")?.unwrap_or(false),
+                        },
+                    );
+                }
+                // Types Tiled might add in the future are ignored rather than failing to load
+                // the whole project.
+                _ => {}
+            }
+        }
+        Ok(types)
+    }
+}
+
+/// Converts a member of a class value to the type of its default, for values whose type couldn't
+/// be told apart when parsing them, such as the members of JSON class values.
+fn coerce_to_default_type(value: &mut PropertyValue, default: &PropertyValue) {
+    let coerced = match (&*value, default) {
+        (PropertyValue::IntValue(v), PropertyValue::FloatValue(_)) => {
+            PropertyValue::FloatValue(*v as f32)
+        }
+        (PropertyValue::IntValue(v), PropertyValue::ObjectValue(_)) if *v >= 0 => {
+            PropertyValue::ObjectValue(*v as u32)
+        }
+        (PropertyValue::StringValue(v), PropertyValue::FileValue(_)) => {
+            PropertyValue::FileValue(v.clone())
+        }
+        (PropertyValue::StringValue(v), PropertyValue::ColorValue(_)) => match v.parse() {
+            Ok(color) => PropertyValue::ColorValue(color),
+            Err(_) => return,
+        },
+        (
+            PropertyValue::StringValue(_) | PropertyValue::IntValue(_),
+            PropertyValue::EnumValue { property_type, .. },
+        ) => PropertyValue::EnumValue {
+            property_type: property_type.clone(),
+            value: Box::new(value.clone()),
+        },
+        _ => {
+            if let (
+                PropertyValue::ClassValue { property_type, .. },
+                PropertyValue::ClassValue {
+                    property_type: default_type,
+                    ..
+                },
+            ) = (value, default)
+            {
+                if property_type.is_empty() {
+                    *property_type = default_type.clone();
+                }
+            }
+            return;
+        }
+    };
+    *value = coerced;
+}
@@ -1,14 +1,19 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{collections::HashMap, io::Write, str::FromStr};
 
 use xml::{attribute::OwnedAttribute, reader::XmlEvent};
 
+#[cfg(feature = "json")]
+use crate::parse::json::{required, JsonObject};
+#[cfg(feature = "json")]
+use crate::write::f32_to_json;
 use crate::{
     error::{Error, Result},
     util::{get_attrs, parse_tag, XmlEventResult},
+    write::{color_to_string, XmlWriter},
 };
 
 /// Represents a RGBA color with 8-bit depth on each channel.
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
 #[allow(missing_docs)]
 pub struct Color {
     pub alpha: u8,
@@ -17,6 +22,18 @@ pub struct Color {
     pub blue: u8,
 }
 
+impl Default for Color {
+    /// Returns opaque black.
+    fn default() -> Self {
+        Self {
+            alpha: 255,
+            red: 0,
+            green: 0,
+            blue: 0,
+        }
+    }
+}
+
 impl FromStr for Color {
     type Err = ();
 
@@ -65,6 +82,8 @@ impl FromStr for Color {
 ///
 /// Also read the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tmx-properties).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
 pub enum PropertyValue {
     /// A boolean value. Corresponds to the `bool` property type.
     BoolValue(bool),
@@ -82,6 +101,30 @@ pub enum PropertyValue {
     /// An object ID value. Corresponds to the `object` property type.
     /// Holds the id of a referenced object, or 0 if unset.
     ObjectValue(u32),
+    /// An instance of a property class. Corresponds to the `class` property type.
+    ///
+    /// Only the members that differ from the class' defaults are stored in maps and tilesets. To
+    /// fill in the rest, load the project's types with
+    /// [`Loader::with_project_types`](crate::Loader::with_project_types).
+    ClassValue {
+        /// The name of the class.
+        property_type: String,
+        /// The members of the instance.
+        properties: Properties,
+    },
+    /// A value of a custom enum. Corresponds to a `string` or `int` property with a
+    /// `propertytype`.
+    ///
+    /// Use [`ProjectTypes::enum_value_names`](crate::ProjectTypes::enum_value_names) to find the
+    /// names of the values it holds.
+    EnumValue {
+        /// The name of the enum.
+        property_type: String,
+        /// The value as it is stored, which is a [`PropertyValue::StringValue`] or a
+        /// [`PropertyValue::IntValue`] depending on the enum's
+        /// [storage type](crate::EnumStorageType).
+        value: Box<PropertyValue>,
+    },
 }
 
 impl PropertyValue {
@@ -124,27 +167,182 @@ impl PropertyValue {
             }),
         }
     }
+
+    /// Returns the value stored for `self`, which for enum values is the value they wrap.
+    fn stored_value(&self) -> &PropertyValue {
+        match self {
+            PropertyValue::EnumValue { value, .. } => value.stored_value(),
+            value => value,
+        }
+    }
+
+    /// Wraps `self` in a [`PropertyValue::EnumValue`] if it has a custom type.
+    fn with_property_type(self, property_type: Option<String>) -> PropertyValue {
+        match property_type {
+            Some(property_type) if !property_type.is_empty() => PropertyValue::EnumValue {
+                property_type,
+                value: Box::new(self),
+            },
+            _ => self,
+        }
+    }
 }
 
 /// A custom property container.
 pub type Properties = HashMap<String, PropertyValue>;
 
+/// Returns a new set of properties containing all of `base` and `overrides`, where `overrides`
+/// takes precedence for properties present in both.
+///
+/// This models how instances of a Tiled property class only store the properties that differ from
+/// the class' defaults.
+///
+/// ## Example
+/// ```
+/// use tiled::{merge_properties, Properties, PropertyValue};
+///
+/// let base = Properties::from([
+///     ("health".to_owned(), PropertyValue::IntValue(100)),
+///     ("name".to_owned(), PropertyValue::StringValue("Goblin".to_owned())),
+/// ]);
+/// let overrides = Properties::from([("health".to_owned(), PropertyValue::IntValue(50))]);
+///
+/// let merged = merge_properties(&base, &overrides);
+/// assert_eq!(merged["health"], PropertyValue::IntValue(50));
+/// assert_eq!(merged["name"], PropertyValue::StringValue("Goblin".to_owned()));
+/// ```
+pub fn merge_properties(base: &Properties, overrides: &Properties) -> Properties {
+    let mut merged = base.clone();
+    merged.extend(
+        overrides
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone())),
+    );
+    merged
+}
+
+/// A collection of property classes and their default property values, used to resolve the full
+/// set of properties of class instances.
+///
+/// Tiled stores these classes in the project file rather than in maps or tilesets, so they must be
+/// registered manually.
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PropertyClassRegistry {
+    classes: HashMap<String, Properties>,
+}
+
+impl PropertyClassRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a class along with its default properties, returning the ones previously
+    /// registered under the same name, if any.
+    pub fn insert(&mut self, class: impl Into<String>, defaults: Properties) -> Option<Properties> {
+        self.classes.insert(class.into(), defaults)
+    }
+
+    /// Returns the default properties of a class, if it is registered.
+    pub fn get(&self, class: &str) -> Option<&Properties> {
+        self.classes.get(class)
+    }
+
+    /// Returns the full set of properties of an instance of `class`, with the instance's own
+    /// properties taking precedence over the class defaults. If the class isn't registered, only
+    /// the instance's properties are returned.
+    pub fn resolve(&self, class: &str, instance: &Properties) -> Properties {
+        match self.classes.get(class) {
+            Some(defaults) => merge_properties(defaults, instance),
+            None => instance.clone(),
+        }
+    }
+}
+
+impl PropertyClassRegistry {
+    /// Parses the contents of the `<objecttypes>` element of an object types file, registering
+    /// each object type as a class.
+    pub(crate) fn parse_object_types_xml(
+        parser: &mut impl Iterator<Item = XmlEventResult>,
+    ) -> Result<Self> {
+        let mut registry = Self::new();
+        parse_tag!(parser, "objecttypes", {
+            "objecttype" => |attrs: Vec<OwnedAttribute>| {
+                let name = get_attrs!(
+                    for v in attrs {
+                        "name" => name = v,
+                    }
+                    name
+                );
+                registry.insert(name, parse_object_type_properties(parser)?);
+                Ok(())
+            },
+        });
+        Ok(registry)
+    }
+}
+
+/// Parses the properties of an `<objecttype>` element, which store their value in a `default`
+/// attribute instead of `value`.
+fn parse_object_type_properties(
+    parser: &mut impl Iterator<Item = XmlEventResult>,
+) -> Result<Properties> {
+    let mut properties = HashMap::new();
+    parse_tag!(parser, "objecttype", {
+        "property" => |attrs: Vec<OwnedAttribute>| {
+            let (t, default, name) = get_attrs!(
+                for v in attrs {
+                    Some("type") => obj_type = v,
+                    Some("default") => default = v,
+                    "name" => name = v,
+                }
+                (obj_type, default, name)
+            );
+            let value = PropertyValue::new(
+                t.unwrap_or_else(|| "string".to_owned()),
+                default.unwrap_or_default(),
+            )?;
+            properties.insert(name, value);
+            Ok(())
+        },
+    });
+    Ok(properties)
+}
+
 pub(crate) fn parse_properties(
     parser: &mut impl Iterator<Item = XmlEventResult>,
 ) -> Result<Properties> {
     let mut p = HashMap::new();
     parse_tag!(parser, "properties", {
         "property" => |attrs:Vec<OwnedAttribute>| {
-            let (t, v_attr, k) = get_attrs!(
+            let (t, v_attr, property_type, k) = get_attrs!(
                 for attr in attrs {
                     Some("type") => obj_type = attr,
                     Some("value") => value = attr,
+                    Some("propertytype") => property_type = attr,
                     "name" => name = attr
                 }
-                (obj_type, value, name)
+                (obj_type, value, property_type, name)
             );
             let t = t.unwrap_or_else(|| "string".to_owned());
 
+            // Class values store their members as a nested list of properties.
+            if t == "class" {
+                let mut properties = HashMap::new();
+                parse_tag!(parser, "property", {
+                    "properties" => |_| {
+                        properties = parse_properties(parser)?;
+                        Ok(())
+                    },
+                });
+                p.insert(k, PropertyValue::ClassValue {
+                    property_type: property_type.unwrap_or_default(),
+                    properties,
+                });
+                return Ok(());
+            }
+
             let v: String = match v_attr {
                 Some(val) => val,
                 None => {
@@ -158,9 +356,222 @@ pub(crate) fn parse_properties(
                 }
             };
 
-            p.insert(k, PropertyValue::new(t, v)?);
+            p.insert(k, PropertyValue::new(t, v)?.with_property_type(property_type));
             Ok(())
         },
     });
     Ok(p)
 }
+
+/// Parses the `properties` array of a JSON object.
+#[cfg(feature = "json")]
+pub(crate) fn parse_json_properties(json: &JsonObject) -> Result<Properties> {
+    parse_json_property_list(json, "properties")
+}
+
+/// Parses an array of properties stored in the `key` member of a JSON object.
+#[cfg(feature = "json")]
+pub(crate) fn parse_json_property_list(json: &JsonObject, key: &str) -> Result<Properties> {
+    let mut p = HashMap::new();
+    for property in json.objects(key)? {
+        let name = required(property.string("name")?, "name")?;
+        let t = property
+            .string("type")?
+            .unwrap_or_else(|| "string".to_owned());
+        let value = required(property.get("value"), "value")?;
+        // Project files spell the custom type's name differently from maps and tilesets.
+        let property_type = match property.string("propertytype")? {
+            Some(property_type) => Some(property_type),
+            None => property.string("propertyType")?,
+        };
+        if t == "class" {
+            p.insert(
+                name,
+                PropertyValue::ClassValue {
+                    property_type: property_type.unwrap_or_default(),
+                    properties: parse_json_class_members(value)?,
+                },
+            );
+            continue;
+        }
+        // Values have their own JSON types, but are parsed the same way as XML attributes.
+        let v = match value {
+            serde_json::Value::String(s) => s.clone(),
+            value => value.to_string(),
+        };
+        p.insert(
+            name,
+            PropertyValue::new(t, v)?.with_property_type(property_type),
+        );
+    }
+    Ok(p)
+}
+
+/// Parses the members of a JSON class value, which are stored without their types. These are
+/// guessed from the JSON types of the values, and corrected when resolving the class with a
+/// [`ProjectTypes`](crate::ProjectTypes).
+#[cfg(feature = "json")]
+fn parse_json_class_members(value: &serde_json::Value) -> Result<Properties> {
+    use serde_json::Value;
+    use std::convert::TryFrom;
+
+    let members = value
+        .as_object()
+        .ok_or_else(|| Error::InvalidPropertyValue {
+            description: "Expected a class value to be an object".to_owned(),
+        })?;
+    let mut p = HashMap::new();
+    for (name, value) in members {
+        let value = match value {
+            Value::Bool(v) => PropertyValue::BoolValue(*v),
+            Value::Number(v) => match v.as_i64().and_then(|v| i32::try_from(v).ok()) {
+                Some(v) => PropertyValue::IntValue(v),
+                None => PropertyValue::FloatValue(v.as_f64().unwrap_or_default() as f32),
+            },
+            Value::String(v) => PropertyValue::StringValue(v.clone()),
+            Value::Object(_) => PropertyValue::ClassValue {
+                property_type: String::new(),
+                properties: parse_json_class_members(value)?,
+            },
+            Value::Null | Value::Array(_) => {
+                return Err(Error::InvalidPropertyValue {
+                    description: format!("Unsupported value for class member '{}'", name),
+                })
+            }
+        };
+        p.insert(name.clone(), value);
+    }
+    Ok(p)
+}
+
+pub(crate) fn write_properties(
+    writer: &mut XmlWriter<impl Write>,
+    properties: &Properties,
+) -> Result<()> {
+    if properties.is_empty() {
+        return Ok(());
+    }
+    let mut properties: Vec<_> = properties.iter().collect();
+    properties.sort_unstable_by_key(|(name, _)| *name);
+
+    writer.start("properties", &[])?;
+    for (name, value) in properties {
+        let (custom_type, value) = match value {
+            PropertyValue::EnumValue {
+                property_type,
+                value,
+            } => (Some(property_type), value.stored_value()),
+            value => (None, value),
+        };
+        let (property_type, value) = match value {
+            PropertyValue::BoolValue(v) => ("bool", v.to_string()),
+            PropertyValue::FloatValue(v) => ("float", v.to_string()),
+            PropertyValue::IntValue(v) => ("int", v.to_string()),
+            PropertyValue::ColorValue(v) => ("color", color_to_string(*v)),
+            PropertyValue::StringValue(v) => ("string", v.clone()),
+            PropertyValue::FileValue(v) => ("file", v.clone()),
+            PropertyValue::ObjectValue(v) => ("object", v.to_string()),
+            PropertyValue::ClassValue {
+                property_type,
+                properties,
+            } => {
+                // The members of class values are stored as nested properties.
+                writer.start(
+                    "property",
+                    &[
+                        ("name", name.clone()),
+                        ("type", "class".to_owned()),
+                        ("propertytype", property_type.clone()),
+                    ],
+                )?;
+                write_properties(writer, properties)?;
+                writer.end()?;
+                continue;
+            }
+            PropertyValue::EnumValue { .. } => unreachable!("enum values were unwrapped above"),
+        };
+        let mut attrs = vec![("name", name.clone())];
+        if property_type != "string" {
+            attrs.push(("type", property_type.to_owned()));
+        }
+        if let Some(custom_type) = custom_type {
+            attrs.push(("propertytype", custom_type.clone()));
+        }
+        // Like Tiled, store multiline strings as the element's contents, since newlines in
+        // attributes don't survive being parsed again.
+        if value.contains('\n') {
+            writer.start("property", &attrs)?;
+            writer.characters(&value)?;
+            writer.end()?;
+        } else {
+            attrs.push(("value", value));
+            writer.empty("property", &attrs)?;
+        }
+    }
+    writer.end()
+}
+
+/// Returns the JSON representation of `properties`, or [`None`] if there are none so that the
+/// member can be omitted.
+#[cfg(feature = "json")]
+pub(crate) fn write_json_properties(properties: &Properties) -> Option<serde_json::Value> {
+    if properties.is_empty() {
+        return None;
+    }
+    let mut properties: Vec<_> = properties.iter().collect();
+    properties.sort_unstable_by_key(|(name, _)| *name);
+
+    Some(
+        properties
+            .into_iter()
+            .map(|(name, value)| {
+                let mut property = serde_json::json!({
+                    "name": name,
+                    "type": json_property_type(value),
+                    "value": json_property_value(value),
+                });
+                if let PropertyValue::ClassValue { property_type, .. }
+                | PropertyValue::EnumValue { property_type, .. } = value
+                {
+                    property["propertytype"] = property_type.clone().into();
+                }
+                property
+            })
+            .collect(),
+    )
+}
+
+#[cfg(feature = "json")]
+fn json_property_type(value: &PropertyValue) -> &'static str {
+    match value {
+        PropertyValue::BoolValue(_) => "bool",
+        PropertyValue::FloatValue(_) => "float",
+        PropertyValue::IntValue(_) => "int",
+        PropertyValue::ColorValue(_) => "color",
+        PropertyValue::StringValue(_) => "string",
+        PropertyValue::FileValue(_) => "file",
+        PropertyValue::ObjectValue(_) => "object",
+        PropertyValue::ClassValue { .. } => "class",
+        PropertyValue::EnumValue { value, .. } => json_property_type(value),
+    }
+}
+
+/// Returns the JSON value of a property. The members of class values are written as an object,
+/// without their types.
+#[cfg(feature = "json")]
+fn json_property_value(value: &PropertyValue) -> serde_json::Value {
+    match value {
+        PropertyValue::BoolValue(v) => (*v).into(),
+        PropertyValue::FloatValue(v) => f32_to_json(*v),
+        PropertyValue::IntValue(v) => (*v).into(),
+        PropertyValue::ColorValue(v) => color_to_string(*v).into(),
+        PropertyValue::StringValue(v) | PropertyValue::FileValue(v) => v.clone().into(),
+        PropertyValue::ObjectValue(v) => (*v).into(),
+        PropertyValue::ClassValue { properties, .. } => properties
+            .iter()
+            .map(|(name, value)| (name.clone(), json_property_value(value)))
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+        PropertyValue::EnumValue { value, .. } => json_property_value(value),
+    }
+}
@@ -0,0 +1,86 @@
+//! Serde support for types whose representation can't simply be derived.
+
+use std::{collections::HashMap, convert::TryInto, fmt};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{ChunkData, Color, LayerTileData};
+
+/// Colors are represented as `#AARRGGBB` strings, the same format used in TMX files.
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!(
+            "#{:02x}{:02x}{:02x}{:02x}",
+            self.alpha, self.red, self.green, self.blue
+        ))
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ColorVisitor;
+
+        impl<'de> de::Visitor<'de> for ColorVisitor {
+            type Value = Color;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a color in the #RRGGBB or #AARRGGBB format")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Color, E> {
+                v.parse()
+                    .map_err(|_| E::invalid_value(de::Unexpected::Str(v), &self))
+            }
+        }
+
+        deserializer.deserialize_str(ColorVisitor)
+    }
+}
+
+/// Chunk tiles are stored in a fixed size array, which serde only supports up to 32 elements.
+pub(crate) mod chunk_tiles {
+    use super::*;
+
+    type Tiles = Box<[Option<LayerTileData>; ChunkData::TILE_COUNT]>;
+
+    pub(crate) fn serialize<S: Serializer>(
+        tiles: &Tiles,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(tiles.iter())
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Tiles, D::Error> {
+        let tiles = Vec::<Option<LayerTileData>>::deserialize(deserializer)?;
+        let len = tiles.len();
+        tiles
+            .into_boxed_slice()
+            .try_into()
+            .map_err(|_| de::Error::invalid_length(len, &"exactly ChunkData::TILE_COUNT tiles"))
+    }
+}
+
+/// Chunks are keyed by their position, which most formats (e.g. JSON) don't allow as a map key,
+/// so they are represented as a sequence of `(position, chunk)` pairs instead.
+pub(crate) mod chunk_map {
+    use super::*;
+
+    type Chunks = HashMap<(i32, i32), ChunkData>;
+
+    pub(crate) fn serialize<S: Serializer>(
+        chunks: &Chunks,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(chunks.iter())
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Chunks, D::Error> {
+        Ok(Vec::<((i32, i32), ChunkData)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
@@ -1,31 +1,43 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use xml::EventReader;
 use xml::{attribute::OwnedAttribute, reader::XmlEvent};
 
 use crate::{
-    util::*, EmbeddedParseResultType, Error, MapTilesetGid, ObjectData, ResourceCache,
-    ResourceReader, Result, Tileset,
+    parse::{cached_or_parse, ParseOptions, ParseReport},
+    util::*,
+    EmbeddedParseResultType, Error, MapTilesetGid, ObjectData, ResourceCache, ResourceReader,
+    Result, Tileset,
 };
 
 /// A template, consisting of an object and a tileset
 ///
 /// Templates define a tileset and object data to use for an object that can be shared between multiple objects and
 /// maps.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Template {
+    /// The path first used in a [`ResourceReader`] to load this template.
+    pub source: PathBuf,
     /// The tileset this template contains a reference to
     pub tileset: Option<Arc<Tileset>>,
     /// The object data for this template
     pub object: ObjectData,
+    /// How the template was parsed, along with the problems found, which
+    /// [`Map::validate`](crate::Map::validate) returns for the maps using it.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) parse_report: ParseReport,
 }
 
 impl Template {
+    /// Parses the template at `path`, which should be done with the
+    /// [options for templates](ParseOptions::for_template).
     pub(crate) fn parse_template(
         path: &Path,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        options: &ParseOptions,
     ) -> Result<Arc<Template>> {
         // Open the template file
         let file = reader
@@ -35,30 +47,42 @@ impl Template {
                 err: Box::new(err),
             })?;
 
-        let mut template_parser = EventReader::new(file);
-        loop {
-            match template_parser.next().map_err(Error::XmlDecodingError)? {
+        let mut template_parser =
+            LimitedEvents::new(EventReader::new(xml_source(file)?).into_iter(), options);
+        while let Some(event) = template_parser.next() {
+            let event = match event {
+                Ok(event) => event,
+                Err(err) => return template_parser.check(Err(Error::XmlDecodingError(err))),
+            };
+            match event {
                 XmlEvent::StartElement {
                     name,
                     attributes: _,
                     ..
                 } if name.local_name == "template" => {
                     let template = Self::parse_external_template(
-                        &mut template_parser.into_iter(),
+                        &mut template_parser,
                         path,
                         reader,
                         cache,
-                    )?;
-                    return Ok(template);
-                }
-                XmlEvent::EndDocument => {
-                    return Err(Error::PrematureEnd(
-                        "Template Document ended before template element was parsed".to_string(),
-                    ))
+                        options,
+                    )
+                    .map(|mut template| {
+                        template.parse_report = ParseReport {
+                            options: *options,
+                            warnings: template_parser.take_warnings(),
+                        };
+                        Arc::new(template)
+                    });
+                    return template_parser.check(template);
                 }
+                XmlEvent::EndDocument => break,
                 _ => {}
             }
         }
+        Err(Error::PrematureEnd(
+            "Template Document ended before template element was parsed".to_string(),
+        ))
     }
 
     fn parse_external_template(
@@ -66,27 +90,24 @@ impl Template {
         template_path: &Path,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
-    ) -> Result<Arc<Template>> {
+        options: &ParseOptions,
+    ) -> Result<Template> {
         let mut object = Option::None;
         let mut tileset = None;
         let mut tileset_gid: Vec<MapTilesetGid> = vec![];
 
         parse_tag!(parser, "template", {
             "object" => |attrs| {
-                object = Some(ObjectData::new(parser, attrs, Some(&tileset_gid), tileset.clone(), template_path.parent().ok_or(Error::PathIsNotFile)?, reader, cache)?);
+                object = Some(ObjectData::new(parser, attrs, Some(&tileset_gid), tileset.clone(), template_path.parent().ok_or(Error::PathIsNotFile)?, reader, cache, options)?);
                 Ok(())
             },
             "tileset" => |attrs: Vec<OwnedAttribute>| {
-                let res = Tileset::parse_xml_in_map(parser, &attrs, template_path, reader, cache)?;
+                let res = Tileset::parse_xml_in_map(parser, &attrs, template_path, template_path.parent().ok_or(Error::PathIsNotFile)?, reader, cache, options)?;
                 match res.result_type {
                     EmbeddedParseResultType::ExternalReference { tileset_path } => {
-                        tileset = Some(if let Some(ts) = cache.get_tileset(&tileset_path) {
-                            ts
-                        } else {
-                            let tileset = Arc::new(crate::parse::xml::parse_tileset(&tileset_path, reader, cache)?);
-                            cache.insert_tileset(tileset_path.clone(), tileset.clone());
-                            tileset
-                        });
+                        tileset = Some(cached_or_parse(cache, &tileset_path, options, |cache| {
+                            crate::parse::parse_tileset(&tileset_path, reader, cache, options).map(Arc::new)
+                        })?);
                     }
                     EmbeddedParseResultType::Embedded { tileset: embedded_tileset } => {
                         tileset = Some(Arc::new(embedded_tileset));
@@ -102,6 +123,11 @@ impl Template {
 
         let object = object.ok_or(Error::TemplateHasNoObject)?;
 
-        Ok(Arc::new(Template { tileset, object }))
+        Ok(Template {
+            source: template_path.to_owned(),
+            tileset,
+            object,
+            parse_report: ParseReport::default(),
+        })
     }
 }
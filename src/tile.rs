@@ -1,22 +1,81 @@
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, io::Write, path::Path};
 
 use xml::attribute::OwnedAttribute;
 
+#[cfg(feature = "json")]
 use crate::{
-    animation::{parse_animation, Frame},
+    animation::write_json_animation,
+    parse::json::{required, JsonObject},
+    properties::write_json_properties,
+    write::{f32_to_json, json_object, JsonWriter},
+};
+use crate::{
+    animation::{parse_animation, write_animation, Frame},
     error::Error,
     image::Image,
     layers::ObjectLayerData,
-    properties::{parse_properties, Properties},
+    parse::ParseOptions,
+    properties::{parse_properties, write_properties, Properties},
     util::{get_attrs, parse_tag, XmlEventResult},
+    write::XmlWriter,
     ResourceCache, ResourceReader, Result, Tileset,
 };
 
 /// A tile ID, local to a tileset.
-pub type TileId = u32;
+///
+/// Unlike a [`Gid`], which identifies a tile across all the tilesets of a map, this ID only has
+/// meaning within the tileset the tile belongs to. Use [`Map::gid_to_local_id`] to turn the former
+/// into the latter.
+///
+/// [`Gid`]: crate::Gid
+/// [`Map::gid_to_local_id`]: crate::Map::gid_to_local_id
+///
+/// ## Example
+/// ```
+/// use tiled::TileId;
+///
+/// let id = TileId::new(5);
+/// assert_eq!(id.get(), 5);
+/// assert_eq!(TileId::from(5), id);
+/// assert_eq!(u32::from(id), 5);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileId(u32);
+
+impl TileId {
+    /// Creates a tile ID from its raw value.
+    pub const fn new(id: u32) -> Self {
+        Self(id)
+    }
+
+    /// Returns the raw value of this tile ID.
+    pub const fn get(self) -> u32 {
+        self.0
+    }
+}
+
+impl From<u32> for TileId {
+    fn from(id: u32) -> Self {
+        Self(id)
+    }
+}
+
+impl From<TileId> for u32 {
+    fn from(id: TileId) -> Self {
+        id.0
+    }
+}
+
+impl std::fmt::Display for TileId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
 
 /// Raw data belonging to a tile.
 #[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TileData {
     /// The image of the tile. Only set when the tile is part of an "image collection" tileset.
     pub image: Option<Image>,
@@ -33,7 +92,7 @@ pub struct TileData {
 }
 
 /// Points to a tile belonging to a tileset.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Tile<'tileset> {
     pub(crate) tileset: &'tileset Tileset,
     pub(crate) data: &'tileset TileData,
@@ -59,6 +118,48 @@ impl<'tileset> std::ops::Deref for Tile<'tileset> {
     }
 }
 
+impl TileData {
+    /// Returns the local ID of the tile to draw `elapsed_ms` milliseconds after this tile's
+    /// animation started, looping it once it ends. Returns [`None`] if the tile isn't animated.
+    ///
+    /// Frames with a duration of 0 are never shown, unless all of them have a duration of 0, in
+    /// which case the first one is always shown.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use tiled::TileId;
+    ///
+    /// let tileset = tiled::Loader::new().load_tsx_tileset("assets/tilesheet_tiles.tsx")?;
+    /// let tile = tileset.get_tile(TileId::new(1)).unwrap();
+    ///
+    /// // The animation shows tile 1 for 100ms, then tile 2 for 200ms.
+    /// assert_eq!(tile.animation_frame_at(50), Some(TileId::new(1)));
+    /// assert_eq!(tile.animation_frame_at(150), Some(TileId::new(2)));
+    /// assert_eq!(tile.animation_frame_at(350), Some(TileId::new(1)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn animation_frame_at(&self, elapsed_ms: u32) -> Option<TileId> {
+        let frames = self.animation.as_deref()?;
+        let first = frames.first()?;
+        let total: u64 = frames.iter().map(|frame| frame.duration as u64).sum();
+        if total == 0 {
+            return Some(first.tile_id);
+        }
+
+        let mut elapsed = elapsed_ms as u64 % total;
+        frames
+            .iter()
+            .find(|frame| {
+                let shown = elapsed < frame.duration as u64;
+                elapsed = elapsed.saturating_sub(frame.duration as u64);
+                shown
+            })
+            .map(|frame| frame.tile_id)
+    }
+}
+
 impl TileData {
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
@@ -66,13 +167,14 @@ impl TileData {
         path_relative_to: &Path,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        options: &ParseOptions,
     ) -> Result<(TileId, TileData)> {
         let ((user_type, user_class, probability), id) = get_attrs!(
             for v in attrs {
                 Some("type") => user_type ?= v.parse(),
                 Some("class") => user_class ?= v.parse(),
                 Some("probability") => probability ?= v.parse(),
-                "id" => id ?= v.parse::<u32>(),
+                "id" => id ?= v.parse::<u32>().map(TileId::new),
             }
             ((user_type, user_class, probability), id)
         );
@@ -93,7 +195,7 @@ impl TileData {
             "objectgroup" => |attrs| {
                 // Tile objects are not allowed within tile object groups, so we can pass None as the
                 // tilesets vector
-                objectgroup = Some(ObjectLayerData::new(parser, attrs, None, None, path_relative_to, reader, cache)?.0);
+                objectgroup = Some(ObjectLayerData::new(parser, attrs, None, None, path_relative_to, reader, cache, options)?.0);
                 Ok(())
             },
             "animation" => |_| {
@@ -114,3 +216,118 @@ impl TileData {
         ))
     }
 }
+
+#[cfg(feature = "json")]
+impl TileData {
+    pub(crate) fn parse_json(
+        json: &JsonObject,
+        path_relative_to: &Path,
+        reader: &mut impl ResourceReader,
+        cache: &mut impl ResourceCache,
+        options: &ParseOptions,
+    ) -> Result<(TileId, TileData)> {
+        let id = TileId::new(required(json.u32("id")?, "id")?);
+        // Tiled 1.9 saved classes as `class`, while other versions use `type`.
+        let user_type = json.string("type")?.or(json.string("class")?);
+        let collision = json
+            .object("objectgroup")?
+            .map(|objectgroup| {
+                // Tile objects are not allowed within tile object groups, so we can pass None as
+                // the tilesets vector
+                ObjectLayerData::parse_json(
+                    &objectgroup,
+                    None,
+                    None,
+                    path_relative_to,
+                    reader,
+                    cache,
+                    options,
+                )
+            })
+            .transpose()?
+            .map(|(collision, _properties)| collision);
+        let animation = json
+            .get("animation")
+            .map(|_| {
+                json.objects("animation")?
+                    .iter()
+                    .map(Frame::parse_json)
+                    .collect::<Result<Vec<_>>>()
+            })
+            .transpose()?;
+
+        Ok((
+            id,
+            TileData {
+                image: Image::parse_json(json, path_relative_to)?,
+                properties: crate::properties::parse_json_properties(json)?,
+                collision,
+                animation,
+                user_type,
+                probability: json.f32("probability")?.unwrap_or(1.0),
+            },
+        ))
+    }
+}
+
+impl TileData {
+    pub(crate) fn write_xml(&self, id: TileId, writer: &mut XmlWriter<impl Write>) -> Result<()> {
+        let mut attrs = vec![("id", id.to_string())];
+        if let Some(user_type) = &self.user_type {
+            attrs.push(("type", user_type.clone()));
+        }
+        if self.probability != 1.0 {
+            attrs.push(("probability", self.probability.to_string()));
+        }
+
+        writer.start("tile", &attrs)?;
+        write_properties(writer, &self.properties)?;
+        if let Some(image) = &self.image {
+            image.write_xml(writer)?;
+        }
+        if let Some(collision) = &self.collision {
+            collision.write_xml(writer, Vec::new(), &Properties::new())?;
+        }
+        if let Some(animation) = &self.animation {
+            write_animation(writer, animation)?;
+        }
+        writer.end()
+    }
+}
+
+#[cfg(feature = "json")]
+impl TileData {
+    pub(crate) fn write_json(&self, id: TileId, writer: &JsonWriter) -> serde_json::Value {
+        let mut json = json_object(vec![("id", id.get().into())]);
+        if let Some(user_type) = &self.user_type {
+            json.insert("type".to_owned(), user_type.clone().into());
+        }
+        if self.probability != 1.0 {
+            json.insert("probability".to_owned(), f32_to_json(self.probability));
+        }
+        if let Some(properties) = write_json_properties(&self.properties) {
+            json.insert("properties".to_owned(), properties);
+        }
+        if let Some(image) = &self.image {
+            image.write_json(writer, &mut json);
+        }
+        if let Some(collision) = &self.collision {
+            let objectgroup = collision.write_json(
+                writer,
+                json_object(vec![
+                    ("name", "".into()),
+                    ("visible", true.into()),
+                    ("opacity", 1.into()),
+                    ("x", 0.into()),
+                    ("y", 0.into()),
+                ]),
+                &Properties::new(),
+            );
+            json.insert("objectgroup".to_owned(), objectgroup);
+        }
+        if let Some(animation) = &self.animation {
+            json.insert("animation".to_owned(), write_json_animation(animation));
+        }
+        json.into()
+    }
+}
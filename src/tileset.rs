@@ -1,13 +1,21 @@
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 use xml::attribute::OwnedAttribute;
 
 use crate::error::{Error, Result};
 use crate::image::Image;
-use crate::properties::{parse_properties, Properties};
+#[cfg(feature = "json")]
+use crate::parse::json::{required, JsonObject};
+use crate::parse::{ParseOptions, ParseReport};
+use crate::properties::{parse_properties, write_properties, Properties};
 use crate::tile::TileData;
-use crate::{util::*, Gid, ResourceCache, ResourceReader, Tile, TileId};
+use crate::write::{push_unparsed_attributes, XmlWriter};
+use crate::{util::*, Gid, Orientation, ProjectTypes, ResourceCache, ResourceReader, Tile, TileId};
 
 mod wangset;
 pub use wangset::*;
@@ -15,8 +23,17 @@ pub use wangset::*;
 /// A collection of tiles for usage in maps and template objects.
 ///
 /// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tileset).
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tileset {
+    /// The path first used in a [`ResourceReader`] to load this tileset.
+    ///
+    /// For embedded tilesets, this path will be the same as the template or map's source path, or
+    /// empty if the map has none.
+    ///
+    /// Paths within the tileset, such as the [source](Image::source) of its images, are already
+    /// joined with the directory of this path, so they can be opened as they are.
+    pub source: PathBuf,
     /// The name of the tileset, set by the user.
     pub name: String,
     /// The (maximum) width in pixels of the tiles in this tileset. Irrelevant for [image collection]
@@ -37,6 +54,9 @@ pub struct Tileset {
     pub margin: u32,
     /// The number of tiles in this tileset. Note that tile IDs don't always have a connection with
     /// the tile count, and as such there may be tiles with an ID bigger than the tile count.
+    ///
+    /// Files made by older versions of Tiled don't store it, in which case it is 0; see
+    /// [`Tileset::computed_tile_count`].
     pub tilecount: u32,
     /// The number of tile columns in the tileset. Editable for image collection tilesets, otherwise
     /// calculated using [image](Self::image) width, [tile width](Self::tile_width),
@@ -67,6 +87,259 @@ pub struct Tileset {
 
     /// The custom tileset type, arbitrarily set by the user.
     pub user_type: Option<String>,
+
+    /// The size tiles from this tileset are drawn at in maps.
+    pub tile_render_size: TileRenderSize,
+    /// How tiles from this tileset are scaled to fit the size they are drawn at, when it isn't
+    /// their own.
+    pub fill_mode: FillMode,
+    /// The point of its tiles that tile objects are positioned at and rotated around.
+    pub object_alignment: ObjectAlignment,
+
+    /// The attributes of the `<tileset>` element this crate doesn't parse, by name, such as the
+    /// ones added by versions of Tiled newer than it supports. They are written back out along
+    /// with the tileset.
+    ///
+    /// Only kept if the tileset was loaded with
+    /// [`Loader::with_unparsed_attributes`](crate::Loader::with_unparsed_attributes), and
+    /// [`None`] if there are none.
+    pub unparsed_attributes: Option<Box<HashMap<String, String>>>,
+
+    /// How the tileset was parsed, along with the problems found, which [`Map::validate`] returns
+    /// for the maps using it.
+    ///
+    /// [`Map::validate`]: crate::Map::validate
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) parse_report: ParseReport,
+}
+
+/// The size the tiles of a [`Tileset`] are drawn at in maps.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum TileRenderSize {
+    /// The size of the tiles themselves.
+    #[default]
+    Tile,
+    /// The size of the tiles of the map's grid, scaling the tileset's tiles if needed.
+    Grid,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// An error arising from trying to parse a [`TileRenderSize`] that is not valid.
+pub struct TileRenderSizeError {
+    /// The invalid string found.
+    pub str_found: String,
+}
+
+impl fmt::Display for TileRenderSizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to parse tile render size, valid options are `tile`, `grid` but got `{}` \
+            instead",
+            self.str_found
+        )
+    }
+}
+
+impl std::error::Error for TileRenderSizeError {}
+
+impl FromStr for TileRenderSize {
+    type Err = TileRenderSizeError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "tile" => Ok(TileRenderSize::Tile),
+            "grid" => Ok(TileRenderSize::Grid),
+            _ => Err(TileRenderSizeError {
+                str_found: s.to_owned(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for TileRenderSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TileRenderSize::Tile => write!(f, "tile"),
+            TileRenderSize::Grid => write!(f, "grid"),
+        }
+    }
+}
+
+/// How the tiles of a [`Tileset`] are scaled when drawn at a size other than their own, such as
+/// with [`TileRenderSize::Grid`].
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum FillMode {
+    /// Tiles are stretched to fill the whole size.
+    #[default]
+    Stretch,
+    /// Tiles are scaled as much as possible while keeping their aspect ratio, and centered.
+    PreserveAspectFit,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// An error arising from trying to parse a [`FillMode`] that is not valid.
+pub struct FillModeError {
+    /// The invalid string found.
+    pub str_found: String,
+}
+
+impl fmt::Display for FillModeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to parse fill mode, valid options are `stretch`, `preserve-aspect-fit` but \
+            got `{}` instead",
+            self.str_found
+        )
+    }
+}
+
+impl std::error::Error for FillModeError {}
+
+impl FromStr for FillMode {
+    type Err = FillModeError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "stretch" => Ok(FillMode::Stretch),
+            "preserve-aspect-fit" => Ok(FillMode::PreserveAspectFit),
+            _ => Err(FillModeError {
+                str_found: s.to_owned(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for FillMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FillMode::Stretch => write!(f, "stretch"),
+            FillMode::PreserveAspectFit => write!(f, "preserve-aspect-fit"),
+        }
+    }
+}
+
+/// The point of the tiles of a [`Tileset`] that tile objects using them are positioned at, and
+/// rotated around.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
+#[allow(missing_docs)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ObjectAlignment {
+    /// [`ObjectAlignment::BottomLeft`] in orthogonal maps and [`ObjectAlignment::Bottom`] in
+    /// isometric ones, for compatibility with versions of Tiled before this setting existed.
+    #[default]
+    Unspecified,
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl ObjectAlignment {
+    /// Returns where the alignment point is within a tile, as fractions of its width and height
+    /// from its top-left corner, given the orientation of the map the tile is used in.
+    pub fn anchor(self, orientation: Orientation) -> (f32, f32) {
+        match self {
+            ObjectAlignment::Unspecified if orientation == Orientation::Isometric => (0.5, 1.0),
+            ObjectAlignment::Unspecified => (0.0, 1.0),
+            ObjectAlignment::TopLeft => (0.0, 0.0),
+            ObjectAlignment::Top => (0.5, 0.0),
+            ObjectAlignment::TopRight => (1.0, 0.0),
+            ObjectAlignment::Left => (0.0, 0.5),
+            ObjectAlignment::Center => (0.5, 0.5),
+            ObjectAlignment::Right => (1.0, 0.5),
+            ObjectAlignment::BottomLeft => (0.0, 1.0),
+            ObjectAlignment::Bottom => (0.5, 1.0),
+            ObjectAlignment::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// An error arising from trying to parse an [`ObjectAlignment`] that is not valid.
+pub struct ObjectAlignmentError {
+    /// The invalid string found.
+    pub str_found: String,
+}
+
+impl fmt::Display for ObjectAlignmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to parse object alignment, valid options are `unspecified`, `topleft`, `top`, \
+            `topright`, `left`, `center`, `right`, `bottomleft`, `bottom`, `bottomright` but got \
+            `{}` instead",
+            self.str_found
+        )
+    }
+}
+
+impl std::error::Error for ObjectAlignmentError {}
+
+impl FromStr for ObjectAlignment {
+    type Err = ObjectAlignmentError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "unspecified" => Ok(ObjectAlignment::Unspecified),
+            "topleft" => Ok(ObjectAlignment::TopLeft),
+            "top" => Ok(ObjectAlignment::Top),
+            "topright" => Ok(ObjectAlignment::TopRight),
+            "left" => Ok(ObjectAlignment::Left),
+            "center" => Ok(ObjectAlignment::Center),
+            "right" => Ok(ObjectAlignment::Right),
+            "bottomleft" => Ok(ObjectAlignment::BottomLeft),
+            "bottom" => Ok(ObjectAlignment::Bottom),
+            "bottomright" => Ok(ObjectAlignment::BottomRight),
+            _ => Err(ObjectAlignmentError {
+                str_found: s.to_owned(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for ObjectAlignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ObjectAlignment::Unspecified => "unspecified",
+            ObjectAlignment::TopLeft => "topleft",
+            ObjectAlignment::Top => "top",
+            ObjectAlignment::TopRight => "topright",
+            ObjectAlignment::Left => "left",
+            ObjectAlignment::Center => "center",
+            ObjectAlignment::Right => "right",
+            ObjectAlignment::BottomLeft => "bottomleft",
+            ObjectAlignment::Bottom => "bottom",
+            ObjectAlignment::BottomRight => "bottomright",
+        })
+    }
+}
+
+/// Parses an optional attribute of a tileset, keeping the reason it is invalid in the error.
+fn parse_optional_attr<T>(value: Option<&str>) -> Result<T>
+where
+    T: FromStr + Default,
+    T::Err: fmt::Display,
+{
+    value
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|err: T::Err| Error::MalformedAttributes(err.to_string()))
+        })
+        .transpose()
+        .map(Option::unwrap_or_default)
 }
 
 pub(crate) enum EmbeddedParseResultType {
@@ -89,11 +362,74 @@ struct TilesetProperties {
     user_type: Option<String>,
     tile_width: u32,
     tile_height: u32,
+    tile_render_size: TileRenderSize,
+    fill_mode: FillMode,
+    object_alignment: ObjectAlignment,
+    /// The path the tileset was loaded from.
+    source: PathBuf,
     /// The root all non-absolute paths contained within the tileset are relative to.
     root_path: PathBuf,
+    #[allow(clippy::box_collection)]
+    unparsed_attributes: Option<Box<HashMap<String, String>>>,
 }
 
 impl Tileset {
+    /// Parses a file hopefully containing a Tiled tileset, using a new [`Loader`](crate::Loader).
+    ///
+    /// This is a shorthand for [`Loader::load_tsx_tileset`](crate::Loader::load_tsx_tileset); use
+    /// a loader directly if you want to reuse its cache across several loads.
+    pub fn parse_file(path: impl AsRef<Path>) -> Result<Tileset> {
+        crate::Loader::new().load_tsx_tileset(path)
+    }
+
+    /// Parses a tileset from `reader`, treating it as if it was read from `origin`, using a new
+    /// [`Loader`](crate::Loader). External files referenced by the tileset, such as its image,
+    /// are resolved relative to `origin`, which doesn't need to exist itself.
+    ///
+    /// This is a shorthand for
+    /// [`Loader::load_tsx_tileset_from`](crate::Loader::load_tsx_tileset_from).
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use std::fs::File;
+    ///
+    /// let path = "assets/tilesheet.tsx";
+    /// let tileset = tiled::Tileset::parse_reader(File::open(path).unwrap(), path)?;
+    ///
+    /// assert_eq!(tileset.tilecount, 84);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn parse_reader(reader: impl Read, origin: impl AsRef<Path>) -> Result<Tileset> {
+        crate::Loader::new().load_tsx_tileset_from(reader, origin)
+    }
+
+    /// Writes the tileset to `writer` as a standalone TSX file, which maps can then reference
+    /// with [`WriteOptions::with_external_tileset`](crate::WriteOptions::with_external_tileset).
+    ///
+    /// Paths, such as the one of the tileset image, are written relative to the directory of
+    /// [`Tileset::source`], so the output should be saved next to the original tileset for them
+    /// to stay valid. For embedded tilesets, this is the directory of the map or template they
+    /// were in.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = tiled::Loader::new().load_tmx_map("assets/tiled_base64.tmx")?;
+    ///
+    /// let mut tsx = Vec::new();
+    /// map.tilesets()[0].write_to(&mut tsx)?;
+    /// assert!(String::from_utf8_lossy(&tsx).contains(r#"<image source="tilesheet.png""#));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn write_to<W: Write>(&self, writer: W) -> Result<()> {
+        let base_path = self.source.parent().unwrap_or_else(|| Path::new(""));
+        let mut writer = XmlWriter::new(writer, base_path, Default::default());
+        self.write_xml(&mut writer, None)
+    }
+
     /// Gets the tile with the specified ID from the tileset.
     #[inline]
     pub fn get_tile(&self, id: TileId) -> Option<Tile> {
@@ -107,19 +443,173 @@ impl Tileset {
             .iter()
             .map(move |(id, data)| (*id, Tile::new(self, data)))
     }
+
+    /// Returns the number of tiles in the tileset, computing it from the size of the tileset's
+    /// image if [`Tileset::tilecount`] is 0, which is the case for files made by older versions
+    /// of Tiled that don't store it.
+    ///
+    /// Returns [`None`] if the count is unknown and the tileset has no image to compute it from.
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut tileset = tiled::Loader::new().load_tsx_tileset("assets/tilesheet.tsx")?;
+    /// tileset.tilecount = 0;
+    ///
+    /// // The image has 14 columns and 6 rows of tiles.
+    /// assert_eq!(tileset.computed_tile_count(), Some(84));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn computed_tile_count(&self) -> Option<u32> {
+        match (self.tilecount, &self.image) {
+            (0, Some(image)) => Some(image_tile_count(
+                image,
+                self.tile_width,
+                self.tile_height,
+                self.margin,
+                self.spacing,
+            )),
+            (0, None) => None,
+            (tilecount, _) => Some(tilecount),
+        }
+    }
+
+    /// Returns the image the tile with the given ID is drawn from: the tileset's own image for
+    /// regular tilesets, or the tile's image for image collection tilesets.
+    ///
+    /// Use [`Tileset::tile_rect`] to find the part of the image that belongs to the tile.
+    pub fn tile_image(&self, id: TileId) -> Option<&Image> {
+        match &self.image {
+            Some(image) if Some(id.get()) < self.computed_tile_count() => Some(image),
+            Some(_) => None,
+            None => self.tiles.get(&id)?.image.as_ref(),
+        }
+    }
+
+    /// Returns the part of its [image](Tileset::tile_image) the tile with the given ID is drawn
+    /// from, in pixels, taking the tileset's margin and spacing into account.
+    ///
+    /// For image collection tilesets, this is the whole image of the tile. Returns [`None`] if
+    /// the tile has no image, which for regular tilesets means its ID is beyond
+    /// [`Tileset::tilecount`].
+    ///
+    /// ## Example
+    /// ```
+    /// # fn main() -> tiled::Result<()> {
+    /// use tiled::TileId;
+    ///
+    /// let tileset = tiled::Loader::new().load_tsx_tileset("assets/tilesheet.tsx")?;
+    ///
+    /// // The tileset has 14 columns of 32x32 tiles.
+    /// let rect = tileset.tile_rect(TileId::new(15)).unwrap();
+    /// assert_eq!((rect.x, rect.y, rect.width, rect.height), (32, 32, 32, 32));
+    /// assert!(tileset.tile_rect(TileId::new(84)).is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tile_rect(&self, id: TileId) -> Option<Rect> {
+        let image = self.tile_image(id)?;
+        if self.image.is_none() {
+            return Some(Rect {
+                x: 0,
+                y: 0,
+                width: image.width as u32,
+                height: image.height as u32,
+            });
+        }
+
+        // Tilesets that weren't loaded from a file may not have their columns set.
+        let columns = match self.columns {
+            0 => Some(tiles_fitting(
+                image.width,
+                self.tile_width,
+                self.margin,
+                self.spacing,
+            ))
+            .filter(|&columns| columns > 0)?,
+            columns => columns,
+        };
+        let id = id.get();
+        Some(Rect {
+            x: (id % columns)
+                .saturating_mul(self.tile_width.saturating_add(self.spacing))
+                .saturating_add(self.margin),
+            y: (id / columns)
+                .saturating_mul(self.tile_height.saturating_add(self.spacing))
+                .saturating_add(self.margin),
+            width: self.tile_width,
+            height: self.tile_height,
+        })
+    }
+}
+
+/// Returns the number of tiles of the given size that fit in `image`, with `margin` pixels around
+/// them and `spacing` pixels between them.
+fn image_tile_count(
+    image: &Image,
+    tile_width: u32,
+    tile_height: u32,
+    margin: u32,
+    spacing: u32,
+) -> u32 {
+    tiles_fitting(image.width, tile_width, margin, spacing).saturating_mul(tiles_fitting(
+        image.height,
+        tile_height,
+        margin,
+        spacing,
+    ))
+}
+
+/// Returns the number of tiles of length `tile_length` that fit in `length` pixels, with `margin`
+/// pixels on either side and `spacing` pixels between them. This never overflows, and is 0 if no
+/// tile fits or tiles have no length.
+fn tiles_fitting(length: i32, tile_length: u32, margin: u32, spacing: u32) -> u32 {
+    let fitting = (i64::from(length) - 2 * i64::from(margin) + i64::from(spacing))
+        .max(0)
+        .checked_div(i64::from(tile_length) + i64::from(spacing))
+        .unwrap_or(0);
+    u32::try_from(fitting).unwrap_or(u32::MAX)
+}
+
+/// A rectangle within an image, in pixels.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rect {
+    /// The X coordinate of the rectangle's left edge.
+    pub x: u32,
+    /// The Y coordinate of the rectangle's top edge.
+    pub y: u32,
+    /// The width of the rectangle.
+    pub width: u32,
+    /// The height of the rectangle.
+    pub height: u32,
 }
 
 impl Tileset {
     pub(crate) fn parse_xml_in_map(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: &[OwnedAttribute],
-        path: &Path, // Template or Map file
+        path: &Path, // Template or Map file, empty for maps parsed from memory
+        path_relative_to: &Path,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        options: &ParseOptions,
     ) -> Result<EmbeddedParseResult> {
-        Tileset::parse_xml_embedded(parser, attrs, path, reader, cache).or_else(|err| {
-            if matches!(err, Error::MalformedAttributes(_)) {
-                Tileset::parse_xml_reference(attrs, path)
+        let is_reference = attrs.iter().any(|attr| attr.name.local_name == "source");
+        Tileset::parse_xml_embedded(
+            parser,
+            attrs,
+            path,
+            path_relative_to,
+            reader,
+            cache,
+            options,
+        )
+        .or_else(|err| {
+            // Errors in embedded tilesets are kept, rather than replaced by a missing source.
+            if is_reference && matches!(err, Error::MalformedAttributes(_)) {
+                Tileset::parse_xml_reference(attrs, path_relative_to)
             } else {
                 Err(err)
             }
@@ -129,31 +619,48 @@ impl Tileset {
     fn parse_xml_embedded(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: &[OwnedAttribute],
-        path: &Path, // Template or Map file
+        path: &Path, // Template or Map file, empty for maps parsed from memory
+        path_relative_to: &Path,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        options: &ParseOptions,
     ) -> Result<EmbeddedParseResult> {
+        let unparsed_attributes;
         let (
-            (spacing, margin, columns, name, user_type, user_class),
-            (tilecount, first_gid, tile_width, tile_height),
+            (
+                spacing,
+                margin,
+                columns,
+                name,
+                user_type,
+                user_class,
+                tile_render_size,
+                fill_mode,
+                object_alignment,
+                tilecount,
+            ),
+            (first_gid, tile_width, tile_height),
         ) = get_attrs!(
-           for v in attrs {
+           for v in attrs, unparsed(options.unparsed_attributes) unparsed_attributes {
             Some("spacing") => spacing ?= v.parse(),
+            Some("tilerendersize") => tile_render_size = v,
+            Some("fillmode") => fill_mode = v,
+            Some("objectalignment") => object_alignment = v,
             Some("margin") => margin ?= v.parse(),
             Some("columns") => columns ?= v.parse(),
             Some("name") => name = v,
             Some("type") => user_type ?= v.parse(),
             Some("class") => user_class ?= v.parse(),
+            Some("tilecount") => tilecount ?= v.parse::<u32>(),
 
-            "tilecount" => tilecount ?= v.parse::<u32>(),
             "firstgid" => first_gid ?= v.parse::<u32>().map(Gid),
             "tilewidth" => tile_width ?= v.parse::<u32>(),
             "tileheight" => tile_height ?= v.parse::<u32>(),
            }
-           ((spacing, margin, columns, name, user_type, user_class), (tilecount, first_gid, tile_width, tile_height))
+           ((spacing, margin, columns, name, user_type, user_class, tile_render_size, fill_mode, object_alignment, tilecount), (first_gid, tile_width, tile_height))
         );
 
-        let root_path = path.parent().ok_or(Error::PathIsNotFile)?.to_owned();
+        let root_path = path_relative_to.to_owned();
 
         Self::finish_parsing_xml(
             parser,
@@ -162,14 +669,20 @@ impl Tileset {
                 margin,
                 name: name.unwrap_or_default(),
                 user_type: user_type.or(user_class),
+                source: path.to_owned(),
                 root_path,
                 columns,
-                tilecount,
+                tilecount: tilecount.unwrap_or(0),
                 tile_height,
                 tile_width,
+                tile_render_size: parse_optional_attr(tile_render_size.as_deref())?,
+                fill_mode: parse_optional_attr(fill_mode.as_deref())?,
+                object_alignment: parse_optional_attr(object_alignment.as_deref())?,
+                unparsed_attributes,
             },
             reader,
             cache,
+            options,
         )
         .map(|tileset| EmbeddedParseResult {
             first_gid,
@@ -179,7 +692,7 @@ impl Tileset {
 
     fn parse_xml_reference(
         attrs: &[OwnedAttribute],
-        map_path: &Path,
+        path_relative_to: &Path,
     ) -> Result<EmbeddedParseResult> {
         let (first_gid, source) = get_attrs!(
             for v in attrs {
@@ -189,7 +702,7 @@ impl Tileset {
             (first_gid, source)
         );
 
-        let tileset_path = map_path.parent().ok_or(Error::PathIsNotFile)?.join(source);
+        let tileset_path = path_relative_to.join(source);
 
         Ok(EmbeddedParseResult {
             first_gid,
@@ -203,24 +716,41 @@ impl Tileset {
         path: &Path,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        options: &ParseOptions,
     ) -> Result<Tileset> {
+        let unparsed_attributes;
         let (
-            (spacing, margin, columns, name, user_type, user_class),
-            (tilecount, tile_width, tile_height),
+            (
+                spacing,
+                margin,
+                columns,
+                name,
+                user_type,
+                user_class,
+                tile_render_size,
+                fill_mode,
+                object_alignment,
+                tilecount,
+            ),
+            (tile_width, tile_height),
         ) = get_attrs!(
-            for v in attrs {
+            // Like maps, TSX files carry the version of the format and the editor they were saved in.
+            for v in attrs, unparsed(options.unparsed_attributes) unparsed_attributes except ["version", "tiledversion"] {
                 Some("spacing") => spacing ?= v.parse(),
+                Some("tilerendersize") => tile_render_size = v,
+                Some("fillmode") => fill_mode = v,
+                Some("objectalignment") => object_alignment = v,
                 Some("margin") => margin ?= v.parse(),
                 Some("columns") => columns ?= v.parse(),
                 Some("name") => name = v,
                 Some("type") => user_type ?= v.parse(),
                 Some("class") => user_class ?= v.parse(),
+                Some("tilecount") => tilecount ?= v.parse::<u32>(),
 
-                "tilecount" => tilecount ?= v.parse::<u32>(),
                 "tilewidth" => tile_width ?= v.parse::<u32>(),
                 "tileheight" => tile_height ?= v.parse::<u32>(),
             }
-            ((spacing, margin, columns, name, user_type, user_class), (tilecount, tile_width, tile_height))
+            ((spacing, margin, columns, name, user_type, user_class, tile_render_size, fill_mode, object_alignment, tilecount), (tile_width, tile_height))
         );
 
         let root_path = path.parent().ok_or(Error::PathIsNotFile)?.to_owned();
@@ -232,14 +762,20 @@ impl Tileset {
                 margin,
                 name: name.unwrap_or_default(),
                 user_type: user_type.or(user_class),
+                source: path.to_owned(),
                 root_path,
                 columns,
-                tilecount,
+                tilecount: tilecount.unwrap_or(0),
                 tile_height,
                 tile_width,
+                tile_render_size: parse_optional_attr(tile_render_size.as_deref())?,
+                fill_mode: parse_optional_attr(fill_mode.as_deref())?,
+                object_alignment: parse_optional_attr(object_alignment.as_deref())?,
+                unparsed_attributes,
             },
             reader,
             cache,
+            options,
         )
     }
 
@@ -248,6 +784,7 @@ impl Tileset {
         prop: TilesetProperties,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        options: &ParseOptions,
     ) -> Result<Tileset> {
         let mut image = Option::None;
         let mut tiles = HashMap::with_capacity(prop.tilecount as usize);
@@ -269,7 +806,7 @@ impl Tileset {
                 Ok(())
             },
             "tile" => |attrs| {
-                let (id, tile) = TileData::new(parser, attrs, &prop.root_path, reader, cache)?;
+                let (id, tile) = TileData::new(parser, attrs, &prop.root_path, reader, cache, options)?;
                 tiles.insert(id, tile);
                 Ok(())
             },
@@ -280,23 +817,39 @@ impl Tileset {
             },
         });
 
-        // A tileset is considered an image collection tileset if there is no image attribute (because its tiles do).
-        let is_image_collection_tileset = image.is_none();
-
-        if !is_image_collection_tileset {
-            for tile_id in 0..prop.tilecount {
-                tiles.entry(tile_id).or_default();
-            }
-        }
+        Self::finish_parsing(prop, image, tiles, wang_sets, properties, offset)
+    }
 
+    /// Builds the tileset out of its parsed contents, filling in the values that can be derived
+    /// from others.
+    fn finish_parsing(
+        prop: TilesetProperties,
+        image: Option<Image>,
+        mut tiles: HashMap<TileId, TileData>,
+        wang_sets: Vec<WangSet>,
+        properties: Properties,
+        offset: (i32, i32),
+    ) -> Result<Tileset> {
         let margin = prop.margin.unwrap_or(0);
         let spacing = prop.spacing.unwrap_or(0);
+
+        // A tileset is considered an image collection tileset if there is no image attribute (because its tiles do).
+        if let Some(image) = &image {
+            let tile_count = match prop.tilecount {
+                0 => image_tile_count(image, prop.tile_width, prop.tile_height, margin, spacing),
+                tilecount => tilecount,
+            };
+            for tile_id in 0..tile_count {
+                tiles.entry(TileId::new(tile_id)).or_default();
+            }
+        }
         let columns = prop
             .columns
             .map(Ok)
             .unwrap_or_else(|| Self::calculate_columns(&image, prop.tile_width, margin, spacing))?;
 
         Ok(Tileset {
+            source: prop.source,
             name: prop.name,
             user_type: prop.user_type,
             tile_width: prop.tile_width,
@@ -311,6 +864,11 @@ impl Tileset {
             tiles,
             wang_sets,
             properties,
+            tile_render_size: prop.tile_render_size,
+            fill_mode: prop.fill_mode,
+            object_alignment: prop.object_alignment,
+            unparsed_attributes: prop.unparsed_attributes,
+            parse_report: ParseReport::default(),
         })
     }
 
@@ -322,7 +880,7 @@ impl Tileset {
     ) -> Result<u32> {
         image
             .as_ref()
-            .map(|image| (image.width as u32 - margin + spacing) / (tile_width + spacing))
+            .map(|image| tiles_fitting(image.width, tile_width, margin, spacing))
             .ok_or_else(|| {
                 Error::MalformedAttributes(
                     "No <image> nor columns attribute in <tileset>".to_string(),
@@ -331,6 +889,231 @@ impl Tileset {
     }
 }
 
+#[cfg(feature = "json")]
+impl Tileset {
+    pub(crate) fn parse_json(
+        json: &JsonObject,
+        path: &Path,
+        reader: &mut impl ResourceReader,
+        cache: &mut impl ResourceCache,
+        options: &ParseOptions,
+    ) -> Result<Tileset> {
+        let root_path = path.parent().ok_or(Error::PathIsNotFile)?.to_owned();
+        let prop = TilesetProperties {
+            spacing: json.u32("spacing")?,
+            margin: json.u32("margin")?,
+            name: json.string("name")?.unwrap_or_default(),
+            // `type` holds the kind of document instead, which is always "tileset".
+            user_type: json.string("class")?,
+            source: path.to_owned(),
+            root_path,
+            columns: json.u32("columns")?,
+            tilecount: json.u32("tilecount")?.unwrap_or(0),
+            tile_height: required(json.u32("tileheight")?, "tileheight")?,
+            tile_width: required(json.u32("tilewidth")?, "tilewidth")?,
+            tile_render_size: parse_optional_attr(json.str("tilerendersize")?)?,
+            fill_mode: parse_optional_attr(json.str("fillmode")?)?,
+            object_alignment: parse_optional_attr(json.str("objectalignment")?)?,
+            unparsed_attributes: None,
+        };
+
+        let image = Image::parse_json(json, &prop.root_path)?;
+        let offset = match json.object("tileoffset")? {
+            Some(offset) => (
+                required(offset.i32("x")?, "x")?,
+                required(offset.i32("y")?, "y")?,
+            ),
+            None => (0, 0),
+        };
+        let mut tiles = HashMap::with_capacity(prop.tilecount as usize);
+        for tile in json.objects("tiles")? {
+            let (id, tile) = TileData::parse_json(&tile, &prop.root_path, reader, cache, options)?;
+            tiles.insert(id, tile);
+        }
+        let wang_sets = json
+            .objects("wangsets")?
+            .iter()
+            .map(WangSet::parse_json)
+            .collect::<Result<_>>()?;
+        let properties = crate::properties::parse_json_properties(json)?;
+
+        Self::finish_parsing(prop, image, tiles, wang_sets, properties, offset)
+    }
+}
+
+impl Tileset {
+    /// Writes the tileset as a `<tileset>` element, embedded in a map if `first_gid` is given.
+    pub(crate) fn write_xml(
+        &self,
+        writer: &mut XmlWriter<impl Write>,
+        first_gid: Option<Gid>,
+    ) -> Result<()> {
+        let mut attrs = Vec::new();
+        if let Some(first_gid) = first_gid {
+            attrs.push(("firstgid", first_gid.0.to_string()));
+        }
+        attrs.push(("name", self.name.clone()));
+        if let Some(user_type) = &self.user_type {
+            attrs.push(("class", user_type.clone()));
+        }
+        attrs.push(("tilewidth", self.tile_width.to_string()));
+        attrs.push(("tileheight", self.tile_height.to_string()));
+        if self.spacing != 0 {
+            attrs.push(("spacing", self.spacing.to_string()));
+        }
+        if self.margin != 0 {
+            attrs.push(("margin", self.margin.to_string()));
+        }
+        attrs.push(("tilecount", self.tilecount.to_string()));
+        attrs.push(("columns", self.columns.to_string()));
+        if self.tile_render_size != TileRenderSize::default() {
+            attrs.push(("tilerendersize", self.tile_render_size.to_string()));
+        }
+        if self.fill_mode != FillMode::default() {
+            attrs.push(("fillmode", self.fill_mode.to_string()));
+        }
+        if self.object_alignment != ObjectAlignment::default() {
+            attrs.push(("objectalignment", self.object_alignment.to_string()));
+        }
+        push_unparsed_attributes(&mut attrs, self.unparsed_attributes.as_deref());
+
+        writer.start("tileset", &attrs)?;
+        if (self.offset_x, self.offset_y) != (0, 0) {
+            writer.empty(
+                "tileoffset",
+                &[
+                    ("x", self.offset_x.to_string()),
+                    ("y", self.offset_y.to_string()),
+                ],
+            )?;
+        }
+        write_properties(writer, &self.properties)?;
+        if let Some(image) = &self.image {
+            image.write_xml(writer)?;
+        }
+
+        // Tiles without any data are filled in when parsing tilesets with an image, so there's no
+        // need to write them.
+        let mut tiles: Vec<_> = self
+            .tiles
+            .iter()
+            .filter(|(_, tile)| self.image.is_none() || **tile != TileData::default())
+            .collect();
+        tiles.sort_unstable_by_key(|(id, _)| **id);
+        for (id, tile) in tiles {
+            tile.write_xml(*id, writer)?;
+        }
+
+        if !self.wang_sets.is_empty() {
+            writer.start("wangsets", &[])?;
+            for wang_set in &self.wang_sets {
+                wang_set.write_xml(writer)?;
+            }
+            writer.end()?;
+        }
+        writer.end()
+    }
+}
+
+impl Tileset {
+    /// Fills in the properties of this tileset and its contents from the classes in `types`.
+    pub(crate) fn resolve_project_types(&mut self, types: &ProjectTypes) {
+        types.resolve_in_place(self.user_type.as_deref(), &mut self.properties);
+        for tile in self.tiles.values_mut() {
+            types.resolve_in_place(tile.user_type.as_deref(), &mut tile.properties);
+            if let Some(collision) = &mut tile.collision {
+                collision.resolve_project_types(types);
+            }
+        }
+        for wang_set in &mut self.wang_sets {
+            types.resolve_in_place(None, &mut wang_set.properties);
+            for wang_color in &mut wang_set.wang_colors {
+                types.resolve_in_place(None, &mut wang_color.properties);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl Tileset {
+    /// Returns the tileset as a JSON tileset, embedded in a map if `first_gid` is given.
+    pub(crate) fn write_json(
+        &self,
+        writer: &crate::write::JsonWriter,
+        first_gid: Option<Gid>,
+    ) -> serde_json::Value {
+        // Tiles without any data are filled in when parsing tilesets with an image, so there's no
+        // need to write them.
+        let mut tiles: Vec<_> = self
+            .tiles
+            .iter()
+            .filter(|(_, tile)| self.image.is_none() || **tile != TileData::default())
+            .collect();
+        tiles.sort_unstable_by_key(|(id, _)| **id);
+
+        let mut json = crate::write::json_object(vec![
+            ("name", self.name.clone().into()),
+            ("tilewidth", self.tile_width.into()),
+            ("tileheight", self.tile_height.into()),
+            ("spacing", self.spacing.into()),
+            ("margin", self.margin.into()),
+            ("tilecount", self.tilecount.into()),
+            ("columns", self.columns.into()),
+        ]);
+        if let Some(first_gid) = first_gid {
+            json.insert("firstgid".to_owned(), first_gid.0.into());
+        } else {
+            json.insert("type".to_owned(), "tileset".into());
+        }
+        if let Some(user_type) = &self.user_type {
+            json.insert("class".to_owned(), user_type.clone().into());
+        }
+        if self.tile_render_size != TileRenderSize::default() {
+            json.insert(
+                "tilerendersize".to_owned(),
+                self.tile_render_size.to_string().into(),
+            );
+        }
+        if self.fill_mode != FillMode::default() {
+            json.insert("fillmode".to_owned(), self.fill_mode.to_string().into());
+        }
+        if self.object_alignment != ObjectAlignment::default() {
+            json.insert(
+                "objectalignment".to_owned(),
+                self.object_alignment.to_string().into(),
+            );
+        }
+        if (self.offset_x, self.offset_y) != (0, 0) {
+            json.insert(
+                "tileoffset".to_owned(),
+                serde_json::json!({ "x": self.offset_x, "y": self.offset_y }),
+            );
+        }
+        if let Some(properties) = crate::properties::write_json_properties(&self.properties) {
+            json.insert("properties".to_owned(), properties);
+        }
+        if let Some(image) = &self.image {
+            image.write_json(writer, &mut json);
+        }
+        if !tiles.is_empty() {
+            json.insert(
+                "tiles".to_owned(),
+                tiles
+                    .into_iter()
+                    .map(|(id, tile)| tile.write_json(*id, writer))
+                    .collect(),
+            );
+        }
+        if !self.wang_sets.is_empty() {
+            json.insert(
+                "wangsets".to_owned(),
+                self.wang_sets.iter().map(WangSet::write_json).collect(),
+            );
+        }
+        json.into()
+    }
+}
+
 /// Parse the optional <tileoffset x=... y=.../> tag.
 fn parse_tileoffset(attrs: Vec<OwnedAttribute>) -> Result<(i32, i32)> {
     Ok(get_attrs!(
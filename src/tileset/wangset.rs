@@ -1,11 +1,14 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, io::Write};
 
 use xml::attribute::OwnedAttribute;
 
+#[cfg(feature = "json")]
+use crate::parse::json::{required, JsonObject};
 use crate::{
     error::Error,
-    properties::{parse_properties, Properties},
+    properties::{parse_properties, write_properties, Properties},
     util::{get_attrs, parse_tag, XmlEventResult},
+    write::XmlWriter,
     Result, TileId,
 };
 
@@ -15,8 +18,9 @@ mod wang_tile;
 pub use wang_tile::*;
 
 /// Wang set's terrain brush connection type.
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum WangSetType {
     Corner,
     Edge,
@@ -31,6 +35,7 @@ impl Default for WangSetType {
 
 /// Raw data belonging to a WangSet.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WangSet {
     /// The name of the Wang set.
     pub name: String,
@@ -67,7 +72,11 @@ impl WangSet {
             "edge" => WangSetType::Edge,
             _ => WangSetType::default(),
         };
-        let tile = if tile >= 0 { Some(tile as u32) } else { None };
+        let tile = if tile >= 0 {
+            Some(TileId::new(tile as u32))
+        } else {
+            None
+        };
 
         // Gather variable data
         let mut wang_colors = Vec::new();
@@ -99,4 +108,105 @@ impl WangSet {
             properties,
         })
     }
+
+    /// Reads data from a JSON object to create a WangSet.
+    #[cfg(feature = "json")]
+    pub(crate) fn parse_json(json: &JsonObject) -> Result<WangSet> {
+        let wang_set_type = match required(json.str("type")?, "type")? {
+            "corner" => WangSetType::Corner,
+            "edge" => WangSetType::Edge,
+            _ => WangSetType::default(),
+        };
+        let tile = required(json.i64("tile")?, "tile")?;
+        let tile = if tile >= 0 {
+            Some(TileId::new(tile as u32))
+        } else {
+            None
+        };
+
+        let wang_colors = json
+            .objects("colors")?
+            .iter()
+            .map(WangColor::parse_json)
+            .collect::<Result<_>>()?;
+        let wang_tiles = json
+            .objects("wangtiles")?
+            .iter()
+            .map(WangTile::parse_json)
+            .collect::<Result<_>>()?;
+
+        Ok(WangSet {
+            name: required(json.string("name")?, "name")?,
+            wang_set_type,
+            tile,
+            wang_colors,
+            wang_tiles,
+            properties: crate::properties::parse_json_properties(json)?,
+        })
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self.wang_set_type {
+            WangSetType::Corner => "corner",
+            WangSetType::Edge => "edge",
+            WangSetType::Mixed => "mixed",
+        }
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn write_json(&self) -> serde_json::Value {
+        let mut wang_tiles: Vec<_> = self.wang_tiles.iter().collect();
+        wang_tiles.sort_unstable_by_key(|(id, _)| **id);
+
+        let mut json = crate::write::json_object(vec![
+            ("name", self.name.clone().into()),
+            ("type", self.type_name().into()),
+            (
+                "tile",
+                self.tile.map_or(-1, |tile| i64::from(tile.get())).into(),
+            ),
+            (
+                "colors",
+                self.wang_colors.iter().map(WangColor::write_json).collect(),
+            ),
+            (
+                "wangtiles",
+                wang_tiles
+                    .into_iter()
+                    .map(|(id, tile)| tile.write_json(*id))
+                    .collect(),
+            ),
+        ]);
+        if let Some(properties) = crate::properties::write_json_properties(&self.properties) {
+            json.insert("properties".to_owned(), properties);
+        }
+        json.into()
+    }
+
+    pub(crate) fn write_xml(&self, writer: &mut XmlWriter<impl Write>) -> Result<()> {
+        let wang_set_type = self.type_name();
+        writer.start(
+            "wangset",
+            &[
+                ("name", self.name.clone()),
+                ("type", wang_set_type.to_owned()),
+                (
+                    "tile",
+                    self.tile
+                        .map_or(-1, |tile| i64::from(tile.get()))
+                        .to_string(),
+                ),
+            ],
+        )?;
+        write_properties(writer, &self.properties)?;
+        for color in &self.wang_colors {
+            color.write_xml(writer)?;
+        }
+        let mut wang_tiles: Vec<_> = self.wang_tiles.iter().collect();
+        wang_tiles.sort_unstable_by_key(|(id, _)| **id);
+        for (id, tile) in wang_tiles {
+            tile.write_xml(*id, writer)?;
+        }
+        writer.end()
+    }
 }
@@ -1,16 +1,20 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, io::Write};
 
 use xml::attribute::OwnedAttribute;
 
+#[cfg(feature = "json")]
+use crate::parse::json::{required, JsonObject};
 use crate::{
     error::Error,
-    properties::{parse_properties, Color, Properties},
+    properties::{parse_properties, write_properties, Color, Properties},
     util::{get_attrs, parse_tag, XmlEventResult},
+    write::{color_to_string, XmlWriter},
     Result, TileId,
 };
 
 /// Stores the data of the Wang color.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WangColor {
     /// The name of this color.
     pub name: String,
@@ -41,7 +45,11 @@ impl WangColor {
             (name, color, tile, probability)
         );
 
-        let tile = if tile >= 0 { Some(tile as u32) } else { None };
+        let tile = if tile >= 0 {
+            Some(TileId::new(tile as u32))
+        } else {
+            None
+        };
 
         // Gather variable data
         let mut properties = HashMap::new();
@@ -60,4 +68,58 @@ impl WangColor {
             properties,
         })
     }
+    /// Reads data from a JSON object to create a WangColor.
+    #[cfg(feature = "json")]
+    pub(crate) fn parse_json(json: &JsonObject) -> Result<WangColor> {
+        let tile = required(json.i64("tile")?, "tile")?;
+        let tile = if tile >= 0 {
+            Some(TileId::new(tile as u32))
+        } else {
+            None
+        };
+
+        Ok(WangColor {
+            name: required(json.string("name")?, "name")?,
+            color: required(json.color("color")?, "color")?,
+            tile,
+            probability: required(json.f32("probability")?, "probability")?,
+            properties: crate::properties::parse_json_properties(json)?,
+        })
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn write_json(&self) -> serde_json::Value {
+        let mut json = crate::write::json_object(vec![
+            ("name", self.name.clone().into()),
+            ("color", color_to_string(self.color).into()),
+            (
+                "tile",
+                self.tile.map_or(-1, |tile| i64::from(tile.get())).into(),
+            ),
+            ("probability", crate::write::f32_to_json(self.probability)),
+        ]);
+        if let Some(properties) = crate::properties::write_json_properties(&self.properties) {
+            json.insert("properties".to_owned(), properties);
+        }
+        json.into()
+    }
+
+    pub(crate) fn write_xml(&self, writer: &mut XmlWriter<impl Write>) -> Result<()> {
+        writer.start(
+            "wangcolor",
+            &[
+                ("name", self.name.clone()),
+                ("color", color_to_string(self.color)),
+                (
+                    "tile",
+                    self.tile
+                        .map_or(-1, |tile| i64::from(tile.get()))
+                        .to_string(),
+                ),
+                ("probability", self.probability.to_string()),
+            ],
+        )?;
+        write_properties(writer, &self.properties)?;
+        writer.end()
+    }
 }
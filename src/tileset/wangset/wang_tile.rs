@@ -1,15 +1,21 @@
-use std::str::FromStr;
+#[cfg(feature = "json")]
+use std::convert::TryFrom;
+use std::{io::Write, str::FromStr};
 
 use xml::attribute::OwnedAttribute;
 
+#[cfg(feature = "json")]
+use crate::parse::json::{required, JsonObject};
 use crate::{
     error::Error,
     util::{get_attrs, XmlEventResult},
+    write::XmlWriter,
     Result, TileId,
 };
 
 /// The Wang ID, stored as an array of 8 u8 values.
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WangId(pub [u8; 8]);
 
 impl FromStr for WangId {
@@ -36,7 +42,8 @@ impl FromStr for WangId {
 }
 
 /// Stores the Wang ID.
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct WangTile {
     #[allow(missing_docs)]
     pub wang_id: WangId,
@@ -51,7 +58,7 @@ impl WangTile {
         // Get common data
         let (tile_id, wang_id) = get_attrs!(
             for v in attrs {
-                "tileid" => tile_id ?= v.parse::<u32>(),
+                "tileid" => tile_id ?= v.parse::<u32>().map(TileId::new),
                 "wangid" => wang_id ?= v.parse(),
             }
             (tile_id, wang_id)
@@ -59,4 +66,47 @@ impl WangTile {
 
         Ok((tile_id, WangTile { wang_id }))
     }
+    /// Reads data from a JSON object to create a WangTile.
+    #[cfg(feature = "json")]
+    pub(crate) fn parse_json(json: &JsonObject) -> Result<(TileId, WangTile)> {
+        let tile_id = TileId::new(required(json.u32("tileid")?, "tileid")?);
+        let wang_id = required(json.get("wangid"), "wangid")?;
+        let invalid = || Error::InvalidWangIdEncoding {
+            read_string: wang_id.to_string(),
+        };
+        let values = wang_id.as_array().ok_or_else(invalid)?;
+        if values.len() != 8 {
+            return Err(invalid());
+        }
+        let mut ret = [0u8; 8];
+        for (value, id) in values.iter().zip(ret.iter_mut()) {
+            *id = value
+                .as_u64()
+                .and_then(|v| u8::try_from(v).ok())
+                .ok_or_else(invalid)?;
+        }
+
+        Ok((
+            tile_id,
+            WangTile {
+                wang_id: WangId(ret),
+            },
+        ))
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn write_json(&self, id: TileId) -> serde_json::Value {
+        serde_json::json!({
+            "tileid": id.get(),
+            "wangid": self.wang_id.0,
+        })
+    }
+
+    pub(crate) fn write_xml(&self, id: TileId, writer: &mut XmlWriter<impl Write>) -> Result<()> {
+        let wang_id: Vec<_> = self.wang_id.0.iter().map(u8::to_string).collect();
+        writer.empty(
+            "wangtile",
+            &[("tileid", id.to_string()), ("wangid", wang_id.join(","))],
+        )
+    }
 }
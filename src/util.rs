@@ -1,3 +1,12 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{BufRead, BufReader, Cursor, ErrorKind, Read},
+};
+
+use xml::attribute::OwnedAttribute;
+
+use crate::{parse::ParseOptions, DuplicatePropertyPolicy, Gid, MapTilesetGid, Warning};
+
 /// Loops through the attributes once and pulls out the ones we ask it to. It
 /// will check that the required ones are there.
 ///
@@ -68,7 +77,41 @@
 ///     ((colour, infinite), (version, orientation, width, height, tile_width, tile_height))
 /// );
 /// ```
+///
+/// Adding `, unparsed($keep) $variable` after the attributes also assigns the attributes no branch
+/// matches to `$variable`, as returned by [`unparsed_attributes`], which are only collected if
+/// `$keep` is true. Names following `except` are treated as parsed as well:
+/// ```ignore
+/// let unparsed;
+/// let name = get_attrs!(
+///     for v in attrs, unparsed(options.unparsed_attributes) unparsed except ["nextobjectid"] {
+///         Some("name") => name = v,
+///     }
+///     name
+/// );
+/// ```
 macro_rules! get_attrs {
+    (
+        for $attr:ident in $attrs:ident, unparsed($keep:expr) $unparsed:ident $(except [$($parsed:literal),* $(,)?])? {
+            $($branches:tt)*
+        }
+        $ret_expr:expr
+    ) => {
+        {
+            $unparsed = $crate::util::unparsed_attributes(
+                &$attrs,
+                &$crate::util::attr_names!([$($($parsed),*)?] $($branches)*),
+                $keep,
+            );
+            $crate::util::get_attrs!(
+                for $attr in $attrs {
+                    $($branches)*
+                }
+                $ret_expr
+            )
+        }
+    };
+
     (
         for $attr:ident in $attrs:ident {
             $($branches:tt)*
@@ -90,6 +133,24 @@ macro_rules! get_attrs {
     };
 }
 
+/// Expands to an array of the attribute names of the branches given to [`get_attrs`], after those
+/// in the leading brackets.
+macro_rules! attr_names {
+    ([$($names:literal),*]) => {
+        [$($names),*]
+    };
+
+    ([$($names:literal),*] Some($attr_pat_opt:literal) => $opt_var:ident $(?)?= $opt_expr:expr $(, $($tail:tt)*)?) => {
+        $crate::util::attr_names!([$($names,)* $attr_pat_opt] $($($tail)*)?)
+    };
+
+    ([$($names:literal),*] $attr_pat_opt:literal => $opt_var:ident $(?)?= $opt_expr:expr $(, $($tail:tt)*)?) => {
+        $crate::util::attr_names!([$($names,)* $attr_pat_opt] $($($tail)*)?)
+    };
+}
+
+pub(crate) use attr_names;
+
 macro_rules! let_attr_branches {
     () => {};
 
@@ -241,10 +302,282 @@ pub(crate) use get_attrs;
 pub(crate) use map_wrapper;
 pub(crate) use parse_tag;
 
-use crate::{Gid, MapTilesetGid};
-
 pub(crate) type XmlEventResult = xml::reader::Result<xml::reader::XmlEvent>;
 
+/// Wraps the events of an XML document, stopping with an error once its elements are nested
+/// deeper than allowed, and applying the [`DuplicatePropertyPolicy`] of the options to the custom
+/// properties in it.
+///
+/// The error yielded is a placeholder; [`LimitedEvents::check`] replaces it with a
+/// [`LimitExceeded`](crate::Error::LimitExceeded) or
+/// [`DuplicateProperty`](crate::Error::DuplicateProperty) error once parsing stops.
+pub(crate) struct LimitedEvents<I> {
+    events: I,
+    depth: usize,
+    max_depth: usize,
+    exceeded: bool,
+    duplicate_properties: DuplicatePropertyPolicy,
+    /// The elements currently open with their IDs, only tracked if duplicate properties are
+    /// looked for.
+    elements: Vec<(String, Option<u32>)>,
+    /// The depth of each `<properties>` element currently open, with the property names found in
+    /// it so far.
+    property_names: Vec<(usize, HashSet<String>)>,
+    duplicate: Option<Warning>,
+    warnings: Vec<Warning>,
+}
+
+impl<I: Iterator<Item = XmlEventResult>> LimitedEvents<I> {
+    pub(crate) fn new(events: I, options: &ParseOptions) -> Self {
+        Self {
+            events,
+            depth: 0,
+            max_depth: options.limits.max_nesting_depth,
+            exceeded: false,
+            duplicate_properties: options.duplicate_properties,
+            elements: Vec::new(),
+            property_names: Vec::new(),
+            duplicate: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Returns the result of parsing the document, unless it was cut short for being nested too
+    /// deep or for having duplicate properties.
+    pub(crate) fn check<T>(&self, result: crate::Result<T>) -> crate::Result<T> {
+        if self.exceeded {
+            return Err(crate::Error::LimitExceeded {
+                description: format!(
+                    "elements are nested deeper than the limit of {}",
+                    self.max_depth
+                ),
+            });
+        }
+        if let Some(Warning::DuplicateProperty {
+            element,
+            element_id,
+            name,
+        }) = &self.duplicate
+        {
+            return Err(crate::Error::DuplicateProperty {
+                element: element.clone(),
+                element_id: *element_id,
+                name: name.clone(),
+            });
+        }
+        result
+    }
+
+    /// Takes the warnings about duplicate properties found so far, if they are to be reported.
+    pub(crate) fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Keeps track of the custom properties of the elements in the document, returning whether
+    /// `event` is a property whose name was already used by a sibling.
+    fn check_properties(&mut self, event: &xml::reader::XmlEvent) -> bool {
+        match event {
+            xml::reader::XmlEvent::StartElement {
+                name, attributes, ..
+            } => {
+                let attribute = |attribute_name: &str| {
+                    attributes
+                        .iter()
+                        .find(|attr| attr.name.local_name == attribute_name)
+                        .map(|attr| attr.value.as_str())
+                };
+                let mut duplicate = false;
+                match name.local_name.as_str() {
+                    "properties" => self.property_names.push((self.depth, HashSet::new())),
+                    "property" => {
+                        if let (Some((properties_depth, names)), Some(property_name)) =
+                            (self.property_names.last_mut(), attribute("name"))
+                        {
+                            if *properties_depth + 1 == self.depth
+                                && !names.insert(property_name.to_owned())
+                            {
+                                // The owner of the properties is the element they are in.
+                                let (element, element_id) = self
+                                    .elements
+                                    .len()
+                                    .checked_sub(2)
+                                    .map(|index| self.elements[index].clone())
+                                    .unwrap_or_default();
+                                let warning = Warning::DuplicateProperty {
+                                    element,
+                                    element_id,
+                                    name: property_name.to_owned(),
+                                };
+                                match self.duplicate_properties {
+                                    DuplicatePropertyPolicy::Error => {
+                                        self.duplicate = Some(warning);
+                                        duplicate = true;
+                                    }
+                                    _ => self.warnings.push(warning),
+                                }
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                let id = attribute("id").and_then(|id| id.parse().ok());
+                self.elements.push((name.local_name.clone(), id));
+                duplicate
+            }
+            xml::reader::XmlEvent::EndElement { .. } => {
+                self.elements.pop();
+                if matches!(self.property_names.last(), Some((depth, _)) if *depth > self.depth) {
+                    self.property_names.pop();
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<I: Iterator<Item = XmlEventResult>> Iterator for LimitedEvents<I> {
+    type Item = XmlEventResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.exceeded {
+            return Some(Err(
+                std::io::Error::other("XML nesting limit exceeded").into()
+            ));
+        }
+        if self.duplicate.is_some() {
+            return Some(Err(std::io::Error::other("duplicate property found").into()));
+        }
+        let event = self.events.next();
+        match &event {
+            Some(Ok(xml::reader::XmlEvent::StartElement { .. })) => {
+                self.depth += 1;
+                if self.depth > self.max_depth {
+                    self.exceeded = true;
+                    return self.next();
+                }
+            }
+            Some(Ok(xml::reader::XmlEvent::EndElement { .. })) => {
+                self.depth = self.depth.saturating_sub(1)
+            }
+            _ => {}
+        }
+        if self.duplicate_properties != DuplicatePropertyPolicy::KeepLast {
+            if let Some(Ok(inner)) = &event {
+                if self.check_properties(inner) {
+                    return self.next();
+                }
+            }
+        }
+        event
+    }
+}
+
+/// Prepares `source` to be read by the XML parser.
+///
+/// The parser tells UTF-16 files apart from UTF-8 ones by their byte order mark, but some tools
+/// save UTF-16 files without one. Those are recognized by how their first `<` is encoded instead,
+/// and given back the mark they are missing.
+pub(crate) fn xml_source(mut source: impl Read) -> crate::Result<impl BufRead> {
+    // Readers may return fewer bytes than asked for, so a single read could cut `<` in half.
+    let mut start = [0; 2];
+    let mut len = 0;
+    while len < start.len() {
+        match source.read(&mut start[len..]) {
+            Ok(0) => break,
+            Ok(read) => len += read,
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => return Err(crate::Error::XmlDecodingError(err.into())),
+        }
+    }
+    let byte_order_mark: &'static [u8] = if start.starts_with(b"<\0") {
+        &[0xFF, 0xFE]
+    } else if start.starts_with(b"\0<") {
+        &[0xFE, 0xFF]
+    } else {
+        &[]
+    };
+    Ok(byte_order_mark
+        .chain(Cursor::new(start).take(len as u64))
+        .chain(BufReader::new(source)))
+}
+
+/// Returns the attributes in `attrs` whose names aren't in `parsed`, so that the ones this crate
+/// doesn't support, such as those added by newer versions of Tiled, aren't lost. Nothing is
+/// collected unless `keep` is true, and [`None`] is returned if there are no such attributes.
+#[allow(clippy::box_collection)]
+pub(crate) fn unparsed_attributes(
+    attrs: &[OwnedAttribute],
+    parsed: &[&str],
+    keep: bool,
+) -> Option<Box<HashMap<String, String>>> {
+    if !keep {
+        return None;
+    }
+    let unparsed: HashMap<String, String> = attrs
+        .iter()
+        .filter(|attr| {
+            attr.name.prefix.is_none() && !parsed.contains(&attr.name.local_name.as_str())
+        })
+        .map(|attr| (attr.name.local_name.clone(), attr.value.clone()))
+        .collect();
+    (!unparsed.is_empty()).then(|| Box::new(unparsed))
+}
+
+/// Reads the rest of the current element, up to and including its closing tag, so that it can be
+/// parsed later by iterating over the events returned.
+pub(crate) fn read_element(
+    parser: &mut impl Iterator<Item = XmlEventResult>,
+) -> crate::Result<Vec<XmlEventResult>> {
+    let mut events = Vec::new();
+    let mut depth = 0usize;
+    for event in parser {
+        let event = event.map_err(crate::Error::XmlDecodingError)?;
+        match &event {
+            xml::reader::XmlEvent::StartElement { .. } => depth += 1,
+            xml::reader::XmlEvent::EndElement { .. } if depth == 0 => {
+                events.push(Ok(event));
+                return Ok(events);
+            }
+            xml::reader::XmlEvent::EndElement { .. } => depth -= 1,
+            xml::reader::XmlEvent::EndDocument => break,
+            _ => {}
+        }
+        events.push(Ok(event));
+    }
+    Err(crate::Error::PrematureEnd(
+        "Document ended before we expected.".to_string(),
+    ))
+}
+
+/// Returns the text contents of the current element, such as a `<data>` or `<chunk>` element, or
+/// `None` if it is empty. The element must not have children.
+///
+/// The XML parser may split the contents into several events, such as around CDATA sections or
+/// comments, so all of them are joined together.
+pub(crate) fn read_characters(
+    parser: &mut impl Iterator<Item = XmlEventResult>,
+) -> crate::Result<Option<String>> {
+    let mut data = String::new();
+    for next in parser {
+        match next.map_err(crate::Error::XmlDecodingError)? {
+            xml::reader::XmlEvent::Characters(s)
+            | xml::reader::XmlEvent::CData(s)
+            | xml::reader::XmlEvent::Whitespace(s) => data.push_str(&s),
+            // The element has no children, so the first closing tag is its own.
+            xml::reader::XmlEvent::EndElement { .. } => {
+                return Ok(if data.trim().is_empty() {
+                    None
+                } else {
+                    Some(data)
+                });
+            }
+            _ => {}
+        }
+    }
+    Err(crate::Error::PrematureEnd("Ran out of XML data".to_owned()))
+}
+
 /// Returns both the tileset and its index
 pub(crate) fn get_tileset_for_gid(
     tilesets: &[MapTilesetGid],
@@ -257,6 +590,17 @@ pub(crate) fn get_tileset_for_gid(
         .find(|(_idx, ts)| ts.first_gid <= gid)
 }
 
+/// Parses a boolean attribute value, such as `visible`. Tiled writes these as integers, but files
+/// converted from JSON or edited by hand may use words, so `1` and `true` are accepted as true and
+/// `0` and `false` as false, ignoring case. Returns [`None`] for any other value.
+pub(crate) fn parse_bool_attr(s: String) -> Option<bool> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" => Some(true),
+        "0" | "false" => Some(false),
+        _ => None,
+    }
+}
+
 pub fn floor_div(a: i32, b: i32) -> i32 {
     let d = a / b;
     let r = a % b;
@@ -0,0 +1,139 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    parse::json::{required, JsonObject},
+    Error, Result,
+};
+
+/// A Tiled world, which arranges several maps next to each other.
+///
+/// Worlds are only available with the `json` feature. Load them with
+/// [`Loader::load_world`](crate::Loader::load_world) or [`parse_world`](crate::parse_world).
+///
+/// Also see the [Tiled docs](https://doc.mapeditor.org/en/stable/manual/worlds/).
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// let mut loader = tiled::Loader::new();
+/// let world = loader.load_world("assets/world.world")?;
+///
+/// for (world_map, map) in world.maps.iter().zip(loader.load_world_maps(&world)?) {
+///     println!(
+///         "{:?} is at ({}, {}) and {} tiles wide",
+///         world_map.file_name, world_map.x, world_map.y, map.width
+///     );
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct World {
+    /// The path first used in a [`ResourceReader`](crate::ResourceReader) to load this world.
+    pub source: PathBuf,
+    /// The maps explicitly listed in the world, in the order they appear in the file.
+    pub maps: Vec<WorldMap>,
+    /// The patterns used to place all maps in the world's directory whose file name matches
+    /// them.
+    ///
+    /// These are only parsed; finding and placing the maps they match is left to the caller.
+    pub patterns: Vec<WorldPattern>,
+    /// Whether Tiled only shows the maps next to the one currently being edited.
+    pub only_show_adjacent_maps: bool,
+}
+
+/// A map in a [`World`], along with its position in it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorldMap {
+    /// The path to the map, already joined with the directory of the world file.
+    pub file_name: PathBuf,
+    /// The X coordinate of the map's top left corner in the world, in pixels.
+    pub x: i32,
+    /// The Y coordinate of the map's top left corner in the world, in pixels.
+    pub y: i32,
+    /// The width of the map in pixels, if stored in the world.
+    pub width: Option<u32>,
+    /// The height of the map in pixels, if stored in the world.
+    pub height: Option<u32>,
+}
+
+/// A pattern placing every map whose file name matches it in a [`World`].
+///
+/// The regular expression must have two captures, the X and Y indices of the map, which are
+/// turned into a position with [`WorldPattern::position`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorldPattern {
+    /// The regular expression file names are matched against, as it is written in the file.
+    pub regexp: String,
+    /// The horizontal distance between maps with consecutive X indices, in pixels.
+    pub multiplier_x: i32,
+    /// The vertical distance between maps with consecutive Y indices, in pixels.
+    pub multiplier_y: i32,
+    /// The X coordinate of the map with an X index of 0, in pixels.
+    pub offset_x: i32,
+    /// The Y coordinate of the map with a Y index of 0, in pixels.
+    pub offset_y: i32,
+    /// The width of the maps matched, in pixels, if stored in the world.
+    pub map_width: Option<u32>,
+    /// The height of the maps matched, in pixels, if stored in the world.
+    pub map_height: Option<u32>,
+}
+
+impl WorldPattern {
+    /// Returns the position in the world of a map matched with the given X and Y indices, or
+    /// [`None`] if it doesn't fit in an `i32`.
+    pub fn position(&self, x_index: i32, y_index: i32) -> Option<(i32, i32)> {
+        Some((
+            x_index
+                .checked_mul(self.multiplier_x)?
+                .checked_add(self.offset_x)?,
+            y_index
+                .checked_mul(self.multiplier_y)?
+                .checked_add(self.offset_y)?,
+        ))
+    }
+}
+
+impl World {
+    pub(crate) fn parse_json(json: &JsonObject, path: &Path) -> Result<World> {
+        let world_dir = path.parent().ok_or(Error::PathIsNotFile)?;
+        let maps = json
+            .objects("maps")?
+            .iter()
+            .map(|map| {
+                Ok(WorldMap {
+                    file_name: world_dir.join(required(map.str("fileName")?, "fileName")?),
+                    x: map.i32("x")?.unwrap_or(0),
+                    y: map.i32("y")?.unwrap_or(0),
+                    width: map.u32("width")?,
+                    height: map.u32("height")?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        let patterns = json
+            .objects("patterns")?
+            .iter()
+            .map(|pattern| {
+                Ok(WorldPattern {
+                    regexp: required(pattern.string("regexp")?, "regexp")?,
+                    multiplier_x: required(pattern.i32("multiplierX")?, "multiplierX")?,
+                    multiplier_y: required(pattern.i32("multiplierY")?, "multiplierY")?,
+                    offset_x: pattern.i32("offsetX")?.unwrap_or(0),
+                    offset_y: pattern.i32("offsetY")?.unwrap_or(0),
+                    map_width: pattern.u32("mapWidth")?,
+                    map_height: pattern.u32("mapHeight")?,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(World {
+            source: path.to_owned(),
+            maps,
+            patterns,
+            only_show_adjacent_maps: json.bool("onlyShowAdjacentMaps")?.unwrap_or(false),
+        })
+    }
+}
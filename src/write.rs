@@ -0,0 +1,369 @@
+//! Helpers used to write maps and their contents back out as XML.
+
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::{Component, Path, PathBuf},
+};
+
+use base64::Engine;
+use xml::writer::{EmitterConfig, EventWriter, XmlEvent};
+
+use crate::{Color, Error, Gid, Result};
+
+/// The way the tile data of tile layers is encoded when writing a map.
+///
+/// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#data).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum TileDataEncoding {
+    /// Comma separated values.
+    Csv,
+    /// Uncompressed base64.
+    Base64,
+    /// Base64 compressed with zlib. This is the default used by Tiled.
+    #[default]
+    Base64Zlib,
+    /// Base64 compressed with gzip.
+    Base64Gzip,
+    /// Base64 compressed with Zstandard.
+    #[cfg(feature = "zstd")]
+    Base64Zstd,
+}
+
+impl TileDataEncoding {
+    /// The `encoding` and `compression` attributes of a `<data>` element using this encoding.
+    pub(crate) fn attributes(self) -> Vec<(&'static str, String)> {
+        let (encoding, compression) = match self {
+            TileDataEncoding::Csv => ("csv", None),
+            TileDataEncoding::Base64 => ("base64", None),
+            TileDataEncoding::Base64Zlib => ("base64", Some("zlib")),
+            TileDataEncoding::Base64Gzip => ("base64", Some("gzip")),
+            #[cfg(feature = "zstd")]
+            TileDataEncoding::Base64Zstd => ("base64", Some("zstd")),
+        };
+        let mut attrs = vec![("encoding", encoding.to_owned())];
+        if let Some(compression) = compression {
+            attrs.push(("compression", compression.to_owned()));
+        }
+        attrs
+    }
+
+    /// Encodes the given tile bits, `width` tiles per row.
+    fn encode(self, bits: &[u32], width: usize) -> std::io::Result<String> {
+        if self == TileDataEncoding::Csv {
+            let rows: Vec<String> = bits
+                .chunks(width.max(1))
+                .map(|row| row.iter().map(u32::to_string).collect::<Vec<_>>().join(","))
+                .collect();
+            return Ok(format!("\n{}\n", rows.join(",\n")));
+        }
+
+        let bytes: Vec<u8> = bits.iter().flat_map(|bits| bits.to_le_bytes()).collect();
+        let bytes = match self {
+            TileDataEncoding::Base64Zlib => {
+                let mut encoder =
+                    flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(&bytes).and_then(|_| encoder.finish())
+            }
+            TileDataEncoding::Base64Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(&bytes).and_then(|_| encoder.finish())
+            }
+            #[cfg(feature = "zstd")]
+            TileDataEncoding::Base64Zstd => zstd::stream::encode_all(&bytes[..], 0),
+            _ => Ok(bytes),
+        }?;
+
+        Ok(base64::engine::GeneralPurpose::new(
+            &base64::alphabet::STANDARD,
+            base64::engine::general_purpose::PAD,
+        )
+        .encode(bytes))
+    }
+}
+
+/// Options used when writing a map with [`Map::write_to_with_options`](crate::Map::write_to_with_options).
+///
+/// ## Example
+/// ```
+/// # fn main() -> tiled::Result<()> {
+/// use tiled::{TileDataEncoding, WriteOptions};
+///
+/// let map = tiled::Loader::new().load_tmx_map("assets/tiled_base64_external.tmx")?;
+///
+/// // Reference a copy of the TSX file the map's only tileset was loaded from.
+/// let options = WriteOptions::new()
+///     .with_encoding(TileDataEncoding::Csv)
+///     .with_external_tileset(0, "tilesets/tilesheet.tsx");
+/// let mut tmx = Vec::new();
+/// map.write_to_with_options(&mut tmx, &options)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct WriteOptions {
+    pub(crate) encoding: TileDataEncoding,
+    pub(crate) external_tilesets: HashMap<usize, PathBuf>,
+    pub(crate) embed_tilesets: bool,
+}
+
+impl WriteOptions {
+    /// Creates the default options, which keep referencing the external tilesets the map was
+    /// loaded with, embed the others and encode tile data as zlib-compressed base64.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the encoding used for the tile data of tile layers.
+    pub fn with_encoding(mut self, encoding: TileDataEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Writes the tileset with the given index in [`Map::tilesets`](crate::Map::tilesets) as a
+    /// reference to an external TSX file instead of embedding it or referencing the file it was
+    /// loaded from.
+    ///
+    /// `source` is written as given, so it should be relative to the directory the map is saved
+    /// in. The TSX file itself isn't written; use [`Tileset::write_to`](crate::Tileset::write_to)
+    /// for that. This takes precedence over [`WriteOptions::with_embedded_tilesets`].
+    pub fn with_external_tileset(
+        mut self,
+        tileset_index: usize,
+        source: impl Into<PathBuf>,
+    ) -> Self {
+        self.external_tilesets.insert(tileset_index, source.into());
+        self
+    }
+
+    /// Sets whether the tilesets the map loaded from external files are embedded in it rather
+    /// than referenced by their [source](crate::TilesetSource::External). Defaults to `false`.
+    pub fn with_embedded_tilesets(mut self, embed_tilesets: bool) -> Self {
+        self.embed_tilesets = embed_tilesets;
+        self
+    }
+}
+
+/// Writes XML elements, keeping track of the information needed to turn a map's contents back
+/// into their TMX representation.
+pub(crate) struct XmlWriter<W: Write> {
+    writer: EventWriter<W>,
+    /// The directory paths are written relative to.
+    base_path: PathBuf,
+    /// The encoding used for tile layer data.
+    pub encoding: TileDataEncoding,
+    /// The first GID of each of the map's tilesets, indexed like [`crate::Map::tilesets`].
+    pub first_gids: Vec<Gid>,
+}
+
+impl<W: Write> XmlWriter<W> {
+    pub(crate) fn new(writer: W, base_path: &Path, encoding: TileDataEncoding) -> Self {
+        Self {
+            writer: EmitterConfig::new()
+                .perform_indent(true)
+                .create_writer(writer),
+            base_path: base_path.to_owned(),
+            encoding,
+            first_gids: Vec::new(),
+        }
+    }
+
+    /// Opens an element with the given attributes.
+    pub(crate) fn start(&mut self, name: &str, attrs: &[(&str, String)]) -> Result<()> {
+        let mut element = XmlEvent::start_element(name);
+        for (name, value) in attrs {
+            element = element.attr(*name, value);
+        }
+        self.writer.write(element).map_err(Error::XmlEncodingError)
+    }
+
+    /// Closes the last element opened.
+    pub(crate) fn end(&mut self) -> Result<()> {
+        self.writer
+            .write(XmlEvent::end_element())
+            .map_err(Error::XmlEncodingError)
+    }
+
+    /// Writes an element with the given attributes and no contents.
+    pub(crate) fn empty(&mut self, name: &str, attrs: &[(&str, String)]) -> Result<()> {
+        self.start(name, attrs)?;
+        self.end()
+    }
+
+    /// Writes text contents into the current element.
+    pub(crate) fn characters(&mut self, text: &str) -> Result<()> {
+        self.writer
+            .write(XmlEvent::characters(text))
+            .map_err(Error::XmlEncodingError)
+    }
+
+    /// Writes a `<data>` (or `<chunk>`) element containing the given tile bits, encoded as
+    /// specified by [`Self::encoding`].
+    pub(crate) fn tile_data(
+        &mut self,
+        name: &str,
+        attrs: &[(&str, String)],
+        bits: &[u32],
+        width: usize,
+    ) -> Result<()> {
+        let data = self
+            .encoding
+            .encode(bits, width)
+            .map_err(|err| Error::XmlEncodingError(err.into()))?;
+        self.start(name, attrs)?;
+        self.characters(&data)?;
+        self.end()
+    }
+
+    /// Returns `path` relative to the directory being written to, using forward slashes like
+    /// Tiled does. Paths that can't be made relative to it are written as they are.
+    pub(crate) fn relative_path(&self, path: &Path) -> String {
+        relative_path(&self.base_path, path)
+    }
+}
+
+/// Builds the JSON representation of a map's contents, keeping track of the same information as
+/// [`XmlWriter`].
+#[cfg(feature = "json")]
+pub(crate) struct JsonWriter {
+    /// The directory paths are written relative to.
+    base_path: PathBuf,
+    /// The encoding used for tile layer data.
+    pub encoding: TileDataEncoding,
+    /// The first GID of each of the map's tilesets, indexed like [`crate::Map::tilesets`].
+    pub first_gids: Vec<Gid>,
+}
+
+#[cfg(feature = "json")]
+impl JsonWriter {
+    pub(crate) fn new(base_path: &Path, encoding: TileDataEncoding) -> Self {
+        Self {
+            base_path: base_path.to_owned(),
+            encoding,
+            first_gids: Vec::new(),
+        }
+    }
+
+    /// Returns the given tile bits encoded as specified by [`Self::encoding`], to be used as the
+    /// `data` member of a layer or chunk. [`TileDataEncoding::Csv`] is written as a plain array
+    /// of GIDs.
+    pub(crate) fn tile_data(&self, bits: &[u32]) -> Result<serde_json::Value> {
+        if self.encoding == TileDataEncoding::Csv {
+            return Ok(bits.into());
+        }
+        self.encoding
+            .encode(bits, 0)
+            .map(Into::into)
+            .map_err(|err| Error::JsonEncodingError(serde_json::Error::io(err)))
+    }
+
+    /// Returns `path` relative to the directory being written to, like
+    /// [`XmlWriter::relative_path`].
+    pub(crate) fn relative_path(&self, path: &Path) -> String {
+        relative_path(&self.base_path, path)
+    }
+}
+
+/// Returns `path` relative to `base_path`, using forward slashes like Tiled does. Paths that can't
+/// be made relative to it are returned as they are.
+fn relative_path(base_path: &Path, path: &Path) -> String {
+    if path.is_absolute() != base_path.is_absolute() {
+        return path_to_string(path);
+    }
+    let mut path_components = path.components().peekable();
+    let mut base_components = base_path.components().peekable();
+    while let (Some(a), Some(b)) = (path_components.peek(), base_components.peek()) {
+        if a != b {
+            break;
+        }
+        path_components.next();
+        base_components.next();
+    }
+
+    let mut relative = Vec::new();
+    for component in base_components {
+        match component {
+            Component::Normal(_) => relative.push("..".into()),
+            Component::CurDir => {}
+            // The rest of the base path can't be undone, so the path is written as it is.
+            _ => return path_to_string(path),
+        }
+    }
+    relative.extend(path_components.map(|c| c.as_os_str().to_string_lossy()));
+    relative.join("/")
+}
+
+/// Formats a path using forward slashes like Tiled does.
+pub(crate) fn path_to_string(path: &Path) -> String {
+    path.components()
+        .map(|component| match component {
+            Component::RootDir => "".into(),
+            component => component.as_os_str().to_string_lossy(),
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Formats a color the way Tiled does, omitting the alpha channel if it is opaque.
+pub(crate) fn color_to_string(color: Color) -> String {
+    if color.alpha == 0xFF {
+        format!("#{:02x}{:02x}{:02x}", color.red, color.green, color.blue)
+    } else {
+        format!(
+            "#{:02x}{:02x}{:02x}{:02x}",
+            color.alpha, color.red, color.green, color.blue
+        )
+    }
+}
+
+/// Formats a boolean attribute the way Tiled does.
+pub(crate) fn bool_to_string(value: bool) -> String {
+    if value { "1" } else { "0" }.to_owned()
+}
+
+/// Appends the attributes an element was loaded with but didn't parse, ordered by name and
+/// skipping the ones already in `attrs`.
+pub(crate) fn push_unparsed_attributes<'a>(
+    attrs: &mut Vec<(&'a str, String)>,
+    unparsed: Option<&'a HashMap<String, String>>,
+) {
+    let mut unparsed: Vec<_> = unparsed
+        .into_iter()
+        .flatten()
+        .filter(|(name, _)| !attrs.iter().any(|(written, _)| written == name))
+        .collect();
+    unparsed.sort();
+    attrs.extend(
+        unparsed
+            .into_iter()
+            .map(|(name, value)| (name.as_str(), value.clone())),
+    );
+}
+
+/// The members of a JSON object.
+#[cfg(feature = "json")]
+pub(crate) type JsonMap = serde_json::Map<String, serde_json::Value>;
+
+/// Creates a JSON object out of the given members.
+#[cfg(feature = "json")]
+pub(crate) fn json_object(members: Vec<(&str, serde_json::Value)>) -> JsonMap {
+    members
+        .into_iter()
+        .map(|(name, value)| (name.to_owned(), value))
+        .collect()
+}
+
+/// Converts a float to JSON, writing whole numbers as integers and avoiding the noise of
+/// widening it to an `f64` like Tiled does.
+#[cfg(feature = "json")]
+pub(crate) fn f32_to_json(value: f32) -> serde_json::Value {
+    if value.fract() == 0.0 && value.abs() < i32::MAX as f32 {
+        (value as i32).into()
+    } else {
+        value
+            .to_string()
+            .parse::<f64>()
+            .map_or(serde_json::Value::Null, Into::into)
+    }
+}
@@ -1,7 +1,12 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tiled::{
-    Color, FiniteTileLayer, GroupLayer, Layer, LayerType, Loader, Map, ObjectLayer, ObjectShape,
-    PropertyValue, ResourceCache, TileLayer, TilesetLocation, WangId,
+    merge_properties, ChunkData, Color, DrawOrder, DuplicatePropertyPolicy, EditorSettings,
+    ExportSettings, FillMode, FiniteTileLayer, FlipFlags, Frame, Gid, GroupLayer, Layer,
+    LayerBuilder, LayerKind, LayerTileData, LayerType, Loader, Map, MapBuilder, ObjectAlignment,
+    ObjectGroupBuilder, ObjectLayer, ObjectShape, Orientation, ParseLimits, Properties,
+    PropertyClassRegistry, PropertyValue, RawTileData, RenderOrder, ResourceCache, StaggerAxis,
+    StaggerIndex, TileDataEncoding, TileId, TileLayer, TileRenderSize, TilesetLocation, WangId,
+    Warning, WriteOptions,
 };
 
 fn as_finite<'map>(data: TileLayer<'map>) -> FiniteTileLayer<'map> {
@@ -11,6 +16,22 @@ fn as_finite<'map>(data: TileLayer<'map>) -> FiniteTileLayer<'map> {
     }
 }
 
+/// Wraps `contents` in a 1x1 orthogonal map with 32x32 tiles, for tests that only need a few
+/// elements of a map.
+fn small_map(contents: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32">{}
+</map>"#,
+        contents
+    )
+}
+
+/// Parses the [`small_map`] holding `contents`.
+fn parse_small_map(contents: &str) -> tiled::Result<Map> {
+    tiled::parse_str(&small_map(contents))
+}
+
 fn compare_everything_but_tileset_sources(r: &Map, e: &Map) {
     assert_eq!(r.version(), e.version());
     assert_eq!(r.orientation, e.orientation);
@@ -49,13 +70,33 @@ fn test_gzip_and_zlib_encoded_and_raw_are_the_same() {
         assert_eq!(layer.height(), 100);
     }
 
-    assert_eq!(layer.get_tile(0, 0).unwrap().id(), 34);
-    assert_eq!(layer.get_tile(0, 1).unwrap().id(), 16);
+    assert_eq!(layer.get_tile(0, 0).unwrap().id(), TileId::new(34));
+    assert_eq!(layer.get_tile(0, 1).unwrap().id(), TileId::new(16));
     assert!(layer.get_tile(0, 2).is_none());
-    assert_eq!(layer.get_tile(1, 2).unwrap().id(), 16);
+    assert_eq!(layer.get_tile(1, 2).unwrap().id(), TileId::new(16));
     assert!((0..99).map(|x| layer.get_tile(x, 99)).all(|t| t.is_none()));
 }
 
+#[test]
+fn test_parse_from_memory() {
+    let from_file = Loader::new().load_tmx_map("assets/tiled_csv.tmx").unwrap();
+    let from_str = tiled::parse_str(include_str!("../assets/tiled_csv.tmx")).unwrap();
+    let from_bytes = tiled::parse_bytes(include_bytes!("../assets/tiled_base64_zlib.tmx")).unwrap();
+    compare_everything_but_tileset_sources(&from_file, &from_str);
+    compare_everything_but_tileset_sources(&from_file, &from_bytes);
+    // Maps parsed from memory, and the tilesets embedded in them, have no path
+    assert_eq!(from_str.source, None);
+    assert_eq!(from_bytes.source, None);
+    assert_eq!(from_str.tilesets()[0].source, PathBuf::new());
+
+    match tiled::parse_str(include_str!("../assets/tiled_base64_external.tmx")) {
+        Err(tiled::Error::ResourceLoadingError { path, .. }) => {
+            assert_eq!(path, PathBuf::from("tilesheet.tsx"))
+        }
+        other => panic!("Expected a resource loading error, got {:?}", other),
+    }
+}
+
 #[test]
 fn test_external_tileset() {
     let mut loader = Loader::new();
@@ -79,10 +120,25 @@ fn test_sources() {
     );
     assert_eq!(
         e.tilesets()[0].image.as_ref().unwrap().source,
-        PathBuf::from("assets/tilesheet.png")
+        Some(PathBuf::from("assets/tilesheet.png"))
     );
 }
 
+#[test]
+fn test_tilesets_are_shared_between_maps() {
+    let mut loader = Loader::new();
+    let a = loader
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    let b = loader
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    // Both maps reference tilesheet.tsx, with different first GIDs.
+    assert!(std::sync::Arc::ptr_eq(&a.tilesets()[0], &b.tilesets()[1]));
+    assert_eq!(a.get_tileset_by_first_gid(Gid(1)), Some(&*a.tilesets()[0]));
+    assert_eq!(b.get_tileset_by_first_gid(Gid(85)), Some(&*b.tilesets()[1]));
+}
+
 #[test]
 fn test_just_tileset() {
     let mut loader = Loader::new();
@@ -102,37 +158,725 @@ fn test_infinite_map() {
         .unwrap();
 
     if let TileLayer::Infinite(inf) = &r.get_layer(1).unwrap().as_tile_layer().unwrap() {
-        assert_eq!(inf.get_tile(2, 10).unwrap().id(), 5);
-        assert_eq!(inf.get_tile(5, 36).unwrap().id(), 73);
-        assert_eq!(inf.get_tile(15, 15).unwrap().id(), 22);
+        assert_eq!(inf.get_tile(2, 10).unwrap().id(), TileId::new(5));
+        assert_eq!(inf.get_tile(5, 36).unwrap().id(), TileId::new(73));
+        assert_eq!(inf.get_tile(15, 15).unwrap().id(), TileId::new(22));
     } else {
         panic!("It is wrongly recognised as a finite map");
     }
     if let TileLayer::Infinite(inf) = &r.get_layer(0).unwrap().as_tile_layer().unwrap() {
         // NW corner
-        assert_eq!(inf.get_tile(-16, 0).unwrap().id(), 17);
+        assert_eq!(inf.get_tile(-16, 0).unwrap().id(), TileId::new(17));
         assert!(inf.get_tile(-17, 0).is_none());
         assert!(inf.get_tile(-16, -1).is_none());
 
         // SW corner
-        assert_eq!(inf.get_tile(-16, 47).unwrap().id(), 17);
+        assert_eq!(inf.get_tile(-16, 47).unwrap().id(), TileId::new(17));
         assert!(inf.get_tile(-17, 47).is_none());
         assert!(inf.get_tile(-16, 48).is_none());
 
         // NE corner
-        assert_eq!(inf.get_tile(31, 0).unwrap().id(), 17);
+        assert_eq!(inf.get_tile(31, 0).unwrap().id(), TileId::new(17));
         assert!(inf.get_tile(31, -1).is_none());
         assert!(inf.get_tile(32, 0).is_none());
 
         // SE corner
-        assert_eq!(inf.get_tile(31, 47).unwrap().id(), 17);
+        assert_eq!(inf.get_tile(31, 47).unwrap().id(), TileId::new(17));
         assert!(inf.get_tile(32, 47).is_none());
         assert!(inf.get_tile(31, 48).is_none());
+
+        // Chunks can be looked up by any of their tiles, and list their tiles by layer position
+        let chunk = inf.get_chunk_at(-16, 47).unwrap();
+        assert_eq!(chunk, inf.get_chunk(-1, 2).unwrap());
+        assert_eq!(chunk.position(), (-1, 2));
+        assert!(inf.get_chunk_at(-1000, 47).is_none());
+        let tiles: Vec<_> = chunk.tiles().collect();
+        let expected: Vec<_> = (0..16)
+            .flat_map(|y| (0..16).map(move |x| (x, y)))
+            .map(|(x, y)| (x - 16, y + 32))
+            .filter_map(|(x, y)| inf.get_tile(x, y).map(|tile| (x, y, tile)))
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(tiles, expected);
     } else {
         panic!("It is wrongly recognised as a finite map");
     }
 }
 
+#[test]
+fn test_tile_iterators() {
+    let r = Loader::new().load_tmx_map("assets/tiled_csv.tmx").unwrap();
+    let layer = as_finite(r.get_layer(0).unwrap().as_tile_layer().unwrap());
+    let expected: Vec<_> = (0..layer.height() as i32)
+        .flat_map(|y| (0..layer.width() as i32).map(move |x| (x, y)))
+        .filter_map(|(x, y)| layer.get_tile(x, y).map(|tile| (x, y, tile)))
+        .collect();
+    assert!(!expected.is_empty());
+    assert_eq!(layer.tiles().collect::<Vec<_>>(), expected);
+    assert_eq!(layer.all_tiles().len(), 100 * 100);
+    assert_eq!(layer.tiles_flat().len(), 100 * 100);
+    assert_eq!(layer.rows().len(), 100);
+    for (x, y, tile) in &expected {
+        let data = layer.tiles_flat()[*x as usize + *y as usize * 100].as_ref();
+        assert_eq!(data, Some(&**tile));
+        assert_eq!(
+            layer.rows().nth(*y as usize).unwrap()[*x as usize].as_ref(),
+            data
+        );
+    }
+    assert_eq!(
+        layer.all_tiles().filter(|(_, _, t)| t.is_some()).count(),
+        expected.len()
+    );
+
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    let layer = r.get_layer(0).unwrap().as_tile_layer().unwrap();
+    let infinite = match &layer {
+        TileLayer::Infinite(infinite) => infinite,
+        TileLayer::Finite(_) => panic!("It is wrongly recognised as a finite map"),
+    };
+    let mut count = 0;
+    for (x, y, tile) in layer.tiles() {
+        assert_eq!(infinite.get_tile(x, y), Some(tile));
+        count += 1;
+    }
+    let expected_count = infinite
+        .chunks()
+        .map(|(_, chunk)| {
+            (0..ChunkData::WIDTH as i32)
+                .flat_map(|x| (0..ChunkData::HEIGHT as i32).map(move |y| (x, y)))
+                .filter(|&(x, y)| chunk.get_tile(x, y).is_some())
+                .count()
+        })
+        .sum::<usize>();
+    assert_eq!(count, expected_count);
+    // The NW corner of the layer, as checked in `test_infinite_map`
+    assert!(layer.tiles().any(|(x, y, _)| (x, y) == (-16, 0)));
+    // Chunks are visited in rows, and so are the tiles within them
+    let order: Vec<_> = layer
+        .tiles()
+        .map(|(x, y, _)| {
+            let (chunk_x, chunk_y) = ChunkData::tile_to_chunk_pos(x, y);
+            (chunk_y, chunk_x, y, x)
+        })
+        .collect();
+    assert!(order.windows(2).all(|pair| pair[0] < pair[1]));
+
+    assert!(r.iter_tiles("Background").unwrap().eq(layer.tiles()));
+    assert!(r.iter_tiles("Object group").is_none());
+    assert!(r.iter_tiles("Nonexistent layer").is_none());
+}
+
+#[test]
+fn test_streaming_tiles() {
+    for path in [
+        "assets/tiled_csv.tmx",
+        "assets/tiled_base64.tmx",
+        "assets/tiled_base64_zlib.tmx",
+        "assets/tiled_base64_gzip.tmx",
+        "assets/tiled_base64_zstandard.tmx",
+        "assets/tiled_base64_zlib_infinite.tmx",
+    ] {
+        let mut streamed = Vec::new();
+        let r = Loader::new()
+            .load_tmx_map_streaming(path, |tile| streamed.push(tile))
+            .unwrap();
+        for layer in r.layers().filter_map(|layer| layer.as_tile_layer()) {
+            assert_eq!(layer.tiles().count(), 0);
+        }
+
+        let e = Loader::new().load_tmx_map(path).unwrap();
+        let mut expected: Vec<_> = e
+            .layers()
+            .filter_map(|layer| Some((layer.id(), layer.as_tile_layer()?)))
+            .flat_map(|(id, layer)| layer.tiles().map(move |(x, y, _)| (id, x, y)))
+            .collect();
+        let mut positions: Vec<_> = streamed
+            .iter()
+            .map(|tile| (tile.layer_id, tile.x, tile.y))
+            .collect();
+        expected.sort_unstable();
+        positions.sort_unstable();
+        assert!(!positions.is_empty());
+        assert_eq!(positions, expected, "{}", path);
+    }
+
+    let mut streamed = Vec::new();
+    Loader::new()
+        .load_tmx_map_streaming("assets/tiled_flipped.tmx", |tile| streamed.push(tile))
+        .unwrap();
+    let flags: Vec<_> = streamed
+        .iter()
+        .map(|tile| (tile.x, tile.y, tile.gid >> 29))
+        .collect();
+    assert_eq!(
+        flags,
+        [(0, 0, 0b111), (1, 0, 0b010), (0, 1, 0b100), (1, 1, 0b001)]
+    );
+}
+
+#[test]
+fn test_raw_tile_data() {
+    use std::{convert::TryInto, io::Read};
+
+    fn decode(raw: &RawTileData) -> Vec<u32> {
+        if raw.encoding == "csv" {
+            let text = String::from_utf8(raw.data.clone()).unwrap();
+            return text.split(',').map(|v| v.trim().parse().unwrap()).collect();
+        }
+        let mut bytes = Vec::new();
+        match raw.compression.as_deref() {
+            None => bytes = raw.data.clone(),
+            Some("zlib") => {
+                flate2::read::ZlibDecoder::new(&raw.data[..])
+                    .read_to_end(&mut bytes)
+                    .unwrap();
+            }
+            Some("gzip") => {
+                flate2::read::GzDecoder::new(&raw.data[..])
+                    .read_to_end(&mut bytes)
+                    .unwrap();
+            }
+            Some(compression) => panic!("Unexpected compression {}", compression),
+        }
+        bytes
+            .chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect()
+    }
+
+    fn raw_tiles(map: &Map) -> Vec<(u32, i32, i32, u32)> {
+        let mut tiles = Vec::new();
+        for layer in map.layers() {
+            let raw: Vec<RawTileData> = match layer.as_tile_layer() {
+                Some(TileLayer::Finite(finite)) => {
+                    assert!(finite.tiles_flat().is_empty());
+                    vec![finite.raw_tile_data().unwrap().clone()]
+                }
+                Some(TileLayer::Infinite(infinite)) => {
+                    assert_eq!(infinite.chunks().count(), 0);
+                    infinite.raw_chunks().to_vec()
+                }
+                None => continue,
+            };
+            for raw in raw {
+                let gids = decode(&raw);
+                assert_eq!(gids.len(), raw.width as usize * raw.height as usize);
+                for (index, gid) in gids.into_iter().enumerate() {
+                    if gid != 0 {
+                        let x = raw.x + (index as u32 % raw.width) as i32;
+                        let y = raw.y + (index as u32 / raw.width) as i32;
+                        tiles.push((layer.id(), x, y, gid));
+                    }
+                }
+            }
+        }
+        tiles.sort_unstable();
+        tiles
+    }
+
+    for path in [
+        "assets/tiled_csv.tmx",
+        "assets/tiled_base64.tmx",
+        "assets/tiled_base64_zlib.tmx",
+        "assets/tiled_base64_gzip.tmx",
+        "assets/tiled_base64_zlib_infinite.tmx",
+    ] {
+        let loader = || Loader::new().with_raw_tile_data(true);
+        let map = loader().load_tmx_map(path).unwrap();
+        let tiles = raw_tiles(&map);
+
+        let mut expected = Vec::new();
+        Loader::new()
+            .load_tmx_map_streaming(path, |tile| {
+                expected.push((tile.layer_id, tile.x, tile.y, tile.gid))
+            })
+            .unwrap();
+        expected.sort_unstable();
+        assert!(!tiles.is_empty());
+        assert_eq!(tiles, expected, "{}", path);
+
+        // The raw data is written back as it was read, whatever the encoding written with
+        let mut tmx = Vec::new();
+        map.write_to_with_options(
+            &mut tmx,
+            &WriteOptions::new().with_encoding(TileDataEncoding::Base64Gzip),
+        )
+        .unwrap();
+        let written = loader().load_tmx_map_from(&tmx[..], path).unwrap();
+        let raw_data = |map: &Map| -> Vec<Vec<RawTileData>> {
+            map.layers()
+                .filter_map(|layer| match layer.as_tile_layer()? {
+                    TileLayer::Finite(finite) => Some(vec![finite.raw_tile_data()?.clone()]),
+                    TileLayer::Infinite(infinite) => Some(infinite.raw_chunks().to_vec()),
+                })
+                .collect()
+        };
+        assert_eq!(raw_data(&written), raw_data(&map), "{}", path);
+        let layers = |map: Map| -> Vec<_> { map.layers().map(|layer| (*layer).clone()).collect() };
+        assert_eq!(
+            layers(Loader::new().load_tmx_map_from(&tmx[..], path).unwrap()),
+            layers(Loader::new().load_tmx_map(path).unwrap()),
+            "{}",
+            path
+        );
+    }
+
+    // Their tiles are left as they are when editing such layers
+    let mut map = Loader::new()
+        .with_raw_tile_data(true)
+        .load_tmx_map("assets/tiled_base64_zlib.tmx")
+        .unwrap();
+    let tiles = raw_tiles(&map);
+    map.resize(50, 50, 1);
+    assert_eq!(raw_tiles(&map), tiles);
+    let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    assert_eq!(layer.width(), Some(100));
+
+    #[cfg(feature = "json")]
+    {
+        let csv = |map: &Map| {
+            let options = WriteOptions::new().with_encoding(TileDataEncoding::Csv);
+            let mut json = Vec::new();
+            map.write_json_to_with_options(&mut json, &options).unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+            json["layers"][0]["data"].clone()
+        };
+        let decoded = Loader::new().load_tmx_map("assets/tiled_csv.tmx").unwrap();
+        let raw = Loader::new()
+            .with_raw_tile_data(true)
+            .load_tmx_map("assets/tiled_csv.tmx")
+            .unwrap();
+        assert_eq!(csv(&raw), csv(&decoded));
+        assert!(csv(&raw).as_array().map_or(false, |data| !data.is_empty()));
+    }
+}
+
+#[test]
+fn test_parse_file() {
+    let r = tiled::parse_file("assets/tiled_base64_external.tmx").unwrap();
+    let e = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    assert_eq!(r, e);
+    assert_eq!(
+        r.source.as_deref(),
+        Some(Path::new("assets/tiled_base64_external.tmx"))
+    );
+    assert_eq!(
+        r.tilesets()[0].source,
+        PathBuf::from("assets/tilesheet.tsx")
+    );
+
+    let tileset = tiled::Tileset::parse_file("assets/tilesheet.tsx").unwrap();
+    assert_eq!(tileset, *r.tilesets()[0]);
+
+    match tiled::parse_file("assets/this_map_does_not_exist.tmx") {
+        Err(tiled::Error::ResourceLoadingError { path, .. }) => {
+            assert_eq!(path, PathBuf::from("assets/this_map_does_not_exist.tmx"))
+        }
+        other => panic!("Expected a resource loading error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parse_tileset_file() {
+    let tileset = tiled::parse_tileset_file("assets/tilesheet.tsx").unwrap();
+    assert_eq!(
+        tileset,
+        tiled::Tileset::parse_file("assets/tilesheet.tsx").unwrap()
+    );
+
+    let source = std::fs::read("assets/tilesheet.tsx").unwrap();
+    let from_reader = tiled::parse_tileset_reader(&source[..], "assets/tilesheet.tsx").unwrap();
+    assert_eq!(tileset, from_reader);
+    assert_eq!(
+        tileset,
+        tiled::Tileset::parse_reader(&source[..], "assets/tilesheet.tsx").unwrap()
+    );
+    assert_eq!(
+        from_reader.image.as_ref().unwrap().source,
+        Some(PathBuf::from("assets/tilesheet.png"))
+    );
+}
+
+#[test]
+fn test_bool_attributes() {
+    let map = |value: &str| {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32" infinite="{0}">
+ <layer id="1" name="Tiles" width="1" height="1" visible="{0}">
+  <data encoding="csv"><chunk x="0" y="0" width="1" height="1">0</chunk></data>
+ </layer>
+ <objectgroup id="2" name="Objects" visible="{0}">
+  <object id="1" x="0" y="0" visible="{0}"/>
+  <object id="2" x="0" y="0"><text wrap="{0}" bold="{0}">Hello</text></object>
+ </objectgroup>
+</map>"#,
+            value
+        )
+    };
+    for (value, expected) in [
+        ("1", true),
+        ("true", true),
+        ("True", true),
+        ("0", false),
+        ("FALSE", false),
+    ] {
+        let r = tiled::parse_str(&map(value)).unwrap();
+        assert_eq!(r.infinite(), expected, "{}", value);
+        assert_eq!(r.get_layer(0).unwrap().visible, expected, "{}", value);
+        let objects = r.get_layer(1).unwrap();
+        assert_eq!(objects.visible, expected, "{}", value);
+        let objects = objects.as_object_layer().unwrap();
+        assert_eq!(
+            objects.get_object(0).unwrap().visible,
+            expected,
+            "{}",
+            value
+        );
+        match &objects.get_object(1).unwrap().shape {
+            ObjectShape::Text { wrap, bold, .. } => {
+                assert_eq!((*wrap, *bold), (expected, expected), "{}", value)
+            }
+            shape => panic!("Expected a text object, got {:?}", shape),
+        }
+    }
+    for value in ["yes", "no", "maybe", "2", ""] {
+        assert!(tiled::parse_str(&map(value)).is_err(), "{}", value);
+    }
+}
+
+#[test]
+fn test_objects_without_size() {
+    let r = parse_small_map(
+        r#"
+ <objectgroup id="1" name="Objects">
+  <object id="1" x="4" y="8"/>
+  <object id="2" x="4" y="8" width="16"/>
+  <object id="3" x="4" y="8"><ellipse/></object>
+ </objectgroup>"#,
+    )
+    .unwrap();
+    let layer = r.get_layer(0).unwrap().as_object_layer().unwrap();
+    let shapes: Vec<_> = layer.objects().map(|object| object.shape.clone()).collect();
+    assert_eq!(
+        shapes,
+        [
+            ObjectShape::Rect {
+                width: 0.0,
+                height: 0.0
+            },
+            ObjectShape::Rect {
+                width: 16.0,
+                height: 0.0
+            },
+            ObjectShape::Ellipse {
+                width: 0.0,
+                height: 0.0
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_split_tile_data() {
+    let map = |infinite: bool, layers: &str| {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="2" tilewidth="32" tileheight="32" infinite="{}">
+ <tileset firstgid="1" name="embedded" tilewidth="32" tileheight="32" tilecount="4" columns="2">
+  <image source="tiles.png" width="64" height="64"/>
+ </tileset>
+ {}
+</map>"#,
+            infinite as u8, layers
+        )
+    };
+
+    // CDATA sections and comments make the XML parser split the text of the data
+    let r = tiled::parse_str(&map(
+        false,
+        r#"<layer id="1" name="CSV" width="2" height="2">
+  <data encoding="csv">1,2,<![CDATA[3,]]><!-- split -->4</data>
+ </layer>
+ <layer id="2" name="Base64" width="2" height="2">
+  <data encoding="base64">AQAAAAIA<!-- split -->AAAD<![CDATA[AAAABAAAgA==]]></data>
+ </layer>"#,
+    ))
+    .unwrap();
+    for index in 0..2 {
+        let layer = as_finite(r.get_layer(index).unwrap().as_tile_layer().unwrap());
+        let ids: Vec<_> = [(0, 0), (1, 0), (0, 1), (1, 1)]
+            .iter()
+            .map(|&(x, y)| layer.get_tile(x, y).unwrap().id())
+            .collect();
+        assert_eq!(ids, [0, 1, 2, 3].map(TileId::new));
+    }
+    let base64 = as_finite(r.get_layer(1).unwrap().as_tile_layer().unwrap());
+    assert_eq!(base64.get_tile(1, 1).unwrap().flip, FlipFlags::HORIZONTAL);
+
+    let r = tiled::parse_str(&map(
+        true,
+        &format!(
+            r#"<layer id="1" name="Chunks" width="2" height="2">
+  <data encoding="csv">
+   <chunk x="0" y="0" width="16" height="16"></chunk>
+   <chunk x="16" y="0" width="16" height="16">{}<![CDATA[{}3]]></chunk>
+  </data>
+ </layer>"#,
+            "0,".repeat(128),
+            "0,".repeat(127)
+        ),
+    ))
+    .unwrap();
+    let chunks = match r.get_layer(0).unwrap().as_tile_layer().unwrap() {
+        TileLayer::Infinite(layer) => layer,
+        TileLayer::Finite(_) => panic!("Not an infinite tile layer"),
+    };
+    assert_eq!(chunks.get_tile(31, 15).unwrap().id(), TileId::new(2));
+    assert!(chunks.get_tile(0, 0).is_none());
+}
+
+#[test]
+fn test_property_class_registry() {
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib.tmx")
+        .unwrap();
+    let tile = r.tilesets()[0].get_tile(TileId::new(1)).unwrap();
+    let instance = tile.properties.clone();
+    assert!(!instance.is_empty());
+
+    let mut registry = PropertyClassRegistry::new();
+    let defaults = Properties::from([
+        (
+            "a tile property".to_owned(),
+            PropertyValue::StringValue("default".to_owned()),
+        ),
+        ("solid".to_owned(), PropertyValue::BoolValue(true)),
+    ]);
+    assert_eq!(registry.insert("wall", defaults.clone()), None);
+    assert_eq!(registry.get("wall"), Some(&defaults));
+
+    let resolved = registry.resolve("wall", &instance);
+    assert_eq!(resolved["solid"], PropertyValue::BoolValue(true));
+    for (name, value) in &instance {
+        assert_eq!(&resolved[name], value);
+    }
+    assert_eq!(resolved, merge_properties(&defaults, &instance));
+    assert_eq!(registry.resolve("unknown", &instance), instance);
+}
+
+#[test]
+fn test_loader_options() {
+    let mut loader = Loader::new().with_base_path("assets");
+    assert_eq!(loader.base_path(), std::path::Path::new("assets"));
+    let r = loader.load_tmx_map("tiled_base64_external.tmx").unwrap();
+    assert_eq!(
+        r.source.as_deref(),
+        Some(Path::new("assets/tiled_base64_external.tmx"))
+    );
+    assert!(loader.cache().get_tileset("assets/tilesheet.tsx").is_some());
+    let tileset = loader.load_tsx_tileset("tilesheet.tsx").unwrap();
+    assert_eq!(tileset, *r.tilesets()[0]);
+
+    let mut loader = loader.with_cache(tiled::DefaultResourceCache::new());
+    assert!(loader.cache().get_tileset("assets/tilesheet.tsx").is_none());
+    let e = loader
+        .load_tmx_map_from(
+            &include_bytes!("../assets/tiled_base64_external.tmx")[..],
+            "tiled_base64_external.tmx",
+        )
+        .unwrap();
+    assert_eq!(r, e);
+}
+
+#[test]
+fn test_embedded_image() {
+    let map = |image: &str| {
+        small_map(&format!(
+            r#"
+ <tileset firstgid="1" name="embedded" tilewidth="32" tileheight="32" tilecount="1" columns="1">
+  {}
+ </tileset>"#,
+            image
+        ))
+    };
+    let r = tiled::parse_str(&map(r#"<image format="png" width="32" height="32">
+   <data encoding="base64">
+    iVBORw0KGgo=
+   </data>
+  </image>"#))
+    .unwrap();
+    let image = r.tilesets()[0].image.as_ref().unwrap();
+    assert_eq!(image.source, None);
+    assert_eq!(
+        image.embedded_data.as_deref(),
+        Some(&b"\x89PNG\r\n\x1a\n"[..])
+    );
+
+    // The data may be split by the XML parser, such as around CDATA sections or comments
+    let r = tiled::parse_str(&map(r#"<image format="png" width="32" height="32">
+   <data encoding="base64"><![CDATA[iVBORw]]><!-- comment -->0KGgo=</data>
+  </image>"#))
+    .unwrap();
+    assert_eq!(
+        r.tilesets()[0]
+            .image
+            .as_ref()
+            .unwrap()
+            .embedded_data
+            .as_deref(),
+        Some(&b"\x89PNG\r\n\x1a\n"[..])
+    );
+
+    assert!(tiled::parse_str(&map(r#"<image width="32" height="32"/>"#)).is_err());
+}
+
+#[test]
+fn test_image_properties() {
+    let tmx = small_map(
+        r#"
+ <tileset firstgid="1" name="filtered" tilewidth="32" tileheight="32" tilecount="84" columns="14">
+  <image source="tilesheet.png" width="448" height="192">
+   <properties>
+    <property name="filter" value="nearest"/>
+   </properties>
+  </image>
+ </tileset>"#,
+    );
+    let map = Loader::new()
+        .load_tmx_map_from(tmx.as_bytes(), "assets/filtered.tmx")
+        .unwrap();
+    let image = map.tilesets()[0].image.as_ref().unwrap();
+    assert_eq!(
+        image.properties["filter"],
+        PropertyValue::StringValue("nearest".to_owned())
+    );
+    assert!(map.tilesets()[0].properties.is_empty());
+
+    let mut written = Vec::new();
+    map.write_to(&mut written).unwrap();
+    let written = Loader::new()
+        .load_tmx_map_from(&written[..], "assets/filtered.tmx")
+        .unwrap();
+    assert_eq!(written.tilesets()[0].image, map.tilesets()[0].image);
+}
+
+#[test]
+fn test_tileset_lookup() {
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    // Both tilesets are named "tilesheet", so the first one should be returned.
+    assert_eq!(r.get_tileset_by_name("tilesheet"), Some(&*r.tilesets()[0]));
+    assert!(r
+        .get_tileset_by_name("this tileset does not exist")
+        .is_none());
+
+    assert_eq!(r.get_tileset_by_first_gid(Gid(1)), Some(&*r.tilesets()[0]));
+    assert_eq!(r.get_tileset_by_first_gid(Gid(85)), Some(&*r.tilesets()[1]));
+    assert!(r.get_tileset_by_first_gid(Gid(2)).is_none());
+
+    let (tileset, id) = r.gid_to_local_id(Gid(84)).unwrap();
+    assert_eq!((tileset, id), (&*r.tilesets()[0], TileId::new(83)));
+    let (tileset, id) = r.gid_to_local_id(Gid::from_bits(85 | 0x80000000)).unwrap();
+    assert_eq!((tileset, id), (&*r.tilesets()[1], TileId::new(0)));
+    assert!(r.gid_to_local_id(Gid(0)).is_none());
+    assert!(r.gid_to_local_id(Gid::from_bits(0xE0000000)).is_none());
+
+    assert_eq!(r.get_tileset_by_gid(Gid(1)), Some(&*r.tilesets()[0]));
+    assert_eq!(r.get_tileset_by_gid(Gid(84)), Some(&*r.tilesets()[0]));
+    assert_eq!(r.get_tileset_by_gid(Gid(85)), Some(&*r.tilesets()[1]));
+    assert_eq!(r.get_tileset_by_gid(Gid(1000)), Some(&*r.tilesets()[1]));
+    assert!(r.get_tileset_by_gid(Gid(0)).is_none());
+}
+
+#[test]
+fn test_nested_relative_paths() {
+    let r = Loader::new()
+        .load_tmx_map("assets/folder/tiled_relative_paths.tmx")
+        .unwrap();
+    assert_eq!(
+        r.source.as_deref(),
+        Some(Path::new("assets/folder/tiled_relative_paths.tmx"))
+    );
+    let tileset = &r.tilesets()[0];
+    assert_eq!(
+        tileset.source,
+        PathBuf::from("assets/folder/../tilesheet.tsx")
+    );
+    // The tileset image is relative to the tileset, not to the map referencing it.
+    assert_eq!(
+        tileset.image.as_ref().unwrap().source,
+        Some(PathBuf::from("assets/folder/../tilesheet.png"))
+    );
+}
+
+#[test]
+fn test_custom_resource_reader() {
+    /// A reader that serves resources from memory, like a pack file would.
+    struct PackReader(std::collections::HashMap<PathBuf, &'static [u8]>);
+
+    impl tiled::ResourceReader for PackReader {
+        type Resource = &'static [u8];
+        type Error = std::io::Error;
+
+        fn read_from(
+            &mut self,
+            path: &std::path::Path,
+        ) -> std::result::Result<Self::Resource, Self::Error> {
+            self.0
+                .get(path)
+                .copied()
+                .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "not in the pack"))
+        }
+    }
+
+    let pack = PackReader(
+        [
+            (
+                "pack/example.tmx",
+                &include_bytes!("../assets/templates/example.tmx")[..],
+            ),
+            (
+                "pack/corner.tx",
+                &include_bytes!("../assets/templates/corner.tx")[..],
+            ),
+            (
+                "pack/edge.tx",
+                &include_bytes!("../assets/templates/edge.tx")[..],
+            ),
+            (
+                "pack/simple_figure.tx",
+                &include_bytes!("../assets/templates/simple_figure.tx")[..],
+            ),
+            (
+                "pack/grass_walls.tsx",
+                &include_bytes!("../assets/templates/grass_walls.tsx")[..],
+            ),
+            (
+                "pack/simple_figure.tsx",
+                &include_bytes!("../assets/templates/simple_figure.tsx")[..],
+            ),
+        ]
+        .iter()
+        .map(|&(path, data)| (PathBuf::from(path), data))
+        .collect(),
+    );
+
+    let mut loader = Loader::new().with_reader(pack);
+    let r = loader.load_tmx_map("pack/example.tmx").unwrap();
+    let e = Loader::new()
+        .load_tmx_map("assets/templates/example.tmx")
+        .unwrap();
+    assert_eq!(r.tilesets().len(), e.tilesets().len());
+    assert_eq!(loader.cache().resources::<tiled::Template>().count(), 3);
+}
+
 #[test]
 fn test_image_layers() {
     let r = Loader::new()
@@ -154,6 +898,8 @@ fn test_image_layers() {
             "{}'s image should be None",
             first.1.name
         );
+        assert!(!first.0.should_tile_x());
+        assert!(!first.0.should_tile_y());
     }
     {
         let second = image_layers.next().unwrap();
@@ -163,9 +909,11 @@ fn test_image_layers() {
             .image
             .as_ref()
             .unwrap_or_else(|| panic!("{}'s image shouldn't be None", second.1.name));
-        assert_eq!(image.source, PathBuf::from("assets/tilesheet.png"));
+        assert_eq!(image.source, Some(PathBuf::from("assets/tilesheet.png")));
         assert_eq!(image.width, 448);
         assert_eq!(image.height, 192);
+        assert!(second.0.should_tile_x());
+        assert!(!second.0.should_tile_y());
     }
 }
 
@@ -175,7 +923,7 @@ fn test_tile_property() {
         .load_tmx_map("assets/tiled_base64.tmx")
         .unwrap();
     let prop_value: String = if let Some(&PropertyValue::StringValue(ref v)) = r.tilesets()[0]
-        .get_tile(1)
+        .get_tile(TileId::new(1))
         .unwrap()
         .properties
         .get("a tile property")
@@ -187,6 +935,29 @@ fn test_tile_property() {
     assert_eq!("123", prop_value);
 }
 
+#[test]
+fn test_tile_property_by_gid() {
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    let expected = PropertyValue::StringValue("123".to_string());
+    // Embedded tileset starting at GID 1 and external one starting at GID 85
+    assert_eq!(
+        r.get_tile_property(Gid(2), "a tile property"),
+        Some(&expected)
+    );
+    assert_eq!(
+        r.get_tile_property(Gid(86), "a tile property"),
+        Some(&expected)
+    );
+    assert_eq!(
+        r.get_tile_property(Gid::from_bits(2 | 0x80000000), "a tile property"),
+        Some(&expected)
+    );
+    assert!(r.get_tile_properties(Gid::EMPTY).is_none());
+    assert!(r.get_tile_property(Gid(3), "a tile property").is_none());
+}
+
 #[test]
 fn test_layer_property() {
     let r = Loader::new()
@@ -232,6 +1003,32 @@ fn test_tileset_property() {
         String::new()
     };
     assert_eq!("tsp", prop_value);
+
+    // The tileset's own properties don't get mixed up with those of its tiles, wherever they are
+    let tsx = r#"<tileset version="1.10" name="props" tilewidth="32" tileheight="32" tilecount="84" columns="14">
+ <image source="tilesheet.png" width="448" height="192"/>
+ <tile id="1">
+  <properties>
+   <property name="a tile property" value="123"/>
+  </properties>
+ </tile>
+ <properties>
+  <property name="material" value="stone"/>
+ </properties>
+</tileset>"#;
+    let tileset = Loader::new()
+        .load_tsx_tileset_from(tsx.as_bytes(), "assets/props.tsx")
+        .unwrap();
+    assert_eq!(tileset.properties.keys().collect::<Vec<_>>(), ["material"]);
+    assert_eq!(
+        tileset
+            .get_tile(TileId::new(1))
+            .unwrap()
+            .properties
+            .keys()
+            .collect::<Vec<_>>(),
+        ["a tile property"]
+    );
 }
 
 #[test]
@@ -248,18 +1045,144 @@ fn test_flipped() {
     assert_eq!(t1.id(), t2.id());
     assert_eq!(t2.id(), t3.id());
     assert_eq!(t3.id(), t4.id());
-    assert!(t1.flip_d);
-    assert!(t1.flip_h);
-    assert!(t1.flip_v);
-    assert!(!t2.flip_d);
-    assert!(!t2.flip_h);
-    assert!(t2.flip_v);
-    assert!(!t3.flip_d);
-    assert!(t3.flip_h);
-    assert!(!t3.flip_v);
-    assert!(t4.flip_d);
-    assert!(!t4.flip_h);
-    assert!(!t4.flip_v);
+    assert_eq!(
+        t1.flip,
+        FlipFlags::HORIZONTAL | FlipFlags::VERTICAL | FlipFlags::DIAGONAL
+    );
+    assert_eq!(t2.flip, FlipFlags::VERTICAL);
+    assert_eq!(t3.flip, FlipFlags::HORIZONTAL);
+    assert_eq!(t4.flip, FlipFlags::DIAGONAL);
+}
+
+#[test]
+fn test_editor_settings() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_editor_settings.tmx")
+        .unwrap();
+    assert_eq!(
+        map.editor_settings,
+        Some(EditorSettings {
+            chunk_size: Some((32, 16)),
+            export: Some(ExportSettings {
+                target: "../exported/level1.json".to_owned(),
+                format: "json".to_owned(),
+            }),
+        })
+    );
+
+    let map = Loader::new().load_tmx_map("assets/tiled_csv.tmx").unwrap();
+    assert_eq!(map.editor_settings, None);
+}
+
+#[test]
+fn test_tile_counts() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    // Three 8x8 layers, two of which are in group layers
+    assert_eq!(map.total_tile_count(), 192);
+    assert_eq!(map.non_empty_tile_count(), 27);
+
+    let map = Loader::new().load_tmx_map("assets/tiled_csv.tmx").unwrap();
+    assert_eq!(map.total_tile_count(), 100 * 100);
+    assert_eq!(map.non_empty_tile_count(), 161);
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    assert_eq!(map.total_tile_count(), 7168);
+    assert_eq!(map.non_empty_tile_count(), 2580);
+}
+
+#[test]
+fn test_defaults() {
+    let map = Map::default();
+    assert_eq!((map.width, map.height), (0, 0));
+    assert_eq!(map.orientation, Orientation::Orthogonal);
+    assert!(map.tilesets().is_empty());
+    assert_eq!(map.layers().len(), 0);
+
+    let layer = tiled::LayerData::default();
+    assert!(layer.visible);
+    assert_eq!(layer.opacity, 1.0);
+    assert!(layer.is_empty());
+
+    let tileset = tiled::Tileset::default();
+    assert_eq!(tileset.name, "");
+    assert_eq!((tileset.tile_width, tileset.tile_height), (0, 0));
+    assert_eq!(tileset.tiles().len(), 0);
+
+    assert_eq!(
+        Color::default(),
+        Color {
+            red: 0,
+            green: 0,
+            blue: 0,
+            alpha: 255
+        }
+    );
+}
+
+#[test]
+fn test_enum_names_roundtrip() {
+    for orientation in [
+        Orientation::Orthogonal,
+        Orientation::Isometric,
+        Orientation::Staggered,
+        Orientation::Hexagonal,
+    ] {
+        assert_eq!(orientation.to_string().parse(), Ok(orientation));
+    }
+    for draw_order in [DrawOrder::TopDown, DrawOrder::Index] {
+        assert_eq!(draw_order.to_string().parse(), Ok(draw_order));
+    }
+    for render_order in [
+        RenderOrder::RightDown,
+        RenderOrder::RightUp,
+        RenderOrder::LeftDown,
+        RenderOrder::LeftUp,
+    ] {
+        assert_eq!(render_order.to_string().parse(), Ok(render_order));
+    }
+    for stagger_axis in [StaggerAxis::X, StaggerAxis::Y] {
+        assert_eq!(stagger_axis.to_string().parse(), Ok(stagger_axis));
+    }
+    for stagger_index in [StaggerIndex::Even, StaggerIndex::Odd] {
+        assert_eq!(stagger_index.to_string().parse(), Ok(stagger_index));
+    }
+    assert_eq!(DrawOrder::TopDown.to_string(), "topdown");
+    assert_eq!(StaggerIndex::Odd.to_string(), "odd");
+}
+
+#[test]
+fn test_flip_flags() {
+    let flip = FlipFlags::from_gid_raw(0xD000_0042);
+    assert!(flip.contains(FlipFlags::HORIZONTAL | FlipFlags::VERTICAL));
+    assert!(flip.contains(FlipFlags::ROTATED_120));
+    assert!(!flip.contains(FlipFlags::DIAGONAL));
+    assert_eq!(flip.bits(), 0b1101);
+    assert_eq!(flip & FlipFlags::DIAGONAL, FlipFlags::EMPTY);
+    assert!(FlipFlags::from_gid_raw(0x0FFF_FFFF).is_empty());
+    assert_eq!(Gid::from_bits(0xD000_0042), Gid(0x42));
+
+    // The 120 degree rotation bit is kept when writing maps back
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_hexagonal.tmx")
+        .unwrap();
+    map.get_layer_mut(0)
+        .unwrap()
+        .set_tile_gid(0, 0, 0x1000_0000 | 2);
+    let mut tmx = Vec::new();
+    map.write_to(&mut tmx).unwrap();
+    let written = Loader::new()
+        .load_tmx_map_from(&tmx[..], map.source.as_ref().unwrap())
+        .unwrap();
+    let layer = as_finite(written.get_layer(0).unwrap().as_tile_layer().unwrap());
+    let tile = layer.get_tile(0, 0).unwrap();
+    assert_eq!(
+        (tile.id(), tile.flip),
+        (TileId::new(1), FlipFlags::ROTATED_120)
+    );
 }
 
 #[test]
@@ -273,7 +1196,7 @@ fn test_ldk_export() {
         assert_eq!(layer.height(), 8);
     }
     assert!(layer.get_tile(0, 0).is_none());
-    assert_eq!(layer.get_tile(0, 1).unwrap().id(), 0);
+    assert_eq!(layer.get_tile(0, 1).unwrap().id(), TileId::new(0));
 }
 
 #[test]
@@ -445,8 +1368,8 @@ fn test_object_template_property() {
         object_nt.get_tile().unwrap().tileset_location(),
         &TilesetLocation::Map(0)
     );
-    assert_eq!(object.get_tile().unwrap().id(), 44);
-    assert_eq!(object_nt.get_tile().unwrap().id(), 44);
+    assert_eq!(object.get_tile().unwrap().id(), TileId::new(44));
+    assert_eq!(object_nt.get_tile().unwrap().id(), TileId::new(44));
 }
 
 #[test]
@@ -454,7 +1377,10 @@ fn test_templates() {
     let mut loader = Loader::new();
     let map = loader.load_tmx_map("assets/templates/example.tmx").unwrap();
 
-    assert_eq!(loader.cache().templates.len(), 3);
+    assert_eq!(loader.cache().resources::<tiled::Template>().count(), 3);
+    for (path, template) in loader.cache().resources::<tiled::Template>() {
+        assert_eq!(&template.source, path);
+    }
     assert_eq!(
         if let LayerType::Tiles(x) = map.get_layer(0).unwrap().layer_type() {
             x
@@ -468,6 +1394,8 @@ fn test_templates() {
         .as_ref()
         .unwrap()
         .source
+        .as_ref()
+        .unwrap()
         .canonicalize()
         .unwrap(),
         PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/tilesheet.png"))
@@ -477,17 +1405,392 @@ fn test_templates() {
 }
 
 #[test]
-fn test_reading_wang_sets() {
-    let mut loader = Loader::new();
-    let map = loader
-        .load_tmx_map("assets/tiled_csv_wangsets.tmx")
-        .unwrap();
+fn test_templates_are_read_once() {
+    /// A reader that counts how many times each file is opened.
+    struct CountingReader(std::collections::HashMap<PathBuf, usize>);
 
-    // We will pick some random data from the wangsets for tessting
+    impl tiled::ResourceReader for CountingReader {
+        type Resource = std::fs::File;
+        type Error = std::io::Error;
+
+        fn read_from(
+            &mut self,
+            path: &std::path::Path,
+        ) -> std::result::Result<Self::Resource, Self::Error> {
+            *self.0.entry(path.to_owned()).or_default() += 1;
+            std::fs::File::open(path)
+        }
+    }
+
+    let mut loader = Loader::new().with_reader(CountingReader(Default::default()));
+    loader.load_tmx_map("assets/templates/example.tmx").unwrap();
+    loader.load_tmx_map("assets/templates/example.tmx").unwrap();
+
+    let reads = &loader.reader().0;
+    // The map itself is read every time, but its tilesets and templates come from the cache.
+    assert_eq!(reads[&PathBuf::from("assets/templates/example.tmx")], 2);
+    for (path, count) in reads {
+        if path.extension().unwrap() != "tmx" {
+            assert_eq!(*count, 1, "{:?}", path);
+        }
+    }
+    assert!(reads.contains_key(&PathBuf::from("assets/templates/simple_figure.tx")));
+}
+
+#[test]
+fn test_cache_invalidation() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("test_cache_invalidation");
+    std::fs::create_dir_all(&dir).unwrap();
+    let map_path = dir.join("map.tmx");
+    let tileset_path = dir.join("tilesheet.tsx");
+    std::fs::copy("assets/tiled_base64_external.tmx", &map_path).unwrap();
+    std::fs::copy("assets/tilesheet.tsx", &tileset_path).unwrap();
+    std::fs::copy("assets/tilesheet.png", dir.join("tilesheet.png")).unwrap();
+
+    let mut loader = Loader::new();
+    let map = loader.load_tmx_map(&map_path).unwrap();
+    let held = loader.cache_mut().remove_tileset(&tileset_path).unwrap();
+    assert!(loader.cache().get_tileset(&tileset_path).is_none());
+    // Removed tilesets remain valid for those still holding them.
+    assert_eq!(*held, *map.tilesets()[0]);
+    loader.load_tmx_map(&map_path).unwrap();
+    assert!(loader.cache().get_tileset(&tileset_path).is_some());
+    loader.cache_mut().clear();
+    assert!(loader.cache().is_empty());
+
+    let set_modified = |time| {
+        std::fs::File::options()
+            .write(true)
+            .open(&tileset_path)
+            .unwrap()
+            .set_modified(time)
+            .unwrap()
+    };
+    let now = std::time::SystemTime::now();
+    set_modified(now - std::time::Duration::from_secs(60));
+    let mut loader =
+        Loader::new().with_cache(tiled::DefaultResourceCache::new().with_modification_checks(true));
+    loader.load_tmx_map(&map_path).unwrap();
+    assert!(loader.cache().get_tileset(&tileset_path).is_some());
+    set_modified(now);
+    assert!(loader.cache().get_tileset(&tileset_path).is_none());
+    loader.load_tmx_map(&map_path).unwrap();
+    assert!(loader.cache().get_tileset(&tileset_path).is_some());
+
+    // Without modification checks, the stale tileset keeps being served.
+    let mut loader = Loader::new();
+    loader.load_tmx_map(&map_path).unwrap();
+    set_modified(now + std::time::Duration::from_secs(60));
+    assert!(loader.cache().get_tileset(&tileset_path).is_some());
+}
+
+#[test]
+fn test_generic_cache() {
+    use std::{any::Any, collections::HashMap, sync::Arc};
+    use tiled::{CachedResource, ResourceKind, ResourcePath, Template, Tileset};
+
+    /// A cache keeping every kind of resource in a single map.
+    #[derive(Default)]
+    struct CountingCache {
+        resources: HashMap<(PathBuf, ResourceKind), Arc<dyn Any + Send + Sync>>,
+    }
+
+    impl ResourceCache for CountingCache {
+        fn get<T: CachedResource>(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<T>> {
+            let key = (path.as_ref().to_owned(), T::KIND);
+            self.resources.get(&key)?.clone().downcast().ok()
+        }
+
+        fn insert<T: CachedResource>(&mut self, path: impl AsRef<ResourcePath>, resource: Arc<T>) {
+            self.resources
+                .insert((path.as_ref().to_owned(), T::KIND), resource);
+        }
+    }
+
+    let mut loader =
+        Loader::with_cache_and_reader(CountingCache::default(), tiled::FilesystemResourceReader);
+    loader.load_tmx_map("assets/templates/example.tmx").unwrap();
+    let kinds: Vec<_> = loader
+        .cache()
+        .resources
+        .keys()
+        .map(|(_, kind)| *kind)
+        .collect();
+    assert_eq!(kinds.len(), 5);
+    assert_eq!(
+        kinds
+            .iter()
+            .filter(|kind| **kind == ResourceKind::Tileset)
+            .count(),
+        2
+    );
+    assert!(loader
+        .cache()
+        .get_tileset("assets/templates/grass_walls.tsx")
+        .is_some());
+
+    // Resources of different kinds never share an entry, even under the same path.
+    let mut cache = tiled::DefaultResourceCache::new();
+    let tileset = Arc::new(
+        Loader::new()
+            .load_tsx_tileset("assets/tilesheet.tsx")
+            .unwrap(),
+    );
+    cache.insert("assets/tilesheet.tsx", tileset.clone());
+    assert!(cache.get::<Template>("assets/tilesheet.tsx").is_none());
+    let cached = cache
+        .insert_with("assets/tilesheet.tsx", |_| -> Result<Arc<Tileset>, ()> {
+            panic!("the tileset is already cached")
+        })
+        .unwrap();
+    assert!(Arc::ptr_eq(&cached, &tileset));
+    assert_eq!(
+        cache.insert_with::<Template, _>("assets/tilesheet.tsx", |_| Err("not found")),
+        Err("not found")
+    );
+    assert_eq!(cache.len(), 1);
+    assert_eq!(
+        cache.remove::<Tileset>("assets/tilesheet.tsx"),
+        Some(tileset)
+    );
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn test_concurrent_cache() {
+    let cache = std::sync::Arc::new(tiled::ConcurrentResourceCache::new());
+    let maps: Vec<_> = (0..4)
+        .map(|_| {
+            let cache = cache.clone();
+            std::thread::spawn(move || {
+                Loader::with_cache_and_reader(cache, tiled::FilesystemResourceReader)
+                    .load_tmx_map("assets/templates/example.tmx")
+                    .unwrap()
+            })
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect();
+    assert_eq!(cache.tileset_count(), 2);
+    assert_eq!(cache.template_count(), 3);
+
+    let cached = (&*cache)
+        .get_tileset("assets/templates/grass_walls.tsx")
+        .unwrap();
+    for map in &maps {
+        assert_eq!(*map.tilesets()[0], *cached);
+    }
+    let cached_again = (&*cache)
+        .get_tileset("assets/templates/grass_walls.tsx")
+        .unwrap();
+    assert!(std::sync::Arc::ptr_eq(&cached, &cached_again));
+
+    cache.clear();
+    assert_eq!(cache.tileset_count(), 0);
+    assert_eq!(cache.template_count(), 0);
+}
+
+#[test]
+fn test_objects_sorted_by_y() {
+    let r = parse_small_map(
+        r#"
+ <objectgroup id="1" name="Objects" draworder="topdown">
+  <object id="1" x="0" y="30" width="4" height="4"/>
+  <object id="2" x="0" y="10">
+   <point/>
+  </object>
+  <object id="3" x="0" y="40">
+   <polygon points="0,0 5,-35 10,0"/>
+  </object>
+  <object id="4" x="0" y="10">
+   <ellipse/>
+  </object>
+  <object id="5" x="0" y="20">
+   <polyline points="0,15 5,10"/>
+  </object>
+ </objectgroup>"#,
+    )
+    .unwrap();
+    let layer = r.get_layer(0).unwrap().as_object_layer().unwrap();
+    let ids: Vec<_> = layer.iter_objects_sorted_by_y().map(|o| o.id()).collect();
+    assert_eq!(ids, [3, 2, 4, 1, 5]);
+}
+
+#[test]
+fn test_object_bounding_box() {
+    let r = parse_small_map(
+        r#"
+ <tileset firstgid="1" name="tiles" tilewidth="32" tileheight="32" tilecount="1" columns="1">
+  <image source="tilesheet.png" width="32" height="32"/>
+ </tileset>
+ <objectgroup id="1" name="Objects">
+  <object id="1" x="314" y="376">
+   <polyline points="0,0 -111,-63 -203,27 -205,-130 -78,-150 -6,-6"/>
+  </object>
+  <object id="2" x="0" y="0" width="10" height="20" rotation="90"/>
+  <object id="3" x="5" y="7">
+   <point/>
+  </object>
+  <object id="4" gid="1" x="100" y="200" width="32" height="16"/>
+ </objectgroup>"#,
+    )
+    .unwrap();
+    let layer = r.get_layer(0).unwrap().as_object_layer().unwrap();
+    let boxes: Vec<_> = layer
+        .objects()
+        .map(|object| {
+            let (x, y, w, h) = object.bounding_box();
+            (x.round(), y.round(), w.round(), h.round())
+        })
+        .collect();
+    assert_eq!(
+        boxes,
+        [
+            (109.0, 226.0, 205.0, 177.0),
+            (-20.0, 0.0, 20.0, 10.0),
+            (5.0, 7.0, 0.0, 0.0),
+            (100.0, 184.0, 32.0, 16.0),
+        ]
+    );
+}
+
+#[test]
+fn test_object_world_corners() {
+    let map = parse_small_map(
+        r#"
+ <tileset firstgid="1" name="tiles" tilewidth="32" tileheight="32" tilecount="1" columns="1" objectalignment="center">
+  <image source="tilesheet.png" width="32" height="32"/>
+ </tileset>
+ <objectgroup id="1" name="Objects">
+  <object id="1" x="100" y="100" width="40" height="20" rotation="90"/>
+  <object id="2" x="0" y="0" width="40" height="20" rotation="90">
+   <ellipse/>
+  </object>
+  <object id="3" gid="1" x="200" y="200" width="32" height="32" rotation="90"/>
+ </objectgroup>"#,
+    )
+    .unwrap();
+    assert_eq!(map.tilesets()[0].object_alignment, ObjectAlignment::Center);
+    let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    let round = |(x, y): (f32, f32)| (x.round(), y.round());
+    let round_box =
+        |(x, y, w, h): (f32, f32, f32, f32)| (x.round(), y.round(), w.round(), h.round());
+
+    // Rectangles and ellipses are rotated around their top-left corner
+    let rect = layer.get_object(0).unwrap();
+    assert_eq!(
+        rect.world_corners().map(round),
+        [(100.0, 100.0), (100.0, 140.0), (80.0, 140.0), (80.0, 100.0)]
+    );
+    assert_eq!(round_box(rect.aabb()), (80.0, 100.0, 20.0, 40.0));
+    let ellipse = layer.get_object(1).unwrap();
+    assert_eq!(round_box(ellipse.aabb()), (-20.0, 0.0, 20.0, 40.0));
+
+    // Tile objects are rotated around the alignment point of their tileset
+    let tile = layer.get_object(2).unwrap();
+    assert_eq!(
+        tile.world_corners().map(round),
+        [
+            (216.0, 184.0),
+            (216.0, 216.0),
+            (184.0, 216.0),
+            (184.0, 184.0)
+        ]
+    );
+    assert_eq!(round_box(tile.aabb()), (184.0, 184.0, 32.0, 32.0));
+    // which `bounding_box` doesn't know of, always using the bottom-left corner
+    assert_eq!(round_box(tile.bounding_box()), (200.0, 200.0, 32.0, 32.0));
+
+    assert_eq!(
+        ObjectAlignment::Unspecified.anchor(Orientation::Isometric),
+        ObjectAlignment::Bottom.anchor(Orientation::Isometric)
+    );
+    assert_eq!(
+        ObjectAlignment::Unspecified.anchor(Orientation::Orthogonal),
+        ObjectAlignment::BottomLeft.anchor(Orientation::Orthogonal)
+    );
+}
+
+#[test]
+fn test_polygon_geometry() {
+    let map = parse_small_map(
+        r#"
+ <objectgroup id="1" name="Collision">
+  <object id="1" x="100" y="50">
+   <polygon points="0,0 20,0 20,20 10,5 0,20"/>
+  </object>
+  <object id="2" x="100" y="50" rotation="90">
+   <polygon points="0,0 20,0 0,20"/>
+  </object>
+  <object id="3" x="0" y="0">
+   <polyline points="-5,2 5,-2"/>
+  </object>
+  <object id="4" x="0" y="0">
+   <polygon points="0,0 10,10"/>
+  </object>
+  <object id="5" x="0" y="0" width="10" height="10"/>
+ </objectgroup>"#,
+    )
+    .unwrap();
+    let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    let object = |index| layer.get_object(index).unwrap();
+
+    // A concave polygon, whose notch isn't inside it
+    let concave = object(0);
+    assert_eq!(concave.shape.bounds(), Some((0.0, 0.0, 20.0, 20.0)));
+    assert!(concave.shape.contains_point(5.0, 10.0));
+    assert!(!concave.shape.contains_point(10.0, 15.0));
+    assert!(!concave.shape.contains_point(25.0, 10.0));
+    assert!(concave.contains_point(105.0, 60.0));
+    assert!(!concave.contains_point(5.0, 10.0));
+    assert_eq!(
+        concave.shape.absolute_points(&concave).unwrap()[..2],
+        [(100.0, 50.0), (120.0, 50.0)]
+    );
+
+    // Rotated objects are rotated around their position
+    let rotated = object(1);
+    let points: Vec<_> = rotated
+        .shape
+        .absolute_points(&rotated)
+        .unwrap()
+        .into_iter()
+        .map(|(x, y)| (x.round(), y.round()))
+        .collect();
+    assert_eq!(points, [(100.0, 50.0), (100.0, 70.0), (80.0, 50.0)]);
+    assert!(rotated.contains_point(95.0, 55.0));
+    assert!(!rotated.contains_point(105.0, 55.0));
+
+    let polyline = object(2);
+    assert_eq!(polyline.shape.bounds(), Some((-5.0, -2.0, 10.0, 4.0)));
+    assert!(!polyline.shape.contains_point(0.0, 0.0));
+
+    // Degenerate polygons contain nothing
+    let degenerate = object(3);
+    assert_eq!(degenerate.shape.bounds(), Some((0.0, 0.0, 10.0, 10.0)));
+    assert!(!degenerate.shape.contains_point(5.0, 5.0));
+    let empty = ObjectShape::Polygon { points: Vec::new() };
+    assert_eq!(empty.bounds(), None);
+    assert!(!empty.contains_point(0.0, 0.0));
+
+    let rect = object(4);
+    assert_eq!(rect.shape.bounds(), None);
+    assert_eq!(rect.shape.absolute_points(&rect), None);
+}
+
+#[test]
+fn test_reading_wang_sets() {
+    let mut loader = Loader::new();
+    let map = loader
+        .load_tmx_map("assets/tiled_csv_wangsets.tmx")
+        .unwrap();
+
+    // We will pick some random data from the wangsets for tessting
     let tileset = map.tilesets().get(0).unwrap();
     assert_eq!(tileset.wang_sets.len(), 3);
     let wangset_2 = tileset.wang_sets.get(1).unwrap();
-    let tile_10 = wangset_2.wang_tiles.get(&10).unwrap();
+    let tile_10 = wangset_2.wang_tiles.get(&TileId::new(10)).unwrap();
     assert_eq!(tile_10.wang_id, WangId([2u8, 2, 0, 2, 0, 2, 2, 2]));
     let wangset_3 = tileset.wang_sets.get(2).unwrap();
     let color_2 = wangset_3.wang_colors.get(1).unwrap();
@@ -495,3 +1798,2648 @@ fn test_reading_wang_sets() {
     let damage_value = &PropertyValue::FloatValue(32.1);
     assert_eq!(readed_damage, damage_value);
 }
+
+#[test]
+fn test_orientation_from_str() {
+    for (s, orientation) in [
+        ("orthogonal", Orientation::Orthogonal),
+        ("isometric", Orientation::Isometric),
+        ("staggered", Orientation::Staggered),
+        ("hexagonal", Orientation::Hexagonal),
+    ] {
+        assert_eq!(s.parse::<Orientation>().unwrap(), orientation);
+        assert_eq!(orientation.to_string(), s);
+    }
+    assert!("Staggered".parse::<Orientation>().is_err());
+    assert!("".parse::<Orientation>().is_err());
+}
+
+#[test]
+fn test_render_order() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64.tmx")
+        .unwrap();
+    assert_eq!(map.render_order, RenderOrder::RightDown);
+    assert_eq!(RenderOrder::LeftUp.to_string(), "left-up");
+    assert!("leftup".parse::<RenderOrder>().is_err());
+
+    let map = tiled::parse_str(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="left-up" width="1" height="1" tilewidth="32" tileheight="32">
+</map>"#,
+    )
+    .unwrap();
+    assert_eq!(map.render_order, RenderOrder::LeftUp);
+    let mut tmx = Vec::new();
+    map.write_to(&mut tmx).unwrap();
+    assert!(String::from_utf8_lossy(&tmx).contains(r#"renderorder="left-up""#));
+    assert_eq!(
+        tiled::parse_bytes(&tmx).unwrap().render_order,
+        RenderOrder::LeftUp
+    );
+    assert!(tiled::parse_str(
+        r#"<map version="1.10" orientation="orthogonal" renderorder="up" width="1" height="1" tilewidth="32" tileheight="32"/>"#
+    )
+    .is_err());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_roundtrip() {
+    for path in [
+        "assets/tiled_base64_zlib_infinite.tmx",
+        "assets/tiled_object_groups.tmx",
+        "assets/tiled_csv_wangsets.tmx",
+        "assets/tiled_image_layers.tmx",
+    ] {
+        let map = Loader::new().load_tmx_map(path).unwrap();
+        let json = serde_json::to_string(&map).unwrap();
+        let deserialized: Map = serde_json::from_str(&json).unwrap();
+        assert_eq!(map, deserialized, "{} did not survive a roundtrip", path);
+    }
+
+    let color = Color {
+        alpha: 0x80,
+        red: 0x12,
+        green: 0x34,
+        blue: 0xab,
+    };
+    assert_eq!(serde_json::to_string(&color).unwrap(), "\"#801234ab\"");
+    assert_eq!(
+        serde_json::from_str::<Color>("\"#1234ab\"").unwrap(),
+        Color {
+            alpha: 0xff,
+            ..color
+        }
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_invalid_tileset_index() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib.tmx")
+        .unwrap();
+    let json = serde_json::to_value(&map).unwrap();
+
+    let mut bad_lookup = json.clone();
+    bad_lookup["gid_lookup"][0][1] = 5.into();
+    assert!(serde_json::from_value::<Map>(bad_lookup).is_err());
+
+    // The tiles of the layers are left referring to the removed tileset
+    let mut no_tilesets = json;
+    no_tilesets["tilesets"] = serde_json::json!([]);
+    no_tilesets["tileset_sources"] = serde_json::json!([]);
+    no_tilesets["gid_lookup"] = serde_json::json!([]);
+    assert!(serde_json::from_value::<Map>(no_tilesets).is_err());
+}
+
+#[test]
+fn test_write_roundtrip() {
+    for (path, encoding) in [
+        ("assets/tiled_base64_zlib.tmx", TileDataEncoding::Base64Zlib),
+        (
+            "assets/tiled_base64_zlib_infinite.tmx",
+            TileDataEncoding::Csv,
+        ),
+        (
+            "assets/tiled_base64_zstandard.tmx",
+            TileDataEncoding::Base64Zstd,
+        ),
+        ("assets/tiled_csv_wangsets.tmx", TileDataEncoding::Base64),
+        ("assets/tiled_editor_settings.tmx", TileDataEncoding::Csv),
+        ("assets/tiled_flipped.tmx", TileDataEncoding::Base64Gzip),
+        ("assets/tiled_group_layers.tmx", TileDataEncoding::Csv),
+        ("assets/tiled_hexagonal.tmx", TileDataEncoding::Base64),
+        (
+            "assets/tiled_image_layers.tmx",
+            TileDataEncoding::Base64Zlib,
+        ),
+        (
+            "assets/tiled_object_groups.tmx",
+            TileDataEncoding::Base64Zlib,
+        ),
+        ("assets/tiled_parallax.tmx", TileDataEncoding::Base64Zlib),
+        (
+            "assets/folder/tiled_relative_paths.tmx",
+            TileDataEncoding::Base64Zlib,
+        ),
+    ] {
+        let map = Loader::new().load_tmx_map(path).unwrap();
+        let mut tmx = Vec::new();
+        map.write_to_with_options(&mut tmx, &WriteOptions::new().with_encoding(encoding))
+            .unwrap();
+        let written = Loader::new()
+            .load_tmx_map_from(&tmx[..], map.source.as_ref().unwrap())
+            .unwrap_or_else(|err| panic!("{} could not be parsed back: {}", path, err));
+
+        compare_everything_but_tileset_sources(&map, &written);
+        assert_eq!(map.stagger_axis, written.stagger_axis);
+        assert_eq!(map.user_type, written.user_type);
+        assert_eq!(map.editor_settings, written.editor_settings);
+        let layers: Vec<_> = map.layers().map(|layer| (*layer).clone()).collect();
+        let written_layers: Vec<_> = written.layers().map(|layer| (*layer).clone()).collect();
+        assert_eq!(layers, written_layers, "{} layers differ", path);
+
+        // External tilesets are still referenced, and embedded ones still embedded.
+        assert_eq!(
+            map.tilesets(),
+            written.tilesets(),
+            "{} tilesets differ",
+            path
+        );
+        assert!(map.map_tilesets().eq(written.map_tilesets()), "{}", path);
+    }
+}
+
+#[test]
+fn test_is_empty() {
+    // Every tile of this map is empty, and its only object layer has no objects.
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_groups.tmx")
+        .unwrap();
+    assert!(map.is_empty());
+    let tile_layer = map.get_layer(0).unwrap();
+    assert!(tile_layer.is_empty());
+    assert!(tile_layer.as_tile_layer().unwrap().is_empty());
+    let group = map.get_layer(1).unwrap().as_group_layer().unwrap();
+    assert!(group.is_empty());
+    assert!(group
+        .get_layer(0)
+        .unwrap()
+        .as_object_layer()
+        .unwrap()
+        .is_empty());
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    assert!(!map.is_empty());
+    assert!(!map.get_layer(0).unwrap().is_empty());
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64.tmx")
+        .unwrap();
+    let object_layer = map
+        .layers()
+        .find_map(|layer| layer.as_object_layer())
+        .unwrap();
+    assert!(!object_layer.is_empty());
+}
+
+#[test]
+fn test_write_external_tilesets() {
+    /// A reader that serves a single file from memory, and everything else from the filesystem.
+    struct OverrideReader(PathBuf, Vec<u8>);
+
+    impl tiled::ResourceReader for OverrideReader {
+        type Resource = Box<dyn std::io::Read>;
+        type Error = std::io::Error;
+
+        fn read_from(
+            &mut self,
+            path: &std::path::Path,
+        ) -> std::result::Result<Self::Resource, Self::Error> {
+            if path == self.0 {
+                Ok(Box::new(std::io::Cursor::new(self.1.clone())))
+            } else {
+                Ok(Box::new(std::fs::File::open(path)?))
+            }
+        }
+    }
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+
+    // The tileset written as TSX should be parsed back exactly as it was.
+    let tileset = &map.tilesets()[0];
+    let mut tsx = Vec::new();
+    tileset.write_to(&mut tsx).unwrap();
+    assert!(!String::from_utf8_lossy(&tsx).contains("firstgid"));
+    let written_tileset = Loader::new()
+        .with_reader(OverrideReader(tileset.source.clone(), tsx))
+        .load_tsx_tileset(&tileset.source)
+        .unwrap();
+    assert_eq!(**tileset, written_tileset);
+
+    // The original TSX file is referenced by default, which gives back the exact same map.
+    let mut tmx = Vec::new();
+    map.write_to(&mut tmx).unwrap();
+    assert!(
+        String::from_utf8_lossy(&tmx).contains(r#"<tileset firstgid="1" source="tilesheet.tsx""#)
+    );
+    let written = Loader::new()
+        .load_tmx_map_from(&tmx[..], map.source.as_ref().unwrap())
+        .unwrap();
+    assert_eq!(map, written);
+
+    // Other files can be referenced instead, or the tileset embedded.
+    let mut tmx = Vec::new();
+    map.write_to_with_options(
+        &mut tmx,
+        &WriteOptions::new().with_external_tileset(0, "tilesets/tilesheet.tsx"),
+    )
+    .unwrap();
+    assert!(String::from_utf8_lossy(&tmx).contains(r#"source="tilesets/tilesheet.tsx""#));
+    let mut tmx = Vec::new();
+    map.write_to_with_options(&mut tmx, &WriteOptions::new().with_embedded_tilesets(true))
+        .unwrap();
+    let written = tiled::parse_bytes(&tmx).unwrap();
+    assert_eq!(
+        written.tileset_source(0),
+        Some(&tiled::TilesetSource::Embedded)
+    );
+    assert_eq!(written.tilesets()[0].name, "tilesheet");
+}
+
+#[test]
+fn test_validate() {
+    for path in [
+        "assets/tiled_base64.tmx",
+        "assets/tiled_base64_zlib_infinite.tmx",
+        "assets/tiled_group_layers.tmx",
+        "assets/tiled_object_property.tmx",
+    ] {
+        let map = Loader::new().load_tmx_map(path).unwrap();
+        assert_eq!(map.validate(), vec![], "{} should have no warnings", path);
+    }
+
+    let map = tiled::parse_str(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="1" tilewidth="16" tileheight="16">
+ <tileset firstgid="1" name="a" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+  <image source="a.png" width="32" height="32"/>
+ </tileset>
+ <tileset firstgid="3" name="b" tilewidth="16" tileheight="16" tilecount="1" columns="1">
+  <image source="b.png" width="16" height="16"/>
+ </tileset>
+ <tileset firstgid="10" name="c" tilewidth="16" tileheight="16" tilecount="4" columns="4">
+  <image source="c.png" width="32" height="16"/>
+ </tileset>
+ <layer id="1" name="layer" width="3" height="1">
+  <data encoding="csv">1,5,0</data>
+ </layer>
+ <layer id="2" name="layer" width="2" height="1">
+  <data encoding="csv">0,0</data>
+ </layer>
+</map>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        map.validate(),
+        vec![
+            Warning::DuplicateLayerName {
+                name: "layer".to_owned()
+            },
+            Warning::LayerLargerThanMap {
+                layer: "layer".to_owned(),
+                width: 3,
+                height: 1
+            },
+            Warning::TileNotInTileset {
+                layer: "layer".to_owned(),
+                x: 1,
+                y: 0,
+                tileset_index: 1,
+                tile_id: TileId::new(2)
+            },
+            Warning::OverlappingTilesets {
+                first: 0,
+                second: 1
+            },
+            Warning::TilesetImageTooSmall { tileset_index: 2 },
+        ]
+    );
+
+    // Tilesets whose GIDs don't fit are reported instead of overflowing
+    let map = parse_small_map(
+        r#"
+ <tileset firstgid="4294967294" name="a" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+  <image source="a.png" width="32" height="32"/>
+ </tileset>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        map.validate(),
+        vec![Warning::TilesetGidOverflow { tileset_index: 0 }]
+    );
+}
+
+#[test]
+fn test_validate_objects_and_data() {
+    let map = tiled::parse_str(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="2" tilewidth="16" tileheight="16">
+ <properties>
+  <property name="boss" type="object" value="7"/>
+ </properties>
+ <tileset firstgid="1" name="a" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+  <image source="a.png" width="32" height="32"/>
+  <tile id="1">
+   <animation>
+    <frame tileid="2" duration="100"/>
+    <frame tileid="9" duration="100"/>
+   </animation>
+  </tile>
+ </tileset>
+ <layer id="1" name="tiles" width="2" height="2">
+  <data encoding="csv">1,2,3</data>
+ </layer>
+ <objectgroup id="2" name="objects">
+  <properties>
+   <property name="none" type="object" value="0"/>
+  </properties>
+  <object id="1" gid="6" x="0" y="0" width="16" height="16">
+   <properties>
+    <property name="next" type="object" value="2"/>
+    <property name="link" type="class" propertytype="Link">
+     <properties>
+      <property name="target" type="object" value="3"/>
+     </properties>
+    </property>
+   </properties>
+  </object>
+  <object id="2" x="0" y="0"/>
+ </objectgroup>
+</map>"#,
+    )
+    .unwrap();
+    assert_eq!(
+        map.validate(),
+        vec![
+            Warning::LayerDataSizeMismatch {
+                layer: "tiles".to_owned(),
+                expected: 4,
+                actual: 3
+            },
+            Warning::MissingObjectReference {
+                layer: None,
+                object_id: None,
+                property: "boss".to_owned(),
+                target: 7
+            },
+            Warning::ObjectTileNotInTileset {
+                layer: "objects".to_owned(),
+                object_id: 1,
+                tileset_index: Some(0),
+                tile_id: TileId::new(5)
+            },
+            Warning::MissingObjectReference {
+                layer: Some("objects".to_owned()),
+                object_id: Some(1),
+                property: "link.target".to_owned(),
+                target: 3
+            },
+            Warning::AnimationFrameNotInTileset {
+                tileset_index: 0,
+                tile_id: TileId::new(1),
+                frame_tile_id: TileId::new(9)
+            },
+        ]
+    );
+    assert_eq!(
+        map.validate()[3].to_string(),
+        "Property 'link.target' of object 1 in layer 'objects' refers to object 3, which isn't in \
+         the map"
+    );
+}
+
+#[test]
+fn test_map_builder() {
+    let source = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib.tmx")
+        .unwrap();
+    let layer = source.get_layer(0).unwrap();
+    let map = MapBuilder::new()
+        .with_size(source.width, source.height)
+        .with_tile_size(source.tile_width, source.tile_height)
+        .with_tileset(source.tilesets()[0].clone())
+        .with_layer((*layer).clone())
+        .build()
+        .unwrap();
+    assert_eq!(map.orientation, Orientation::Orthogonal);
+    assert_eq!(map.source, None);
+    assert_eq!(
+        map.get_tileset_by_first_gid(Gid(1)).unwrap().name,
+        "tilesheet"
+    );
+    assert_eq!(
+        as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap())
+            .get_tile(0, 0)
+            .map(|tile| tile.id()),
+        as_finite(layer.as_tile_layer().unwrap())
+            .get_tile(0, 0)
+            .map(|tile| tile.id())
+    );
+
+    assert!(matches!(
+        MapBuilder::new().with_size(10, 10).build(),
+        Err(tiled::Error::MissingBuilderField { field }) if field == "tile_size"
+    ));
+    assert!(matches!(
+        MapBuilder::new()
+            .with_size(10, 10)
+            .with_tile_size(32, 32)
+            .with_layer((*layer).clone())
+            .build(),
+        Err(tiled::Error::InvalidTileFound)
+    ));
+}
+
+#[test]
+fn test_class_properties() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_project_types.tmx")
+        .unwrap();
+    // Without the project's types, only the members stored in the map are available.
+    assert_eq!(
+        map.properties["enemy"],
+        PropertyValue::ClassValue {
+            property_type: "Enemy".to_owned(),
+            properties: Properties::from([("health".to_owned(), PropertyValue::IntValue(50))]),
+        }
+    );
+    assert_eq!(
+        map.properties["boss"],
+        PropertyValue::ClassValue {
+            property_type: "Enemy".to_owned(),
+            properties: Properties::new(),
+        }
+    );
+
+    let mut tmx = Vec::new();
+    map.write_to(&mut tmx).unwrap();
+    let written = Loader::new()
+        .load_tmx_map_from(&tmx[..], "assets/written.tmx")
+        .unwrap();
+    assert_eq!(map.properties, written.properties);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_project_types() {
+    let mut loader = Loader::new();
+    let types = loader
+        .load_project_types("assets/project.tiled-project")
+        .unwrap();
+    let mut loader = loader.with_project_types(types.clone());
+    let map = loader
+        .load_tmx_map("assets/tiled_project_types.tmx")
+        .unwrap();
+
+    let enemy = match &map.properties["enemy"] {
+        PropertyValue::ClassValue { properties, .. } => properties,
+        _ => panic!("Not a class value"),
+    };
+    assert_eq!(enemy["health"], PropertyValue::IntValue(50));
+    assert_eq!(enemy["speed"], PropertyValue::FloatValue(1.5));
+    assert_eq!(
+        enemy["facing"],
+        PropertyValue::EnumValue {
+            property_type: "Direction".to_owned(),
+            value: Box::new(PropertyValue::IntValue(0)),
+        }
+    );
+    // Nested class values are filled in from both the class' defaults and the parent's.
+    assert_eq!(
+        enemy["loot"],
+        PropertyValue::ClassValue {
+            property_type: "Loot".to_owned(),
+            properties: Properties::from([
+                ("gold".to_owned(), PropertyValue::IntValue(25)),
+                (
+                    "item".to_owned(),
+                    PropertyValue::StringValue("potion".to_owned())
+                ),
+            ]),
+        }
+    );
+    match &map.properties["boss"] {
+        PropertyValue::ClassValue { properties, .. } => {
+            assert_eq!(properties["health"], PropertyValue::IntValue(100))
+        }
+        _ => panic!("Not a class value"),
+    }
+
+    // Objects get the members of their class as properties.
+    let object_layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    let goblin = object_layer.get_object(0).unwrap();
+    assert_eq!(
+        goblin.properties["facing"],
+        PropertyValue::EnumValue {
+            property_type: "Direction".to_owned(),
+            value: Box::new(PropertyValue::IntValue(2)),
+        }
+    );
+    assert_eq!(goblin.properties["health"], PropertyValue::IntValue(100));
+
+    let direction = &types.enums["Direction"];
+    assert_eq!(
+        direction.value_names(&goblin.properties["facing"]),
+        Some(vec!["South"])
+    );
+    assert_eq!(direction.value_names(&PropertyValue::IntValue(4)), None);
+    let element = &types.enums["Element"];
+    assert_eq!(
+        element.value_names(&goblin.properties["element"]),
+        Some(vec!["Fire", "Water"])
+    );
+    assert_eq!(
+        element.value_names(&PropertyValue::IntValue(0b101)),
+        Some(vec!["Fire", "Earth"])
+    );
+    // Enum values name their enum, so they can be resolved without knowing it
+    assert_eq!(
+        types.enum_value_names(&goblin.properties["element"]),
+        Some(vec!["Fire", "Water"])
+    );
+    assert_eq!(
+        types.enum_value_names(&PropertyValue::StringValue("Fire".to_owned())),
+        None
+    );
+
+    // The enum an enum value belongs to is kept without project types too, and written back
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_project_types.tmx")
+        .unwrap();
+    let object_layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    let goblin = object_layer.get_object(0).unwrap();
+    assert_eq!(
+        goblin.properties["element"],
+        PropertyValue::EnumValue {
+            property_type: "Element".to_owned(),
+            value: Box::new(PropertyValue::StringValue("Fire,Water".to_owned())),
+        }
+    );
+    let mut tmx = Vec::new();
+    map.write_to(&mut tmx).unwrap();
+    let written = tiled::parse_bytes(&tmx).unwrap();
+    let written_layer = written.get_layer(1).unwrap().as_object_layer().unwrap();
+    assert_eq!(
+        written_layer.get_object(0).unwrap().properties,
+        goblin.properties
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_json_tilesets() {
+    // The JSON tilesets are exports of the TSX tilesets of the same name
+    for name in ["assets/tilesheet", "assets/tilesheet_tiles"] {
+        let tsx = tiled::parse_tileset_file(format!("{}.tsx", name)).unwrap();
+        let mut tsj = tiled::parse_tileset_file(format!("{}.tsj", name)).unwrap();
+        assert_eq!(tsj.source, PathBuf::from(format!("{}.tsj", name)));
+        tsj.source = tsx.source.clone();
+        assert_eq!(tsx, tsj);
+    }
+
+    // The format is found from the contents when the extension isn't a known one.
+    let source = std::fs::read("assets/tilesheet_tiles.tsj").unwrap();
+    let sniffed = tiled::parse_tileset_reader(&source[..], "assets/tilesheet_tiles.dat").unwrap();
+    assert_eq!(sniffed.name, "tilesheet_tiles");
+
+    let tsx_map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    let tsj_map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external_tsj.tmx")
+        .unwrap();
+    compare_everything_but_tileset_sources(&tsx_map, &tsj_map);
+    assert_eq!(
+        tsj_map.tilesets()[0].source,
+        PathBuf::from("assets/tilesheet.tsj")
+    );
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_world() {
+    let mut loader = Loader::new().with_base_path("assets");
+    let world = loader.load_world("world.world").unwrap();
+    assert_eq!(world.source, PathBuf::from("assets/world.world"));
+    assert!(!world.only_show_adjacent_maps);
+
+    assert_eq!(world.maps.len(), 2);
+    let second = &world.maps[1];
+    assert_eq!(second.file_name, PathBuf::from("assets/tiled_csv.tmx"));
+    assert_eq!((second.x, second.y), (3200, -3200));
+    assert_eq!((second.width, second.height), (Some(3200), Some(3200)));
+
+    assert_eq!(world.patterns.len(), 1);
+    let pattern = &world.patterns[0];
+    assert_eq!(pattern.regexp, r"ow-p([0-9]+)-n([0-9]+)\.tmx");
+    assert_eq!((pattern.map_width, pattern.map_height), (None, None));
+    assert_eq!(pattern.position(1, 2), Some((3200, 9600)));
+    assert_eq!(pattern.position(i32::MAX, 0), None);
+
+    let maps = loader.load_world_maps(&world).unwrap();
+    assert_eq!(maps.len(), 2);
+    assert_eq!(
+        maps[1].source.as_deref(),
+        Some(Path::new("assets/tiled_csv.tmx"))
+    );
+    assert_eq!(maps[0].width, 100);
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn test_write_json() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_str(&map.to_json_string().unwrap()).unwrap();
+    assert_eq!(json["type"], "map");
+    assert_eq!(json["width"], 100);
+    assert_eq!(
+        json["tilesets"][0],
+        serde_json::json!({"firstgid": 1, "source": "tilesheet.tsx"})
+    );
+    let mut embedded = Vec::new();
+    map.write_json_to_with_options(
+        &mut embedded,
+        &WriteOptions::new().with_embedded_tilesets(true),
+    )
+    .unwrap();
+    let embedded: serde_json::Value = serde_json::from_slice(&embedded).unwrap();
+    assert_eq!(embedded["tilesets"][0]["firstgid"], 1);
+    assert_eq!(embedded["tilesets"][0]["image"], "tilesheet.png");
+
+    let layer = &json["layers"][0];
+    assert_eq!(layer["type"], "tilelayer");
+    assert_eq!(layer["properties"][0]["name"], "prop1");
+    let tiles = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+    let data = layer["data"].as_array().unwrap();
+    assert_eq!(data.len(), 100 * 100);
+    for (index, gid) in data.iter().enumerate() {
+        let expected = tiles
+            .get_tile_data(index as i32 % 100, index as i32 / 100)
+            .map_or(0, |tile| tile.id().get() + 1);
+        assert_eq!(gid, expected);
+    }
+
+    let objects = &json["layers"][1]["objects"];
+    assert_eq!(objects[1]["ellipse"], true);
+    assert_eq!(
+        objects[2]["polyline"][1],
+        serde_json::json!({"x": -111, "y": -63})
+    );
+
+    let options = WriteOptions::new()
+        .with_encoding(TileDataEncoding::Base64Zlib)
+        .with_external_tileset(0, "tilesheet.tsj");
+    let mut written = Vec::new();
+    map.write_json_to_with_options(&mut written, &options)
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&written).unwrap();
+    assert_eq!(
+        json["tilesets"][0],
+        serde_json::json!({"firstgid": 1, "source": "tilesheet.tsj"})
+    );
+    assert_eq!(json["layers"][0]["encoding"], "base64");
+    assert_eq!(json["layers"][0]["compression"], "zlib");
+    assert!(json["layers"][0]["data"].is_string());
+
+    // Properties carry their types
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_groups.tmx")
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_str(&map.to_json_string().unwrap()).unwrap();
+    assert_eq!(
+        json["layers"][1]["layers"][0]["properties"][0],
+        serde_json::json!({"name": "an object group property", "type": "bool", "value": true})
+    );
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_str(&map.to_json_string().unwrap()).unwrap();
+    let chunks = json["layers"][0]["chunks"].as_array().unwrap();
+    assert!(!chunks.is_empty());
+    assert_eq!(chunks[0]["data"].as_array().unwrap().len(), 16 * 16);
+}
+
+#[test]
+fn test_layer_builder() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    let tileset = map.tilesets()[0].clone();
+
+    let layer = LayerBuilder::new()
+        .with_name("generated")
+        .with_size(3, 2)
+        .fill(2)
+        .set_tile(1, 1, 0)
+        .set_tile(2, 0, 5 | 0x80000000)
+        .build(&[tileset.clone()]);
+    let map = MapBuilder::new()
+        .with_size(3, 2)
+        .with_tile_size(32, 32)
+        .with_tileset(tileset)
+        .with_layer(layer)
+        .build()
+        .unwrap();
+
+    let layer = map.get_layer(0).unwrap();
+    assert_eq!(layer.name, "generated");
+    let tiles = as_finite(layer.as_tile_layer().unwrap());
+    assert_eq!((tiles.width(), tiles.height()), (3, 2));
+    assert_eq!(
+        tiles.get_tile(0, 0).map(|tile| tile.id()),
+        Some(TileId::new(1))
+    );
+    assert!(tiles.get_tile(1, 1).is_none());
+    let flipped = tiles.get_tile(2, 0).unwrap();
+    assert_eq!(flipped.id(), TileId::new(4));
+    assert_eq!(flipped.flip, FlipFlags::HORIZONTAL);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic]
+fn test_layer_builder_out_of_bounds() {
+    LayerBuilder::new().with_size(2, 2).set_tile(2, 0, 1);
+}
+
+#[test]
+fn test_object_group_builder() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64.tmx")
+        .unwrap();
+    let objects: Vec<_> = map
+        .layers()
+        .find_map(|layer| layer.as_object_layer())
+        .unwrap()
+        .objects()
+        .map(|object| (*object).clone())
+        .collect();
+
+    let layer = ObjectGroupBuilder::new()
+        .with_name("generated")
+        .with_draw_order(DrawOrder::Index)
+        .with_next_object_id(10)
+        .add_object(objects[1].clone())
+        .add_object(objects[0].clone())
+        .add_property("level", PropertyValue::IntValue(2))
+        .build();
+    let map = MapBuilder::new()
+        .with_size(4, 4)
+        .with_tile_size(32, 32)
+        .with_layer(layer)
+        .build()
+        .unwrap();
+
+    let mut tmx = Vec::new();
+    map.write_to(&mut tmx).unwrap();
+    let map = Loader::new()
+        .load_tmx_map_from(&tmx[..], "assets/written.tmx")
+        .unwrap();
+    let layer = map.get_layer(0).unwrap();
+    assert_eq!(layer.name, "generated");
+    assert_eq!(layer.properties["level"], PropertyValue::IntValue(2));
+    let layer = layer.as_object_layer().unwrap();
+    assert_eq!(layer.draw_order, DrawOrder::Index);
+    let ids: Vec<_> = layer.objects().map(|object| object.id()).collect();
+    assert_eq!(ids, [10, 11]);
+    assert_eq!(layer.get_object(0).unwrap().name, objects[1].name);
+
+    // Collision data in tilesets can use either draw order too
+    let tileset = Loader::new()
+        .load_tsx_tileset("assets/tilesheet_tiles.tsx")
+        .unwrap();
+    assert!(tileset.tiles().any(|(_, tile)| tile
+        .collision
+        .as_ref()
+        .map_or(false, |collision| collision.draw_order == DrawOrder::Index)));
+}
+
+#[test]
+fn test_parse_limits() {
+    let limited = |limits: ParseLimits| Loader::new().with_limits(limits);
+    // The errors of decoding finite tile layers also hold the name of the layer
+    fn is_tile_data_limit(result: tiled::Result<Map>) -> bool {
+        match result {
+            Err(tiled::Error::LimitExceeded { .. }) => true,
+            Err(tiled::Error::LayerDecodingError { err, .. }) => {
+                matches!(*err, tiled::Error::LimitExceeded { .. })
+            }
+            _ => false,
+        }
+    }
+
+    // The defaults are enough for every map in the repository
+    let map = limited(ParseLimits::new())
+        .load_tmx_map("assets/tiled_base64_zlib.tmx")
+        .unwrap();
+    assert_eq!(map.width, 100);
+
+    assert!(matches!(
+        limited(ParseLimits::new().with_max_map_size(50))
+            .load_tmx_map("assets/tiled_base64_zlib.tmx"),
+        Err(tiled::Error::LimitExceeded { .. })
+    ));
+    assert!(is_tile_data_limit(
+        limited(ParseLimits::new().with_max_tile_data_size(1024))
+            .load_tmx_map("assets/tiled_base64_zlib.tmx")
+    ));
+    assert!(is_tile_data_limit(
+        limited(ParseLimits::new().with_max_tile_data_size(1024))
+            .load_tmx_map("assets/tiled_csv.tmx")
+    ));
+    assert!(matches!(
+        limited(ParseLimits::new().with_max_tile_data_size(1024))
+            .load_tmx_map_streaming("assets/tiled_base64_gzip.tmx", |_| {}),
+        Err(tiled::Error::LimitExceeded { .. })
+    ));
+
+    let nested = small_map(&format!(
+        "{}{}",
+        "<group>".repeat(100),
+        "</group>".repeat(100)
+    ));
+    assert!(matches!(
+        Loader::new().load_tmx_map_from(nested.as_bytes(), "assets/nested.tmx"),
+        Err(tiled::Error::LimitExceeded { .. })
+    ));
+    assert!(limited(ParseLimits::new().with_max_nesting_depth(128))
+        .load_tmx_map_from(nested.as_bytes(), "assets/nested.tmx")
+        .is_ok());
+
+    // Layers with empty data would be filled with empty tiles, so their size alone is checked
+    // against the tile data limit
+    let huge = r#"<map version="1.10" orientation="orthogonal" width="65536" height="65536" tilewidth="1" tileheight="1">
+ <layer id="1" name="empty" width="65536" height="65536">
+  <data encoding="csv"></data>
+ </layer>
+</map>"#;
+    assert!(matches!(
+        Loader::new().load_tmx_map_from(huge.as_bytes(), "assets/huge.tmx"),
+        Err(tiled::Error::LimitExceeded { .. })
+    ));
+    let chunk = r#"<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="1" tileheight="1" infinite="1">
+ <layer id="1" name="empty" width="1" height="1">
+  <data encoding="csv"><chunk x="0" y="0" width="65536" height="65536"></chunk></data>
+ </layer>
+</map>"#;
+    assert!(matches!(
+        Loader::new().load_tmx_map_from(chunk.as_bytes(), "assets/huge.tmx"),
+        Err(tiled::Error::LimitExceeded { .. })
+    ));
+}
+
+#[test]
+fn test_duplicate_properties() {
+    let tmx = small_map(
+        r#"
+ <properties>
+  <property name="speed" type="int" value="1"/>
+  <property name="speed" type="int" value="2"/>
+ </properties>
+ <tileset firstgid="1" name="tiles" tilewidth="1" tileheight="1" tilecount="1" columns="1">
+  <tile id="0">
+   <properties>
+    <property name="solid" type="bool" value="true"/>
+   </properties>
+  </tile>
+ </tileset>
+ <objectgroup id="1" name="Objects">
+  <properties>
+   <property name="solid" type="bool" value="false"/>
+  </properties>
+  <object id="3">
+   <properties>
+    <property name="outer" type="class">
+     <properties>
+      <property name="solid" type="bool" value="true"/>
+     </properties>
+    </property>
+    <property name="solid" type="bool" value="true"/>
+    <property name="solid" type="bool" value="false"/>
+   </properties>
+  </object>
+ </objectgroup>"#,
+    );
+    let load = |policy: DuplicatePropertyPolicy| {
+        Loader::new()
+            .with_duplicate_properties(policy)
+            .load_tmx_map_from(tmx.as_bytes(), "assets/duplicates.tmx")
+    };
+
+    // By default, the last property wins without any warning
+    let map = load(DuplicatePropertyPolicy::KeepLast).unwrap();
+    assert_eq!(map.properties["speed"], PropertyValue::IntValue(2));
+    assert!(map.validate().is_empty());
+
+    let map = load(DuplicatePropertyPolicy::Warn).unwrap();
+    assert_eq!(map.properties["speed"], PropertyValue::IntValue(2));
+    let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    let object = layer.get_object(0).unwrap();
+    assert_eq!(object.properties["solid"], PropertyValue::BoolValue(false));
+    assert_eq!(
+        map.validate(),
+        vec![
+            Warning::DuplicateProperty {
+                element: "map".to_owned(),
+                element_id: None,
+                name: "speed".to_owned(),
+            },
+            Warning::DuplicateProperty {
+                element: "object".to_owned(),
+                element_id: Some(3),
+                name: "solid".to_owned(),
+            },
+        ]
+    );
+
+    assert!(matches!(
+        load(DuplicatePropertyPolicy::Error),
+        Err(tiled::Error::DuplicateProperty { element, element_id: None, name })
+            if element == "map" && name == "speed"
+    ));
+}
+
+#[test]
+fn test_duplicate_properties_in_shared_files() {
+    use tiled::MemoryResourceReader;
+
+    let tmx = r#"<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="1" tileheight="1">
+ <tileset firstgid="1" source="tiles.tsx"/>
+ <objectgroup id="1" name="Objects">
+  <object id="1" template="enemy.tx"/>
+  <object id="2" template="enemy.tx"/>
+ </objectgroup>
+</map>"#;
+    let tsx = r#"<tileset name="tiles" tilewidth="1" tileheight="1" tilecount="1" columns="1">
+ <properties>
+  <property name="depth" type="int" value="1"/>
+  <property name="depth" type="int" value="2"/>
+ </properties>
+</tileset>"#;
+    let tx = r#"<template>
+ <object width="1" height="1">
+  <properties>
+   <property name="health" type="int" value="1"/>
+   <property name="health" type="int" value="2"/>
+  </properties>
+ </object>
+</template>"#;
+    let reader = MemoryResourceReader::new()
+        .with_resource("map.tmx", tmx.as_bytes())
+        .with_resource("tiles.tsx", tsx.as_bytes())
+        .with_resource("enemy.tx", tx.as_bytes());
+    let mut loader = Loader::new().with_reader(reader);
+    let map = loader.load_tmx_map("map.tmx").unwrap();
+    assert!(map.validate().is_empty());
+
+    // The tileset and template cached with the default policy are parsed again with another one
+    let mut loader = loader.with_duplicate_properties(DuplicatePropertyPolicy::Warn);
+    let warned = loader.load_tmx_map("map.tmx").unwrap();
+    assert_eq!(
+        warned.validate(),
+        vec![
+            Warning::DuplicateProperty {
+                element: "tileset".to_owned(),
+                element_id: None,
+                name: "depth".to_owned(),
+            },
+            Warning::DuplicateProperty {
+                element: "object".to_owned(),
+                element_id: None,
+                name: "health".to_owned(),
+            },
+        ]
+    );
+    // Warnings aren't part of the map itself
+    assert_eq!(warned, map);
+
+    let mut loader = loader.with_duplicate_properties(DuplicatePropertyPolicy::Error);
+    assert!(matches!(
+        loader.load_tmx_map("map.tmx"),
+        Err(tiled::Error::DuplicateProperty { element, .. }) if element == "tileset"
+    ));
+}
+
+#[test]
+fn test_layer_decoding_error() {
+    let tmx = r#"<map version="1.10" orientation="orthogonal" width="2" height="1" tilewidth="1" tileheight="1">
+ <layer id="1" name="fine" width="2" height="1"><data encoding="csv">0,0</data></layer>
+ <group id="2" name="group">
+  <layer id="3" name="broken" width="2" height="1"><data encoding="base64" compression="zlib">not base64</data></layer>
+ </group>
+</map>"#;
+    let err = Loader::new()
+        .load_tmx_map_from(tmx.as_bytes(), "assets/broken.tmx")
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        tiled::Error::LayerDecodingError { layer_name, err }
+            if layer_name == "broken" && matches!(*err, tiled::Error::Base64DecodingError(_))
+    ));
+}
+
+#[test]
+fn test_common_traits() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64.tmx")
+        .unwrap();
+    let copy = map.clone();
+    assert_eq!(map, copy);
+
+    // Borrowed views compare the data they point to
+    assert_eq!(
+        map.get_layer(0).unwrap().layer_type(),
+        copy.get_layer(0).unwrap().layer_type()
+    );
+    let tileset = &map.tilesets()[0];
+    assert_eq!(
+        tileset.get_tile(TileId::new(1)),
+        copy.tilesets()[0].get_tile(TileId::new(1))
+    );
+    assert_ne!(
+        tileset.get_tile(TileId::new(1)),
+        tileset.get_tile(TileId::new(2))
+    );
+
+    let colors: std::collections::HashSet<Color> = ["#ff0000", "#ff0000", "#00ff00"]
+        .iter()
+        .map(|color| color.parse().unwrap())
+        .collect();
+    assert_eq!(colors.len(), 2);
+}
+
+#[test]
+fn test_iso_coordinates() {
+    let map = MapBuilder::new()
+        .with_orientation(Orientation::Isometric)
+        .with_size(10, 10)
+        .with_tile_size(64, 32)
+        .build()
+        .unwrap();
+
+    let pairs = [
+        ((0, 0), (0, 0)),
+        ((1, 0), (32, 16)),
+        ((0, 1), (-32, 16)),
+        ((3, 5), (-64, 128)),
+        ((-2, 1), (-96, -16)),
+    ];
+    for &((col, row), screen) in &pairs {
+        assert_eq!(map.iso_tile_to_screen(col, row), screen);
+        assert_eq!(map.iso_screen_to_tile(screen.0, screen.1), (col, row));
+    }
+
+    // Points inside the diamond of tile (0, 0), whose top corner is at the origin
+    assert_eq!(map.iso_screen_to_tile(0, 1), (0, 0));
+    assert_eq!(map.iso_screen_to_tile(0, 31), (0, 0));
+    assert_eq!(map.iso_screen_to_tile(31, 16), (0, 0));
+    assert_eq!(map.iso_screen_to_tile(-31, 16), (0, 0));
+    // Just past its edges
+    assert_eq!(map.iso_screen_to_tile(0, 32), (1, 1));
+    assert_eq!(map.iso_screen_to_tile(33, 16), (1, -1));
+    assert_eq!(map.iso_screen_to_tile(-33, 16), (-1, 1));
+    assert_eq!(map.iso_screen_to_tile(0, -1), (-1, -1));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic]
+fn test_iso_coordinates_on_orthogonal_map() {
+    let map = MapBuilder::new().with_size(10, 10).build().unwrap();
+    map.iso_tile_to_screen(0, 0);
+}
+
+#[test]
+fn test_tile_render_size() {
+    let tileset = tiled::parse_tileset_file("assets/tilesheet_grid_size.tsx").unwrap();
+    assert_eq!(tileset.tile_render_size, TileRenderSize::Grid);
+    assert_eq!(tileset.fill_mode, FillMode::PreserveAspectFit);
+
+    let mut tsx = Vec::new();
+    tileset.write_to(&mut tsx).unwrap();
+    let tsx = String::from_utf8(tsx).unwrap();
+    assert!(tsx.contains(r#"tilerendersize="grid""#));
+    assert!(tsx.contains(r#"fillmode="preserve-aspect-fit""#));
+
+    let tileset = tiled::parse_tileset_file("assets/tilesheet.tsx").unwrap();
+    assert_eq!(tileset.tile_render_size, TileRenderSize::Tile);
+    assert_eq!(tileset.fill_mode, FillMode::Stretch);
+
+    let err = parse_small_map(
+        r#"
+ <tileset firstgid="1" name="t" tilewidth="32" tileheight="32" tilecount="1" columns="1" fillmode="zoom">
+  <image source="tiles.png" width="32" height="32"/>
+ </tileset>"#,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("`zoom`"), "{}", err);
+}
+
+#[test]
+fn test_tile_rect() {
+    let mut loader = Loader::new();
+
+    // 30x30 tiles with a margin of 1 and a spacing of 2, in 14 columns
+    let tileset = loader
+        .load_tsx_tileset("assets/tilesheet_margin.tsx")
+        .unwrap();
+    assert_eq!(tileset.columns, 14);
+    let rect = |id| {
+        tileset
+            .tile_rect(TileId::new(id))
+            .map(|rect| (rect.x, rect.y, rect.width, rect.height))
+    };
+    assert_eq!(rect(0), Some((1, 1, 30, 30)));
+    assert_eq!(rect(1), Some((33, 1, 30, 30)));
+    assert_eq!(rect(13), Some((417, 1, 30, 30)));
+    assert_eq!(rect(14), Some((1, 33, 30, 30)));
+    assert_eq!(rect(71), Some((33, 161, 30, 30)));
+    assert_eq!(rect(72), None);
+    assert_eq!(tileset.tile_image(TileId::new(0)), tileset.image.as_ref());
+
+    // Tilesets built in code may not know their columns
+    let mut tileset = tileset.clone();
+    tileset.columns = 0;
+    assert_eq!(
+        tileset
+            .tile_rect(TileId::new(14))
+            .map(|rect| (rect.x, rect.y)),
+        Some((1, 33))
+    );
+    // The margin is on both sides of the image, as when counting its tiles
+    tileset.tilecount = 0;
+    tileset.margin = 17;
+    assert_eq!(tileset.computed_tile_count(), Some(13 * 5));
+    assert_eq!(
+        tileset
+            .tile_rect(TileId::new(13))
+            .map(|rect| (rect.x, rect.y)),
+        Some((17, 49))
+    );
+    // Spacings and margins too large to fit an image's size don't overflow
+    tileset.spacing = u32::MAX;
+    assert_eq!(tileset.computed_tile_count(), Some(1));
+    assert_eq!(
+        tileset
+            .tile_rect(TileId::new(0))
+            .map(|rect| (rect.x, rect.y)),
+        Some((17, 17))
+    );
+    assert_eq!(tileset.tile_rect(TileId::new(1)), None);
+    tileset.margin = u32::MAX;
+    tileset.spacing = 2;
+    assert_eq!(tileset.tile_rect(TileId::new(0)), None);
+
+    let tileset = loader
+        .load_tsx_tileset("assets/tilesheet_collection.tsx")
+        .unwrap();
+    let rect = tileset.tile_rect(TileId::new(3)).unwrap();
+    assert_eq!((rect.x, rect.y, rect.width, rect.height), (0, 0, 32, 16));
+    assert_eq!(
+        tileset.tile_image(TileId::new(3)),
+        tileset.get_tile(TileId::new(3)).unwrap().image.as_ref()
+    );
+    assert_eq!(tileset.tile_rect(TileId::new(1)), None);
+}
+
+#[test]
+fn test_staggered_coordinates() {
+    let mut map = MapBuilder::new()
+        .with_orientation(Orientation::Staggered)
+        .with_size(10, 10)
+        .with_tile_size(64, 32)
+        .build()
+        .unwrap();
+
+    let cases = [
+        (
+            StaggerAxis::Y,
+            StaggerIndex::Odd,
+            [(0, 0), (32, 16), (64, 32), (224, 48)],
+        ),
+        (
+            StaggerAxis::Y,
+            StaggerIndex::Even,
+            [(32, 0), (0, 16), (96, 32), (192, 48)],
+        ),
+        (
+            StaggerAxis::X,
+            StaggerIndex::Odd,
+            [(0, 0), (32, 16), (64, 32), (96, 112)],
+        ),
+        (
+            StaggerAxis::X,
+            StaggerIndex::Even,
+            [(0, 16), (32, 0), (64, 48), (96, 96)],
+        ),
+    ];
+    for &(stagger_axis, stagger_index, screen) in &cases {
+        map.stagger_axis = stagger_axis;
+        map.stagger_index = stagger_index;
+        let tiles = if stagger_axis == StaggerAxis::Y {
+            [(0, 0), (0, 1), (1, 2), (3, 3)]
+        } else {
+            [(0, 0), (1, 0), (2, 1), (3, 3)]
+        };
+        for (&(col, row), &expected) in tiles.iter().zip(&screen) {
+            assert_eq!(map.staggered_tile_to_screen(col, row), expected);
+        }
+
+        // Points near the corners of each diamond map back to its tile
+        for col in -3..4 {
+            for row in -3..4 {
+                let (x, y) = map.staggered_tile_to_screen(col, row);
+                let (cx, cy) = (x + 32, y + 16);
+                for &(dx, dy) in &[(0, 0), (-31, 0), (31, 0), (0, -15), (0, 15)] {
+                    assert_eq!(
+                        map.staggered_screen_to_tile(cx + dx, cy + dy),
+                        (col, row),
+                        "{:?} {:?}",
+                        stagger_axis,
+                        stagger_index
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_tile_pixel_coordinates() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_hexagonal.tmx")
+        .unwrap();
+    assert_eq!(map.hex_side_length, 16);
+
+    // Pointy top hexagons, with odd rows shifted right
+    assert_eq!(map.tile_to_pixel(0, 0), (0.0, 0.0));
+    assert_eq!(map.tile_to_pixel(1, 0), (28.0, 0.0));
+    assert_eq!(map.tile_to_pixel(0, 1), (14.0, 24.0));
+    assert_eq!(map.tile_to_pixel(2, 3), (70.0, 72.0));
+    assert_eq!(map.pixel_to_tile(84.0, 88.0), (2, 3));
+    // The top left corner of a tile's box belongs to the tile above it
+    assert_eq!(map.pixel_to_tile(1.0, 1.0), (-1, -1));
+    assert_eq!(map.pixel_to_tile(27.0, 1.0), (0, -1));
+    assert_eq!(map.pixel_to_tile(14.0, 1.0), (0, 0));
+
+    // The center of every tile maps back to it, in every stagger combination
+    for &stagger_axis in &[StaggerAxis::X, StaggerAxis::Y] {
+        for &stagger_index in &[StaggerIndex::Odd, StaggerIndex::Even] {
+            map.stagger_axis = stagger_axis;
+            map.stagger_index = stagger_index;
+            for col in -3..4 {
+                for row in -3..4 {
+                    let (x, y) = map.tile_to_pixel(col, row);
+                    assert_eq!(
+                        map.pixel_to_tile(x + 14.0, y + 16.0),
+                        (col, row),
+                        "{:?} {:?}",
+                        stagger_axis,
+                        stagger_index
+                    );
+                }
+            }
+        }
+    }
+
+    let map = MapBuilder::new()
+        .with_size(4, 4)
+        .with_tile_size(32, 16)
+        .build()
+        .unwrap();
+    assert_eq!(map.tile_to_pixel(2, 3), (64.0, 48.0));
+    assert_eq!(map.pixel_to_tile(63.5, 48.0), (1, 3));
+    assert_eq!(map.pixel_to_tile(-0.5, 0.0), (-1, 0));
+
+    let map = MapBuilder::new()
+        .with_orientation(Orientation::Isometric)
+        .with_size(4, 4)
+        .with_tile_size(64, 32)
+        .build()
+        .unwrap();
+    // The map's bounding box starts at the left corner of its bottom left tile
+    assert_eq!(map.tile_to_pixel(0, 3), (0.0, 48.0));
+    assert_eq!(map.tile_to_pixel(3, 0), (192.0, 48.0));
+    assert_eq!(map.pixel_to_tile(32.0, 64.0), (0, 3));
+    assert_eq!(map.pixel_to_tile(128.0, 1.0), (0, 0));
+    assert_eq!(map.pixel_to_tile(100.0, 1.0), (-1, 0));
+
+    let map = MapBuilder::new()
+        .with_orientation(Orientation::Staggered)
+        .with_size(4, 4)
+        .with_tile_size(64, 32)
+        .build()
+        .unwrap();
+    assert_eq!(map.tile_to_pixel(2, 1), (160.0, 16.0));
+    assert_eq!(map.pixel_to_tile(192.5, 32.5), (2, 1));
+}
+
+#[test]
+fn test_hex_neighbors() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_hexagonal.tmx")
+        .unwrap();
+    assert_eq!(
+        map.hex_neighbors(1, 2),
+        [(2, 2), (1, 3), (0, 3), (0, 2), (0, 1), (1, 1)]
+    );
+
+    // The neighbors of a tile are the six whose centers are closest to its own
+    for &stagger_axis in &[StaggerAxis::X, StaggerAxis::Y] {
+        for &stagger_index in &[StaggerIndex::Odd, StaggerIndex::Even] {
+            map.stagger_axis = stagger_axis;
+            map.stagger_index = stagger_index;
+            for &(col, row) in &[(0, 0), (1, 0), (0, 1), (1, 1), (-3, 2)] {
+                let (x, y) = map.hex_tile_to_screen(col, row);
+                let mut others: Vec<_> = (col - 3..col + 4)
+                    .flat_map(|other_col| {
+                        (row - 3..row + 4).map(move |other_row| (other_col, other_row))
+                    })
+                    .filter(|&tile| tile != (col, row))
+                    .collect();
+                others.sort_by_key(|&(other_col, other_row)| {
+                    let (other_x, other_y) = map.hex_tile_to_screen(other_col, other_row);
+                    (other_x - x).pow(2) + (other_y - y).pow(2)
+                });
+
+                let mut neighbors = map.hex_neighbors(col, row).to_vec();
+                neighbors.sort_unstable();
+                let mut closest = others[..6].to_vec();
+                closest.sort_unstable();
+                assert_eq!(neighbors, closest, "{:?} {:?}", stagger_axis, stagger_index);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_animation_frame_at() {
+    let tileset = Loader::new()
+        .load_tsx_tileset("assets/tilesheet_tiles.tsx")
+        .unwrap();
+    let tile = tileset.get_tile(TileId::new(1)).unwrap();
+    assert_eq!(tile.animation_frame_at(0), Some(TileId::new(1)));
+    assert_eq!(tile.animation_frame_at(99), Some(TileId::new(1)));
+    assert_eq!(tile.animation_frame_at(100), Some(TileId::new(2)));
+    assert_eq!(tile.animation_frame_at(299), Some(TileId::new(2)));
+    assert_eq!(tile.animation_frame_at(300), Some(TileId::new(1)));
+    assert_eq!(tile.animation_frame_at(u32::MAX), Some(TileId::new(2)));
+    assert_eq!(
+        tileset
+            .get_tile(TileId::new(0))
+            .unwrap()
+            .animation_frame_at(100),
+        None
+    );
+
+    let mut tile = (*tile).clone();
+    tile.animation = Some(vec![
+        Frame {
+            tile_id: TileId::new(3),
+            duration: 0,
+        },
+        Frame {
+            tile_id: TileId::new(4),
+            duration: 10,
+        },
+        Frame {
+            tile_id: TileId::new(5),
+            duration: 0,
+        },
+    ]);
+    assert_eq!(tile.animation_frame_at(0), Some(TileId::new(4)));
+    assert_eq!(tile.animation_frame_at(15), Some(TileId::new(4)));
+    for frame in tile.animation.as_mut().unwrap() {
+        frame.duration = 0;
+    }
+    assert_eq!(tile.animation_frame_at(15), Some(TileId::new(3)));
+    tile.animation = Some(Vec::new());
+    assert_eq!(tile.animation_frame_at(15), None);
+}
+
+#[test]
+fn test_tile_neighbors() {
+    let map = MapBuilder::new()
+        .with_size(10, 10)
+        .with_tile_size(32, 32)
+        .build()
+        .unwrap();
+    assert_eq!(
+        map.tile_neighbors(4, 5),
+        vec![(5, 5), (4, 6), (3, 5), (4, 4)]
+    );
+    assert_eq!(map.tile_neighbors(9, 9), vec![(8, 9), (9, 8)]);
+    assert_eq!(
+        map.tile_neighbors_including_diagonals(4, 5),
+        vec![
+            (5, 5),
+            (5, 6),
+            (4, 6),
+            (3, 6),
+            (3, 5),
+            (3, 4),
+            (4, 4),
+            (5, 4)
+        ]
+    );
+    assert_eq!(
+        map.tile_neighbors_including_diagonals(0, 0),
+        vec![(1, 0), (1, 1), (0, 1)]
+    );
+
+    let map = MapBuilder::new()
+        .with_size(10, 10)
+        .with_tile_size(32, 32)
+        .with_infinite(true)
+        .build()
+        .unwrap();
+    assert_eq!(
+        map.tile_neighbors(0, 0),
+        vec![(1, 0), (0, 1), (-1, 0), (0, -1)]
+    );
+
+    let mut map = MapBuilder::new()
+        .with_orientation(Orientation::Staggered)
+        .with_size(10, 10)
+        .with_tile_size(64, 32)
+        .build()
+        .unwrap();
+    assert_eq!(
+        map.tile_neighbors(2, 3),
+        vec![(3, 4), (2, 4), (2, 2), (3, 2)]
+    );
+    assert_eq!(
+        map.tile_neighbors_including_diagonals(2, 3),
+        vec![
+            (3, 4),
+            (2, 5),
+            (2, 4),
+            (1, 3),
+            (2, 2),
+            (2, 1),
+            (3, 2),
+            (3, 3)
+        ]
+    );
+    map.stagger_axis = StaggerAxis::X;
+    assert_eq!(
+        map.tile_neighbors_including_diagonals(3, 2),
+        vec![
+            (4, 2),
+            (5, 2),
+            (4, 3),
+            (3, 3),
+            (2, 3),
+            (1, 2),
+            (2, 2),
+            (3, 1)
+        ]
+    );
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_hexagonal.tmx")
+        .unwrap();
+    assert_eq!(map.tile_neighbors(0, 0), vec![(1, 0), (0, 1)]);
+    assert_eq!(map.tile_neighbors(1, 1).len(), 6);
+    assert_eq!(
+        map.tile_neighbors_including_diagonals(1, 1),
+        map.tile_neighbors(1, 1)
+    );
+}
+
+#[test]
+fn test_object_lookups() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_names.tmx")
+        .unwrap();
+    let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+
+    assert_eq!(layer.get_object_by_name("player_spawn").unwrap().id(), 1);
+    // Names may repeat; the first object in draw order wins, which is the topmost one here
+    assert_eq!(layer.get_object_by_name("enemy").unwrap().id(), 3);
+    let enemies: Vec<_> = layer
+        .get_objects_by_name("enemy")
+        .map(|object| object.id())
+        .collect();
+    assert_eq!(enemies, [3, 2]);
+    assert!(layer.get_object_by_name("tree").is_none());
+    assert_eq!(layer.get_objects_by_name("tree").count(), 0);
+
+    assert_eq!(layer.get_object_by_id(4).unwrap().name, "sign");
+    assert!(layer.get_object_by_id(5).is_none());
+
+    let npcs: Vec<_> = layer
+        .get_objects_by_type("npc")
+        .map(|object| object.id())
+        .collect();
+    assert_eq!(npcs, [3, 2]);
+    assert_eq!(layer.get_objects_by_type("spawn").count(), 1);
+
+    // Layers drawn in index order keep the order objects were declared in
+    let tmx = std::fs::read_to_string("assets/tiled_object_names.tmx")
+        .unwrap()
+        .replace(r#"name="Entities""#, r#"name="Entities" draworder="index""#);
+    let map = Loader::new()
+        .load_tmx_map_from(tmx.as_bytes(), "assets/tiled_object_names.tmx")
+        .unwrap();
+    let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    assert_eq!(layer.get_object_by_name("enemy").unwrap().id(), 2);
+    let enemies: Vec<_> = layer
+        .get_objects_by_name("enemy")
+        .map(|object| object.id())
+        .collect();
+    assert_eq!(enemies, [2, 3]);
+    let npcs: Vec<_> = layer
+        .get_objects_by_type("npc")
+        .map(|object| object.id())
+        .collect();
+    assert_eq!(npcs, [2, 3]);
+}
+
+#[test]
+fn test_layer_set_tile() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_base64.tmx")
+        .unwrap();
+    let mut layer = map.get_layer_mut(0).unwrap();
+    layer.set_tile(3, 4, Some(LayerTileData::new(0, TileId::new(20))));
+    layer.set_tile_gid(5, 6, 0x8000_0000 | 31);
+    layer.set_tile(0, 0, None);
+    layer.name = "Edited".to_owned();
+
+    assert_eq!(map.get_layer(0).unwrap().name, "Edited");
+    let layer = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+    assert_eq!(layer.get_tile(3, 4).unwrap().id(), TileId::new(20));
+    let flipped = layer.get_tile(5, 6).unwrap();
+    assert_eq!(
+        (flipped.id(), flipped.flip),
+        (TileId::new(30), FlipFlags::HORIZONTAL)
+    );
+    assert!(layer.get_tile(0, 0).is_none());
+
+    // Writing the map back keeps the changes
+    let mut tmx = Vec::new();
+    map.write_to(&mut tmx).unwrap();
+    let written = tiled::parse_bytes(&tmx).unwrap();
+    let layer = as_finite(written.get_layer(0).unwrap().as_tile_layer().unwrap());
+    assert_eq!(layer.get_tile(3, 4).unwrap().id(), TileId::new(20));
+
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    let mut layer = map.get_layer_mut(0).unwrap();
+    layer.set_tile_gid(-1000, 500, 86);
+    // Emptying a tile in a chunk that doesn't exist doesn't create it
+    layer.set_tile(2000, 2000, None);
+
+    let layer = match map.get_layer(0).unwrap().as_tile_layer().unwrap() {
+        TileLayer::Infinite(layer) => layer,
+        TileLayer::Finite(_) => panic!("Not an infinite tile layer"),
+    };
+    let tile = layer.get_tile(-1000, 500).unwrap();
+    assert_eq!((tile.tileset_index(), tile.id()), (1, TileId::new(1)));
+    let chunk_pos = ChunkData::tile_to_chunk_pos(-1000, 500);
+    assert!(layer.get_chunk(chunk_pos.0, chunk_pos.1).is_some());
+    let chunk_pos = ChunkData::tile_to_chunk_pos(2000, 2000);
+    assert!(layer.get_chunk(chunk_pos.0, chunk_pos.1).is_none());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic]
+fn test_layer_set_tile_out_of_bounds() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_base64.tmx")
+        .unwrap();
+    map.get_layer_mut(0).unwrap().set_tile_gid(100, 0, 1);
+}
+
+#[test]
+fn test_map_get_object_by_id() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_names.tmx")
+        .unwrap();
+    let unindexed = map.clone();
+
+    // The tileset's collision shapes reuse IDs 1 to 4, but aren't map objects
+    let (layer, object) = map.get_object_by_id(1).unwrap();
+    assert_eq!(
+        (layer.name.as_str(), object.name.as_str()),
+        ("Entities", "player_spawn")
+    );
+    let (layer, object) = map.get_object_by_id(5).unwrap();
+    assert_eq!(
+        (layer.name.as_str(), object.name.as_str()),
+        ("Decorations", "tree")
+    );
+    let (layer, object) = map.get_object_by_id(6).unwrap();
+    assert_eq!(
+        (layer.name.as_str(), object.name.as_str()),
+        ("Spawns", "enemy_spawn")
+    );
+    assert!(map.get_object_by_id(7).is_none());
+
+    // Building the index doesn't change the map
+    assert_eq!(map, unindexed);
+
+    // Objects are found again after the layers they are in are replaced
+    let mut map = map;
+    let spawn = (*map.get_object_by_id(1).unwrap().1).clone();
+    *map.get_layer_mut(0).unwrap() = ObjectGroupBuilder::new()
+        .with_name("Replaced")
+        .with_next_object_id(50)
+        .add_object(spawn)
+        .build();
+    assert!(map.get_object_by_id(1).is_none());
+    let (layer, object) = map.get_object_by_id(50).unwrap();
+    assert_eq!(
+        (layer.name.as_str(), object.id(), object.name.as_str()),
+        ("Replaced", 50, "player_spawn")
+    );
+    let (layer, _) = map.get_object_by_id(5).unwrap();
+    assert_eq!(layer.name, "Decorations");
+}
+
+#[test]
+fn test_layers_of_type() {
+    let map = parse_small_map(
+        r#"
+ <layer id="1" name="ground" width="1" height="1">
+  <data encoding="csv">0</data>
+ </layer>
+ <layer id="2" name="hidden" width="1" height="1" visible="0">
+  <data encoding="csv">0</data>
+ </layer>
+ <group id="3" name="shown">
+  <objectgroup id="4" name="objects"/>
+  <layer id="5" name="decor" width="1" height="1">
+   <data encoding="csv">0</data>
+  </layer>
+ </group>
+ <group id="6" name="hidden group" visible="0">
+  <imagelayer id="7" name="sky"/>
+ </group>"#,
+    )
+    .unwrap();
+
+    let kinds: Vec<_> = map
+        .layers_of_type(LayerKind::Tiles)
+        .map(|layer| layer.kind())
+        .collect();
+    assert_eq!(kinds, [LayerKind::Tiles; 3]);
+    assert_eq!(map.layers_of_type(LayerKind::Group).count(), 2);
+    assert_eq!(map.layers_of_type(LayerKind::Objects).count(), 1);
+    assert!(matches!(
+        map.layers_of_type(LayerKind::Image).next(),
+        Some(LayerType::Image(_))
+    ));
+
+    // Layers in hidden groups are hidden too
+    let visible: Vec<_> = map
+        .visible_layers()
+        .map(|layer| layer.name.clone())
+        .collect();
+    assert_eq!(visible, ["ground", "shown", "objects", "decor"]);
+}
+
+#[test]
+fn test_map_find_objects() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_names.tmx")
+        .unwrap();
+
+    let ids = |objects: &mut dyn Iterator<Item = tiled::Object>| -> Vec<u32> {
+        objects.map(|object| object.id()).collect()
+    };
+    // Objects in group layers are found too, and tile collision shapes aren't
+    assert_eq!(ids(&mut map.find_objects_by_type("spawn")), [1, 6]);
+    assert_eq!(ids(&mut map.find_objects_by_type("prop")), [5]);
+    assert_eq!(ids(&mut map.find_objects_by_type("")), [4]);
+    assert_eq!(map.find_objects_by_type("hitbox").count(), 0);
+    assert_eq!(ids(&mut map.find_objects_by_name("enemy")), [2, 3]);
+    assert_eq!(ids(&mut map.find_objects_by_name("tree")), [5]);
+    assert_eq!(map.find_objects_by_name("door").count(), 0);
+}
+
+#[test]
+fn test_malformed_xml() {
+    let head = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="8" tileheight="8">"#;
+
+    // Mismatched tags, truncated files and invalid UTF-8 are all reported by the XML parser
+    let mismatched = tiled::parse_str(&format!("{}<properties></map>", head));
+    assert!(matches!(mismatched, Err(tiled::Error::XmlDecodingError(_))));
+    let truncated = tiled::parse_str(head);
+    assert!(matches!(truncated, Err(tiled::Error::XmlDecodingError(_))));
+    // The error says where the file stopped making sense, even if it ends within a tag
+    let truncated_tag = tiled::parse_str(&format!("{}\n<layer id=\"1\" na", head));
+    match truncated_tag {
+        Err(tiled::Error::XmlDecodingError(err)) => {
+            assert_eq!(xml::common::Position::position(&err).row, 2)
+        }
+        other => panic!("Expected an XML error, got {:?}", other),
+    }
+    let mut bytes = head.as_bytes().to_vec();
+    bytes
+        .extend_from_slice(b"<properties><property name=\"\xff\" value=\"1\"/></properties></map>");
+    let invalid_utf8 = tiled::parse_bytes(&bytes);
+    assert!(matches!(
+        invalid_utf8,
+        Err(tiled::Error::XmlDecodingError(_))
+    ));
+}
+
+#[test]
+fn test_text_encodings() {
+    let path = "assets/tiled_base64_external.tmx";
+    let expected = Loader::new().load_tmx_map(path).unwrap();
+    let text = std::fs::read_to_string(path)
+        .unwrap()
+        .replace(r#"encoding="UTF-8""#, r#"encoding="UTF-16""#);
+    let utf16 = |to_bytes: fn(u16) -> [u8; 2]| -> Vec<u8> {
+        text.encode_utf16().flat_map(to_bytes).collect()
+    };
+
+    let mut utf8_bom = b"\xEF\xBB\xBF".to_vec();
+    utf8_bom.extend_from_slice(std::fs::read(path).unwrap().as_slice());
+    let mut utf16_le_bom = vec![0xFF, 0xFE];
+    utf16_le_bom.extend(utf16(u16::to_le_bytes));
+    let mut utf16_be_bom = vec![0xFE, 0xFF];
+    utf16_be_bom.extend(utf16(u16::to_be_bytes));
+    // Files saved as UTF-16 without a byte order mark work too
+    for source in [
+        utf8_bom,
+        utf16_le_bom,
+        utf16_be_bom,
+        utf16(u16::to_le_bytes),
+        utf16(u16::to_be_bytes),
+    ] {
+        let map = Loader::new().load_tmx_map_from(&source[..], path).unwrap();
+        assert_eq!(map, expected);
+    }
+
+    // The byte order mark is found even when reading a byte at a time
+    struct ByteByByte<'a>(&'a [u8]);
+    impl std::io::Read for ByteByByte<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = self.0.len().min(buf.len()).min(1);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+    let source = utf16(u16::to_le_bytes);
+    let map = Loader::new()
+        .load_tmx_map_from(ByteByByte(&source), path)
+        .unwrap();
+    assert_eq!(map, expected);
+
+    // And when loading files
+    let dir = std::env::temp_dir().join(format!("tiled-text-encodings-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy("assets/tilesheet.tsx", dir.join("tilesheet.tsx")).unwrap();
+    std::fs::write(dir.join("utf16.tmx"), utf16(u16::to_be_bytes)).unwrap();
+    let map = Loader::new().load_tmx_map(dir.join("utf16.tmx"));
+    std::fs::remove_dir_all(&dir).unwrap();
+    let map = map.unwrap();
+    let tiles = |map: &Map| {
+        as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap())
+            .tiles_flat()
+            .to_vec()
+    };
+    assert_eq!(tiles(&map), tiles(&expected));
+
+    #[cfg(feature = "json")]
+    {
+        let mut tsj = b"\xEF\xBB\xBF".to_vec();
+        tsj.extend_from_slice(&std::fs::read("assets/tilesheet_tiles.tsj").unwrap());
+        for path in ["assets/tilesheet_tiles.tsj", "assets/tilesheet_tiles.dat"] {
+            let tileset = tiled::parse_tileset_reader(&tsj[..], path).unwrap();
+            assert_eq!(tileset.name, "tilesheet_tiles");
+        }
+    }
+}
+
+#[test]
+fn test_layer_effective_color() {
+    let base = Color {
+        alpha: 200,
+        red: 10,
+        green: 20,
+        blue: 30,
+    };
+    let mut layer = tiled::LayerData::default();
+    assert_eq!(layer.effective_color(base), base);
+    layer.opacity = 0.5;
+    assert_eq!(layer.effective_color(base), Color { alpha: 100, ..base });
+    layer.opacity = 1.5;
+    assert_eq!(layer.effective_color(base).alpha, 200);
+    layer.opacity = -1.0;
+    assert_eq!(layer.effective_color(base).alpha, 0);
+
+    let map = MapBuilder::new()
+        .with_size(1, 1)
+        .with_tile_size(16, 16)
+        .with_layer(layer)
+        .build()
+        .unwrap();
+    assert_eq!(map.get_layer(0).unwrap().effective_color(base).alpha, 0);
+}
+
+#[test]
+fn test_tileset_sources() {
+    use tiled::TilesetSource;
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    let tilesets: Vec<_> = map.map_tilesets().collect();
+    assert_eq!(tilesets.len(), map.tilesets().len());
+    for (index, tileset) in tilesets.iter().enumerate() {
+        assert!(std::sync::Arc::ptr_eq(
+            tileset.tileset,
+            &map.tilesets()[index]
+        ));
+        assert_eq!(
+            map.get_tileset_by_first_gid(tileset.first_gid),
+            Some(&**tileset.tileset)
+        );
+        assert_eq!(map.tileset_source(index), Some(tileset.source));
+    }
+    assert_eq!(tilesets[0].source, &TilesetSource::Embedded);
+    assert_eq!(
+        tilesets[1].source,
+        &TilesetSource::External(PathBuf::from("assets/tilesheet.tsx"))
+    );
+    assert_eq!(tilesets[1].first_gid, Gid(85));
+    assert_eq!(map.tileset_source(2), None);
+
+    let built = MapBuilder::new()
+        .with_size(1, 1)
+        .with_tile_size(32, 32)
+        .with_tileset(tilesets[1].tileset.clone())
+        .build()
+        .unwrap();
+    assert_eq!(built.tileset_source(0), Some(&TilesetSource::Embedded));
+}
+
+#[test]
+fn test_background_color_or_transparent() {
+    let mut map = Map::default();
+    assert_eq!(
+        map.background_color_or_transparent(),
+        Color {
+            alpha: 0,
+            red: 0,
+            green: 0,
+            blue: 0,
+        }
+    );
+    let color = Color {
+        alpha: 128,
+        red: 1,
+        green: 2,
+        blue: 3,
+    };
+    map.background_color = Some(color);
+    assert_eq!(map.background_color_or_transparent(), color);
+}
+
+#[test]
+fn test_object_layer_properties() {
+    let property =
+        r#"<properties><property name="collision_layer" type="int" value="2"/></properties>"#;
+    let object = r#"<object id="1" x="0" y="0" width="8" height="8"/>"#;
+    for contents in [
+        format!("{property}{object}{object}"),
+        format!("{object}{property}{object}"),
+        format!("{object}{object}{property}"),
+    ] {
+        let map = parse_small_map(&format!(
+            r#"
+                <objectgroup id="1" name="Objects">{contents}</objectgroup>
+                <imagelayer id="2" name="Image">{property}</imagelayer>
+                <group id="3" name="Group">{property}<objectgroup id="4" name="Inner"/></group>"#
+        ))
+        .unwrap();
+        for layer in map.layers() {
+            assert_eq!(
+                layer.properties.get("collision_layer"),
+                Some(&PropertyValue::IntValue(2)),
+                "{}",
+                layer.name
+            );
+        }
+        assert_eq!(
+            map.get_layer(0)
+                .unwrap()
+                .as_object_layer()
+                .unwrap()
+                .objects()
+                .len(),
+            2
+        );
+    }
+}
+
+#[test]
+fn test_invalid_csv_tile() {
+    let map = r#"<map version="1.10" orientation="orthogonal" width="2" height="1" tilewidth="8" tileheight="8">
+        <layer id="1" name="Tiles" width="2" height="1">
+            <data encoding="csv">1,x</data>
+        </layer>
+    </map>"#;
+    let err = tiled::parse_str(map).unwrap_err();
+    assert!(
+        matches!(
+            &err,
+            tiled::Error::LayerDecodingError { err, .. }
+                if matches!(**err, tiled::Error::InvalidTileFound)
+        ),
+        "{:?}",
+        err
+    );
+}
+
+#[test]
+fn test_trailing_tile_data() {
+    // Both decode to 5 bytes: one tile and a byte left over.
+    for (compression, data) in [
+        ("", "AQAAAAI="),
+        (r#"compression="zlib""#, "eJxjZGBgYAIAAAwABA=="),
+    ] {
+        let map = format!(
+            r#"<map version="1.10" orientation="orthogonal" width="2" height="1" tilewidth="8" tileheight="8">
+                <layer id="1" name="Tiles" width="2" height="1">
+                    <data encoding="base64" {compression}>{data}</data>
+                </layer>
+            </map>"#
+        );
+        let err = tiled::parse_str(&map).unwrap_err();
+        assert!(
+            matches!(
+                &err,
+                tiled::Error::LayerDecodingError { err, .. }
+                    if matches!(**err, tiled::Error::InvalidTileDataLength { length: 5 })
+            ),
+            "{:?}",
+            err
+        );
+
+        let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("test_trailing_tile_data");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("map.tmx");
+        std::fs::write(&path, &map).unwrap();
+        let mut streamed = Vec::new();
+        let err = Loader::new()
+            .load_tmx_map_streaming(&path, |tile| streamed.push(tile.gid))
+            .unwrap_err();
+        assert!(
+            matches!(err, tiled::Error::InvalidTileDataLength { length: 5 }),
+            "{:?}",
+            err
+        );
+        assert_eq!(streamed, [1]);
+    }
+}
+
+#[test]
+fn test_streaming_tiles_outside_layer() {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("test_streaming_tiles_outside_layer");
+    std::fs::create_dir_all(&dir).unwrap();
+    for (width, data, expected) in [(1, "1,1,1", vec![1]), (0, "1", vec![])] {
+        let map = format!(
+            r#"<map version="1.10" orientation="orthogonal" width="{width}" height="1" tilewidth="8" tileheight="8">
+                <layer id="1" name="Tiles" width="{width}" height="1">
+                    <data encoding="csv">{data}</data>
+                </layer>
+            </map>"#
+        );
+        let path = dir.join("map.tmx");
+        std::fs::write(&path, &map).unwrap();
+        let mut streamed = Vec::new();
+        let err = Loader::new()
+            .load_tmx_map_streaming(&path, |tile| streamed.push(tile.gid))
+            .unwrap_err();
+        assert!(
+            matches!(err, tiled::Error::InvalidTileDataLength { .. }),
+            "{:?}",
+            err
+        );
+        assert_eq!(streamed, expected);
+    }
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_deprecated_colour_accessors() {
+    let tileset = Loader::new()
+        .load_tsx_tileset("assets/tilesheet_tiles.tsx")
+        .unwrap();
+    let image = tileset.image.as_ref().unwrap();
+    assert!(image.transparent_color.is_some());
+    assert_eq!(image.transparent_colour(), image.transparent_color);
+
+    let mut layer = tiled::ObjectLayerData::default();
+    layer.color = Some(Color {
+        alpha: 255,
+        red: 1,
+        green: 2,
+        blue: 3,
+    });
+    assert_eq!(layer.colour(), layer.color);
+}
+
+#[test]
+fn test_nested_class_properties() {
+    let map = parse_small_map(
+        r#"
+            <objectgroup id="1" name="Objects">
+                <object id="1" x="0" y="0">
+                    <properties>
+                        <property name="config" type="class" propertytype="EnemyConfig">
+                            <properties>
+                                <property name="speed" type="float" value="2.5"/>
+                                <property name="drops" type="class" propertytype="Loot">
+                                    <properties>
+                                        <property name="gold" type="int" value="10"/>
+                                    </properties>
+                                </property>
+                            </properties>
+                        </property>
+                    </properties>
+                </object>
+            </objectgroup>"#,
+    )
+    .unwrap();
+    let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    let object = layer.get_object(0).unwrap();
+    assert_eq!(
+        object.properties["config"],
+        PropertyValue::ClassValue {
+            property_type: "EnemyConfig".to_owned(),
+            properties: Properties::from([
+                ("speed".to_owned(), PropertyValue::FloatValue(2.5)),
+                (
+                    "drops".to_owned(),
+                    PropertyValue::ClassValue {
+                        property_type: "Loot".to_owned(),
+                        properties: Properties::from([(
+                            "gold".to_owned(),
+                            PropertyValue::IntValue(10)
+                        )]),
+                    }
+                ),
+            ]),
+        }
+    );
+}
+
+#[test]
+fn test_empty_tile_data() {
+    let map = tiled::parse_str(
+        r#"<map version="1.10" orientation="orthogonal" width="3" height="2" tilewidth="32" tileheight="32">
+            <tileset firstgid="1" name="tiles" tilewidth="32" tileheight="32" tilecount="4" columns="2"/>
+            <layer id="1" name="Empty CSV" width="3" height="2"><data encoding="csv"></data></layer>
+            <layer id="2" name="Empty base64" width="3" height="2"><data encoding="base64"> </data></layer>
+            <layer id="3" name="Populated" width="3" height="2"><data encoding="csv">1,0,0,0,0,2</data></layer>
+        </map>"#,
+    )
+    .unwrap();
+    for layer in map.layers().take(2) {
+        let tiles = as_finite(layer.as_tile_layer().unwrap());
+        assert_eq!(tiles.tiles_flat().len(), 6, "{}", layer.name);
+        assert!(tiles.tiles_flat().iter().all(Option::is_none));
+        assert_eq!(tiles.rows().count(), 2);
+        assert!(tiles.get_tile(2, 1).is_none());
+        assert!(layer.is_empty());
+    }
+    let populated = as_finite(map.get_layer(2).unwrap().as_tile_layer().unwrap());
+    assert_eq!(populated.tiles_flat().len(), 6);
+    assert!(populated.tiles_flat()[0].is_some());
+}
+
+#[test]
+fn test_tilesets_after_layers() {
+    let r = Loader::new()
+        .load_tmx_map("assets/tiled_tilesets_last.tmx")
+        .unwrap();
+    let e = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    assert_eq!(r.tilesets(), e.tilesets());
+    assert_eq!(
+        r.properties["order"],
+        PropertyValue::StringValue("tilesets last".to_owned())
+    );
+    let tiles = as_finite(r.get_layer(0).unwrap().as_tile_layer().unwrap());
+    let expected = as_finite(e.get_layer(0).unwrap().as_tile_layer().unwrap());
+    assert_eq!(tiles.tiles_flat(), expected.tiles_flat());
+    assert!(tiles.tiles_flat().iter().any(Option::is_some));
+
+    let objects = r.get_layer(1).unwrap().as_object_layer().unwrap();
+    let tile = objects.get_object_by_id(5).unwrap().get_tile().unwrap();
+    assert_eq!(tile.id(), TileId::new(1));
+    assert_eq!(tile.get_tileset().name, "tilesheet");
+}
+
+#[test]
+fn test_object_types() {
+    let classes = Loader::new()
+        .load_object_types("assets/objecttypes.xml")
+        .unwrap();
+    assert_eq!(
+        classes.get("Enemy"),
+        Some(&Properties::from([
+            ("health".to_owned(), PropertyValue::IntValue(100)),
+            ("speed".to_owned(), PropertyValue::FloatValue(1.5)),
+            ("hostile".to_owned(), PropertyValue::BoolValue(true)),
+            (
+                "name".to_owned(),
+                PropertyValue::StringValue("goblin".to_owned())
+            ),
+        ]))
+    );
+    assert_eq!(
+        classes.get("Chest").unwrap()["item"],
+        PropertyValue::StringValue("key".to_owned())
+    );
+    assert_eq!(classes.get("Marker"), Some(&Properties::new()));
+
+    let instance = Properties::from([("health".to_owned(), PropertyValue::IntValue(5))]);
+    let resolved = classes.resolve("Enemy", &instance);
+    assert_eq!(resolved["health"], PropertyValue::IntValue(5));
+    assert_eq!(resolved["speed"], PropertyValue::FloatValue(1.5));
+
+    let source = include_bytes!("../assets/objecttypes.xml");
+    assert_eq!(tiled::parse_object_types(&source[..]).unwrap(), classes);
+    assert!(matches!(
+        tiled::parse_object_types(&br#"<objecttypes><objecttype name="A"><property name="x" type="int" default="many"/></objecttype></objecttypes>"#[..]),
+        Err(tiled::Error::InvalidPropertyValue { .. })
+    ));
+    assert!(tiled::parse_object_types(&b"<map/>"[..]).is_err());
+}
+
+#[cfg(not(feature = "json"))]
+#[test]
+fn test_json_tileset_unsupported() {
+    let err = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external_tsj.tmx")
+        .unwrap_err();
+    assert!(
+        matches!(err, tiled::Error::UnsupportedFeature { .. }),
+        "{:?}",
+        err
+    );
+}
+
+#[cfg(not(feature = "zstd"))]
+#[test]
+fn test_zstd_unsupported() {
+    let err = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zstandard.tmx")
+        .unwrap_err();
+    assert!(
+        matches!(
+            &err,
+            tiled::Error::LayerDecodingError { err, .. }
+                if matches!(**err, tiled::Error::UnsupportedFeature { .. })
+        ),
+        "{:?}",
+        err
+    );
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_load_tmx_map_async() {
+    use std::{
+        future::Future,
+        path::Path,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use tiled::{AsyncResourceReader, DefaultResourceCache};
+
+    /// Reads files from the filesystem, remembering which ones were read.
+    struct RecordingReader(Vec<PathBuf>);
+
+    impl AsyncResourceReader for RecordingReader {
+        type Error = std::io::Error;
+
+        async fn read_from(&mut self, path: &Path) -> std::io::Result<Vec<u8>> {
+            self.0.push(path.to_owned());
+            std::fs::read(path)
+        }
+    }
+
+    // The reader never waits, so polling the future once is enough.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        match pin!(future).poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("the reader never waits"),
+        }
+    }
+
+    let mut loader =
+        Loader::with_cache_and_reader(DefaultResourceCache::new(), RecordingReader(Vec::new()));
+    fn assert_send<T: Send>(value: T) -> T {
+        value
+    }
+
+    // Loading can be spawned as a task on multithreaded runtimes.
+    let future = assert_send(loader.load_tmx_map_async("assets/tiled_object_template.tmx"));
+    let map = block_on(future).unwrap();
+    let expected = Loader::new()
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    assert_eq!(map, expected);
+
+    let mut reads = loader.reader().0.clone();
+    reads.sort();
+    assert_eq!(
+        reads,
+        [
+            "assets/tiled_object_template.tmx",
+            "assets/tiled_object_template.tx",
+            "assets/tilesheet.tsx",
+            "assets/tilesheet_template.tsx",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect::<Vec<_>>()
+    );
+
+    // Cached tilesets and templates aren't fetched again.
+    loader.reader_mut().0.clear();
+    block_on(loader.load_tmx_map_async("assets/tiled_object_template.tmx")).unwrap();
+    assert_eq!(
+        loader.reader().0,
+        [PathBuf::from("assets/tiled_object_template.tmx")]
+    );
+
+    let err = block_on(loader.load_tmx_map_async("assets/missing.tmx")).unwrap_err();
+    assert!(matches!(
+        err,
+        tiled::Error::ResourceLoadingError { path, .. } if path == Path::new("assets/missing.tmx")
+    ));
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_tokio_resource_reader() {
+    use tiled::TokioResourceReader;
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let mut loader = Loader::new().with_reader(TokioResourceReader);
+    let map = runtime
+        .block_on(loader.load_tmx_map_async("assets/tiled_object_template.tmx"))
+        .unwrap();
+    let expected = Loader::new()
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    assert_eq!(map, expected);
+
+    let err = runtime
+        .block_on(loader.load_tmx_map_async("assets/missing.tmx"))
+        .unwrap_err();
+    assert!(matches!(err, tiled::Error::ResourceLoadingError { .. }));
+}
+
+#[test]
+fn test_computed_tile_count() {
+    let tsx = std::fs::read_to_string("assets/tilesheet.tsx")
+        .unwrap()
+        .replace(r#" tilecount="84""#, "");
+    let tileset = Loader::new()
+        .load_tsx_tileset_from(tsx.as_bytes(), "assets/tilesheet.tsx")
+        .unwrap();
+    assert_eq!(tileset.tilecount, 0);
+    assert_eq!(tileset.computed_tile_count(), Some(84));
+    assert!(tileset.get_tile(TileId::new(83)).is_some());
+    assert!(tileset.get_tile(TileId::new(84)).is_none());
+    assert!(tileset.tile_image(TileId::new(83)).is_some());
+
+    // The margin is on both sides of the 448x192 image.
+    let mut tileset = tileset;
+    tileset.tile_width = 30;
+    tileset.tile_height = 30;
+    tileset.spacing = 2;
+    tileset.margin = 17;
+    assert_eq!(tileset.computed_tile_count(), Some(13 * 5));
+
+    let tsx = r#"<tileset name="collection" tilewidth="32" tileheight="32" columns="0">
+ <tile id="0"><image width="32" height="32" source="tilesheet.png"/></tile>
+</tileset>"#;
+    let tileset = Loader::new()
+        .load_tsx_tileset_from(tsx.as_bytes(), "assets/collection.tsx")
+        .unwrap();
+    assert_eq!(tileset.computed_tile_count(), None);
+    assert!(tileset.get_tile(TileId::new(0)).is_some());
+
+    // GIDs are assigned after the computed count when building maps from such tilesets.
+    let tsx = std::fs::read_to_string("assets/tilesheet.tsx")
+        .unwrap()
+        .replace(r#" tilecount="84""#, "");
+    let tilesets: Vec<_> = (0..2)
+        .map(|_| {
+            std::sync::Arc::new(
+                Loader::new()
+                    .load_tsx_tileset_from(tsx.as_bytes(), "assets/tilesheet.tsx")
+                    .unwrap(),
+            )
+        })
+        .collect();
+    let layer = LayerBuilder::new()
+        .with_size(2, 1)
+        .set_tile(0, 0, 84)
+        .set_tile(1, 0, 85)
+        .build(&tilesets);
+    let map = MapBuilder::new()
+        .with_size(2, 1)
+        .with_tile_size(32, 32)
+        .with_tileset(tilesets[0].clone())
+        .with_tileset(tilesets[1].clone())
+        .with_layer(layer)
+        .build()
+        .unwrap();
+    let tiles = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+    let ids = |x| {
+        let tile = tiles.get_tile(x, 0).unwrap();
+        (tile.tileset_index(), tile.id())
+    };
+    assert_eq!(
+        (ids(0), ids(1)),
+        ((0, TileId::new(83)), (1, TileId::new(0)))
+    );
+    assert_eq!(map.validate(), vec![]);
+}
+
+#[test]
+fn test_memory_resource_reader() {
+    use tiled::MemoryResourceReader;
+
+    let reader = MemoryResourceReader::new()
+        .with_resource(
+            "./level.tmx",
+            include_bytes!("../assets/tiled_object_template.tmx"),
+        )
+        .with_resource(
+            "tiled_object_template.tx",
+            include_bytes!("../assets/tiled_object_template.tx"),
+        )
+        .with_resource("tilesheet.tsx", include_bytes!("../assets/tilesheet.tsx"))
+        .with_resource(
+            "./tilesheet_template.tsx",
+            include_bytes!("../assets/tilesheet_template.tsx"),
+        );
+    let mut loader = Loader::new().with_reader(reader);
+    let map = loader.load_tmx_map("level.tmx").unwrap();
+    assert_eq!(map.tilesets()[0].name, "tilesheet");
+    assert!(loader
+        .cache()
+        .get_template("tiled_object_template.tx")
+        .is_some());
+
+    // References going up a directory resolve to the resources registered there.
+    let nested = std::fs::read_to_string("assets/tiled_base64_external.tmx")
+        .unwrap()
+        .replace(r#"source="tilesheet.tsx""#, r#"source="../tilesheet.tsx""#);
+    loader.reader_mut().insert(
+        "maps/level.tmx",
+        Box::leak(nested.into_bytes().into_boxed_slice()),
+    );
+    let map = loader.load_tmx_map("maps/level.tmx").unwrap();
+    assert_eq!(map.tilesets()[0].name, "tilesheet");
+
+    // References that only differ in their `.` components share the cached tileset.
+    let current_dir = std::fs::read_to_string("assets/tiled_base64_external.tmx")
+        .unwrap()
+        .replace(r#"source="tilesheet.tsx""#, r#"source="./tilesheet.tsx""#);
+    loader.reader_mut().insert(
+        "current_dir.tmx",
+        Box::leak(current_dir.into_bytes().into_boxed_slice()),
+    );
+    let first = loader.load_tmx_map("level.tmx").unwrap();
+    let second = loader.load_tmx_map("./current_dir.tmx").unwrap();
+    assert!(std::sync::Arc::ptr_eq(
+        &first.tilesets()[0],
+        &second.tilesets()[0]
+    ));
+    assert!(loader.cache().get_tileset("./tilesheet.tsx").is_none());
+
+    let err = loader.load_tmx_map("missing.tmx").unwrap_err();
+    assert!(matches!(err, tiled::Error::ResourceLoadingError { .. }));
+}
+
+#[test]
+fn test_resize_layers() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_base64.tmx")
+        .unwrap();
+    let original = map.clone();
+    let original_layer = as_finite(original.get_layer(0).unwrap().as_tile_layer().unwrap());
+    let tile_at = |map: &Map, x: i32, y: i32| {
+        as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap())
+            .get_tile_data(x, y)
+            .copied()
+    };
+
+    map.get_layer_mut(0).unwrap().resize(7, 5, 0);
+    let layer = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+    assert_eq!((layer.width(), layer.height()), (7, 5));
+    for y in 0..5 {
+        for x in 0..7 {
+            assert_eq!(
+                layer.get_tile_data(x, y),
+                original_layer.get_tile_data(x, y)
+            );
+        }
+    }
+    // Only the layer is resized, not the map.
+    assert_eq!((map.width, map.height), (100, 100));
+
+    map.get_layer_mut(0).unwrap().resize(9, 6, 2);
+    assert_eq!(
+        tile_at(&map, 4, 4).as_ref(),
+        original_layer.get_tile_data(4, 4)
+    );
+    for (x, y) in [(7, 0), (8, 4), (0, 5), (8, 5)] {
+        assert_eq!(
+            tile_at(&map, x, y).map(|tile| tile.id()),
+            Some(TileId::new(1))
+        );
+    }
+
+    map.resize(3, 12, 0);
+    assert_eq!((map.width, map.height), (3, 12));
+    assert_eq!(
+        tile_at(&map, 2, 4).as_ref(),
+        original_layer.get_tile_data(2, 4)
+    );
+    assert_eq!(
+        tile_at(&map, 2, 5).map(|tile| tile.id()),
+        Some(TileId::new(1))
+    );
+    assert_eq!(tile_at(&map, 0, 11), None);
+
+    // Tile layers in groups are resized too.
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    map.resize(4, 4, 0);
+    fn check_sizes(layer: Layer) {
+        match layer.layer_type() {
+            LayerType::Tiles(layer) => {
+                assert_eq!((layer.width(), layer.height()), (Some(4), Some(4)))
+            }
+            LayerType::Group(group) => group.layers().for_each(check_sizes),
+            _ => {}
+        }
+    }
+    map.layers().for_each(check_sizes);
+
+    // Layers loaded without their tiles keep the size of the data they were loaded from.
+    let mut map = Loader::new()
+        .load_tmx_map_streaming("assets/tiled_base64.tmx", |_| {})
+        .unwrap();
+    map.get_layer_mut(0).unwrap().resize(7, 5, 0);
+    let layer = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+    assert_eq!((layer.width(), layer.height()), (100, 100));
+}
+
+#[test]
+fn test_get_all_properties() {
+    let map = parse_small_map(
+        r#"
+ <properties>
+  <property name="collidable" type="bool" value="false"/>
+  <property name="author" value="me"/>
+ </properties>
+ <tileset firstgid="1" name="a" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+  <properties>
+   <property name="collidable" type="bool" value="false"/>
+  </properties>
+  <image source="a.png" width="32" height="32"/>
+  <tile id="3">
+   <properties>
+    <property name="collidable" type="bool" value="true"/>
+   </properties>
+  </tile>
+ </tileset>
+ <group id="1" name="group">
+  <objectgroup id="2" name="Ground">
+   <properties>
+    <property name="collidable" type="bool" value="true"/>
+   </properties>
+   <object id="42" x="0" y="0">
+    <properties>
+     <property name="collidable" type="bool" value="true"/>
+    </properties>
+   </object>
+  </objectgroup>
+ </group>"#,
+    )
+    .unwrap();
+
+    let collidable: Vec<_> = map
+        .get_all_properties()
+        .filter(|(_, name, _)| *name == "collidable")
+        .map(|(owner, _, value)| (owner.to_string(), value.clone()))
+        .collect();
+    assert_eq!(
+        collidable,
+        [
+            ("map", false),
+            ("tileset:0", false),
+            ("tile:0:3", true),
+            ("layer:Ground", true),
+            ("object:42", true),
+        ]
+        .iter()
+        .map(|(owner, value)| (owner.to_string(), PropertyValue::BoolValue(*value)))
+        .collect::<Vec<_>>()
+    );
+
+    let (owner, name, _) = map.get_all_properties().next().unwrap();
+    assert_eq!((owner, name), (tiled::PropertyOwner::Map, "author"));
+    assert_eq!(map.get_all_properties().count(), 6);
+}
+
+#[test]
+fn test_unparsed_attributes() {
+    let tmx = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" tiledversion="1.10.2" orientation="orthogonal" width="1" height="1" tilewidth="16" tileheight="16" compressionlevel="-1" nextlayerid="4" nextobjectid="2">
+ <tileset firstgid="1" name="a" tilewidth="16" tileheight="16" tilecount="1" columns="1" future="tileset">
+  <image source="a.png" width="16" height="16"/>
+ </tileset>
+ <layer id="1" name="Tiles" width="1" height="1" locked="1">
+  <data encoding="csv">1</data>
+ </layer>
+ <group id="2" name="Group" future="group">
+  <objectgroup id="3" name="Objects" color="#ff0000" future="objectgroup">
+   <object id="1" x="0" y="0" future="object"/>
+  </objectgroup>
+ </group>
+</map>"##;
+    let loader = || Loader::new().with_unparsed_attributes(true);
+    let map = loader()
+        .load_tmx_map_from(tmx.as_bytes(), "assets/map.tmx")
+        .unwrap();
+
+    let check = |map: &Map| {
+        let attrs = |pairs: &[(&str, &str)]| -> Option<Box<std::collections::HashMap<_, _>>> {
+            Some(Box::new(
+                pairs
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect(),
+            ))
+        };
+        // The attributes describing the file itself are left out.
+        assert_eq!(
+            map.unparsed_attributes,
+            attrs(&[("compressionlevel", "-1")])
+        );
+        assert_eq!(
+            map.tilesets()[0].unparsed_attributes,
+            attrs(&[("future", "tileset")])
+        );
+        assert_eq!(
+            map.get_layer(0).unwrap().unparsed_attributes,
+            attrs(&[("locked", "1")])
+        );
+        let group = map.get_layer(1).unwrap();
+        assert_eq!(group.unparsed_attributes, attrs(&[("future", "group")]));
+        let group = group.as_group_layer().unwrap();
+        let objects = group.get_layer(0).unwrap();
+        assert_eq!(
+            objects.unparsed_attributes,
+            attrs(&[("future", "objectgroup")])
+        );
+        let object = objects.as_object_layer().unwrap().get_object(0).unwrap();
+        assert_eq!(object.unparsed_attributes, attrs(&[("future", "object")]));
+    };
+    check(&map);
+
+    let mut written = Vec::new();
+    map.write_to(&mut written).unwrap();
+    check(
+        &loader()
+            .load_tmx_map_from(&written[..], "assets/map.tmx")
+            .unwrap(),
+    );
+
+    // They aren't kept by default
+    let map = tiled::parse_str(tmx).unwrap();
+    assert_eq!(map.unparsed_attributes, None);
+    let group = map.get_layer(1).unwrap();
+    assert_eq!(group.unparsed_attributes, None);
+    let group = group.as_group_layer().unwrap();
+    let objects = group.get_layer(0).unwrap();
+    let object = objects.as_object_layer().unwrap().get_object(0).unwrap();
+    assert_eq!(object.unparsed_attributes, None);
+}